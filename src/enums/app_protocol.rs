@@ -1,59 +1,91 @@
 #![allow(clippy::upper_case_acronyms)]
+use std::collections::HashMap;
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
 /// Enum representing the possible observed values of application layer protocol.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AppProtocol {
     /// File Transfer Protocol
+    #[serde(rename = "FTP")]
     FTP,
     /// Secure Shell
+    #[serde(rename = "SSH")]
     SSH,
     /// Telnet
+    #[serde(rename = "Telnet")]
     Telnet,
     /// Simple Mail Transfer Protocol
+    #[serde(rename = "SMTP")]
     SMTP,
     /// Terminal Access Controller Access-Control System
+    #[serde(rename = "TACACS")]
     TACACS,
     /// Domain Name System
+    #[serde(rename = "DNS")]
     DNS,
     /// Dynamic Host Configuration Protocol
+    #[serde(rename = "DHCP")]
     DHCP,
     /// Trivial File Transfer Protocol
+    #[serde(rename = "TFTP")]
     TFTP,
     /// Hypertext Transfer Protocol
+    #[serde(rename = "HTTP")]
     HTTP,
     /// Post Office Protocol
+    #[serde(rename = "POP")]
     POP,
     /// Network Time Protocol
+    #[serde(rename = "NTP")]
     NTP,
     /// NetBIOS
+    #[serde(rename = "NetBIOS")]
     NetBIOS,
     /// Post Office Protocol 3 over TLS/SSL
+    #[serde(rename = "POP3S")]
     POP3S,
     /// Internet Message Access Protocol
+    #[serde(rename = "IMAP")]
     IMAP,
     /// Simple Network Management Protocol
+    #[serde(rename = "SNMP")]
     SNMP,
     /// Border Gateway Protocol
+    #[serde(rename = "BGP")]
     BGP,
     /// Lightweight Directory Access Protocol
+    #[serde(rename = "LDAP")]
     LDAP,
     ///Hypertext Transfer Protocol over TLS/SSL
+    #[serde(rename = "HTTPS")]
     HTTPS,
+    /// QUIC (carrying HTTP/3)
+    #[serde(rename = "QUIC")]
+    QUIC,
     /// Lightweight Directory Access Protocol over TLS/SSL
+    #[serde(rename = "LDAPS")]
     LDAPS,
     /// File Transfer Protocol over TLS/SSL
+    #[serde(rename = "FTPS")]
     FTPS,
     /// Multicast DNS
     #[allow(non_camel_case_types)]
+    #[serde(rename = "mDNS")]
     mDNS,
     ///Internet Message Access Protocol over TLS/SSL
+    #[serde(rename = "IMAPS")]
     IMAPS,
     /// Simple Service Discovery Protocol
+    #[serde(rename = "SSDP")]
     SSDP,
     /// Extensible Messaging and Presence Protocol |
+    #[serde(rename = "XMPP")]
     XMPP,
     /// not identified
+    #[serde(rename = "Other")]
     Other,
 }
 
@@ -108,6 +140,44 @@ pub fn from_port_to_application_protocol(port: u16) -> AppProtocol {
     }
 }
 
+/// Returns the application protocol of a packet exchanged between the two ports.
+///
+/// The user defined `port_protocol_overrides` are looked up first, for either port:
+/// only if neither port is overridden, the well-known ports are looked up (the first port taking precedence).
+pub fn from_ports_to_application_protocol(
+    port1: u16,
+    port2: u16,
+    port_protocol_overrides: &HashMap<u16, AppProtocol>,
+) -> AppProtocol {
+    if let Some(app_protocol) = port_protocol_overrides
+        .get(&port1)
+        .or_else(|| port_protocol_overrides.get(&port2))
+    {
+        return *app_protocol;
+    }
+    match from_port_to_application_protocol(port1) {
+        AppProtocol::Other => from_port_to_application_protocol(port2),
+        app_protocol => app_protocol,
+    }
+}
+
+/// Parses the ports of the application protocol overrides specified in the configuration,
+/// discarding (with a warning) the ones that are not valid port numbers.
+pub fn get_port_protocol_overrides(
+    overrides: &HashMap<String, AppProtocol>,
+) -> HashMap<u16, AppProtocol> {
+    overrides
+        .iter()
+        .filter_map(|(port, app_protocol)| match port.trim().parse::<u16>() {
+            Ok(port) => Some((port, *app_protocol)),
+            Err(_) => {
+                warn!(%port, %app_protocol, "application protocol override ignored, invalid port");
+                None
+            }
+        })
+        .collect()
+}
+
 impl fmt::Display for AppProtocol {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.eq(&AppProtocol::Other) {
@@ -120,7 +190,7 @@ impl fmt::Display for AppProtocol {
 
 /// Defines a constant to be used in the picklist in gui initial page
 impl AppProtocol {
-    pub(crate) const ALL: [AppProtocol; 25] = [
+    pub(crate) const ALL: [AppProtocol; 26] = [
         AppProtocol::Other,
         AppProtocol::BGP,
         AppProtocol::DHCP,
@@ -138,6 +208,7 @@ impl AppProtocol {
         AppProtocol::NTP,
         AppProtocol::POP,
         AppProtocol::POP3S,
+        AppProtocol::QUIC,
         AppProtocol::SMTP,
         AppProtocol::SNMP,
         AppProtocol::SSDP,
@@ -173,6 +244,61 @@ mod tests {
         assert_eq!(AppProtocol::Other, result);
     }
 
+    #[test]
+    fn from_ports_to_application_protocol_override() {
+        let overrides = HashMap::from([
+            (2222, AppProtocol::SSH),
+            (8080, AppProtocol::HTTPS),
+            (53, AppProtocol::mDNS),
+        ]);
+        // without overrides, the well-known ports are used
+        assert_eq!(
+            from_ports_to_application_protocol(50000, 8080, &HashMap::new()),
+            AppProtocol::HTTP
+        );
+        assert_eq!(
+            from_ports_to_application_protocol(50000, 2222, &HashMap::new()),
+            AppProtocol::Other
+        );
+        // an override takes precedence over a well-known port
+        assert_eq!(
+            from_ports_to_application_protocol(50000, 8080, &overrides),
+            AppProtocol::HTTPS
+        );
+        assert_eq!(
+            from_ports_to_application_protocol(53, 50000, &overrides),
+            AppProtocol::mDNS
+        );
+        // even if the well-known port is the other one
+        assert_eq!(
+            from_ports_to_application_protocol(80, 2222, &overrides),
+            AppProtocol::SSH
+        );
+        // ports without overrides fall back to the well-known ones
+        assert_eq!(
+            from_ports_to_application_protocol(50000, 443, &overrides),
+            AppProtocol::HTTPS
+        );
+        assert_eq!(
+            from_ports_to_application_protocol(50000, 50001, &overrides),
+            AppProtocol::Other
+        );
+    }
+
+    #[test]
+    fn port_protocol_overrides_from_config() {
+        let config = HashMap::from([
+            ("2222".to_string(), AppProtocol::SSH),
+            ("8443".to_string(), AppProtocol::HTTPS),
+            ("http".to_string(), AppProtocol::HTTP),
+            ("70000".to_string(), AppProtocol::HTTP),
+        ]);
+        assert_eq!(
+            get_port_protocol_overrides(&config),
+            HashMap::from([(2222, AppProtocol::SSH), (8443, AppProtocol::HTTPS)])
+        );
+    }
+
     #[test]
     fn app_protocol_display_ftp() {
         let test_str = AppProtocol::FTP.to_string();
@@ -184,4 +310,24 @@ mod tests {
         let test_str = AppProtocol::Other.to_string();
         assert_eq!(test_str, "-");
     }
+
+    #[test]
+    fn test_app_protocol_serde_round_trip() {
+        assert_eq!(
+            serde_json::to_string(&AppProtocol::HTTPS).unwrap(),
+            "\"HTTPS\""
+        );
+        assert_eq!(
+            serde_json::to_string(&AppProtocol::mDNS).unwrap(),
+            "\"mDNS\""
+        );
+        for app_protocol in AppProtocol::ALL {
+            let json = serde_json::to_string(&app_protocol).unwrap();
+            assert_eq!(json, format!("\"{app_protocol:?}\""));
+            assert_eq!(
+                serde_json::from_str::<AppProtocol>(&json).unwrap(),
+                app_protocol
+            );
+        }
+    }
 }
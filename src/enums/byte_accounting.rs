@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Enum representing which length of the packets is accounted as exchanged bytes
+/// (in the connections, in the charts and in the notifications thresholds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ByteAccounting {
+    /// Length of the IP payload, as declared by the IP header: the transport header and the application data,
+    /// without the IP and link layer headers
+    #[default]
+    Payload,
+    /// Length of the whole frame on the wire, as reported by the capture: it also includes the IP and link layer
+    /// headers (e.g. Ethernet, or the radiotap header of wireless captures), and the Ethernet FCS
+    /// only if the adapter doesn't strip it.
+    /// It's the length to be accounted to measure the bandwidth actually used on the link.
+    Frame,
+}
+
+impl ByteAccounting {
+    /// Returns the bytes to be accounted for a packet, given the length of its frame and of its IP payload.
+    pub fn exchanged_bytes(self, frame_len: u128, payload_len: u128) -> u128 {
+        match self {
+            ByteAccounting::Payload => payload_len,
+            ByteAccounting::Frame => frame_len,
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::enums::byte_multiple::ByteMultiple::{B, GB, KB, MB};
+use crate::enums::byte_multiple::ByteMultiple::{B, GB, KB, MB, TB};
+use crate::UnitSystem;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -13,6 +14,8 @@ pub enum ByteMultiple {
     MB,
     /// A billion Bytes
     GB,
+    /// A trillion Bytes
+    TB,
 }
 
 impl fmt::Display for ByteMultiple {
@@ -30,15 +33,52 @@ impl ByteMultiple {
             KB => 1_000,
             MB => 1_000_000,
             GB => 1_000_000_000,
+            TB => 1_000_000_000_000,
         }
     }
 
+    /// Returns the number of bytes in the multiple, according to the unit system.
+    pub fn get_divisor(self, unit_system: UnitSystem) -> u128 {
+        let base = unit_system.get_base();
+        match self {
+            B => 1,
+            KB => base,
+            MB => base.pow(2),
+            GB => base.pow(3),
+            TB => base.pow(4),
+        }
+    }
+
+    /// Returns the prefix of the multiple: K, M, G, T (decimal) or Ki, Mi, Gi, Ti (binary).
+    pub fn get_prefix(self, unit_system: UnitSystem) -> &'static str {
+        match (self, unit_system) {
+            (B, _) => "",
+            (KB, UnitSystem::Decimal) => "K",
+            (MB, UnitSystem::Decimal) => "M",
+            (GB, UnitSystem::Decimal) => "G",
+            (TB, UnitSystem::Decimal) => "T",
+            (KB, UnitSystem::Binary) => "Ki",
+            (MB, UnitSystem::Binary) => "Mi",
+            (GB, UnitSystem::Binary) => "Gi",
+            (TB, UnitSystem::Binary) => "Ti",
+        }
+    }
+
+    /// Returns the largest multiple in which the given quantity of bytes is at least 1.
+    pub fn fitting(bytes: u128, unit_system: UnitSystem) -> ByteMultiple {
+        [TB, GB, MB, KB]
+            .into_iter()
+            .find(|multiple| bytes >= multiple.get_divisor(unit_system))
+            .unwrap_or(B)
+    }
+
     pub fn get_char(&self) -> &str {
         match self {
             B => "",
             KB => "K",
             MB => "M",
             GB => "G",
+            TB => "T",
         }
     }
 }
@@ -55,6 +95,7 @@ pub fn from_char_to_multiple(ch: char) -> ByteMultiple {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::UnitSystem;
 
     #[test]
     fn test_interpret_suffix_correctly() {
@@ -64,6 +105,42 @@ mod tests {
         assert_eq!(from_char_to_multiple('g'), ByteMultiple::GB);
     }
 
+    #[test]
+    fn test_fitting_multiple() {
+        assert_eq!(
+            ByteMultiple::fitting(0, UnitSystem::Decimal),
+            ByteMultiple::B
+        );
+        assert_eq!(
+            ByteMultiple::fitting(999, UnitSystem::Decimal),
+            ByteMultiple::B
+        );
+        assert_eq!(
+            ByteMultiple::fitting(1000, UnitSystem::Decimal),
+            ByteMultiple::KB
+        );
+        assert_eq!(
+            ByteMultiple::fitting(1000, UnitSystem::Binary),
+            ByteMultiple::B
+        );
+        assert_eq!(
+            ByteMultiple::fitting(1024, UnitSystem::Binary),
+            ByteMultiple::KB
+        );
+        assert_eq!(
+            ByteMultiple::fitting(1_048_575, UnitSystem::Binary),
+            ByteMultiple::KB
+        );
+        assert_eq!(
+            ByteMultiple::fitting(1_048_576, UnitSystem::Binary),
+            ByteMultiple::MB
+        );
+        assert_eq!(
+            ByteMultiple::fitting(u128::MAX, UnitSystem::Decimal),
+            ByteMultiple::TB
+        );
+    }
+
     #[test]
     fn test_interpret_unknown_suffix_correctly() {
         assert_eq!(from_char_to_multiple('T'), ByteMultiple::B);
@@ -1,15 +1,19 @@
 use crate::utility::translations::both_translation;
 use crate::Language;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Enum representing the possible observed values of IP protocol version.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IpVersion {
     /// Internet Protocol version 4
+    #[serde(rename = "IPv4")]
     IPv4,
     /// Internet Protocol version 6
+    #[serde(rename = "IPv6")]
     IPv6,
     /// Not identified
+    #[serde(rename = "Other")]
     Other,
 }
 
@@ -30,3 +34,22 @@ impl IpVersion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::IpVersion;
+
+    #[test]
+    fn test_ip_version_serde_round_trip() {
+        assert_eq!(serde_json::to_string(&IpVersion::IPv6).unwrap(), "\"IPv6\"");
+        for ip_version in IpVersion::ALL {
+            let json = serde_json::to_string(&ip_version).unwrap();
+            assert_eq!(json, format!("\"{ip_version}\""));
+            assert_eq!(
+                serde_json::from_str::<IpVersion>(&json).unwrap(),
+                ip_version
+            );
+        }
+        assert!(serde_json::from_str::<IpVersion>("\"ipv6\"").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+use tracing::level_filters::LevelFilter;
+
+/// Enum representing the verbosity levels of the diagnostic events, from the least to the most verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Failures the application recovered from
+    Error,
+    /// Unexpected conditions that don't prevent the application from working
+    Warn,
+    /// Significant events, such as the start of a capture or a triggered notification
+    Info,
+    /// Periodic events, such as the flush of the buffered traffic
+    Debug,
+    /// Events related to single packets
+    Trace,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{self:?}").to_uppercase())
+    }
+}
+
+impl LogLevel {
+    pub(crate) const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    /// Parses a level from its case insensitive name (e.g. `debug`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        LogLevel::ALL
+            .into_iter()
+            .find(|level| level.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the filter enabling the events not more verbose than this level.
+    pub fn to_level_filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::level_filters::LevelFilter;
+
+    use crate::enums::log_level::LogLevel;
+
+    #[test]
+    fn test_log_level_from_name() {
+        assert_eq!(LogLevel::from_name("debug"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::from_name("WARN"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::from_name("verbose"), None);
+        assert!(LogLevel::Error < LogLevel::Trace);
+        assert_eq!(LogLevel::Info.to_level_filter(), LevelFilter::INFO);
+    }
+}
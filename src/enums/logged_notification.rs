@@ -1,8 +1,16 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::enums::notification_direction::NotificationDirection;
+use crate::enums::threshold_mode::ThresholdMode;
 use crate::structs::address_port_pair::AddressPortPair;
 use crate::structs::info_address_port_pair::InfoAddressPortPair;
-use crate::ByteMultiple;
+use crate::{ByteMultiple, UnitSystem};
 
 /// Enum representing the possible observed values of IP protocol version.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
 pub enum LoggedNotification {
     /// Packets threshold exceeded
     PacketsThresholdExceeded(PacketsThresholdExceeded),
@@ -10,27 +18,83 @@ pub enum LoggedNotification {
     BytesThresholdExceeded(BytesThresholdExceeded),
     /// Favorite connection exchanged data
     FavoriteTransmitted(FavoriteTransmitted),
+    /// Bytes per second threshold exceeded by a single connection
+    ConnectionRateExceeded(ConnectionRateExceeded),
+    /// Traffic exchanged with a country never contacted before
+    NewCountry(NewCountry),
+    /// Packets processed too late after their capture
+    CaptureLag(CaptureLag),
+}
+
+impl LoggedNotification {
+    /// Returns the time at which the notification has been emitted.
+    pub fn timestamp(&self) -> DateTime<Local> {
+        match self {
+            LoggedNotification::PacketsThresholdExceeded(packets) => packets.timestamp,
+            LoggedNotification::BytesThresholdExceeded(bytes) => bytes.timestamp,
+            LoggedNotification::FavoriteTransmitted(favorite) => favorite.timestamp,
+            LoggedNotification::ConnectionRateExceeded(connection_rate) => {
+                connection_rate.timestamp
+            }
+            LoggedNotification::NewCountry(new_country) => new_country.timestamp,
+            LoggedNotification::CaptureLag(capture_lag) => capture_lag.timestamp,
+        }
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct PacketsThresholdExceeded {
     pub(crate) threshold: u32,
+    /// Traffic compared with the threshold
+    pub(crate) direction: NotificationDirection,
     pub(crate) incoming: u32,
     pub(crate) outgoing: u32,
-    pub(crate) timestamp: String,
+    pub(crate) timestamp: DateTime<Local>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct BytesThresholdExceeded {
     pub(crate) threshold: u64,
     pub(crate) byte_multiple: ByteMultiple,
+    /// System in which the multiple of the threshold is interpreted
+    pub(crate) unit_system: UnitSystem,
+    /// Whether the incoming and outgoing bytes (and the threshold) are per second or per refresh interval
+    pub(crate) threshold_mode: ThresholdMode,
+    /// Traffic compared with the threshold
+    pub(crate) direction: NotificationDirection,
     pub(crate) incoming: u32,
     pub(crate) outgoing: u32,
-    pub(crate) timestamp: String,
+    pub(crate) timestamp: DateTime<Local>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct FavoriteTransmitted {
     pub(crate) connection: (AddressPortPair, InfoAddressPortPair),
-    pub(crate) timestamp: String,
+    pub(crate) timestamp: DateTime<Local>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct NewCountry {
+    pub(crate) country: String,
+    pub(crate) connection: (AddressPortPair, InfoAddressPortPair),
+    pub(crate) timestamp: DateTime<Local>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ConnectionRateExceeded {
+    pub(crate) threshold: u64,
+    pub(crate) byte_multiple: ByteMultiple,
+    /// System in which the multiple of the threshold is interpreted
+    pub(crate) unit_system: UnitSystem,
+    pub(crate) connection: (AddressPortPair, InfoAddressPortPair),
+    pub(crate) rate: u64,
+    pub(crate) timestamp: DateTime<Local>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CaptureLag {
+    pub(crate) threshold_seconds: u64,
+    /// Maximum delay between the capture of a packet and its processing in the last interval
+    pub(crate) lag_ms: u64,
+    pub(crate) timestamp: DateTime<Local>,
 }
@@ -1,6 +1,10 @@
 use crate::enums::my_overlay::MyOverlay;
+use crate::enums::network_scope::NetworkScope;
 use crate::enums::running_page::RunningPage;
-use crate::structs::notifications::{BytesNotification, FavoriteNotification, PacketsNotification};
+use crate::enums::sort_column::SortColumn;
+use crate::structs::notifications::{
+    BytesNotification, FavoriteNotification, NewCountryNotification, PacketsNotification,
+};
 use crate::{AppProtocol, ChartType, IpVersion, Language, ReportType, StyleType, TransProtocol};
 
 #[derive(Debug, Clone)]
@@ -18,16 +22,34 @@ pub enum Message {
     TransportProtocolSelection(TransProtocol),
     /// Select application filter
     AppProtocolSelection(AppProtocol),
+    /// Select network scope filter
+    NetworkScopeSelection(NetworkScope),
     /// Select chart type to be displayed
     ChartSelection(ChartType),
+    /// Toggle the logarithmic scale of the chart
+    ChartLogScale(bool),
+    /// Toggle the composition by application protocol of the packets chart
+    ChartPerAppProtocol(bool),
+    /// Toggle the aggregation of the broadcast and multicast traffic
+    AggregateBroadcastMulticast(bool),
     /// Select report type to be displayed
     ReportSelection(ReportType),
+    /// Filter the connections displayed in the report by address, port or country
+    Search(String),
+    /// Sort the connections displayed in the report by the given column (toggling the direction if already selected)
+    SortReport(SortColumn),
     /// Saves the given connection into the favorites
-    SaveConnection(usize),
+    SaveConnection(u64),
     /// Un-saves the given connection into the favorites
-    UnSaveConnection(usize),
+    UnSaveConnection(u64),
     /// Open Sniffnet's complete textual report
     OpenReport,
+    /// Export the connections to a CSV file
+    ExportCsv,
+    /// Pause the current run, discarding the sniffed packets
+    PauseCapture,
+    /// Resume the paused run
+    ResumeCapture,
     /// Open Sniffnet's GitHub page
     OpenGithub,
     /// Start sniffing packets
@@ -50,12 +72,16 @@ pub enum Message {
     UpdatePacketsNotification(PacketsNotification, bool),
     /// Set bytes notification
     UpdateBytesNotification(BytesNotification, bool),
+    /// Set connection rate notification
+    UpdateConnectionRateNotification(BytesNotification, bool),
     /// Set favorite notification
     UpdateFavoriteNotification(FavoriteNotification, bool),
+    /// Set new country notification
+    UpdateNewCountryNotification(NewCountryNotification, bool),
     /// Clear all received notifications
     ClearAllNotifications,
     /// Set notifications volume
     ChangeVolume(u8),
-    /// Quits the app. Used when Ctrl+Q keys are pressed.
+    /// Quits the app. Used when Ctrl+Q keys are pressed or the window is closed.
     Exit,
 }
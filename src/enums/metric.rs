@@ -0,0 +1,8 @@
+/// Enum representing the possible measures of the traffic exchanged by a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Amount of exchanged bytes
+    Bytes,
+    /// Amount of exchanged packets
+    Packets,
+}
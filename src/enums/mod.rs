@@ -1,16 +1,25 @@
 pub mod app_protocol;
+pub mod byte_accounting;
 pub mod byte_multiple;
 pub mod chart_type;
 pub mod element_type;
 pub mod ip_version;
 pub mod language;
+pub mod log_level;
 pub mod logged_notification;
 pub mod message;
+pub mod metric;
 pub mod my_overlay;
+pub mod network_scope;
+pub mod notification_direction;
 pub mod report_type;
 pub mod running_page;
+pub mod sort_column;
 pub mod sound;
 pub mod status;
 pub mod style_type;
+pub mod tcp_state;
+pub mod threshold_mode;
 pub mod traffic_type;
 pub mod trans_protocol;
+pub mod unit_system;
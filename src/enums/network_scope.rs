@@ -0,0 +1,33 @@
+use crate::utility::translations::{all_translation, local_network_translation, wan_translation};
+use crate::Language;
+use std::fmt;
+
+/// Enum representing the possible scopes of the observed connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkScope {
+    /// Connections between two local addresses
+    Local,
+    /// Connections involving at least a remote address
+    Wan,
+    /// Not filtered
+    Other,
+}
+
+impl fmt::Display for NetworkScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl NetworkScope {
+    pub(crate) const ALL: [NetworkScope; 3] =
+        [NetworkScope::Local, NetworkScope::Wan, NetworkScope::Other];
+
+    pub fn get_radio_label(&self, language: Language) -> &str {
+        match self {
+            NetworkScope::Local => local_network_translation(language),
+            NetworkScope::Wan => wan_translation(language),
+            NetworkScope::Other => all_translation(language),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum representing which traffic is compared with a packets or bytes threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum NotificationDirection {
+    /// Both the incoming and the outgoing traffic, summed up
+    #[default]
+    Both,
+    /// Only the incoming traffic (e.g. to detect a flood of requests)
+    Inbound,
+    /// Only the outgoing traffic (e.g. to detect data exfiltration)
+    Outbound,
+}
+
+impl fmt::Display for NotificationDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl NotificationDirection {
+    /// Returns the amount of traffic to be compared with the threshold.
+    pub fn compared_value<T: std::ops::Add<Output = T>>(self, incoming: T, outgoing: T) -> T {
+        match self {
+            NotificationDirection::Both => incoming + outgoing,
+            NotificationDirection::Inbound => incoming,
+            NotificationDirection::Outbound => outgoing,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+/// Enum representing the fields by which the connections can be sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    /// Amount of exchanged bytes
+    Bytes,
+    /// Amount of exchanged packets
+    Packets,
+    /// Last occurrence of information exchange
+    Recency,
+    /// Time elapsed between the first and the last packet
+    Duration,
+    /// Country code of the remote address
+    Country,
+    /// Application layer protocol (in alphabetical order, with unidentified protocols first)
+    AppProtocol,
+}
@@ -3,15 +3,21 @@ use crate::utility::translations::none_translation;
 use crate::Language;
 use rodio::{Decoder, OutputStream, Sink};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::ffi::OsStr;
 use std::fmt;
+use std::path::PathBuf;
 use std::thread;
+use tracing::warn;
 
 /// Enum representing the possible notification sounds.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Sound {
     Gulp,
     Pop,
     Swhoosh,
+    /// User supplied audio file (MP3, WAV or Ogg Vorbis)
+    Custom(PathBuf),
     None,
 }
 
@@ -19,9 +25,15 @@ pub const GULP: &[u8] = include_bytes!("../../resources/sounds/gulp.mp3");
 pub const POP: &[u8] = include_bytes!("../../resources/sounds/pop.mp3");
 pub const SWHOOSH: &[u8] = include_bytes!("../../resources/sounds/swhoosh.mp3");
 
+/// Built-in sound played in place of a custom one that cannot be read
+const FALLBACK_SOUND: &[u8] = GULP;
+
 impl fmt::Display for Sound {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Sound::Custom(path) => write!(f, "{}", path.display()),
+            _ => write!(f, "{self:?}"),
+        }
     }
 }
 
@@ -29,12 +41,33 @@ impl fmt::Display for Sound {
 impl Sound {
     pub(crate) const ALL: [Sound; 4] = [Gulp, Pop, Swhoosh, Sound::None];
 
-    fn mp3_sound(self) -> &'static [u8] {
+    /// Returns the sounds selectable for a notification whose current sound is `current`:
+    /// the built-in ones, preceded by the custom one if it's the current one.
+    pub fn selectable(current: &Sound) -> Vec<Sound> {
+        let mut sounds = Vec::with_capacity(Sound::ALL.len() + 1);
+        if let Sound::Custom(_) = current {
+            sounds.push(current.clone());
+        }
+        sounds.extend(Sound::ALL);
+        sounds
+    }
+
+    /// Returns the encoded audio data of the sound.
+    ///
+    /// Custom sounds are read from their file: if it cannot be read, the built-in fallback sound is returned.
+    fn audio_data(&self) -> Cow<'static, [u8]> {
         match self {
-            Gulp => GULP,
-            Pop => POP,
-            Swhoosh => SWHOOSH,
-            Sound::None => &[],
+            Gulp => Cow::Borrowed(GULP),
+            Pop => Cow::Borrowed(POP),
+            Swhoosh => Cow::Borrowed(SWHOOSH),
+            Sound::Custom(path) => std::fs::read(path).map_or_else(
+                |err| {
+                    warn!(path = %path.display(), error = %err, "cannot read custom sound, playing the built-in one");
+                    Cow::Borrowed(FALLBACK_SOUND)
+                },
+                Cow::Owned,
+            ),
+            Sound::None => Cow::Borrowed(&[]),
         }
     }
 
@@ -43,16 +76,17 @@ impl Sound {
             Gulp => "Gulp",
             Pop => "Pop",
             Swhoosh => "Swhoosh",
+            Sound::Custom(path) => path.file_name().and_then(OsStr::to_str).unwrap_or_default(),
             Sound::None => none_translation(language),
         }
     }
 }
 
-pub fn play(sound: Sound, volume: u8) {
+pub fn play(sound: &Sound, volume: u8) {
     if sound.eq(&Sound::None) || volume == 0 {
         return;
     }
-    let mp3_sound = sound.mp3_sound();
+    let sound = sound.clone();
     thread::Builder::new()
         .name("thread_play_sound".to_string())
         .spawn(move || {
@@ -60,9 +94,16 @@ pub fn play(sound: Sound, volume: u8) {
             let (_stream, stream_handle) = OutputStream::try_default().unwrap();
             let sink = Sink::try_new(&stream_handle).unwrap();
             //load data
-            let data = std::io::Cursor::new(mp3_sound);
+            let data = std::io::Cursor::new(sound.audio_data());
             // Decode that sound file into a source
-            let source = Decoder::new(data).unwrap();
+            let source = match Decoder::new(data) {
+                Ok(source) => source,
+                Err(err) => {
+                    // only custom sounds may be in a format not supported
+                    warn!(%sound, error = %err, "unsupported sound format, playing the built-in one");
+                    Decoder::new(std::io::Cursor::new(Cow::Borrowed(FALLBACK_SOUND))).unwrap()
+                }
+            };
             // Play the sound directly on the device
             sink.set_volume(f32::from(volume) / 200.0); // set the desired volume
             sink.append(source);
@@ -72,3 +113,39 @@ pub fn play(sound: Sound, volume: u8) {
         })
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::enums::sound::{Sound, FALLBACK_SOUND, POP};
+    use crate::Language;
+
+    #[test]
+    fn test_custom_sound_audio_data() {
+        let path = std::env::temp_dir().join("sniffnet_test_custom_sound.mp3");
+        fs::write(&path, POP).unwrap();
+        let custom = Sound::Custom(path.clone());
+        assert_eq!(custom.audio_data(), Cow::Borrowed(POP));
+        assert_eq!(
+            custom.get_radio_label(Language::EN),
+            "sniffnet_test_custom_sound.mp3"
+        );
+        fs::remove_file(&path).unwrap();
+        // the file is missing: the built-in sound is played instead
+        assert_eq!(custom.audio_data(), Cow::Borrowed(FALLBACK_SOUND));
+        assert!(Sound::None.audio_data().is_empty());
+    }
+
+    #[test]
+    fn test_selectable_sounds() {
+        assert_eq!(Sound::selectable(&Sound::Pop), Sound::ALL.to_vec());
+        let custom = Sound::Custom(PathBuf::from("/home/user/alert.ogg"));
+        let selectable = Sound::selectable(&custom);
+        assert_eq!(selectable.len(), Sound::ALL.len() + 1);
+        assert_eq!(selectable[0], custom);
+        assert_eq!(selectable[1..], Sound::ALL);
+    }
+}
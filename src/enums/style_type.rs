@@ -7,6 +7,10 @@ pub enum StyleType {
     Day,
     DeepSea,
     MonAmour,
+    /// High contrast palette, distinguishable by colorblind users
+    Colorblind,
+    /// Palette defined by the user in the configuration file
+    Custom,
 }
 
 impl Default for StyleType {
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Enum representing the state of a TCP connection, as inferred from the flags of the sniffed segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TcpState {
+    /// The connection is being opened (SYN seen), but the handshake is not complete yet
+    SynSeen,
+    /// The connection is open and data can be exchanged
+    Established,
+    /// At least one of the endpoints asked to close the connection (FIN seen)
+    Closing,
+    /// The connection has been closed by both the endpoints or aborted (RST seen)
+    Closed,
+}
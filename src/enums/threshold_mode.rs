@@ -0,0 +1,32 @@
+use crate::utility::translations::{refresh_interval_translation, second_translation};
+use crate::Language;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum representing how a bytes threshold is compared with the exchanged traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ThresholdMode {
+    /// The threshold is a rate: bytes exchanged per second, whatever the refresh interval
+    #[default]
+    PerSecond,
+    /// The threshold is an amount: bytes exchanged during a single refresh interval
+    PerInterval,
+}
+
+impl fmt::Display for ThresholdMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl ThresholdMode {
+    pub(crate) const ALL: [ThresholdMode; 2] =
+        [ThresholdMode::PerSecond, ThresholdMode::PerInterval];
+
+    pub fn get_radio_label(&self, language: Language) -> &str {
+        match self {
+            ThresholdMode::PerSecond => second_translation(language),
+            ThresholdMode::PerInterval => refresh_interval_translation(language),
+        }
+    }
+}
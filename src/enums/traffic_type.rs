@@ -1,14 +1,60 @@
-/// Enum representing the possible traffic type (incoming, outgoing or multicast).
-#[derive(Clone, Copy, PartialEq, Eq)]
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Enum representing the possible traffic type (incoming, outgoing, local, multicast or broadcast).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TrafficType {
     /// Incoming traffic (from remote address to local interface)
+    #[serde(rename = "Incoming")]
     Incoming,
     /// Outgoing traffic (from local interface to remote address)
+    #[serde(rename = "Outgoing")]
     Outgoing,
+    /// Local traffic (between two addresses of this host, e.g. on the loopback adapter)
+    #[serde(rename = "Local")]
+    Local,
     /// Multicast traffic (from remote address to multicast address)
+    #[serde(rename = "Multicast")]
     Multicast,
     /// Multicast traffic (from remote address to broadcast address)
+    #[serde(rename = "Broadcast")]
     Broadcast,
     /// Not identified
+    #[serde(rename = "Other")]
     Other,
 }
+
+impl fmt::Display for TrafficType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::traffic_type::TrafficType;
+
+    #[test]
+    fn test_traffic_type_serde_round_trip() {
+        assert_eq!(
+            serde_json::to_string(&TrafficType::Outgoing).unwrap(),
+            "\"Outgoing\""
+        );
+        for traffic_type in [
+            TrafficType::Incoming,
+            TrafficType::Outgoing,
+            TrafficType::Local,
+            TrafficType::Multicast,
+            TrafficType::Broadcast,
+            TrafficType::Other,
+        ] {
+            let json = serde_json::to_string(&traffic_type).unwrap();
+            assert_eq!(json, format!("\"{traffic_type}\""));
+            assert_eq!(
+                serde_json::from_str::<TrafficType>(&json).unwrap(),
+                traffic_type
+            );
+        }
+    }
+}
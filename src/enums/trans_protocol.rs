@@ -1,16 +1,26 @@
 #![allow(clippy::upper_case_acronyms)]
 use crate::utility::translations::both_translation;
 use crate::Language;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Enum representing the possible observed values of transport layer protocol.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TransProtocol {
     /// Transmission Control Protocol
+    #[serde(rename = "TCP")]
     TCP,
     /// User Datagram Protocol
+    #[serde(rename = "UDP")]
     UDP,
+    /// Internet Control Message Protocol
+    #[serde(rename = "ICMP")]
+    ICMP,
+    /// Internet Control Message Protocol for IPv6
+    #[serde(rename = "ICMPv6")]
+    ICMPv6,
     /// Not identified
+    #[serde(rename = "Other")]
     Other,
 }
 
@@ -21,14 +31,46 @@ impl fmt::Display for TransProtocol {
 }
 
 impl TransProtocol {
-    pub(crate) const ALL: [TransProtocol; 3] =
-        [TransProtocol::TCP, TransProtocol::UDP, TransProtocol::Other];
+    pub(crate) const ALL: [TransProtocol; 5] = [
+        TransProtocol::TCP,
+        TransProtocol::UDP,
+        TransProtocol::ICMP,
+        TransProtocol::ICMPv6,
+        TransProtocol::Other,
+    ];
 
     pub fn get_radio_label(&self, language: Language) -> &str {
         match self {
             TransProtocol::TCP => "TCP",
             TransProtocol::UDP => "UDP",
+            TransProtocol::ICMP => "ICMP",
+            TransProtocol::ICMPv6 => "ICMPv6",
             TransProtocol::Other => both_translation(language),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::TransProtocol;
+
+    #[test]
+    fn test_trans_protocol_serde_round_trip() {
+        assert_eq!(
+            serde_json::to_string(&TransProtocol::TCP).unwrap(),
+            "\"TCP\""
+        );
+        assert_eq!(
+            serde_json::to_string(&TransProtocol::ICMPv6).unwrap(),
+            "\"ICMPv6\""
+        );
+        for trans_protocol in TransProtocol::ALL {
+            let json = serde_json::to_string(&trans_protocol).unwrap();
+            assert_eq!(json, format!("\"{trans_protocol}\""));
+            assert_eq!(
+                serde_json::from_str::<TransProtocol>(&json).unwrap(),
+                trans_protocol
+            );
+        }
+    }
+}
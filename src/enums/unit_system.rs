@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum representing the system in which the multiples of bytes are expressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum UnitSystem {
+    /// Powers of 1000 (KB, MB, GB): the convention of network throughputs
+    #[default]
+    Decimal,
+    /// Powers of 1024 (KiB, MiB, GiB): the convention of storage and memory sizes
+    Binary,
+}
+
+impl fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl UnitSystem {
+    /// Returns the ratio between two consecutive multiples of bytes.
+    pub fn get_base(self) -> u128 {
+        match self {
+            UnitSystem::Decimal => 1000,
+            UnitSystem::Binary => 1024,
+        }
+    }
+}
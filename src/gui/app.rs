@@ -5,11 +5,10 @@
 use iced::widget::Column;
 use iced::{executor, window, Application, Command, Element, Subscription, Theme};
 use pcap::Device;
-use std::cell::RefCell;
-use std::collections::{HashSet, VecDeque};
-use std::rc::Rc;
-use std::thread;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
+use tracing::warn;
 
 use crate::enums::message::Message;
 use crate::enums::my_overlay::MyOverlay;
@@ -29,18 +28,16 @@ use crate::gui::pages::settings_style_page::settings_style_page;
 use crate::structs::configs::ConfigSettings;
 use crate::structs::sniffer::Sniffer;
 use crate::structs::traffic_chart::TrafficChart;
-use crate::thread_parse_packets::parse_packets_loop;
-use crate::utility::get_formatted_strings::get_report_path;
-use crate::utility::manage_charts_data::update_charts_data;
-use crate::utility::manage_notifications::notify_and_log;
-use crate::utility::manage_packets::get_capture_result;
-use crate::utility::manage_report_data::update_report_data;
+use crate::utility::export_connections::export_connections_csv;
+use crate::utility::get_formatted_strings::{get_csv_path, get_report_path};
+use crate::utility::logging::lock_or_recover;
+use crate::utility::manage_report_data::{
+    update_country_breakdown, update_discovery_traffic, update_per_interface, update_report_data,
+    update_size_histogram, update_top_talkers,
+};
 use crate::utility::style_constants::get_font;
-use crate::{ConfigDevice, InfoTraffic, ReportType, RunTimeData};
+use crate::{ConfigDevice, ReportType};
 
-/// Update period when app is running
-pub const PERIOD_RUNNING: u64 = 1000;
-//milliseconds
 /// Update period when app is in its initial state
 pub const PERIOD_INIT: u64 = 5000; //milliseconds
 
@@ -62,39 +59,25 @@ impl Application for Sniffer {
         match message {
             Message::TickInit => {}
             Message::TickRun => {
-                let mut info_traffic_lock = self.info_traffic.lock().unwrap();
-                self.runtime_data.borrow_mut().all_packets = info_traffic_lock.all_packets;
-                if info_traffic_lock.tot_received_packets + info_traffic_lock.tot_sent_packets == 0
-                {
-                    drop(info_traffic_lock);
+                if !self.refresh_runtime_data() {
                     return self.update(Message::Waiting);
                 }
-                self.runtime_data.borrow_mut().tot_sent_packets =
-                    info_traffic_lock.tot_sent_packets;
-                self.runtime_data.borrow_mut().tot_received_packets =
-                    info_traffic_lock.tot_received_packets;
-                self.runtime_data.borrow_mut().all_packets = info_traffic_lock.all_packets;
-                self.runtime_data.borrow_mut().all_bytes = info_traffic_lock.all_bytes;
-                self.runtime_data.borrow_mut().tot_received_bytes =
-                    info_traffic_lock.tot_received_bytes;
-                self.runtime_data.borrow_mut().tot_sent_bytes = info_traffic_lock.tot_sent_bytes;
-                self.runtime_data.borrow_mut().app_protocols =
-                    info_traffic_lock.app_protocols.clone();
-                self.runtime_data.borrow_mut().favorites_last_interval =
-                    info_traffic_lock.favorites_last_interval.clone();
-                info_traffic_lock.favorites_last_interval = HashSet::new();
-                drop(info_traffic_lock);
-                notify_and_log(
-                    self.runtime_data.borrow_mut(),
-                    self.notifications,
-                    &self.info_traffic.clone(),
-                );
-                update_charts_data(self.runtime_data.borrow_mut());
                 update_report_data(
                     self.runtime_data.borrow_mut(),
                     &self.info_traffic,
                     self.report_type,
+                    self.report_sort,
+                    &self.search,
+                );
+                update_top_talkers(
+                    self.runtime_data.borrow_mut(),
+                    &self.info_traffic,
+                    self.traffic_chart.chart_type,
                 );
+                update_country_breakdown(self.runtime_data.borrow_mut(), &self.info_traffic);
+                update_size_histogram(self.runtime_data.borrow_mut(), &self.info_traffic);
+                update_discovery_traffic(self.runtime_data.borrow_mut(), &self.info_traffic);
+                update_per_interface(self.runtime_data.borrow_mut(), &self.info_traffic);
                 // update ConfigDevice stored if different from last sniffed device
                 if self.device.name.ne(&self.last_device_name_sniffed) {
                     self.last_device_name_sniffed = self.device.name.clone();
@@ -131,16 +114,66 @@ impl Application for Sniffer {
             Message::AppProtocolSelection(protocol) => {
                 self.filters.application = protocol;
             }
+            Message::NetworkScopeSelection(scope) => {
+                self.filters.scope = scope;
+            }
             Message::ChartSelection(what_to_display) => {
                 self.traffic_chart.change_kind(what_to_display);
+                update_top_talkers(
+                    self.runtime_data.borrow_mut(),
+                    &self.info_traffic,
+                    self.traffic_chart.chart_type,
+                );
+            }
+            Message::Search(search) => {
+                self.search = search;
+                update_report_data(
+                    self.runtime_data.borrow_mut(),
+                    &self.info_traffic,
+                    self.report_type,
+                    self.report_sort,
+                    &self.search,
+                );
+            }
+            Message::SortReport(sort_column) => {
+                self.report_sort = match self.report_sort {
+                    Some((column, descending)) if column == sort_column => {
+                        Some((column, !descending))
+                    }
+                    _ => Some((sort_column, true)),
+                };
+                update_report_data(
+                    self.runtime_data.borrow_mut(),
+                    &self.info_traffic,
+                    self.report_type,
+                    self.report_sort,
+                    &self.search,
+                );
+            }
+            Message::ChartLogScale(log_scale) => {
+                self.traffic_chart.log_scale = log_scale;
+            }
+            Message::ChartPerAppProtocol(per_app_protocol) => {
+                self.traffic_chart.per_app_protocol = per_app_protocol;
+            }
+            Message::AggregateBroadcastMulticast(aggregate) => {
+                self.aggregate_broadcast_multicast = aggregate;
+                lock_or_recover(
+                    &self.info_traffic,
+                    "aggregating broadcast and multicast traffic",
+                )
+                .aggregate_broadcast_multicast = aggregate;
             }
             Message::ReportSelection(what_to_display) => {
-                if what_to_display.ne(&self.report_type) {
+                if what_to_display.ne(&self.report_type) || self.report_sort.is_some() {
                     self.report_type = what_to_display;
+                    self.report_sort = None;
                     update_report_data(
                         self.runtime_data.borrow_mut(),
                         &self.info_traffic,
                         self.report_type,
+                        self.report_sort,
+                        &self.search,
                     );
                 }
             }
@@ -163,6 +196,17 @@ impl Application for Sniffer {
                     .spawn()
                     .unwrap();
             }
+            Message::ExportCsv => {
+                let csv_path = get_csv_path();
+                let info_traffic = lock_or_recover(&self.info_traffic, "exporting connections");
+                if let Err(err) =
+                    export_connections_csv(&csv_path, &info_traffic, self.anonymization_salt)
+                {
+                    warn!(path = %csv_path.display(), %err, "cannot export connections");
+                }
+            }
+            Message::PauseCapture => self.pause(),
+            Message::ResumeCapture => self.resume(),
             Message::OpenGithub => {
                 #[cfg(target_os = "windows")]
                 std::process::Command::new("explorer")
@@ -181,41 +225,29 @@ impl Application for Sniffer {
                     .unwrap();
             }
             Message::Start => {
-                let device = self.device.clone();
-                let (pcap_error, cap) = get_capture_result(&device);
-                self.pcap_error = pcap_error.clone();
-                *self.status_pair.0.lock().unwrap() = Status::Running;
-                let info_traffic_mutex = self.info_traffic.clone();
-                *info_traffic_mutex.lock().unwrap() = InfoTraffic::new();
-                self.runtime_data = Rc::new(RefCell::new(RunTimeData::new()));
-                self.traffic_chart =
-                    TrafficChart::new(self.runtime_data.clone(), self.style, self.language);
-
-                if pcap_error.is_none() {
-                    // no pcap error
-                    let current_capture_id = self.current_capture_id.clone();
-                    let filters = self.filters.clone();
-                    self.status_pair.1.notify_all();
-                    thread::Builder::new()
-                        .name("thread_parse_packets".to_string())
-                        .spawn(move || {
-                            parse_packets_loop(
-                                &current_capture_id,
-                                device.clone(),
-                                cap.unwrap(),
-                                &filters,
-                                &info_traffic_mutex,
-                            );
-                        })
-                        .unwrap();
-                }
+                self.start_capture();
+                let window_seconds = self.traffic_chart.window_seconds;
+                let log_scale = self.traffic_chart.log_scale;
+                let per_app_protocol = self.traffic_chart.per_app_protocol;
+                self.traffic_chart = TrafficChart::new(
+                    self.runtime_data.clone(),
+                    self.style,
+                    self.language,
+                    self.unit_system,
+                );
+                self.traffic_chart.window_seconds = window_seconds;
+                self.traffic_chart.log_scale = log_scale;
+                self.traffic_chart.per_app_protocol = per_app_protocol;
             }
             Message::Reset => {
-                *self.status_pair.0.lock().unwrap() = Status::Init;
+                *lock_or_recover(&self.status_pair.0, "resetting capture") = Status::Init;
                 self.running_page = RunningPage::Overview;
-                *self.current_capture_id.lock().unwrap() += 1; //change capture id to kill previous capture and to rewrite output file
+                self.capture_stop_flag.store(true, Ordering::Relaxed); // stop the threads parsing packets
+                *lock_or_recover(&self.current_capture_id, "resetting capture") += 1; //change capture id to rewrite output file
                 self.pcap_error = None;
                 self.report_type = ReportType::MostRecent;
+                self.report_sort = None;
+                self.search = String::new();
                 return self.update(Message::HideModal(false));
             }
             Message::Style(style) => {
@@ -228,28 +260,24 @@ impl Application for Sniffer {
                 }
                 self.waiting = ".".repeat(self.waiting.len() + 1);
             }
-            Message::SaveConnection(index) => {
-                let mut info_traffic = self.info_traffic.lock().unwrap();
-                info_traffic.favorite_connections.insert(index);
-                let key_val = info_traffic.map.get_index_mut(index).unwrap();
-                key_val.1.is_favorite = true;
-                drop(info_traffic);
+            Message::SaveConnection(id) => {
+                self.set_favorite(id, true);
                 update_report_data(
                     self.runtime_data.borrow_mut(),
                     &self.info_traffic,
                     self.report_type,
+                    self.report_sort,
+                    &self.search,
                 );
             }
-            Message::UnSaveConnection(index) => {
-                let mut info_traffic = self.info_traffic.lock().unwrap();
-                info_traffic.favorite_connections.remove(&index);
-                let key_val = info_traffic.map.get_index_mut(index).unwrap();
-                key_val.1.is_favorite = false;
-                drop(info_traffic);
+            Message::UnSaveConnection(id) => {
+                self.set_favorite(id, false);
                 update_report_data(
                     self.runtime_data.borrow_mut(),
                     &self.info_traffic,
                     self.report_type,
+                    self.report_sort,
+                    &self.search,
                 );
             }
             Message::ShowModal(overlay) => {
@@ -263,8 +291,10 @@ impl Application for Sniffer {
                     self.last_opened_setting = last_opened.unwrap();
                     let store = ConfigSettings {
                         style: self.style,
-                        notifications: self.notifications,
+                        notifications: self.notifications.clone(),
                         language: self.language,
+                        resolve_hostnames: self.resolve_hostnames,
+                        unit_system: self.unit_system,
                     };
                     confy::store("sniffnet", "settings", store).unwrap_or(());
                 }
@@ -278,24 +308,36 @@ impl Application for Sniffer {
             }
             Message::UpdatePacketsNotification(value, emit_sound) => {
                 if emit_sound {
-                    play(value.sound, self.notifications.volume);
+                    play(&value.sound, self.notifications.volume);
                 }
                 self.notifications.packets_notification = value;
             }
             Message::UpdateBytesNotification(value, emit_sound) => {
                 if emit_sound {
-                    play(value.sound, self.notifications.volume);
+                    play(&value.sound, self.notifications.volume);
                 }
                 self.notifications.bytes_notification = value;
             }
+            Message::UpdateConnectionRateNotification(value, emit_sound) => {
+                if emit_sound {
+                    play(&value.sound, self.notifications.volume);
+                }
+                self.notifications.connection_rate_notification = value;
+            }
             Message::UpdateFavoriteNotification(value, emit_sound) => {
                 if emit_sound {
-                    play(value.sound, self.notifications.volume);
+                    play(&value.sound, self.notifications.volume);
                 }
                 self.notifications.favorite_notification = value;
             }
+            Message::UpdateNewCountryNotification(value, emit_sound) => {
+                if emit_sound {
+                    play(&value.sound, self.notifications.volume);
+                }
+                self.notifications.new_country_notification = value;
+            }
             Message::ChangeVolume(volume) => {
-                play(Sound::Pop, volume);
+                play(&Sound::Pop, volume);
                 self.notifications.volume = volume;
             }
             Message::ClearAllNotifications => {
@@ -303,6 +345,7 @@ impl Application for Sniffer {
                 return self.update(Message::HideModal(false));
             }
             Message::Exit => {
+                self.save_on_exit();
                 return window::close();
             }
         }
@@ -310,7 +353,7 @@ impl Application for Sniffer {
     }
 
     fn view(&self) -> Element<Message> {
-        let status = *self.status_pair.0.lock().unwrap();
+        let status = *lock_or_recover(&self.status_pair.0, "rendering view");
         let style = self.style;
         let font = get_font(style);
 
@@ -319,7 +362,7 @@ impl Application for Sniffer {
             Status::Running => header(
                 style,
                 true,
-                self.info_traffic.lock().unwrap().all_packets,
+                lock_or_recover(&self.info_traffic, "rendering header").all_packets,
                 self.language,
                 self.last_opened_setting,
             ),
@@ -354,25 +397,26 @@ impl Application for Sniffer {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        let quit_keys_subscription = iced_native::subscription::events_with(|event, _| {
-            if let iced_native::Event::Keyboard(iced_native::keyboard::Event::KeyPressed {
-                key_code: iced_native::keyboard::KeyCode::Q,
-                modifiers: iced_native::keyboard::Modifiers::CTRL,
-            }) = event
-            {
-                Some(Message::Exit)
-            } else {
-                None
-            }
-        });
-        let time_subscription = match *self.status_pair.0.lock().unwrap() {
-            Status::Running => {
-                iced::time::every(Duration::from_millis(PERIOD_RUNNING)).map(|_| Message::TickRun)
-            }
-            Status::Init => {
-                iced::time::every(Duration::from_millis(PERIOD_INIT)).map(|_| Message::TickInit)
-            }
-        };
+        let quit_keys_subscription =
+            iced_native::subscription::events_with(|event, _| match event {
+                iced_native::Event::Keyboard(iced_native::keyboard::Event::KeyPressed {
+                    key_code: iced_native::keyboard::KeyCode::Q,
+                    modifiers: iced_native::keyboard::Modifiers::CTRL,
+                })
+                | iced_native::Event::Window(iced_native::window::Event::CloseRequested) => {
+                    Some(Message::Exit)
+                }
+                _ => None,
+            });
+        let time_subscription =
+            match *lock_or_recover(&self.status_pair.0, "computing subscriptions") {
+                Status::Running => {
+                    iced::time::every(self.refresh_interval).map(|_| Message::TickRun)
+                }
+                Status::Init => {
+                    iced::time::every(Duration::from_millis(PERIOD_INIT)).map(|_| Message::TickInit)
+                }
+            };
         Subscription::batch([quit_keys_subscription, time_subscription])
     }
 }
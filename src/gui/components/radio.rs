@@ -1,13 +1,18 @@
 use crate::enums::element_type::ElementType;
 use crate::enums::message::Message;
+use crate::enums::network_scope::NetworkScope;
 use crate::enums::sound::Sound;
-use crate::structs::notifications::{BytesNotification, FavoriteNotification, PacketsNotification};
+use crate::enums::threshold_mode::ThresholdMode;
+use crate::structs::notifications::{
+    BytesNotification, FavoriteNotification, NewCountryNotification, PacketsNotification,
+};
 use crate::structs::style_tuple::StyleTuple;
 use crate::utility::countries::get_flag;
 use crate::utility::style_constants::FONT_SIZE_SUBTITLE;
 use crate::utility::translations::{
-    ip_version_translation, relevant_connections_translation, sound_translation,
-    traffic_rate_translation, transport_protocol_translation,
+    ip_version_translation, network_scope_translation, relevant_connections_translation,
+    sound_translation, threshold_per_translation, traffic_rate_translation,
+    transport_protocol_translation,
 };
 use crate::{ChartType, IpVersion, Language, ReportType, StyleType, TransProtocol};
 use iced::widget::{Column, Radio, Row, Text};
@@ -44,6 +49,36 @@ pub fn ip_version_radios(
     ret_val
 }
 
+pub fn network_scope_radios(
+    active: NetworkScope,
+    font: Font,
+    style: StyleType,
+    language: Language,
+) -> Column<'static, Message> {
+    let mut ret_val = Column::new().spacing(10).push(
+        network_scope_translation(language)
+            .font(font)
+            .size(FONT_SIZE_SUBTITLE),
+    );
+    for option in NetworkScope::ALL {
+        ret_val = ret_val.push(
+            Radio::new(
+                option,
+                option.get_radio_label(language),
+                Some(active),
+                Message::NetworkScopeSelection,
+            )
+            .font(font)
+            .size(15)
+            .style(<StyleTuple as Into<iced::theme::Radio>>::into(StyleTuple(
+                style,
+                ElementType::Standard,
+            ))),
+        );
+    }
+    ret_val
+}
+
 pub fn transport_protocol_radios(
     active: TransProtocol,
     font: Font,
@@ -116,17 +151,17 @@ pub fn sound_packets_threshold_radios(
     let mut ret_val = Row::new()
         .spacing(20)
         .push(Text::new(sound_translation(language)).font(font));
-    for option in Sound::ALL {
+    for option in Sound::selectable(&packets_notification.sound) {
         ret_val = ret_val.push(
             Radio::new(
-                option,
+                &option,
                 option.get_radio_label(language),
-                Some(packets_notification.sound),
+                Some(&packets_notification.sound),
                 |value| {
                     Message::UpdatePacketsNotification(
                         PacketsNotification {
-                            sound: value,
-                            ..packets_notification
+                            sound: value.clone(),
+                            ..packets_notification.clone()
                         },
                         value.ne(&Sound::None),
                     )
@@ -144,8 +179,10 @@ pub fn sound_packets_threshold_radios(
     ret_val
 }
 
+/// Sound radios of a bytes threshold notification, updated via the `message` constructor.
 pub fn sound_bytes_threshold_radios(
     bytes_notification: BytesNotification,
+    message: fn(BytesNotification, bool) -> Message,
     font: Font,
     style: StyleType,
     language: Language,
@@ -153,17 +190,17 @@ pub fn sound_bytes_threshold_radios(
     let mut ret_val = Row::new()
         .spacing(20)
         .push(Text::new(sound_translation(language)).font(font));
-    for option in Sound::ALL {
+    for option in Sound::selectable(&bytes_notification.sound) {
         ret_val = ret_val.push(
             Radio::new(
-                option,
+                &option,
                 option.get_radio_label(language),
-                Some(bytes_notification.sound),
+                Some(&bytes_notification.sound),
                 |value| {
-                    Message::UpdateBytesNotification(
+                    message(
                         BytesNotification {
-                            sound: value,
-                            ..bytes_notification
+                            sound: value.clone(),
+                            ..bytes_notification.clone()
                         },
                         value.ne(&Sound::None),
                     )
@@ -181,6 +218,44 @@ pub fn sound_bytes_threshold_radios(
     ret_val
 }
 
+pub fn threshold_mode_radios(
+    bytes_notification: BytesNotification,
+    message: fn(BytesNotification, bool) -> Message,
+    font: Font,
+    style: StyleType,
+    language: Language,
+) -> Row<'static, Message> {
+    let mut ret_val = Row::new()
+        .spacing(20)
+        .push(Text::new(threshold_per_translation(language)).font(font));
+    for option in ThresholdMode::ALL {
+        ret_val = ret_val.push(
+            Radio::new(
+                option,
+                option.get_radio_label(language),
+                Some(bytes_notification.threshold_mode),
+                |value| {
+                    message(
+                        BytesNotification {
+                            threshold_mode: value,
+                            ..bytes_notification.clone()
+                        },
+                        false,
+                    )
+                },
+            )
+            .spacing(7)
+            .font(font)
+            .size(15)
+            .style(<StyleTuple as Into<iced::theme::Radio>>::into(StyleTuple(
+                style,
+                ElementType::Standard,
+            ))),
+        );
+    }
+    ret_val
+}
+
 pub fn sound_favorite_radios(
     favorite_notification: FavoriteNotification,
     font: Font,
@@ -190,17 +265,54 @@ pub fn sound_favorite_radios(
     let mut ret_val = Row::new()
         .spacing(20)
         .push(Text::new(sound_translation(language)).font(font));
-    for option in Sound::ALL {
+    for option in Sound::selectable(&favorite_notification.sound) {
         ret_val = ret_val.push(
             Radio::new(
-                option,
+                &option,
                 option.get_radio_label(language),
-                Some(favorite_notification.sound),
+                Some(&favorite_notification.sound),
                 |value| {
                     Message::UpdateFavoriteNotification(
                         FavoriteNotification {
-                            sound: value,
-                            ..favorite_notification
+                            sound: value.clone(),
+                            ..favorite_notification.clone()
+                        },
+                        value.ne(&Sound::None),
+                    )
+                },
+            )
+            .spacing(7)
+            .font(font)
+            .size(15)
+            .style(<StyleTuple as Into<iced::theme::Radio>>::into(StyleTuple(
+                style,
+                ElementType::Standard,
+            ))),
+        );
+    }
+    ret_val
+}
+
+pub fn sound_new_country_radios(
+    new_country_notification: NewCountryNotification,
+    font: Font,
+    style: StyleType,
+    language: Language,
+) -> Row<'static, Message> {
+    let mut ret_val = Row::new()
+        .spacing(20)
+        .push(Text::new(sound_translation(language)).font(font));
+    for option in Sound::selectable(&new_country_notification.sound) {
+        ret_val = ret_val.push(
+            Radio::new(
+                &option,
+                option.get_radio_label(language),
+                Some(&new_country_notification.sound),
+                |value| {
+                    Message::UpdateNewCountryNotification(
+                        NewCountryNotification {
+                            sound: value.clone(),
+                            ..new_country_notification.clone()
                         },
                         value.ne(&Sound::None),
                     )
@@ -13,7 +13,9 @@ use pcap::Device;
 
 use crate::enums::element_type::ElementType;
 use crate::enums::message::Message;
-use crate::gui::components::radio::{ip_version_radios, transport_protocol_radios};
+use crate::gui::components::radio::{
+    ip_version_radios, network_scope_radios, transport_protocol_radios,
+};
 use crate::structs::sniffer::Sniffer;
 use crate::structs::style_tuple::StyleTuple;
 use crate::utility::style_constants::{
@@ -33,10 +35,13 @@ pub fn initial_page(sniffer: &Sniffer) -> Container<Message> {
 
     let ip_active = sniffer.filters.ip;
     let col_ip_radio = ip_version_radios(ip_active, font, sniffer.style, sniffer.language);
+    let col_scope_radio =
+        network_scope_radios(sniffer.filters.scope, font, sniffer.style, sniffer.language);
     let col_ip = Column::new()
-        .spacing(10)
+        .spacing(30)
         .width(FillPortion(1))
-        .push(col_ip_radio);
+        .push(col_ip_radio)
+        .push(col_scope_radio);
 
     let transport_active = sniffer.filters.transport;
     let col_transport_radio =
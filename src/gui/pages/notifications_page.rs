@@ -1,22 +1,29 @@
 use crate::enums::element_type::ElementType;
 use crate::enums::logged_notification::{
-    BytesThresholdExceeded, FavoriteTransmitted, LoggedNotification, PacketsThresholdExceeded,
+    BytesThresholdExceeded, CaptureLag, ConnectionRateExceeded, FavoriteTransmitted,
+    LoggedNotification, NewCountry, PacketsThresholdExceeded,
 };
 use crate::enums::message::Message;
 use crate::enums::my_overlay::MyOverlay;
+use crate::enums::notification_direction::NotificationDirection;
+use crate::enums::threshold_mode::ThresholdMode;
 use crate::enums::traffic_type::TrafficType;
 use crate::gui::components::header::get_button_settings;
 use crate::gui::components::tab::get_pages_tabs;
 use crate::structs::style_tuple::StyleTuple;
 use crate::utility::countries::get_flag;
-use crate::utility::get_formatted_strings::get_formatted_bytes_string;
+use crate::utility::get_formatted_strings::{
+    get_formatted_bytes_string, get_formatted_lag, get_formatted_time,
+};
 use crate::utility::style_constants::{get_font, FONT_SIZE_FOOTER, HEIGHT_BODY, ICONS};
 use crate::utility::translations::{
     application_protocol_translation, bytes_exceeded_translation, bytes_exceeded_value_translation,
-    clear_all_translation, favorite_transmitted_translation, incoming_translation,
-    no_notifications_received_translation, no_notifications_set_translation,
-    only_last_30_translation, outgoing_translation, packets_exceeded_translation,
-    packets_exceeded_value_translation, per_second_translation, threshold_translation,
+    capture_lag_hint_translation, capture_lag_translation, capture_lag_value_translation,
+    clear_all_translation, connection_rate_exceeded_translation, favorite_transmitted_translation,
+    incoming_translation, new_country_translation, no_notifications_received_translation,
+    no_notifications_set_translation, only_last_n_translation, outgoing_translation,
+    packets_exceeded_translation, packets_exceeded_value_translation, per_interval_translation,
+    per_second_translation, threshold_translation,
 };
 use crate::{Language, RunningPage, Sniffer, StyleType};
 use iced::alignment::{Horizontal, Vertical};
@@ -28,7 +35,7 @@ use iced_native::widget::{button, vertical_space};
 
 /// Computes the body of gui notifications page
 pub fn notifications_page(sniffer: &Sniffer) -> Container<Message> {
-    let notifications = sniffer.notifications;
+    let notifications = &sniffer.notifications;
     let font = get_font(sniffer.style);
 
     let mut body = Column::new()
@@ -64,7 +71,12 @@ pub fn notifications_page(sniffer: &Sniffer) -> Container<Message> {
 
     if notifications.packets_notification.threshold.is_none()
         && notifications.bytes_notification.threshold.is_none()
+        && notifications
+            .connection_rate_notification
+            .threshold
+            .is_none()
         && !notifications.favorite_notification.notify_on_favorite
+        && !notifications.new_country_notification.notify_on_new_country
         && sniffer
             .runtime_data
             .borrow()
@@ -135,16 +147,39 @@ pub fn notifications_page(sniffer: &Sniffer) -> Container<Message> {
                         sniffer.style,
                     )
                 }
+                LoggedNotification::ConnectionRateExceeded(connection_rate_exceeded) => {
+                    connection_rate_notification_log(
+                        connection_rate_exceeded.clone(),
+                        sniffer.language,
+                        sniffer.style,
+                    )
+                }
+                LoggedNotification::NewCountry(new_country) => new_country_notification_log(
+                    new_country.clone(),
+                    sniffer.language,
+                    sniffer.style,
+                ),
+                LoggedNotification::CaptureLag(capture_lag) => capture_lag_notification_log(
+                    capture_lag.clone(),
+                    sniffer.language,
+                    sniffer.style,
+                ),
             });
         }
         let body_row = Row::new()
             .width(Length::Fill)
             .push(
                 Container::new(
-                    if sniffer.runtime_data.borrow().logged_notifications.len() < 30 {
+                    if sniffer.runtime_data.borrow().logged_notifications.len()
+                        < sniffer.notifications.max_logged_notifications
+                    {
                         Text::new("")
                     } else {
-                        Text::new(only_last_30_translation(sniffer.language)).font(font)
+                        Text::new(only_last_n_translation(
+                            sniffer.language,
+                            sniffer.notifications.max_logged_notifications,
+                        ))
+                        .font(font)
                     },
                 )
                 .padding(10)
@@ -184,6 +219,7 @@ fn packets_notification_log(
     let mut threshold_str = threshold_translation(language);
     threshold_str.push_str(&logged_notification.threshold.to_string());
     threshold_str.push_str(&format!(" {}", per_second_translation(language)));
+    threshold_str.push_str(&get_direction_str(logged_notification.direction, language));
     let mut incoming_str = " - ".to_string();
     incoming_str.push_str(incoming_translation(language));
     incoming_str.push_str(": ");
@@ -192,46 +228,47 @@ fn packets_notification_log(
     outgoing_str.push_str(outgoing_translation(language));
     outgoing_str.push_str(": ");
     outgoing_str.push_str(&logged_notification.outgoing.to_string());
-    let content = Row::new()
-        .spacing(30)
-        .push(
-            Tooltip::new(
-                Text::new("e").font(ICONS).size(80),
-                packets_exceeded_translation(language),
-                Position::Left,
-            )
-            .gap(5)
-            .font(font)
-            .style(<StyleTuple as Into<iced::theme::Container>>::into(
-                StyleTuple(style, ElementType::Tooltip),
-            )),
-        )
-        .push(
-            Column::new()
-                .spacing(7)
-                .width(Length::Fixed(250.0))
-                .push(
-                    Row::new()
-                        .spacing(5)
-                        .push(Text::new("9").font(ICONS))
-                        .push(Text::new(logged_notification.timestamp).font(font)),
+    let content =
+        Row::new()
+            .spacing(30)
+            .push(
+                Tooltip::new(
+                    Text::new("e").font(ICONS).size(80),
+                    packets_exceeded_translation(language),
+                    Position::Left,
                 )
-                .push(Text::new(packets_exceeded_translation(language)).font(font))
-                .push(Text::new(threshold_str).size(FONT_SIZE_FOOTER).font(font)),
-        )
-        .push(
-            Column::new()
-                .spacing(7)
-                .push(
-                    Text::new(packets_exceeded_value_translation(
-                        language,
-                        logged_notification.incoming + logged_notification.outgoing,
+                .gap(5)
+                .font(font)
+                .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                    StyleTuple(style, ElementType::Tooltip),
+                )),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fixed(250.0))
+                    .push(Row::new().spacing(5).push(Text::new("9").font(ICONS)).push(
+                        Text::new(get_formatted_time(logged_notification.timestamp)).font(font),
                     ))
-                    .font(font),
-                )
-                .push(Text::new(incoming_str).font(font))
-                .push(Text::new(outgoing_str).font(font)),
-        );
+                    .push(Text::new(packets_exceeded_translation(language)).font(font))
+                    .push(Text::new(threshold_str).size(FONT_SIZE_FOOTER).font(font)),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .push(
+                        Text::new(packets_exceeded_value_translation(
+                            language,
+                            logged_notification.direction.compared_value(
+                                logged_notification.incoming,
+                                logged_notification.outgoing,
+                            ),
+                        ))
+                        .font(font),
+                    )
+                    .push(Text::new(incoming_str).font(font))
+                    .push(Text::new(outgoing_str).font(font)),
+            );
     Container::new(content)
         .height(Length::Fixed(120.0))
         .width(Length::Fixed(800.0))
@@ -254,63 +291,76 @@ fn bytes_notification_log(
     );
     threshold_str.push_str(&format!(
         " {}",
-        logged_notification.byte_multiple.get_char()
+        logged_notification
+            .byte_multiple
+            .get_prefix(logged_notification.unit_system)
     ));
-    threshold_str.push_str(&format!(" {}", per_second_translation(language)));
+    threshold_str.push_str(&format!(
+        " {}",
+        match logged_notification.threshold_mode {
+            ThresholdMode::PerSecond => per_second_translation(language),
+            ThresholdMode::PerInterval => per_interval_translation(language),
+        }
+    ));
+    threshold_str.push_str(&get_direction_str(logged_notification.direction, language));
     let mut incoming_str = " - ".to_string();
     incoming_str.push_str(incoming_translation(language));
     incoming_str.push_str(": ");
-    incoming_str.push_str(&get_formatted_bytes_string(u128::from(
-        logged_notification.incoming,
-    )));
+    incoming_str.push_str(&get_formatted_bytes_string(
+        u128::from(logged_notification.incoming),
+        logged_notification.unit_system,
+    ));
     let mut outgoing_str = " - ".to_string();
     outgoing_str.push_str(outgoing_translation(language));
     outgoing_str.push_str(": ");
-    outgoing_str.push_str(&get_formatted_bytes_string(u128::from(
-        logged_notification.outgoing,
-    )));
-    let content = Row::new()
-        .spacing(30)
-        .push(
-            Tooltip::new(
-                Text::new("f").font(ICONS).size(80),
-                bytes_exceeded_translation(language),
-                Position::Left,
-            )
-            .gap(5)
-            .font(font)
-            .style(<StyleTuple as Into<iced::theme::Container>>::into(
-                StyleTuple(style, ElementType::Tooltip),
-            )),
-        )
-        .push(
-            Column::new()
-                .spacing(7)
-                .width(Length::Fixed(250.0))
-                .push(
-                    Row::new()
-                        .spacing(5)
-                        .push(Text::new("9").font(ICONS))
-                        .push(Text::new(logged_notification.timestamp).font(font)),
+    outgoing_str.push_str(&get_formatted_bytes_string(
+        u128::from(logged_notification.outgoing),
+        logged_notification.unit_system,
+    ));
+    let content =
+        Row::new()
+            .spacing(30)
+            .push(
+                Tooltip::new(
+                    Text::new("f").font(ICONS).size(80),
+                    bytes_exceeded_translation(language),
+                    Position::Left,
                 )
-                .push(Text::new(bytes_exceeded_translation(language)).font(font))
-                .push(Text::new(threshold_str).size(FONT_SIZE_FOOTER).font(font)),
-        )
-        .push(
-            Column::new()
-                .spacing(7)
-                .push(
-                    Text::new(bytes_exceeded_value_translation(
-                        language,
-                        &get_formatted_bytes_string(u128::from(
-                            logged_notification.incoming + logged_notification.outgoing,
-                        )),
+                .gap(5)
+                .font(font)
+                .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                    StyleTuple(style, ElementType::Tooltip),
+                )),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fixed(250.0))
+                    .push(Row::new().spacing(5).push(Text::new("9").font(ICONS)).push(
+                        Text::new(get_formatted_time(logged_notification.timestamp)).font(font),
                     ))
-                    .font(font),
-                )
-                .push(Text::new(incoming_str).font(font))
-                .push(Text::new(outgoing_str).font(font)),
-        );
+                    .push(Text::new(bytes_exceeded_translation(language)).font(font))
+                    .push(Text::new(threshold_str).size(FONT_SIZE_FOOTER).font(font)),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .push(
+                        Text::new(bytes_exceeded_value_translation(
+                            language,
+                            &get_formatted_bytes_string(
+                                u128::from(logged_notification.direction.compared_value(
+                                    logged_notification.incoming,
+                                    logged_notification.outgoing,
+                                )),
+                                logged_notification.unit_system,
+                            ),
+                        ))
+                        .font(font),
+                    )
+                    .push(Text::new(incoming_str).font(font))
+                    .push(Text::new(outgoing_str).font(font)),
+            );
     Container::new(content)
         .height(Length::Fixed(120.0))
         .width(Length::Fixed(800.0))
@@ -350,40 +400,115 @@ fn favorite_notification_log(
             row_src_flag = row_src_flag.push(get_flag(&country));
         }
     }
-    let content = Row::new()
-        .spacing(30)
-        .push(
-            Tooltip::new(
-                Text::new("g").font(ICONS).size(80),
-                favorite_transmitted_translation(language),
-                Position::Left,
+    let content =
+        Row::new()
+            .spacing(30)
+            .push(
+                Tooltip::new(
+                    Text::new("g").font(ICONS).size(80),
+                    favorite_transmitted_translation(language),
+                    Position::Left,
+                )
+                .gap(5)
+                .font(font)
+                .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                    StyleTuple(style, ElementType::Tooltip),
+                )),
             )
-            .gap(5)
-            .font(font)
-            .style(<StyleTuple as Into<iced::theme::Container>>::into(
-                StyleTuple(style, ElementType::Tooltip),
-            )),
-        )
-        .push(
-            Column::new()
-                .width(Length::Fixed(250.0))
-                .spacing(7)
-                .push(
-                    Row::new()
-                        .spacing(5)
-                        .push(Text::new("9").font(ICONS))
-                        .push(Text::new(logged_notification.timestamp).font(font)),
+            .push(
+                Column::new()
+                    .width(Length::Fixed(250.0))
+                    .spacing(7)
+                    .push(Row::new().spacing(5).push(Text::new("9").font(ICONS)).push(
+                        Text::new(get_formatted_time(logged_notification.timestamp)).font(font),
+                    ))
+                    .push(Text::new(favorite_transmitted_translation(language)).font(font)), // .push(Text::new(threshold_str).font(font)),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fill)
+                    .push(row_src_flag)
+                    .push(row_dst_flag)
+                    .push(Text::new(app_str).font(font)),
+            );
+    Container::new(content)
+        .height(Length::Fixed(120.0))
+        .width(Length::Fixed(800.0))
+        .padding(15)
+        .style(<StyleTuple as Into<iced::theme::Container>>::into(
+            StyleTuple(style, ElementType::BorderedRound),
+        ))
+}
+
+fn connection_rate_notification_log(
+    logged_notification: ConnectionRateExceeded,
+    language: Language,
+    style: StyleType,
+) -> Container<'static, Message> {
+    let font = get_font(style);
+    let mut threshold_str = threshold_translation(language);
+    threshold_str.push_str(
+        &(logged_notification.threshold / logged_notification.byte_multiple.get_multiplier())
+            .to_string(),
+    );
+    threshold_str.push_str(&format!(
+        " {}",
+        logged_notification
+            .byte_multiple
+            .get_prefix(logged_notification.unit_system)
+    ));
+    threshold_str.push_str(&format!(" {}", per_second_translation(language)));
+    let src_str = format!(
+        "Src: {}:{}",
+        logged_notification.connection.0.address1, logged_notification.connection.0.port1
+    );
+    let dst_str = format!(
+        "Dst: {}:{}",
+        logged_notification.connection.0.address2, logged_notification.connection.0.port2
+    );
+    let content =
+        Row::new()
+            .spacing(30)
+            .push(
+                Tooltip::new(
+                    Text::new("f").font(ICONS).size(80),
+                    connection_rate_exceeded_translation(language),
+                    Position::Left,
                 )
-                .push(Text::new(favorite_transmitted_translation(language)).font(font)), // .push(Text::new(threshold_str).font(font)),
-        )
-        .push(
-            Column::new()
-                .spacing(7)
-                .width(Length::Fill)
-                .push(row_src_flag)
-                .push(row_dst_flag)
-                .push(Text::new(app_str).font(font)),
-        );
+                .gap(5)
+                .font(font)
+                .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                    StyleTuple(style, ElementType::Tooltip),
+                )),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fixed(250.0))
+                    .push(Row::new().spacing(5).push(Text::new("9").font(ICONS)).push(
+                        Text::new(get_formatted_time(logged_notification.timestamp)).font(font),
+                    ))
+                    .push(Text::new(connection_rate_exceeded_translation(language)).font(font))
+                    .push(Text::new(threshold_str).size(FONT_SIZE_FOOTER).font(font)),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new(bytes_exceeded_value_translation(
+                            language,
+                            &get_formatted_bytes_string(
+                                u128::from(logged_notification.rate),
+                                logged_notification.unit_system,
+                            ),
+                        ))
+                        .font(font),
+                    )
+                    .push(Text::new(src_str).font(font))
+                    .push(Text::new(dst_str).font(font)),
+            );
     Container::new(content)
         .height(Length::Fixed(120.0))
         .width(Length::Fixed(800.0))
@@ -393,6 +518,129 @@ fn favorite_notification_log(
         ))
 }
 
+fn new_country_notification_log(
+    logged_notification: NewCountry,
+    language: Language,
+    style: StyleType,
+) -> Container<'static, Message> {
+    let font = get_font(style);
+    let src_str = format!("Src: {}", logged_notification.connection.0.address1);
+    let dst_str = format!("Dst: {}", logged_notification.connection.0.address2);
+    let mut app_str = application_protocol_translation(language).to_string();
+    app_str.push_str(&format!(
+        ": {:?}",
+        logged_notification.connection.1.app_protocol
+    ));
+    let content =
+        Row::new()
+            .spacing(30)
+            .push(
+                Tooltip::new(
+                    get_flag(&logged_notification.country),
+                    new_country_translation(language),
+                    Position::Left,
+                )
+                .gap(5)
+                .font(font)
+                .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                    StyleTuple(style, ElementType::Tooltip),
+                )),
+            )
+            .push(
+                Column::new()
+                    .width(Length::Fixed(250.0))
+                    .spacing(7)
+                    .push(Row::new().spacing(5).push(Text::new("9").font(ICONS)).push(
+                        Text::new(get_formatted_time(logged_notification.timestamp)).font(font),
+                    ))
+                    .push(Text::new(new_country_translation(language)).font(font))
+                    .push(Text::new(logged_notification.country).font(font)),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fill)
+                    .push(Text::new(src_str).font(font))
+                    .push(Text::new(dst_str).font(font))
+                    .push(Text::new(app_str).font(font)),
+            );
+    Container::new(content)
+        .height(Length::Fixed(120.0))
+        .width(Length::Fixed(800.0))
+        .padding(15)
+        .style(<StyleTuple as Into<iced::theme::Container>>::into(
+            StyleTuple(style, ElementType::BorderedRound),
+        ))
+}
+
+fn capture_lag_notification_log(
+    logged_notification: CaptureLag,
+    language: Language,
+    style: StyleType,
+) -> Container<'static, Message> {
+    let font = get_font(style);
+    let mut threshold_str = threshold_translation(language);
+    threshold_str.push_str(&format!("{} s", logged_notification.threshold_seconds));
+    let content =
+        Row::new()
+            .spacing(30)
+            .push(
+                Tooltip::new(
+                    Text::new("9").font(ICONS).size(80),
+                    capture_lag_translation(language),
+                    Position::Left,
+                )
+                .gap(5)
+                .font(font)
+                .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                    StyleTuple(style, ElementType::Tooltip),
+                )),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fixed(250.0))
+                    .push(Row::new().spacing(5).push(Text::new("9").font(ICONS)).push(
+                        Text::new(get_formatted_time(logged_notification.timestamp)).font(font),
+                    ))
+                    .push(Text::new(capture_lag_translation(language)).font(font))
+                    .push(Text::new(threshold_str).size(FONT_SIZE_FOOTER).font(font)),
+            )
+            .push(
+                Column::new()
+                    .spacing(7)
+                    .width(Length::Fill)
+                    .push(
+                        Text::new(capture_lag_value_translation(
+                            language,
+                            &get_formatted_lag(logged_notification.lag_ms),
+                        ))
+                        .font(font),
+                    )
+                    .push(
+                        Text::new(capture_lag_hint_translation(language))
+                            .size(FONT_SIZE_FOOTER)
+                            .font(font),
+                    ),
+            );
+    Container::new(content)
+        .height(Length::Fixed(120.0))
+        .width(Length::Fixed(800.0))
+        .padding(15)
+        .style(<StyleTuple as Into<iced::theme::Container>>::into(
+            StyleTuple(style, ElementType::BorderedRound),
+        ))
+}
+
+/// Returns the qualifier of the traffic compared with a threshold (empty if both the directions are compared).
+fn get_direction_str(direction: NotificationDirection, language: Language) -> String {
+    match direction {
+        NotificationDirection::Both => String::new(),
+        NotificationDirection::Inbound => format!(" ({})", incoming_translation(language)),
+        NotificationDirection::Outbound => format!(" ({})", outgoing_translation(language)),
+    }
+}
+
 pub fn get_button_clear_all(style: StyleType, language: Language) -> Tooltip<'static, Message> {
     let content = button(
         Text::new('h'.to_string())
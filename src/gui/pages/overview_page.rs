@@ -5,31 +5,42 @@
 
 use iced::alignment::{Horizontal, Vertical};
 use iced::widget::scrollable::Properties;
-use iced::widget::{button, vertical_space, Column, Container, Row, Scrollable, Text, Tooltip};
+use iced::widget::{
+    button, vertical_space, Checkbox, Column, Container, Row, Scrollable, Text, TextInput, Tooltip,
+};
 use iced::Length::FillPortion;
-use iced::{alignment, Alignment, Length};
+use iced::{alignment, Alignment, Font, Length};
 use iced_native::widget::tooltip::Position;
 use thousands::Separable;
 //use dns_lookup::lookup_addr;
 
 use crate::enums::element_type::ElementType;
 use crate::enums::message::Message;
+use crate::enums::sort_column::SortColumn;
+use crate::enums::tcp_state::TcpState;
 use crate::gui::components::radio::{chart_radios, report_radios};
 use crate::gui::components::tab::get_pages_tabs;
 use crate::structs::sniffer::Sniffer;
 use crate::structs::style_tuple::StyleTuple;
-use crate::utility::countries::{get_flag, FLAGS_WIDTH};
+use crate::utility::countries::{country_name, get_flag, FLAGS_WIDTH};
 use crate::utility::get_formatted_strings::{
     get_active_filters_string, get_active_filters_string_nobr, get_app_count_string,
-    get_connection_color, get_formatted_bytes_string, get_percentage_string, get_report_path,
+    get_connection_color, get_country_breakdown_string, get_csv_path, get_discovery_traffic_string,
+    get_formatted_bytes_string, get_per_interface_string, get_percentage_string, get_report_path,
+    get_size_histogram_string, get_top_talkers_string, get_traffic_summary_string,
 };
 use crate::utility::style_constants::{get_font, HEIGHT_BODY, ICONS, INCONSOLATA_BOLD};
 use crate::utility::translations::{
-    error_translation, filtered_application_translation, filtered_bytes_translation,
-    filtered_packets_translation, no_addresses_translation, no_favorites_translation,
-    open_report_translation, some_observed_translation, waiting_translation,
+    aggregate_broadcast_multicast_translation, aggregated_traffic_translation,
+    capture_stalled_translation, country_breakdown_translation, discovery_traffic_translation,
+    dropped_packets_translation, error_translation, export_csv_translation,
+    filtered_application_translation, filtered_bytes_translation, filtered_packets_translation,
+    gateway_translation, log_scale_translation, no_addresses_translation, no_favorites_translation,
+    open_report_translation, packet_sizes_translation, pause_capture_translation,
+    per_app_protocol_translation, per_interface_translation, resume_capture_translation,
+    search_translation, some_observed_translation, top_talkers_translation, waiting_translation,
 };
-use crate::{AppProtocol, ReportType, RunningPage};
+use crate::{AppProtocol, ChartType, ReportType, RunningPage};
 
 /// Computes the body of gui run page
 pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
@@ -70,7 +81,7 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
         let filtered_bytes = sniffer.runtime_data.borrow().tot_sent_bytes
             + sniffer.runtime_data.borrow().tot_received_bytes;
         let app_protocols = sniffer.runtime_data.borrow().app_protocols.clone();
-        let filtered_bytes_string = get_formatted_bytes_string(filtered_bytes);
+        let filtered_bytes_string = get_formatted_bytes_string(filtered_bytes, sniffer.unit_system);
 
         match (observed, filtered) {
             (0, 0) => {
@@ -129,8 +140,31 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                 tab_and_body = tab_and_body.push(tabs);
 
                 let active_radio_chart = sniffer.traffic_chart.chart_type;
-                let row_radio_chart =
-                    chart_radios(active_radio_chart, font, sniffer.style, sniffer.language);
+                let chart_checkbox = |label, is_checked, message: fn(bool) -> Message| {
+                    Checkbox::new(label, is_checked, message)
+                        .spacing(7)
+                        .size(15)
+                        .font(font)
+                        .style(<StyleTuple as Into<iced::theme::Checkbox>>::into(
+                            StyleTuple(sniffer.style, ElementType::Standard),
+                        ))
+                };
+                let mut row_radio_chart =
+                    chart_radios(active_radio_chart, font, sniffer.style, sniffer.language).push(
+                        chart_checkbox(
+                            log_scale_translation(sniffer.language),
+                            sniffer.traffic_chart.log_scale,
+                            Message::ChartLogScale,
+                        ),
+                    );
+                // the packets of each application protocol are counted, but not their bytes
+                if active_radio_chart == ChartType::Packets {
+                    row_radio_chart = row_radio_chart.push(chart_checkbox(
+                        per_app_protocol_translation(sniffer.language),
+                        sniffer.traffic_chart.per_app_protocol,
+                        Message::ChartPerAppProtocol,
+                    ));
+                }
                 let col_chart = Container::new(
                     Column::new()
                         .push(row_radio_chart)
@@ -172,14 +206,120 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                         )
                         .font(font),
                     );
+                let dropped = sniffer.runtime_data.borrow().dropped_packets;
+                let if_dropped = sniffer.runtime_data.borrow().if_dropped_packets;
+                if dropped + if_dropped > 0 {
+                    col_packets = col_packets.push(Text::new(" ")).push(
+                        dropped_packets_translation(
+                            sniffer.language,
+                            &dropped.separate_with_spaces(),
+                            &if_dropped.separate_with_spaces(),
+                        )
+                        .font(font),
+                    );
+                }
+                if let Some(capture_error) = &sniffer.runtime_data.borrow().capture_error {
+                    col_packets = col_packets.push(Text::new(" ")).push(
+                        capture_stalled_translation(sniffer.language, capture_error).font(font),
+                    );
+                }
+                col_packets = col_packets.push(Text::new(" ")).push(
+                    Checkbox::new(
+                        aggregate_broadcast_multicast_translation(sniffer.language),
+                        sniffer.aggregate_broadcast_multicast,
+                        Message::AggregateBroadcastMulticast,
+                    )
+                    .spacing(7)
+                    .size(15)
+                    .font(font)
+                    .style(<StyleTuple as Into<iced::theme::Checkbox>>::into(
+                        StyleTuple(sniffer.style, ElementType::Standard),
+                    )),
+                );
+                if sniffer.aggregate_broadcast_multicast {
+                    let chart_type = sniffer.traffic_chart.chart_type;
+                    col_packets = col_packets.push(
+                        aggregated_traffic_translation(
+                            sniffer.language,
+                            &get_traffic_summary_string(
+                                &sniffer.runtime_data.borrow().broadcast_summary,
+                                chart_type,
+                                sniffer.unit_system,
+                            ),
+                            &get_traffic_summary_string(
+                                &sniffer.runtime_data.borrow().multicast_summary,
+                                chart_type,
+                                sniffer.unit_system,
+                            ),
+                        )
+                        .font(font),
+                    );
+                }
+                let top_talkers = get_top_talkers_string(
+                    &sniffer.runtime_data.borrow().top_talkers,
+                    sniffer.traffic_chart.chart_type,
+                    sniffer.unit_system,
+                );
+                if !top_talkers.is_empty() {
+                    col_packets = col_packets
+                        .push(Text::new(" "))
+                        .push(top_talkers_translation(sniffer.language).font(font))
+                        .push(Text::new(top_talkers).font(font));
+                }
+                let country_breakdown = get_country_breakdown_string(
+                    &sniffer.runtime_data.borrow().country_breakdown,
+                    sniffer.traffic_chart.chart_type,
+                    sniffer.unit_system,
+                );
+                if !country_breakdown.is_empty() {
+                    col_packets = col_packets
+                        .push(Text::new(" "))
+                        .push(country_breakdown_translation(sniffer.language).font(font))
+                        .push(Text::new(country_breakdown).font(font));
+                }
+                let size_histogram =
+                    get_size_histogram_string(&sniffer.runtime_data.borrow().size_histogram);
+                if !size_histogram.is_empty() {
+                    col_packets = col_packets
+                        .push(Text::new(" "))
+                        .push(packet_sizes_translation(sniffer.language).font(font))
+                        .push(Text::new(size_histogram).font(font));
+                }
+                if !sniffer.runtime_data.borrow().discovery_traffic.is_empty() {
+                    let discovery_traffic = get_discovery_traffic_string(
+                        &sniffer.runtime_data.borrow().discovery_traffic,
+                        sniffer.language,
+                    );
+                    col_packets = col_packets
+                        .push(Text::new(" "))
+                        .push(discovery_traffic_translation(sniffer.language).font(font))
+                        .push(Text::new(discovery_traffic).font(font));
+                }
+                // the breakdown is meaningful only when sniffing on more than one adapter
+                if sniffer.runtime_data.borrow().per_interface.len() > 1 {
+                    let per_interface = get_per_interface_string(
+                        &sniffer.runtime_data.borrow().per_interface,
+                        sniffer.traffic_chart.chart_type,
+                        sniffer.unit_system,
+                        sniffer.language,
+                    );
+                    col_packets = col_packets
+                        .push(Text::new(" "))
+                        .push(per_interface_translation(sniffer.language).font(font))
+                        .push(Text::new(per_interface).font(font));
+                }
                 if sniffer.filters.application.eq(&AppProtocol::Other) {
                     col_packets = col_packets
                         .push(Text::new(" "))
                         .push(filtered_application_translation(sniffer.language).font(font))
                         .push(
                             Scrollable::new(
-                                Text::new(get_app_count_string(&app_protocols, filtered))
-                                    .font(font),
+                                Text::new(get_app_count_string(
+                                    &app_protocols,
+                                    filtered,
+                                    sniffer.unit_system,
+                                ))
+                                .font(font),
                             )
                             .style(<StyleTuple as Into<
                                 iced::theme::Scrollable,
@@ -193,10 +333,23 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                 let row_radio_report =
                     report_radios(active_radio_report, font, sniffer.style, sniffer.language);
 
+                let search_input = TextInput::new(
+                    search_translation(sniffer.language),
+                    &sniffer.search,
+                    Message::Search,
+                )
+                .padding([2, 5])
+                .font(font)
+                .width(Length::Fixed(300.0))
+                .style(<StyleTuple as Into<iced::theme::TextInput>>::into(
+                    StyleTuple(sniffer.style, ElementType::Standard),
+                ));
+
                 let mut col_report = Column::new()
                     .height(Length::Fill)
                     .width(Length::Fill)
-                    .push(row_radio_report);
+                    .push(row_radio_report)
+                    .push(Container::new(search_input).padding([0, 15, 10, 15]));
 
                 if sniffer.report_type.eq(&ReportType::Favorites)
                     && sniffer.runtime_data.borrow().report_vec.is_empty()
@@ -211,23 +364,46 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                     );
                 } else {
                     col_report = col_report
-                        .push(Text::new("     Src IP address       Src port      Dst IP address       Dst port  Layer4   Layer7     Packets      Bytes   Country").font(font))
+                        .push(report_header(sniffer, font))
                         .push(Text::new("------------------------------------------------------------------------------------------------------------------------").font(font))
                     ;
                     let mut scroll_report = Column::new();
                     for key_val in &sniffer.runtime_data.borrow().report_vec {
-                        let entry_color =
+                        let mut entry_color =
                             get_connection_color(key_val.1.traffic_type, sniffer.style);
+                        if key_val.1.tcp_state == Some(TcpState::Closed) {
+                            // gray out the connections already torn down
+                            entry_color.a = 0.5;
+                        }
                         let mut entry_row = Row::new().align_items(Alignment::Center).push(
                             Text::new(format!(
                                 "{}{}",
-                                key_val.0.print_gui(),
-                                key_val.1.print_gui()
+                                key_val.0.print_gui(ADDRESS_COLUMN_WIDTH),
+                                key_val.1.print_gui(sniffer.unit_system)
                             ))
                             .style(iced::theme::Text::Color(entry_color))
                             .font(INCONSOLATA_BOLD),
                         );
-                        if key_val.1.country.is_empty() {
+                        if key_val.1.country.is_empty() && key_val.1.is_gateway {
+                            entry_row = entry_row
+                                .push(
+                                    Tooltip::new(
+                                        Text::new("GW")
+                                            .width(Length::Fixed(FLAGS_WIDTH))
+                                            .style(iced::theme::Text::Color(entry_color))
+                                            .font(INCONSOLATA_BOLD),
+                                        gateway_translation(sniffer.language),
+                                        Position::FollowCursor,
+                                    )
+                                    .font(font)
+                                    .style(
+                                        <StyleTuple as Into<iced::theme::Container>>::into(
+                                            StyleTuple(sniffer.style, ElementType::Tooltip),
+                                        ),
+                                    ),
+                                )
+                                .push(Text::new("    "));
+                        } else if key_val.1.country.is_empty() {
                             entry_row = entry_row
                                 .push(
                                     Text::new("?")
@@ -237,8 +413,26 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                                 )
                                 .push(Text::new("    "));
                         } else {
+                            let mut tooltip_text = country_name(&key_val.1.country).to_string();
+                            let remote_host_description = key_val.1.remote_host_description();
+                            if !remote_host_description.is_empty() {
+                                tooltip_text.push('\n');
+                                tooltip_text.push_str(&remote_host_description);
+                            }
                             entry_row = entry_row
-                                .push(get_flag(&key_val.1.country))
+                                .push(
+                                    Tooltip::new(
+                                        get_flag(&key_val.1.country),
+                                        tooltip_text,
+                                        Position::FollowCursor,
+                                    )
+                                    .font(font)
+                                    .style(
+                                        <StyleTuple as Into<iced::theme::Container>>::into(
+                                            StyleTuple(sniffer.style, ElementType::Tooltip),
+                                        ),
+                                    ),
+                                )
                                 .push(Text::new("  "));
                         }
                         entry_row = entry_row
@@ -266,9 +460,9 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                                 )
                                 .on_press(
                                     if key_val.1.is_favorite {
-                                        Message::UnSaveConnection(key_val.1.index)
+                                        Message::UnSaveConnection(key_val.1.id)
                                     } else {
-                                        Message::SaveConnection(key_val.1.index)
+                                        Message::SaveConnection(key_val.1.id)
                                     },
                                 ),
                             )
@@ -294,6 +488,14 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                         )),
                 );
 
+                let export_csv_translation = export_csv_translation(sniffer.language);
+                let csv_path = get_csv_path().to_string_lossy().to_string();
+                let export_csv_tooltip = format!(
+                    "{:^len$}\n{csv_path}",
+                    export_csv_translation,
+                    len = csv_path.len()
+                );
+
                 let open_report_translation = open_report_translation(sniffer.language);
                 let report_path = get_report_path().to_string_lossy().to_string();
                 let open_report_tooltip = format!(
@@ -327,30 +529,77 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
                                 .width(Length::Fill)
                                 .push(row_report)
                                 .push(
-                                    Tooltip::new(
-                                        button(
-                                            Text::new('8'.to_string())
-                                                .font(ICONS)
-                                                .horizontal_alignment(alignment::Horizontal::Center)
-                                                .vertical_alignment(alignment::Vertical::Center),
+                                    Column::new()
+                                        .spacing(10)
+                                        .push(
+                                            Tooltip::new(
+                                                button(
+                                                    Text::new('8'.to_string())
+                                                        .font(ICONS)
+                                                        .horizontal_alignment(
+                                                            alignment::Horizontal::Center,
+                                                        )
+                                                        .vertical_alignment(
+                                                            alignment::Vertical::Center,
+                                                        ),
+                                                )
+                                                .padding(10)
+                                                .height(Length::Fixed(50.0))
+                                                .width(Length::Fixed(75.0))
+                                                .style(
+                                                    StyleTuple(
+                                                        sniffer.style,
+                                                        ElementType::Standard,
+                                                    )
+                                                    .into(),
+                                                )
+                                                .on_press(Message::OpenReport),
+                                                open_report_tooltip,
+                                                Position::Top,
+                                            )
+                                            .gap(5)
+                                            .font(font)
+                                            .style(
+                                                <StyleTuple as Into<iced::theme::Container>>::into(
+                                                    StyleTuple(sniffer.style, ElementType::Tooltip),
+                                                ),
+                                            ),
                                         )
-                                        .padding(10)
-                                        .height(Length::Fixed(50.0))
-                                        .width(Length::Fixed(75.0))
-                                        .style(
-                                            StyleTuple(sniffer.style, ElementType::Standard).into(),
+                                        .push(
+                                            Tooltip::new(
+                                                button(
+                                                    Text::new("CSV")
+                                                        .font(font)
+                                                        .horizontal_alignment(
+                                                            alignment::Horizontal::Center,
+                                                        )
+                                                        .vertical_alignment(
+                                                            alignment::Vertical::Center,
+                                                        ),
+                                                )
+                                                .padding(10)
+                                                .height(Length::Fixed(50.0))
+                                                .width(Length::Fixed(75.0))
+                                                .style(
+                                                    StyleTuple(
+                                                        sniffer.style,
+                                                        ElementType::Standard,
+                                                    )
+                                                    .into(),
+                                                )
+                                                .on_press(Message::ExportCsv),
+                                                export_csv_tooltip,
+                                                Position::Bottom,
+                                            )
+                                            .gap(5)
+                                            .font(font)
+                                            .style(
+                                                <StyleTuple as Into<iced::theme::Container>>::into(
+                                                    StyleTuple(sniffer.style, ElementType::Tooltip),
+                                                ),
+                                            ),
                                         )
-                                        .on_press(Message::OpenReport),
-                                        open_report_tooltip,
-                                        Position::Top,
-                                    )
-                                    .gap(5)
-                                    .font(font)
-                                    .style(
-                                        <StyleTuple as Into<iced::theme::Container>>::into(
-                                            StyleTuple(sniffer.style, ElementType::Tooltip),
-                                        ),
-                                    ),
+                                        .push(pause_button(sniffer, font)),
                                 ),
                         )
                         .align_x(Horizontal::Center)
@@ -381,3 +630,76 @@ pub fn overview_page(sniffer: &Sniffer) -> Container<Message> {
             StyleTuple(sniffer.style, ElementType::Standard),
         ))
 }
+
+/// Computes the header of the report, whose Layer7, Packets, Bytes, Duration and Country columns can be clicked to sort the connections
+/// Width of the address columns of the connections report, matching its header
+const ADDRESS_COLUMN_WIDTH: usize = 25;
+
+fn report_header(sniffer: &Sniffer, font: Font) -> Row<'static, Message> {
+    let mut header = Row::new().push(
+        Text::new(
+            "     Src IP address       Src port      Dst IP address       Dst port  Layer4  ",
+        )
+        .font(font),
+    );
+    for (separator, label, sort_column) in [
+        ("", "Layer7", SortColumn::AppProtocol),
+        ("    ", "Packets", SortColumn::Packets),
+        ("     ", "Bytes", SortColumn::Bytes),
+        ("  ", "Duration", SortColumn::Duration),
+        ("  ", "Country", SortColumn::Country),
+    ] {
+        // the sort direction of the selected column is shown in place of the space preceding it
+        let (marker, element_type) = match sniffer.report_sort {
+            Some((column, true)) if column == sort_column => ("v", ElementType::Standard),
+            Some((column, false)) if column == sort_column => ("^", ElementType::Standard),
+            _ => (" ", ElementType::NotStarred),
+        };
+        header = header
+            .push(Text::new(format!("{separator}{marker}")).font(font))
+            .push(
+                button(Text::new(label).font(font))
+                    .padding(0)
+                    .style(StyleTuple(sniffer.style, element_type).into())
+                    .on_press(Message::SortReport(sort_column)),
+            );
+    }
+    header
+}
+
+/// Button pausing the current run, or resuming it if already paused
+fn pause_button(sniffer: &Sniffer, font: Font) -> Tooltip<'static, Message> {
+    let (label, message, tooltip) = if sniffer.is_paused() {
+        (
+            ">",
+            Message::ResumeCapture,
+            resume_capture_translation(sniffer.language),
+        )
+    } else {
+        (
+            "||",
+            Message::PauseCapture,
+            pause_capture_translation(sniffer.language),
+        )
+    };
+    Tooltip::new(
+        button(
+            Text::new(label)
+                .font(font)
+                .horizontal_alignment(alignment::Horizontal::Center)
+                .vertical_alignment(alignment::Vertical::Center),
+        )
+        .padding(10)
+        .height(Length::Fixed(50.0))
+        .width(Length::Fixed(75.0))
+        .style(StyleTuple(sniffer.style, ElementType::Standard).into())
+        .on_press(message),
+        tooltip,
+        Position::Bottom,
+    )
+    .gap(5)
+    .font(font)
+    .style(<StyleTuple as Into<iced::theme::Container>>::into(
+        StyleTuple(sniffer.style, ElementType::Tooltip),
+    ))
+}
@@ -1,19 +1,25 @@
 use crate::enums::element_type::ElementType;
 use crate::enums::message::Message;
 use crate::enums::my_overlay::MyOverlay;
+use crate::enums::threshold_mode::ThresholdMode;
 use crate::gui::components::radio::{
-    sound_bytes_threshold_radios, sound_favorite_radios, sound_packets_threshold_radios,
+    sound_bytes_threshold_radios, sound_favorite_radios, sound_new_country_radios,
+    sound_packets_threshold_radios, threshold_mode_radios,
 };
 use crate::gui::components::tab::get_settings_tabs;
-use crate::structs::notifications::{BytesNotification, FavoriteNotification, PacketsNotification};
+use crate::structs::notifications::{
+    BytesNotification, FavoriteNotification, NewCountryNotification, PacketsNotification,
+};
 use crate::structs::style_tuple::StyleTuple;
 use crate::utility::style_constants::{
     get_font, get_font_headers, FONT_SIZE_FOOTER, FONT_SIZE_SUBTITLE, FONT_SIZE_TITLE, ICONS,
 };
 use crate::utility::translations::{
-    bytes_threshold_translation, favorite_notification_translation, hide_translation,
-    notifications_title_translation, packets_threshold_translation, per_second_translation,
-    settings_translation, specify_multiples_translation, threshold_translation, volume_translation,
+    bytes_threshold_translation, connection_rate_threshold_translation,
+    favorite_notification_translation, hide_translation, new_country_notification_translation,
+    notifications_title_translation, packets_threshold_translation, per_interval_translation,
+    per_second_translation, settings_translation, specify_multiples_translation,
+    threshold_translation, volume_translation,
 };
 use crate::{Language, Sniffer, StyleType};
 use iced::alignment::{Horizontal, Vertical};
@@ -70,17 +76,33 @@ pub fn settings_notifications_page(sniffer: &Sniffer) -> Container<Message> {
                 Column::new()
                     .width(Fixed(720.0))
                     .push(get_packets_notify(
-                        sniffer.notifications.packets_notification,
+                        sniffer.notifications.packets_notification.clone(),
+                        sniffer.language,
+                        sniffer.style,
+                    ))
+                    .push(get_bytes_notify(
+                        sniffer.notifications.bytes_notification.clone(),
+                        bytes_threshold_translation(sniffer.language),
+                        Message::UpdateBytesNotification,
+                        true,
                         sniffer.language,
                         sniffer.style,
                     ))
                     .push(get_bytes_notify(
-                        sniffer.notifications.bytes_notification,
+                        sniffer.notifications.connection_rate_notification.clone(),
+                        connection_rate_threshold_translation(sniffer.language),
+                        Message::UpdateConnectionRateNotification,
+                        false,
                         sniffer.language,
                         sniffer.style,
                     ))
                     .push(get_favorite_notify(
-                        sniffer.notifications.favorite_notification,
+                        sniffer.notifications.favorite_notification.clone(),
+                        sniffer.language,
+                        sniffer.style,
+                    ))
+                    .push(get_new_country_notify(
+                        sniffer.notifications.new_country_notification.clone(),
                         sniffer.language,
                         sniffer.style,
                     )),
@@ -106,6 +128,7 @@ fn get_packets_notify(
     style: StyleType,
 ) -> Column<'static, Message> {
     let font = get_font(style);
+    let previous = packets_notification.clone();
     let checkbox = Checkbox::new(
         packets_threshold_translation(language),
         packets_notification.threshold.is_some(),
@@ -113,8 +136,8 @@ fn get_packets_notify(
             if toggled {
                 Message::UpdatePacketsNotification(
                     PacketsNotification {
-                        threshold: Some(packets_notification.previous_threshold),
-                        ..packets_notification
+                        threshold: Some(previous.previous_threshold),
+                        ..previous.clone()
                     },
                     false,
                 )
@@ -122,7 +145,7 @@ fn get_packets_notify(
                 Message::UpdatePacketsNotification(
                     PacketsNotification {
                         threshold: None,
-                        ..packets_notification
+                        ..previous.clone()
                     },
                     false,
                 )
@@ -150,7 +173,11 @@ fn get_packets_notify(
         let input_row = Row::new()
             .push(horizontal_space(Fixed(50.0)))
             .push(Text::new(threshold_translation(language)).font(font))
-            .push(input_group_packets(packets_notification, style, language));
+            .push(input_group_packets(
+                packets_notification.clone(),
+                style,
+                language,
+            ));
         let sound_row =
             Row::new()
                 .push(horizontal_space(Fixed(50.0)))
@@ -175,29 +202,35 @@ fn get_packets_notify(
     }
 }
 
+/// Settings of a bytes threshold notification, labelled with `label` and updated via the `message` constructor;
+/// the threshold mode can be selected only if `with_threshold_mode` is set.
 fn get_bytes_notify(
     bytes_notification: BytesNotification,
+    label: &'static str,
+    message: fn(BytesNotification, bool) -> Message,
+    with_threshold_mode: bool,
     language: Language,
     style: StyleType,
 ) -> Column<'static, Message> {
     let font = get_font(style);
+    let previous = bytes_notification.clone();
     let checkbox = Checkbox::new(
-        bytes_threshold_translation(language),
+        label,
         bytes_notification.threshold.is_some(),
         move |toggled| {
             if toggled {
-                Message::UpdateBytesNotification(
+                message(
                     BytesNotification {
-                        threshold: Some(bytes_notification.previous_threshold),
-                        ..bytes_notification
+                        threshold: Some(previous.previous_threshold),
+                        ..previous.clone()
                     },
                     false,
                 )
             } else {
-                Message::UpdateBytesNotification(
+                message(
                     BytesNotification {
                         threshold: None,
-                        ..bytes_notification
+                        ..previous.clone()
                     },
                     false,
                 )
@@ -225,20 +258,37 @@ fn get_bytes_notify(
         let input_row = Row::new()
             .push(horizontal_space(Fixed(50.0)))
             .push(Text::new(threshold_translation(language)).font(font))
-            .push(input_group_bytes(bytes_notification, style, language));
+            .push(input_group_bytes(
+                bytes_notification.clone(),
+                message,
+                style,
+                language,
+            ));
+        let threshold_mode_row =
+            Row::new()
+                .push(horizontal_space(Fixed(50.0)))
+                .push(threshold_mode_radios(
+                    bytes_notification.clone(),
+                    message,
+                    font,
+                    style,
+                    language,
+                ));
         let sound_row =
             Row::new()
                 .push(horizontal_space(Fixed(50.0)))
                 .push(sound_bytes_threshold_radios(
                     bytes_notification,
+                    message,
                     font,
                     style,
                     language,
                 ));
-        ret_val = ret_val
-            .push(vertical_space(Fixed(5.0)))
-            .push(input_row)
-            .push(sound_row);
+        ret_val = ret_val.push(vertical_space(Fixed(5.0))).push(input_row);
+        if with_threshold_mode {
+            ret_val = ret_val.push(threshold_mode_row);
+        }
+        ret_val = ret_val.push(sound_row);
         Column::new().padding(5).push(
             Container::new(ret_val)
                 .padding(10)
@@ -256,15 +306,16 @@ fn get_favorite_notify(
     style: StyleType,
 ) -> Column<'static, Message> {
     let font = get_font(style);
+    let sound = favorite_notification.sound.clone();
     let checkbox = Checkbox::new(
         favorite_notification_translation(language),
         favorite_notification.notify_on_favorite,
         move |toggled| {
             Message::UpdateFavoriteNotification(
                 if toggled {
-                    FavoriteNotification::on(favorite_notification.sound)
+                    FavoriteNotification::on(sound.clone())
                 } else {
-                    FavoriteNotification::off(favorite_notification.sound)
+                    FavoriteNotification::off(sound.clone())
                 },
                 false,
             )
@@ -308,6 +359,57 @@ fn get_favorite_notify(
     }
 }
 
+fn get_new_country_notify(
+    new_country_notification: NewCountryNotification,
+    language: Language,
+    style: StyleType,
+) -> Column<'static, Message> {
+    let font = get_font(style);
+    let sound = new_country_notification.sound.clone();
+    let checkbox = Checkbox::new(
+        new_country_notification_translation(language),
+        new_country_notification.notify_on_new_country,
+        move |toggled| {
+            Message::UpdateNewCountryNotification(
+                if toggled {
+                    NewCountryNotification::on(sound.clone())
+                } else {
+                    NewCountryNotification::off(sound.clone())
+                },
+                false,
+            )
+        },
+    )
+    .size(18)
+    .font(font)
+    .style(<StyleTuple as Into<iced::theme::Checkbox>>::into(
+        StyleTuple(style, ElementType::Standard),
+    ));
+
+    let mut ret_val = Column::new().spacing(5).push(checkbox);
+
+    if new_country_notification.notify_on_new_country {
+        let sound_row =
+            Row::new()
+                .push(horizontal_space(Fixed(50.0)))
+                .push(sound_new_country_radios(
+                    new_country_notification,
+                    font,
+                    style,
+                    language,
+                ));
+        ret_val = ret_val.push(vertical_space(Fixed(5.0))).push(sound_row);
+    }
+    Column::new().padding(5).push(
+        Container::new(ret_val)
+            .padding(10)
+            .width(Fixed(700.0))
+            .style(<StyleTuple as Into<iced::theme::Container>>::into(
+                StyleTuple(style, ElementType::BorderedRound),
+            )),
+    )
+}
+
 fn input_group_packets(
     packets_notification: PacketsNotification,
     style: StyleType,
@@ -327,7 +429,7 @@ fn input_group_packets(
                 },
                 move |value| {
                     let packets_notification =
-                        PacketsNotification::from(&value, Some(packets_notification));
+                        PacketsNotification::from(&value, Some(packets_notification.clone()));
                     Message::UpdatePacketsNotification(packets_notification, false)
                 },
             )
@@ -351,11 +453,16 @@ fn input_group_packets(
 
 fn input_group_bytes(
     bytes_notification: BytesNotification,
+    message: fn(BytesNotification, bool) -> Message,
     style: StyleType,
     language: Language,
 ) -> Container<'static, Message> {
     let font = get_font(style);
-    let mut info_str = per_second_translation(language).to_string();
+    let mut info_str = match bytes_notification.threshold_mode {
+        ThresholdMode::PerSecond => per_second_translation(language),
+        ThresholdMode::PerInterval => per_interval_translation(language),
+    }
+    .to_string();
     info_str.push_str(specify_multiples_translation(language));
     let mut curr_threshold_str = (bytes_notification.threshold.unwrap()
         / bytes_notification.byte_multiple.get_multiplier())
@@ -373,8 +480,8 @@ fn input_group_bytes(
                 },
                 move |value| {
                     let bytes_notification =
-                        BytesNotification::from(&value, Some(bytes_notification));
-                    Message::UpdateBytesNotification(bytes_notification, false)
+                        BytesNotification::from(&value, Some(bytes_notification.clone()));
+                    message(bytes_notification, false)
                 },
             )
             .padding(1)
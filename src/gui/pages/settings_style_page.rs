@@ -5,15 +5,15 @@ use crate::gui::components::tab::get_settings_tabs;
 use crate::gui::pages::settings_notifications_page::settings_header;
 use crate::structs::style_tuple::StyleTuple;
 use crate::utility::style_constants::{
-    get_font, DEEP_SEA, FONT_SIZE_SUBTITLE, MON_AMOUR, YETI_DAY, YETI_NIGHT,
+    get_font, COLORBLIND, DEEP_SEA, FONT_SIZE_SUBTITLE, MON_AMOUR, YETI_DAY, YETI_NIGHT,
 };
 use crate::utility::translations::{
-    appearance_title_translation, deep_sea_translation, mon_amour_translation,
-    yeti_day_translation, yeti_night_translation,
+    appearance_title_translation, colorblind_translation, deep_sea_translation,
+    mon_amour_translation, yeti_day_translation, yeti_night_translation,
 };
-use crate::StyleType::{Day, DeepSea, MonAmour, Night};
+use crate::StyleType::{Colorblind, Day, DeepSea, MonAmour, Night};
 use crate::{Sniffer, StyleType};
-use iced::widget::{Button, Column, Container, Image, Row, Text};
+use iced::widget::{Button, Column, Container, Image, Row, Scrollable, Text};
 use iced::{Alignment, Length};
 use iced_native::image::Handle;
 use iced_native::widget::{horizontal_space, vertical_space};
@@ -48,41 +48,59 @@ pub fn settings_style_page(sniffer: &Sniffer) -> Container<Message> {
         )
         .push(vertical_space(Length::Fixed(10.0)))
         .push(
-            Row::new()
-                .push(get_palette_container(
-                    sniffer.style,
-                    YETI_NIGHT,
-                    "Yeti Night".to_string(),
-                    yeti_night_translation(sniffer.language).to_string(),
-                    Night,
-                ))
-                .push(horizontal_space(Length::Fixed(33.0)))
-                .push(get_palette_container(
-                    sniffer.style,
-                    YETI_DAY,
-                    "Yeti Day".to_string(),
-                    yeti_day_translation(sniffer.language).to_string(),
-                    Day,
-                )),
-        )
-        .push(vertical_space(Length::Fixed(10.0)))
-        .push(
-            Row::new()
-                .push(get_palette_container(
-                    sniffer.style,
-                    DEEP_SEA,
-                    "Deep Sea".to_string(),
-                    deep_sea_translation(sniffer.language).to_string(),
-                    DeepSea,
-                ))
-                .push(horizontal_space(Length::Fixed(33.0)))
-                .push(get_palette_container(
-                    sniffer.style,
-                    MON_AMOUR,
-                    "Mon Amour".to_string(),
-                    mon_amour_translation(sniffer.language).to_string(),
-                    MonAmour,
-                )),
+            Scrollable::new(
+                Column::new()
+                    .align_items(Alignment::Center)
+                    .width(Length::Fill)
+                    .push(
+                        Row::new()
+                            .push(get_palette_container(
+                                sniffer.style,
+                                YETI_NIGHT,
+                                "Yeti Night".to_string(),
+                                yeti_night_translation(sniffer.language).to_string(),
+                                Night,
+                            ))
+                            .push(horizontal_space(Length::Fixed(33.0)))
+                            .push(get_palette_container(
+                                sniffer.style,
+                                YETI_DAY,
+                                "Yeti Day".to_string(),
+                                yeti_day_translation(sniffer.language).to_string(),
+                                Day,
+                            )),
+                    )
+                    .push(vertical_space(Length::Fixed(10.0)))
+                    .push(
+                        Row::new()
+                            .push(get_palette_container(
+                                sniffer.style,
+                                DEEP_SEA,
+                                "Deep Sea".to_string(),
+                                deep_sea_translation(sniffer.language).to_string(),
+                                DeepSea,
+                            ))
+                            .push(horizontal_space(Length::Fixed(33.0)))
+                            .push(get_palette_container(
+                                sniffer.style,
+                                MON_AMOUR,
+                                "Mon Amour".to_string(),
+                                mon_amour_translation(sniffer.language).to_string(),
+                                MonAmour,
+                            )),
+                    )
+                    .push(vertical_space(Length::Fixed(10.0)))
+                    .push(get_palette_container(
+                        sniffer.style,
+                        COLORBLIND,
+                        "Colorblind".to_string(),
+                        colorblind_translation(sniffer.language).to_string(),
+                        Colorblind,
+                    )),
+            )
+            .style(<StyleTuple as Into<iced::theme::Scrollable>>::into(
+                StyleTuple(sniffer.style, ElementType::Standard),
+            )),
         );
 
     Container::new(content)
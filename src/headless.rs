@@ -0,0 +1,215 @@
+//! Module containing the entry point of the headless mode, in which the traffic is captured,
+//! notified and exported without the GUI (e.g. on a server).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::structs::sniffer::Sniffer;
+use crate::utility::get_formatted_strings::get_throughput_summary_string;
+
+/// Interval between two consecutive checks of the interruption flag
+const INTERRUPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set when the process is interrupted (Ctrl+C), to quit gracefully
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Captures the traffic of the configured network adapters until the process is interrupted (Ctrl+C),
+/// evaluating the notifications thresholds at every refresh interval as the GUI does.
+///
+/// On interruption, the capture is stopped, a summary of the throughput (peak and 95th percentile) is printed,
+/// and the traffic data are exported as they would be when quitting the GUI (SQLite database, JSON export
+/// and session file, if configured).
+/// Triggered notifications are emitted as `info` events, enabled by default in this mode.
+///
+/// Returns an error if the capture cannot be opened.
+pub fn run_headless(mut sniffer: Sniffer) -> Result<(), String> {
+    install_interrupt_handler();
+
+    sniffer.start_capture();
+    if let Some(err) = sniffer.pcap_error.take() {
+        return Err(format!(
+            "cannot sniff network adapter {} ({err})",
+            sniffer.device.name
+        ));
+    }
+    println!(
+        "Sniffing network adapter {} (press Ctrl+C to quit)",
+        sniffer.device.name
+    );
+
+    let mut last_refresh = Instant::now();
+    while !INTERRUPTED.load(Ordering::Relaxed) {
+        thread::sleep(INTERRUPT_POLL_INTERVAL);
+        if sniffer
+            .capture_threads
+            .iter()
+            .all(thread::JoinHandle::is_finished)
+        {
+            warn!("no network adapter is being sniffed anymore");
+            break;
+        }
+        if last_refresh.elapsed() >= sniffer.refresh_interval {
+            last_refresh = Instant::now();
+            sniffer.refresh_runtime_data();
+        }
+    }
+
+    quit(&mut sniffer);
+    Ok(())
+}
+
+/// Stops the capture, waiting for the traffic still buffered to be merged, prints the throughput summary
+/// and exports the traffic data.
+fn quit(sniffer: &mut Sniffer) {
+    sniffer.stop_capture();
+    if let Some(summary) = get_throughput_summary_string(
+        &sniffer.runtime_data.borrow().throughput_history,
+        sniffer.refresh_interval,
+        sniffer.unit_system,
+    ) {
+        println!("{summary}");
+    }
+    sniffer.save_on_exit();
+}
+
+/// Sets the interruption flag when the process is interrupted (SIGINT on Unix systems,
+/// Ctrl+C or Ctrl+Break on Windows), instead of terminating it.
+fn install_interrupt_handler() {
+    if let Err(err) = ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::Relaxed)) {
+        warn!(%err, "cannot handle interruptions, the traffic data won't be exported on Ctrl+C");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::fs;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::enums::status::Status;
+    use crate::headless::quit;
+    use crate::structs::cli_args::CliArgs;
+    use crate::structs::configs::{ConfigCapture, ConfigDevice, ConfigMmdb, ConfigSettings};
+    use crate::structs::filters::Filters;
+    use crate::structs::runtime_data::RunTimeData;
+    use crate::structs::sniffer::Sniffer;
+    use crate::structs::traffic_buffer::FlushCadence;
+    use crate::thread_parse_packets::parse_packets_loop;
+    use crate::utility::countries::MmdbReaders;
+    use crate::utility::logging::lock_or_recover;
+    use crate::InfoTraffic;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exports_on_interrupt_include_buffered_traffic() {
+        use std::fs::File;
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+
+        use pcap::{Capture, Device};
+
+        let json_path = std::env::temp_dir().join("sniffnet_test_headless_export.json");
+        let sqlite_path = std::env::temp_dir().join("sniffnet_test_headless_export.sqlite");
+        fs::remove_file(&sqlite_path).unwrap_or(());
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut sniffer = Sniffer::new(
+            Arc::new(Mutex::new(0)),
+            info_traffic_mutex.clone(),
+            Rc::new(RefCell::new(RunTimeData::new())),
+            Arc::new((Mutex::new(Status::Running), Condvar::new())),
+            &ConfigSettings::default(),
+            &ConfigDevice::default(),
+            ConfigMmdb::default(),
+            &ConfigCapture::default(),
+            CliArgs {
+                export_json: Some(json_path.clone()),
+                headless: true,
+                ..CliArgs::default()
+            },
+        );
+
+        // the traffic is read from a pipe, which (like a live adapter) waits for the next packet
+        let file = fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("pcaps")
+                .join("dns.pcap"),
+        )
+        .unwrap();
+        // global header and first record, then the second record
+        let first_caplen = u32::from_ne_bytes(file[32..36].try_into().unwrap());
+        let first_end = 24 + 16 + usize::try_from(first_caplen).unwrap();
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut writer = unsafe { File::from_raw_fd(fds[1]) };
+        writer.write_all(&file[..24]).unwrap();
+        let cap = unsafe { Capture::from_raw_fd(fds[0]) }.unwrap();
+        let capture_thread = {
+            let stop_flag = sniffer.capture_stop_flag.clone();
+            let info_traffic_mutex = info_traffic_mutex.clone();
+            thread::spawn(move || {
+                parse_packets_loop(
+                    &stop_flag,
+                    &Arc::new(AtomicBool::new(false)),
+                    Device::from("offline"),
+                    cap,
+                    &Filters::default(),
+                    &MmdbReaders::open(&ConfigMmdb::default()),
+                    &info_traffic_mutex,
+                    None,
+                );
+            })
+        };
+        sniffer.capture_threads.push(capture_thread);
+        sniffer.start_sql_writer(sqlite_path.clone(), &info_traffic_mutex);
+
+        // enough copies of the first packet to fill the buffer, flushing it right after the last one;
+        // then the second packet is sniffed after the interruption, while the capture threads are being joined
+        let max_packets = FlushCadence::default().max_packets;
+        let packets_writer = {
+            let stop_flag = sniffer.capture_stop_flag.clone();
+            thread::spawn(move || {
+                for _ in 0..max_packets {
+                    writer.write_all(&file[24..first_end]).unwrap();
+                }
+                while !stop_flag.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                writer.write_all(&file[first_end..]).unwrap();
+            })
+        };
+        // once merged, the capture thread waits for the next packet
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let merged_bytes = loop {
+            let info_traffic = lock_or_recover(&info_traffic_mutex, "test");
+            if info_traffic.all_packets == max_packets {
+                break info_traffic.all_bytes;
+            }
+            drop(info_traffic);
+            assert!(Instant::now() < deadline, "first packets never merged");
+            thread::sleep(Duration::from_millis(10));
+        };
+        quit(&mut sniffer);
+        packets_writer.join().unwrap();
+
+        let json = fs::read_to_string(&json_path).unwrap();
+        let logged_connections: u32 = rusqlite::Connection::open(&sqlite_path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM connections", [], |row| row.get(0))
+            .unwrap();
+        fs::remove_file(&json_path).unwrap();
+        fs::remove_file(&sqlite_path).unwrap();
+        // the two packets of the capture amount to 92 bytes
+        let all_bytes = merged_bytes + 92 - merged_bytes / max_packets;
+        assert!(json.contains(&format!("\"all_packets\":{},", max_packets + 1)));
+        assert!(json.contains(&format!("\"all_bytes\":{all_bytes},")));
+        assert_eq!(logged_connections, 2);
+    }
+}
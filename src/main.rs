@@ -3,10 +3,11 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::{Arc, Condvar, Mutex};
-use std::{panic, process, thread};
+use std::{process, thread};
 
 use iced::window::Position;
 use iced::{window, Application, Settings};
+use tracing::warn;
 
 use utility::style_constants::FONT_SIZE_BODY;
 
@@ -15,31 +16,55 @@ use crate::enums::byte_multiple::ByteMultiple;
 use crate::enums::chart_type::ChartType;
 use crate::enums::ip_version::IpVersion;
 use crate::enums::language::Language;
+use crate::enums::log_level::LogLevel;
 use crate::enums::report_type::ReportType;
 use crate::enums::running_page::RunningPage;
 use crate::enums::status::Status;
 use crate::enums::style_type::StyleType;
 use crate::enums::trans_protocol::TransProtocol;
-use crate::structs::configs::{ConfigDevice, ConfigSettings};
+use crate::enums::unit_system::UnitSystem;
+use crate::headless::run_headless;
+use crate::structs::cli_args::{CliArgs, USAGE};
+use crate::structs::configs::{
+    ConfigCapture, ConfigDevice, ConfigMmdb, ConfigPalette, ConfigSettings,
+};
 use crate::structs::info_traffic::InfoTraffic;
-use crate::structs::palette::get_colors;
+use crate::structs::palette::{get_colors, set_custom_palette};
 use crate::structs::runtime_data::RunTimeData;
 use crate::structs::sniffer::Sniffer;
 use crate::structs::traffic_chart::TrafficChart;
 use crate::thread_write_report::sleep_and_write_report_loop;
 use crate::utility::get_formatted_strings::print_cli_welcome_message;
+use crate::utility::logging::{init_logging, install_panic_hook};
 
 mod enums;
 mod gui;
+mod headless;
 mod structs;
 mod thread_parse_packets;
+mod thread_resolve_hostnames;
+#[cfg(feature = "metrics")]
+mod thread_serve_metrics;
 mod thread_write_report;
+mod thread_write_sql;
 mod utility;
 
 /// Entry point of application execution
 ///
 /// It initializes shared variables and loads configuration parameters
 pub fn main() -> iced::Result {
+    let cli_args = CliArgs::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("Error: {err}\n{USAGE}");
+        process::exit(1);
+    });
+    // in headless mode the triggered notifications are emitted as info events
+    let default_log_level = if cli_args.headless {
+        LogLevel::Info
+    } else {
+        LogLevel::Warn
+    };
+    init_logging(cli_args.log_level, default_log_level);
+
     let current_capture_id1 = Arc::new(Mutex::new(0));
     let current_capture_id2 = current_capture_id1.clone();
 
@@ -51,20 +76,14 @@ pub fn main() -> iced::Result {
 
     let runtime_data = Rc::new(RefCell::new(RunTimeData::new()));
 
-    // to kill the main thread as soon as a secondary thread panics
-    let orig_hook = panic::take_hook();
-    panic::set_hook(Box::new(move |panic_info| {
-        // invoke the default handler and exit the process
-        orig_hook(panic_info);
-        process::exit(1);
-    }));
+    install_panic_hook();
 
     let config_settings_result = confy::load::<ConfigSettings>("sniffnet", "settings");
     if config_settings_result.is_err() {
         // it happens when changing the ConfigSettings struct fields during development or after new releases
         confy::store("sniffnet", "settings", ConfigSettings::default()).unwrap_or(());
     }
-    let config_settings = config_settings_result.unwrap_or(ConfigSettings::default());
+    let mut config_settings = config_settings_result.unwrap_or(ConfigSettings::default());
 
     let config_device_result = confy::load::<ConfigDevice>("sniffnet", "device");
     if config_device_result.is_err() {
@@ -73,6 +92,35 @@ pub fn main() -> iced::Result {
     }
     let config_device = config_device_result.unwrap_or(ConfigDevice::default());
 
+    let config_mmdb_result = confy::load::<ConfigMmdb>("sniffnet", "mmdb");
+    if config_mmdb_result.is_err() {
+        // it happens when changing the ConfigMmdb struct fields during development or after new releases
+        confy::store("sniffnet", "mmdb", ConfigMmdb::default()).unwrap_or(());
+    }
+    let config_mmdb = config_mmdb_result.unwrap_or_default();
+
+    let config_capture_result = confy::load::<ConfigCapture>("sniffnet", "capture");
+    if config_capture_result.is_err() {
+        // it happens when changing the ConfigCapture struct fields during development or after new releases
+        confy::store("sniffnet", "capture", ConfigCapture::default()).unwrap_or(());
+    }
+    let config_capture = config_capture_result.unwrap_or_default();
+
+    let config_palette_result = confy::load::<ConfigPalette>("sniffnet", "palette");
+    if config_palette_result.is_err() {
+        // it happens when changing the ConfigPalette struct fields during development or after new releases
+        confy::store("sniffnet", "palette", ConfigPalette::default()).unwrap_or(());
+    }
+    if let Some(custom_palette) = config_palette_result.unwrap_or_default().custom {
+        match custom_palette.to_palette() {
+            Ok(palette) => {
+                set_custom_palette(palette);
+                config_settings.style = StyleType::Custom;
+            }
+            Err(err) => warn!(%err, "custom palette ignored"),
+        }
+    }
+
     thread::Builder::new()
         .name("thread_write_report".to_string())
         .spawn(move || {
@@ -82,6 +130,25 @@ pub fn main() -> iced::Result {
 
     print_cli_welcome_message();
 
+    if cli_args.headless {
+        let sniffer = Sniffer::new(
+            current_capture_id1,
+            mutex_map1,
+            runtime_data,
+            status_pair1,
+            &config_settings,
+            &config_device,
+            config_mmdb,
+            &config_capture,
+            cli_args,
+        );
+        if let Err(err) = run_headless(sniffer) {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
     Sniffer::run(Settings {
         id: None,
         window: window::Settings {
@@ -103,12 +170,15 @@ pub fn main() -> iced::Result {
             status_pair1,
             &config_settings,
             &config_device,
+            config_mmdb,
+            &config_capture,
+            cli_args,
         ),
         default_font: Some(include_bytes!("../resources/fonts/inconsolata-regular.ttf")),
         default_text_size: FONT_SIZE_BODY,
         text_multithreading: true,
         antialiasing: false,
-        exit_on_close_request: true,
+        exit_on_close_request: false,
         try_opengles_first: false,
     })
 }
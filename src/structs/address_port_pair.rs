@@ -1,11 +1,18 @@
 //! Module defining the `AddressPortPair` struct, which represents a network address:port pair.
 
-use std::fmt;
+use serde::{Deserialize, Serialize};
 
 use crate::TransProtocol;
 
+/// Length of the longest textual representation of an IPv4 address (`255.255.255.255`)
+const MAX_IPV4_ADDRESS_LEN: usize = 15;
+
+/// Length of the longest textual representation of an IPv6 address
+/// (an IPv4-mapped one, as `INET6_ADDRSTRLEN` without the terminator), also fitting any IPv4 address
+pub const MAX_IPV6_ADDRESS_LEN: usize = 45;
+
 /// Struct representing a network address:port pair.
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct AddressPortPair {
     /// Network layer IPv4 or IPv6 source address.
     pub address1: String,
@@ -43,25 +50,73 @@ impl AddressPortPair {
         }
     }
 
-    pub fn print_gui(&self) -> String {
-        self.to_string().replace('|', "")
+    /// Returns the columns of the connections report displayed in the GUI for the pair.
+    pub fn print_gui(&self, column_width: usize) -> String {
+        self.format_columns(column_width).replace('|', "")
     }
-}
 
-impl fmt::Display for AddressPortPair {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.address1.len() > 25 || self.address2.len() > 25 {
-            write!(
-                f,
-                "|{:^45}|{:>8}  |{:^45}|{:>8}  |   {}   |",
-                self.address1, self.port1, self.address2, self.port2, self.trans_protocol
-            )
+    /// Returns the width of the address columns needed to display the pair in columns `column_width` wide:
+    /// if any of the addresses doesn't fit, the columns are widened to fit any address of its family.
+    pub fn address_column_width(&self, column_width: usize) -> usize {
+        let longest = self.address1.len().max(self.address2.len());
+        if longest <= column_width {
+            column_width
+        } else if self.address1.contains(':') || self.address2.contains(':') {
+            MAX_IPV6_ADDRESS_LEN.max(longest)
         } else {
-            write!(
-                f,
-                "|{:^25}|{:>8}  |{:^25}|{:>8}  |   {}   |",
-                self.address1, self.port1, self.address2, self.port2, self.trans_protocol
-            )
+            MAX_IPV4_ADDRESS_LEN.max(longest)
         }
     }
+
+    /// Formats the pair as the columns of a connections report row,
+    /// with address columns `column_width` wide (see `address_column_width`).
+    pub fn format_columns(&self, column_width: usize) -> String {
+        let width = self.address_column_width(column_width);
+        format!(
+            "|{:^width$}|{:>8}  |{:^width$}|{:>8}  |   {}   |",
+            self.address1, self.port1, self.address2, self.port2, self.trans_protocol
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::TransProtocol;
+
+    fn pair(address1: &str, address2: &str) -> AddressPortPair {
+        AddressPortPair::new(
+            address1.to_string(),
+            443,
+            address2.to_string(),
+            50000,
+            TransProtocol::TCP,
+        )
+    }
+
+    #[test]
+    fn test_long_address_widens_the_columns() {
+        let short = pair("fe80::1ff:fe23:4567:890a", "fe80::10");
+        assert_eq!(short.address_column_width(25), 25);
+        let long = pair("2001:db8:85a3:8d3:1319:8a2e:370:7348", "2001:db8::10");
+        assert_eq!(long.address_column_width(25), 45);
+        // the long addresses take 20 more characters each
+        assert_eq!(
+            long.format_columns(25).len(),
+            short.format_columns(25).len() + 40
+        );
+    }
+
+    #[test]
+    fn test_ipv6_address_not_cut_at_ipv4_width() {
+        let ipv4 = pair("255.255.255.255", "192.168.1.10");
+        assert_eq!(ipv4.address_column_width(15), 15);
+        assert_eq!(ipv4.address_column_width(10), 15);
+        let address = "2001:db8:85a3:8d3:1319:8a2e:370:7348";
+        let ipv6 = pair(address, "2001:db8::10");
+        assert_eq!(ipv6.address_column_width(15), 45);
+        let columns = ipv6.format_columns(15);
+        assert!(columns.starts_with(&format!("|{address:^45}|")));
+        assert!(ipv6.print_gui(15).contains(address));
+    }
 }
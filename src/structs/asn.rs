@@ -0,0 +1,24 @@
+//! Module defining the `Asn` struct, which represents the Autonomous System an address belongs to.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Struct representing an Autonomous System (number and organization owning it).
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Asn {
+    /// Autonomous System Number (0 if unknown).
+    pub number: u32,
+    /// Name of the organization owning the Autonomous System (empty if unknown).
+    pub org: String,
+}
+
+impl fmt::Display for Asn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.number == 0 {
+            write!(f, "")
+        } else {
+            write!(f, "AS{} {}", self.number, self.org)
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Module defining the `CaptureOptions` struct, which specifies how the network adapters are opened.
+
+use tracing::warn;
+
+use crate::structs::configs::ConfigCapture;
+
+/// Default maximum number of bytes captured for each packet
+pub const DEFAULT_SNAPLEN: i32 = 256;
+
+/// Minimum number of bytes captured for each packet, holding the headers parsed by Sniffnet:
+/// Ethernet (14 bytes), IPv4 with options (up to 60 bytes) and TCP with options (up to 60 bytes)
+pub const MIN_SNAPLEN: i32 = 14 + 60 + 60;
+
+/// Default time after which a read returns even if no packet has been sniffed, in milliseconds
+pub const DEFAULT_READ_TIMEOUT_MS: i32 = 150;
+
+/// Options applied when opening the captures on the network adapters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CaptureOptions {
+    /// Whether the traffic not addressed to this host is captured as well
+    pub promiscuous: bool,
+    /// Maximum number of bytes captured for each packet (the rest is truncated)
+    pub snaplen: i32,
+    /// Time after which a read returns even if no packet has been sniffed, in milliseconds;
+    /// the shorter it is, the more often the buffered packets are flushed and the stop flag is checked
+    pub read_timeout_ms: i32,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            promiscuous: true,
+            snaplen: DEFAULT_SNAPLEN,
+            read_timeout_ms: DEFAULT_READ_TIMEOUT_MS,
+        }
+    }
+}
+
+impl CaptureOptions {
+    /// Returns the options specified in the configuration, with the defaults for the missing ones.
+    ///
+    /// A snapshot length too short to hold the parsed headers is raised to `MIN_SNAPLEN`,
+    /// and a non-positive read timeout (which would block reads forever on some platforms) is replaced
+    /// by the default one, with a warning.
+    pub fn from_config(config_capture: &ConfigCapture) -> Self {
+        let default = CaptureOptions::default();
+        let snaplen = match config_capture.snaplen {
+            Some(snaplen) if snaplen < MIN_SNAPLEN => {
+                warn!(
+                    snaplen,
+                    used = MIN_SNAPLEN,
+                    "snaplen too short to hold the packet headers"
+                );
+                MIN_SNAPLEN
+            }
+            Some(snaplen) => snaplen,
+            None => default.snaplen,
+        };
+        let read_timeout_ms = match config_capture.read_timeout_ms {
+            Some(timeout) if timeout <= 0 => {
+                warn!(
+                    read_timeout_ms = timeout,
+                    used_ms = DEFAULT_READ_TIMEOUT_MS,
+                    "read timeout not positive"
+                );
+                default.read_timeout_ms
+            }
+            Some(timeout) => timeout,
+            None => default.read_timeout_ms,
+        };
+        CaptureOptions {
+            promiscuous: config_capture.promiscuous.unwrap_or(default.promiscuous),
+            snaplen,
+            read_timeout_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::capture_options::{CaptureOptions, MIN_SNAPLEN};
+    use crate::structs::configs::ConfigCapture;
+
+    #[test]
+    fn test_capture_options_from_config() {
+        assert_eq!(
+            CaptureOptions::from_config(&ConfigCapture::default()),
+            CaptureOptions::default()
+        );
+
+        let config_capture = ConfigCapture {
+            promiscuous: Some(false),
+            snaplen: Some(65535),
+            read_timeout_ms: Some(20),
+            ..ConfigCapture::default()
+        };
+        assert_eq!(
+            CaptureOptions::from_config(&config_capture),
+            CaptureOptions {
+                promiscuous: false,
+                snaplen: 65535,
+                read_timeout_ms: 20,
+            }
+        );
+
+        let config_capture = ConfigCapture {
+            snaplen: Some(64),
+            read_timeout_ms: Some(0),
+            ..ConfigCapture::default()
+        };
+        let options = CaptureOptions::from_config(&config_capture);
+        assert_eq!(options.snaplen, MIN_SNAPLEN);
+        assert_eq!(
+            options.read_timeout_ms,
+            CaptureOptions::default().read_timeout_ms
+        );
+    }
+}
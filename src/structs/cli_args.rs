@@ -0,0 +1,113 @@
+//! Module defining the `CliArgs` struct, which represents the command line arguments
+//! accepted by the application.
+
+use std::path::PathBuf;
+
+use crate::enums::log_level::LogLevel;
+
+/// Usage message printed when invalid arguments are supplied
+pub const USAGE: &str =
+    "Usage: sniffnet [--headless] [--export-json <path>] [--anonymize] [--log-level <error|warn|info|debug|trace>]";
+
+/// Command line arguments accepted by the application
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct CliArgs {
+    /// Path of the JSON file where the traffic data are dumped on exit
+    pub export_json: Option<PathBuf>,
+    /// Whether the addresses are anonymized in the exported connections (CSV and JSON)
+    pub anonymize: bool,
+    /// Maximum level of the diagnostic events emitted on the standard error
+    pub log_level: Option<LogLevel>,
+    /// Whether the traffic is captured without the GUI, until the process is interrupted
+    pub headless: bool,
+}
+
+impl CliArgs {
+    /// Parses the command line arguments (excluding the program name).
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut cli_args = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--export-json" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| format!("missing path after {arg}"))?;
+                    cli_args.export_json = Some(PathBuf::from(path));
+                }
+                "--log-level" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| format!("missing level after {arg}"))?;
+                    cli_args.log_level = Some(
+                        LogLevel::from_name(&name)
+                            .ok_or_else(|| format!("unknown log level {name}"))?,
+                    );
+                }
+                "--headless" => cli_args.headless = true,
+                "--anonymize" => cli_args.anonymize = true,
+                _ => return Err(format!("unknown argument {arg}")),
+            }
+        }
+        Ok(cli_args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::enums::log_level::LogLevel;
+    use crate::structs::cli_args::CliArgs;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        CliArgs::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn test_parse_cli_args() {
+        assert_eq!(parse(&[]), Ok(CliArgs::default()));
+        assert_eq!(
+            parse(&["--export-json", "/tmp/traffic.json"]),
+            Ok(CliArgs {
+                export_json: Some(PathBuf::from("/tmp/traffic.json")),
+                ..CliArgs::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--log-level", "debug", "--export-json", "traffic.json"]),
+            Ok(CliArgs {
+                export_json: Some(PathBuf::from("traffic.json")),
+                log_level: Some(LogLevel::Debug),
+                ..CliArgs::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--headless"]),
+            Ok(CliArgs {
+                headless: true,
+                ..CliArgs::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--anonymize", "--export-json", "traffic.json"]),
+            Ok(CliArgs {
+                export_json: Some(PathBuf::from("traffic.json")),
+                anonymize: true,
+                ..CliArgs::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--log-level", "loud"]),
+            Err("unknown log level loud".to_string())
+        );
+        assert_eq!(
+            parse(&["--export-json"]),
+            Err("missing path after --export-json".to_string())
+        );
+        assert_eq!(
+            parse(&["--verbose"]),
+            Err("unknown argument --verbose".to_string())
+        );
+    }
+}
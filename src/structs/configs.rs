@@ -1,17 +1,30 @@
 //! Module defining the `Config` struct, which allows to save and reload
 //! the application default configuration.
 
+use crate::enums::byte_accounting::ByteAccounting;
 use crate::enums::language::Language;
+use crate::enums::traffic_type::TrafficType;
+use crate::structs::favorite_rule::FavoriteRule;
 use crate::structs::notifications::Notifications;
-use crate::StyleType;
+use crate::structs::palette::Palette;
+use crate::structs::pcap_export::PcapExport;
+use crate::{AppProtocol, StyleType, UnitSystem};
 use pcap::Device;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct ConfigSettings {
     pub style: StyleType,
     pub language: Language,
     pub notifications: Notifications,
+    /// Whether the hostnames of the remote addresses have to be resolved via reverse DNS
+    #[serde(default)]
+    pub resolve_hostnames: bool,
+    /// Whether the multiples of bytes are powers of 1000 (KB, MB, GB) or of 1024 (KiB, MiB, GiB)
+    #[serde(default)]
+    pub unit_system: UnitSystem,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -37,3 +50,110 @@ impl ConfigDevice {
         Device::lookup().unwrap().unwrap()
     }
 }
+
+/// Paths of user supplied MMDB files, used in place of the embedded ones
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ConfigMmdb {
+    /// GeoLite2 or GeoIP2 database used to retrieve the country of the remote addresses
+    pub country_path: Option<PathBuf>,
+    /// GeoLite2 or GeoIP2 database used to retrieve the Autonomous System of the remote addresses
+    pub asn_path: Option<PathBuf>,
+    /// GeoLite2 or GeoIP2 City database used to retrieve the coordinates of the remote addresses;
+    /// if not specified, `GeoLite2-City.mmdb` is looked up in the folder of the configuration files
+    pub city_path: Option<PathBuf>,
+}
+
+/// Options applied to the packets capture
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ConfigCapture {
+    /// Destination of the pcap export of the packets accepted by the filters, if any
+    pub pcap_export: Option<PcapExport>,
+    /// Berkeley Packet Filter expression applied to the capture in the kernel, if any
+    pub bpf: Option<String>,
+    /// Filters preselected at startup, as whitespace separated `key=value` tokens
+    /// (e.g. `"ip=ipv6 transport=udp app=dns port=53"`), if any
+    pub filter: Option<String>,
+    /// Direction of the connections to be reported (e.g. `Outgoing`), if any
+    pub direction_filter: Option<TrafficType>,
+    /// Ports never reported (e.g. `[123, 1900]` to hide NTP and SSDP)
+    pub port_blocklist: Vec<u16>,
+    /// Ports exclusively reported, if not empty (the blocklist takes precedence)
+    pub port_allowlist: Vec<u16>,
+    /// Rules to automatically mark connections as favorites as soon as they appear
+    pub favorite_rules: Vec<FavoriteRule>,
+    /// Ports whose connections are flagged as notable as soon as they appear (e.g. `[22, 445, 3389]`
+    /// to highlight SSH, SMB and RDP traffic); if empty no connection is flagged
+    pub notable_ports: Vec<u16>,
+    /// Application protocols of the services running on nonstandard ports (e.g. `"2222" = "SSH"`),
+    /// taking precedence over the well-known ports
+    pub port_protocol_overrides: HashMap<String, AppProtocol>,
+    /// Names of the network adapters sniffed together with the one selected in the GUI
+    pub additional_devices: Vec<String>,
+    /// File where the traffic session is saved on exit and restored at the next start, if any
+    pub session_path: Option<PathBuf>,
+    /// SQLite database to which the connections are periodically logged, if any (created if missing)
+    pub sqlite_path: Option<PathBuf>,
+    /// Address (e.g. `127.0.0.1:9100`) on which the traffic statistics are served as Prometheus metrics, if any
+    /// (only available if built with the `metrics` feature)
+    pub metrics_address: Option<String>,
+    /// Seconds after which the connections not exchanging traffic are evicted; if not specified they are never evicted
+    pub connection_ttl_seconds: Option<u64>,
+    /// Maximum number of connections kept in memory: when it's reached, the least recently active one is evicted
+    /// to make room for a new one (favorites are always kept); if not specified there's no limit
+    pub max_connections: Option<usize>,
+    /// Whether the network adapters are sniffed in promiscuous mode, capturing also the traffic
+    /// not addressed to this host (e.g. on a mirrored switch port); if not specified it's enabled.
+    /// On some platforms promiscuous mode requires elevated privileges.
+    pub promiscuous: Option<bool>,
+    /// Maximum number of bytes captured for each packet; if not specified 256 bytes are captured
+    pub snaplen: Option<i32>,
+    /// Milliseconds after which a read returns even if no packet has been sniffed; if not specified it's 150 ms
+    pub read_timeout_ms: Option<i32>,
+    /// Milliseconds between two consecutive refreshes of the charts and evaluations of the notifications thresholds
+    /// (always expressed per second); if not specified it's 1000 ms
+    pub refresh_interval_ms: Option<u64>,
+    /// Seconds of traffic rates kept to compute statistics about them (e.g. percentiles),
+    /// extended to the time window of the chart if shorter; if not specified it's 300 s
+    pub throughput_history_seconds: Option<usize>,
+    /// Whether the broadcast and multicast traffic is aggregated in two summary entries
+    /// instead of being reported per connection (it can also be changed at runtime)
+    pub aggregate_broadcast_multicast: bool,
+    /// Whether the two directions of each conversation with a remote host are reported as a single connection,
+    /// with the local endpoint first, tracking the sent and received bytes separately
+    pub combine_bidirectional_flows: bool,
+    /// Length of the packets accounted as exchanged bytes: the IP payload (`Payload`, by default)
+    /// or the whole frame on the wire (`Frame`), to measure the bandwidth used on the link
+    pub byte_accounting: ByteAccounting,
+}
+
+/// User defined palette, applied in place of the one selected in the GUI
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ConfigPalette {
+    /// Colors of the palette, if any
+    pub custom: Option<CustomPalette>,
+}
+
+/// Colors of a user defined palette, in the `#RRGGBB` format (see `Palette` for their meaning)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CustomPalette {
+    pub primary: String,
+    pub secondary: String,
+    pub buttons: String,
+    pub incoming: String,
+    pub outgoing: String,
+}
+
+impl CustomPalette {
+    /// Parses the colors, returning a readable error if any of them is not valid.
+    pub fn to_palette(&self) -> Result<Palette, String> {
+        Palette::from_hex(
+            &self.primary,
+            &self.secondary,
+            &self.buttons,
+            &self.incoming,
+            &self.outgoing,
+        )
+    }
+}
@@ -0,0 +1,115 @@
+//! Module defining the `DiscoveryTraffic` struct, which keeps track of the LAN discovery traffic
+//! (ARP and IGMP) not reported per connection.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+/// Type of IGMP membership queries; all the other IGMP messages are membership reports or leave messages
+const IGMP_MEMBERSHIP_QUERY: u8 = 0x11;
+
+/// LAN discovery packet, carried without a transport layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiscoveryPacket {
+    /// ARP request asking who has the target address
+    ArpRequest { target: Ipv4Addr },
+    /// ARP reply announcing the link layer address of the sender
+    ArpReply,
+    /// IGMP message, labeled with its type
+    Igmp { message_type: u8 },
+}
+
+/// ARP and IGMP packets observed, whether or not they are accepted by the filters
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct DiscoveryTraffic {
+    /// ARP requests ("who has <address>?")
+    pub arp_requests: u128,
+    /// ARP replies ("<address> is at <link layer address>")
+    pub arp_replies: u128,
+    /// Number of ARP requests for each target address
+    pub arp_who_has: HashMap<Ipv4Addr, u128>,
+    /// IGMP membership queries, sent by the multicast routers
+    pub igmp_queries: u128,
+    /// IGMP membership reports and leave messages, sent by the members of the multicast groups
+    pub igmp_reports: u128,
+}
+
+impl DiscoveryTraffic {
+    /// Accounts a LAN discovery packet.
+    pub fn add(&mut self, packet: DiscoveryPacket) {
+        match packet {
+            DiscoveryPacket::ArpRequest { target } => {
+                self.arp_requests += 1;
+                *self.arp_who_has.entry(target).or_default() += 1;
+            }
+            DiscoveryPacket::ArpReply => self.arp_replies += 1,
+            DiscoveryPacket::Igmp {
+                message_type: IGMP_MEMBERSHIP_QUERY,
+            } => self.igmp_queries += 1,
+            DiscoveryPacket::Igmp { .. } => self.igmp_reports += 1,
+        }
+    }
+
+    /// Moves the traffic accounted in `other` into `self`, leaving `other` empty.
+    pub fn merge(&mut self, other: &mut DiscoveryTraffic) {
+        let other = std::mem::take(other);
+        self.arp_requests += other.arp_requests;
+        self.arp_replies += other.arp_replies;
+        for (target, requests) in other.arp_who_has {
+            *self.arp_who_has.entry(target).or_default() += requests;
+        }
+        self.igmp_queries += other.igmp_queries;
+        self.igmp_reports += other.igmp_reports;
+    }
+
+    /// Whether no LAN discovery packet has been accounted
+    pub fn is_empty(&self) -> bool {
+        self.arp_requests + self.arp_replies + self.igmp_queries + self.igmp_reports == 0
+    }
+
+    /// Returns the addresses most requested via ARP (at most `n`), with their number of requests.
+    pub fn most_requested(&self, n: usize) -> Vec<(Ipv4Addr, u128)> {
+        let mut who_has: Vec<(Ipv4Addr, u128)> = self
+            .arp_who_has
+            .iter()
+            .map(|(target, requests)| (*target, *requests))
+            .collect();
+        who_has.sort_by(|(t1, r1), (t2, r2)| r2.cmp(r1).then_with(|| t1.cmp(t2)));
+        who_has.truncate(n);
+        who_has
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use crate::structs::discovery_traffic::{DiscoveryPacket, DiscoveryTraffic};
+
+    #[test]
+    fn test_discovery_traffic_accounting() {
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        let printer = Ipv4Addr::new(192, 168, 1, 20);
+        let mut buffered = DiscoveryTraffic::default();
+        assert!(buffered.is_empty());
+        for target in [printer, gateway, printer, Ipv4Addr::new(192, 168, 1, 30)] {
+            buffered.add(DiscoveryPacket::ArpRequest { target });
+        }
+        buffered.add(DiscoveryPacket::ArpReply);
+        buffered.add(DiscoveryPacket::Igmp { message_type: 0x11 });
+        buffered.add(DiscoveryPacket::Igmp { message_type: 0x16 });
+        buffered.add(DiscoveryPacket::Igmp { message_type: 0x17 });
+
+        let mut total = DiscoveryTraffic::default();
+        total.add(DiscoveryPacket::ArpRequest { target: gateway });
+        total.merge(&mut buffered);
+        assert!(buffered.is_empty());
+        assert!(buffered.arp_who_has.is_empty());
+        assert_eq!(total.arp_requests, 5);
+        assert_eq!(total.arp_replies, 1);
+        assert_eq!(total.igmp_queries, 1);
+        assert_eq!(total.igmp_reports, 2);
+        // ties are sorted by address
+        assert_eq!(total.most_requested(2), vec![(gateway, 2), (printer, 2)]);
+        assert_eq!(total.most_requested(10).len(), 3);
+    }
+}
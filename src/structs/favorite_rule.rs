@@ -0,0 +1,228 @@
+//! Module defining the `FavoriteRule` struct, used to automatically mark as favorites
+//! the connections matching an address or port pattern.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::structs::address_port_pair::AddressPortPair;
+
+/// Rule specified in the configuration file to mark connections as favorites as soon as they appear.
+///
+/// A connection matches the rule if either of its endpoints matches all the specified fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FavoriteRule {
+    /// Network in CIDR notation (e.g. `10.0.0.0/8` or `2001:db8::/32`) or single IP address;
+    /// if not specified any address matches
+    pub network: Option<String>,
+    /// Port number; if not specified any port matches
+    pub port: Option<u16>,
+}
+
+impl FavoriteRule {
+    /// Parses the rule, returning a readable error if the network is not valid.
+    pub fn to_matcher(&self) -> Result<FavoriteMatcher, String> {
+        let network = match &self.network {
+            None => None,
+            Some(network) => Some(network.parse()?),
+        };
+        Ok(FavoriteMatcher {
+            network,
+            port: self.port,
+            peer: None,
+        })
+    }
+}
+
+/// Parses the favorite rules, discarding (with a warning) the invalid ones.
+pub fn get_favorite_matchers(rules: &[FavoriteRule]) -> Vec<FavoriteMatcher> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            rule.to_matcher()
+                .map_err(|err| warn!(%err, "favorite rule ignored"))
+                .ok()
+        })
+        .collect()
+}
+
+/// Parsed version of a `FavoriteRule` (or of a saved favorite), ready to be matched against connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FavoriteMatcher {
+    network: Option<IpNetwork>,
+    port: Option<u16>,
+    /// Address of the other endpoint, if the rule matches a single address pair
+    peer: Option<IpAddr>,
+}
+
+impl FavoriteMatcher {
+    /// Constructs the matcher of the connections between the two addresses, in either direction and with any port.
+    pub fn address_pair(address1: IpAddr, address2: IpAddr) -> Self {
+        let max_prefix_len = if address1.is_ipv4() { 32 } else { 128 };
+        FavoriteMatcher {
+            network: Some(IpNetwork {
+                address: address1,
+                prefix_len: max_prefix_len,
+            }),
+            port: None,
+            peer: Some(address2),
+        }
+    }
+
+    /// Checks if either of the endpoints of the connection matches the rule.
+    pub fn matches(&self, key: &AddressPortPair) -> bool {
+        self.matches_endpoint(&key.address1, key.port1, &key.address2)
+            || self.matches_endpoint(&key.address2, key.port2, &key.address1)
+    }
+
+    fn matches_endpoint(&self, address: &str, port: u16, peer_address: &str) -> bool {
+        let port_matches = self.port.is_none_or(|rule_port| rule_port == port);
+        let network_matches = self.network.is_none_or(|network| {
+            address
+                .parse()
+                .is_ok_and(|address| network.contains(address))
+        });
+        let peer_matches = self
+            .peer
+            .is_none_or(|peer| peer_address.parse() == Ok(peer));
+        port_matches && network_matches && peer_matches
+    }
+}
+
+/// IPv4 or IPv6 network, identified by an address and a prefix length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Checks if the address belongs to the network (addresses of a different IP version never do).
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(network: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match network.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (network, None),
+        };
+        let address: IpAddr = address
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid network address in \"{network}\""))?;
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            None => max_prefix_len,
+            Some(prefix_len) => prefix_len
+                .trim()
+                .parse()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_prefix_len)
+                .ok_or_else(|| format!("invalid prefix length in \"{network}\""))?,
+        };
+        Ok(IpNetwork {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::favorite_rule::{get_favorite_matchers, FavoriteRule, IpNetwork};
+    use crate::TransProtocol;
+
+    fn rule(network: Option<&str>, port: Option<u16>) -> FavoriteRule {
+        FavoriteRule {
+            network: network.map(ToString::to_string),
+            port,
+        }
+    }
+
+    fn key(address1: &str, port1: u16, address2: &str, port2: u16) -> AddressPortPair {
+        AddressPortPair::new(
+            address1.to_string(),
+            port1,
+            address2.to_string(),
+            port2,
+            TransProtocol::TCP,
+        )
+    }
+
+    #[test]
+    fn test_ip_network_contains() {
+        let network: IpNetwork = "10.0.0.0/8".parse().unwrap();
+        assert!(network.contains("10.255.1.2".parse().unwrap()));
+        assert!(!network.contains("11.0.0.1".parse().unwrap()));
+        assert!(!network.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let network: IpNetwork = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains("2001:db8:1::1".parse().unwrap()));
+        assert!(!network.contains("2001:db9::1".parse().unwrap()));
+        assert!(!network.contains("10.0.0.1".parse().unwrap()));
+
+        let single: IpNetwork = "192.168.1.1".parse().unwrap();
+        assert!(single.contains("192.168.1.1".parse().unwrap()));
+        assert!(!single.contains("192.168.1.2".parse().unwrap()));
+
+        let any: IpNetwork = "::/0".parse().unwrap();
+        assert!(any.contains("fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_invalid_networks() {
+        for network in [
+            "10.0.0/8",
+            "10.0.0.0/33",
+            "2001:db8::/129",
+            "host/24",
+            "10.0.0.0/",
+        ] {
+            assert!(network.parse::<IpNetwork>().is_err(), "{network}");
+        }
+        let matchers =
+            get_favorite_matchers(&[rule(Some("10.0.0.0/33"), None), rule(None, Some(22))]);
+        assert_eq!(matchers.len(), 1);
+    }
+
+    #[test]
+    fn test_favorite_rules_matching() {
+        let matchers = get_favorite_matchers(&[
+            rule(Some("192.168.1.0/24"), Some(22)),
+            rule(Some("2001:db8::/32"), None),
+            rule(None, Some(853)),
+        ]);
+        let matches = |key: &AddressPortPair| matchers.iter().any(|matcher| matcher.matches(key));
+
+        assert!(matches(&key("10.0.0.1", 50000, "192.168.1.7", 22)));
+        assert!(matches(&key("192.168.1.7", 22, "10.0.0.1", 50000)));
+        // network and port must match on the same endpoint
+        assert!(!matches(&key("192.168.1.7", 50000, "10.0.0.1", 22)));
+        assert!(matches(&key("2001:db8::5", 443, "fe80::1", 50000)));
+        assert!(!matches(&key("2001:db9::5", 443, "fe80::1", 50000)));
+        assert!(matches(&key("10.0.0.1", 50000, "1.1.1.1", 853)));
+        assert!(!matches(&key("10.0.0.1", 50000, "1.1.1.1", 443)));
+    }
+}
@@ -1,5 +1,13 @@
 //! Module defining the `Filters` struct, which represents the possible filters applicable on network traffic.
 
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::enums::byte_accounting::ByteAccounting;
+use crate::enums::network_scope::NetworkScope;
+use crate::enums::traffic_type::TrafficType;
+use crate::structs::favorite_rule::FavoriteMatcher;
+use crate::structs::pcap_export::PcapExport;
 use crate::{AppProtocol, IpVersion, TransProtocol};
 
 /// Possible filters applicable to network traffic
@@ -11,6 +19,30 @@ pub struct Filters {
     pub transport: TransProtocol,
     /// Application layer protocol
     pub application: AppProtocol,
+    /// Scope of the connections (local network only or WAN only)
+    pub scope: NetworkScope,
+    /// Direction of the connections (e.g. only outgoing ones), if any.
+    ///
+    /// It's applied when the packets are parsed, like the other filters: since connections are identified
+    /// by source and destination, all the packets of a connection share its direction, and the packets
+    /// in the opposite direction (e.g. the replies to outgoing requests) belong to a distinct connection.
+    pub direction_filter: Option<TrafficType>,
+    /// Ports never reported: packets with either port in this list are rejected
+    pub port_blocklist: Vec<u16>,
+    /// Ports exclusively reported, if not empty: packets with neither port in this list are rejected
+    pub port_allowlist: Vec<u16>,
+    /// Destination of the pcap export of the accepted packets, if any
+    pub pcap_export: Option<PcapExport>,
+    /// Berkeley Packet Filter expression applied to the capture, if any
+    pub bpf: Option<String>,
+    /// Rules to automatically mark connections as favorites
+    pub favorite_rules: Vec<FavoriteMatcher>,
+    /// Ports whose connections are flagged as notable: packets with either port in this list mark their connection
+    pub notable_ports: Vec<u16>,
+    /// Application protocols of the ports, taking precedence over the well-known ones
+    pub port_protocol_overrides: HashMap<u16, AppProtocol>,
+    /// Length of the packets accounted as exchanged bytes
+    pub byte_accounting: ByteAccounting,
 }
 
 impl Default for Filters {
@@ -19,6 +51,288 @@ impl Default for Filters {
             ip: IpVersion::Other,
             transport: TransProtocol::Other,
             application: AppProtocol::Other,
+            scope: NetworkScope::Other,
+            direction_filter: None,
+            port_blocklist: Vec::new(),
+            port_allowlist: Vec::new(),
+            pcap_export: None,
+            bpf: None,
+            favorite_rules: Vec::new(),
+            notable_ports: Vec::new(),
+            port_protocol_overrides: HashMap::new(),
+            byte_accounting: ByteAccounting::default(),
+        }
+    }
+}
+
+/// Returns the variant whose name matches the value (case-insensitive).
+fn parse_variant<T: fmt::Debug>(
+    variants: impl IntoIterator<Item = T>,
+    key: &str,
+    value: &str,
+) -> Result<T, FilterParseError> {
+    variants
+        .into_iter()
+        .find(|variant| format!("{variant:?}").eq_ignore_ascii_case(value))
+        .ok_or_else(|| FilterParseError::UnknownValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+}
+
+/// Error occurred while parsing the textual representation of the filters
+#[derive(Debug, PartialEq, Eq)]
+pub enum FilterParseError {
+    /// Token not in the `key=value` form
+    MissingValue(String),
+    /// Key not corresponding to any filter
+    UnknownKey(String),
+    /// Value not valid for the filter identified by the key
+    UnknownValue { key: String, value: String },
+}
+
+impl fmt::Display for FilterParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterParseError::MissingValue(token) => {
+                write!(f, "\"{token}\" is not in the key=value form")
+            }
+            FilterParseError::UnknownKey(key) => write!(
+                f,
+                "unknown filter \"{key}\" (expected ip, transport, app, port, scope or direction)"
+            ),
+            FilterParseError::UnknownValue { key, value } => {
+                write!(f, "unknown value \"{value}\" for filter \"{key}\"")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FilterParseError {}
+
+impl Filters {
+    /// Parses filters expressed as whitespace separated `key=value` tokens,
+    /// e.g. `"ip=ipv6 transport=udp app=dns port=53"`.
+    ///
+    /// Keys and values are case-insensitive; the `port` key can be repeated, and its values are added to the allowlist.
+    /// The filters not specified are not applied.
+    pub fn parse(s: &str) -> Result<Filters, FilterParseError> {
+        let mut filters = Filters::default();
+        for token in s.split_whitespace() {
+            let Some((key, value)) = token.split_once('=') else {
+                return Err(FilterParseError::MissingValue(token.to_string()));
+            };
+            match key.to_ascii_lowercase().as_str() {
+                // the `Other` variants of the protocols mean that they are not filtered
+                "ip" => {
+                    let versions = IpVersion::ALL.into_iter();
+                    filters.ip =
+                        parse_variant(versions.filter(|v| *v != IpVersion::Other), key, value)?;
+                }
+                "transport" => {
+                    let protocols = TransProtocol::ALL.into_iter();
+                    filters.transport = parse_variant(
+                        protocols.filter(|p| *p != TransProtocol::Other),
+                        key,
+                        value,
+                    )?;
+                }
+                "app" => {
+                    let protocols = AppProtocol::ALL.into_iter();
+                    filters.application =
+                        parse_variant(protocols.filter(|p| *p != AppProtocol::Other), key, value)?;
+                }
+                "port" => filters.port_allowlist.push(value.parse().map_err(|_| {
+                    FilterParseError::UnknownValue {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    }
+                })?),
+                "scope" => {
+                    filters.scope =
+                        parse_variant([NetworkScope::Local, NetworkScope::Wan], key, value)?;
+                }
+                "direction" => {
+                    filters.direction_filter = Some(parse_variant(
+                        [
+                            TrafficType::Incoming,
+                            TrafficType::Outgoing,
+                            TrafficType::Local,
+                            TrafficType::Multicast,
+                            TrafficType::Broadcast,
+                            TrafficType::Other,
+                        ],
+                        key,
+                        value,
+                    )?);
+                }
+                _ => return Err(FilterParseError::UnknownKey(key.to_string())),
+            }
+        }
+        Ok(filters)
+    }
+
+    /// Returns the textual representation of the filters, in the syntax accepted by `parse`
+    /// (e.g. `"ip=IPv6 transport=UDP port=53"`, or an empty string if no filter is applied).
+    ///
+    /// The ports blocklist, the BPF expression and the other capture options are not represented.
+    pub fn to_filter_string(&self) -> String {
+        let mut tokens = Vec::new();
+        if self.ip != IpVersion::Other {
+            tokens.push(format!("ip={:?}", self.ip));
+        }
+        if self.transport != TransProtocol::Other {
+            tokens.push(format!("transport={:?}", self.transport));
+        }
+        if self.application != AppProtocol::Other {
+            tokens.push(format!("app={:?}", self.application));
+        }
+        if self.scope != NetworkScope::Other {
+            tokens.push(format!("scope={:?}", self.scope));
+        }
+        if let Some(direction) = self.direction_filter {
+            tokens.push(format!("direction={direction:?}"));
+        }
+        for port in &self.port_allowlist {
+            tokens.push(format!("port={port}"));
+        }
+        tokens.join(" ")
+    }
+
+    /// Checks whether a packet with the given ports is accepted by the ports lists.
+    ///
+    /// The blocklist takes precedence: a blocked port is rejected even if it's also in the allowlist.
+    pub fn accepts_ports(&self, port1: u16, port2: u16) -> bool {
+        let is_blocked =
+            self.port_blocklist.contains(&port1) || self.port_blocklist.contains(&port2);
+        let is_allowed = self.port_allowlist.is_empty()
+            || self.port_allowlist.contains(&port1)
+            || self.port_allowlist.contains(&port2);
+        !is_blocked && is_allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::network_scope::NetworkScope;
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::filters::{FilterParseError, Filters};
+    use crate::{AppProtocol, IpVersion, TransProtocol};
+
+    #[test]
+    fn test_accepts_ports_without_lists() {
+        let filters = Filters::default();
+        assert!(filters.accepts_ports(50000, 443));
+        assert!(filters.accepts_ports(0, 0));
+    }
+
+    #[test]
+    fn test_accepts_ports_blocklist() {
+        let filters = Filters {
+            port_blocklist: vec![123, 1900],
+            ..Filters::default()
+        };
+        assert!(!filters.accepts_ports(50000, 123));
+        assert!(!filters.accepts_ports(1900, 50000));
+        assert!(filters.accepts_ports(50000, 443));
+    }
+
+    #[test]
+    fn test_accepts_ports_allowlist() {
+        let filters = Filters {
+            port_allowlist: vec![53, 443],
+            ..Filters::default()
+        };
+        assert!(filters.accepts_ports(50000, 443));
+        assert!(filters.accepts_ports(53, 50000));
+        assert!(!filters.accepts_ports(50000, 80));
+    }
+
+    #[test]
+    fn test_accepts_ports_blocklist_takes_precedence() {
+        let filters = Filters {
+            port_blocklist: vec![123],
+            port_allowlist: vec![123, 443],
+            ..Filters::default()
+        };
+        // blocked even if allowed
+        assert!(!filters.accepts_ports(50000, 123));
+        // one port allowed, the other blocked
+        assert!(!filters.accepts_ports(443, 123));
+        assert!(filters.accepts_ports(50000, 443));
+        assert!(!filters.accepts_ports(50000, 80));
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        let filters = Filters::parse("ip=ipv6 transport=udp app=dns port=53").unwrap();
+        assert_eq!(filters.ip, IpVersion::IPv6);
+        assert_eq!(filters.transport, TransProtocol::UDP);
+        assert_eq!(filters.application, AppProtocol::DNS);
+        assert_eq!(filters.port_allowlist, vec![53]);
+        assert_eq!(filters.scope, NetworkScope::Other);
+        assert_eq!(filters.direction_filter, None);
+
+        let filters =
+            Filters::parse("  APP=mdns\tscope=WAN direction=outgoing port=5353 port=53 ").unwrap();
+        assert_eq!(filters.application, AppProtocol::mDNS);
+        assert_eq!(filters.scope, NetworkScope::Wan);
+        assert_eq!(filters.direction_filter, Some(TrafficType::Outgoing));
+        assert_eq!(filters.port_allowlist, vec![5353, 53]);
+        assert_eq!(filters.ip, IpVersion::Other);
+
+        let filters = Filters::parse("").unwrap();
+        assert_eq!(filters.ip, IpVersion::Other);
+        assert!(filters.port_allowlist.is_empty());
+    }
+
+    #[test]
+    fn test_filter_string_round_trip() {
+        assert_eq!(Filters::default().to_filter_string(), "");
+        for filter in [
+            "ip=IPv6 transport=UDP app=DNS port=53",
+            "app=mDNS scope=Wan direction=Outgoing port=5353 port=53",
+            "ip=IPv4 scope=Local",
+        ] {
+            let filters = Filters::parse(filter).unwrap();
+            assert_eq!(filters.to_filter_string(), filter);
+        }
+    }
+
+    #[test]
+    fn test_parse_malformed_filters() {
+        assert_eq!(
+            Filters::parse("ip=ipv6 udp").err(),
+            Some(FilterParseError::MissingValue("udp".to_string()))
+        );
+        assert_eq!(
+            Filters::parse("protocol=udp").err(),
+            Some(FilterParseError::UnknownKey("protocol".to_string()))
+        );
+        for (filter, key, value) in [
+            ("app=gopher", "app", "gopher"),
+            ("transport=sctp", "transport", "sctp"),
+            ("ip=ipv5", "ip", "ipv5"),
+            ("ip=", "ip", ""),
+            // the unfiltered values are not accepted
+            ("app=other", "app", "other"),
+            ("port=65536", "port", "65536"),
+            ("port=http", "port", "http"),
+            ("scope=lan", "scope", "lan"),
+            ("direction=sideways", "direction", "sideways"),
+        ] {
+            assert_eq!(
+                Filters::parse(filter).err(),
+                Some(FilterParseError::UnknownValue {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                }),
+                "{filter}"
+            );
         }
+        assert_eq!(
+            Filters::parse("app=gopher").err().unwrap().to_string(),
+            "unknown value \"gopher\" for filter \"app\""
+        );
     }
 }
@@ -0,0 +1,63 @@
+//! Module defining the `HostnameCache` struct, used to remember the results of reverse DNS lookups.
+
+use std::net::IpAddr;
+
+use indexmap::IndexMap;
+
+/// Bounded least recently used cache of the hostnames associated with IP addresses.
+///
+/// Failed lookups are cached as well (with a `None` hostname), to avoid repeating them.
+pub struct HostnameCache {
+    /// Maximum number of addresses stored in the cache
+    capacity: usize,
+    /// Cached hostnames, ordered from the least to the most recently used
+    entries: IndexMap<IpAddr, Option<String>>,
+}
+
+impl HostnameCache {
+    /// Constructs a new empty `HostnameCache` storing at most `capacity` addresses.
+    pub fn new(capacity: usize) -> Self {
+        HostnameCache {
+            capacity,
+            entries: IndexMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the cached lookup result for the given address, if any, marking it as the most recently used.
+    pub fn get(&mut self, address: &IpAddr) -> Option<Option<String>> {
+        let hostname = self.entries.shift_remove(address)?;
+        self.entries.insert(*address, hostname.clone());
+        Some(hostname)
+    }
+
+    /// Stores the lookup result for the given address, evicting the least recently used one if the cache is full.
+    pub fn insert(&mut self, address: IpAddr, hostname: Option<String>) {
+        self.entries.shift_remove(&address);
+        if self.entries.len() >= self.capacity {
+            self.entries.shift_remove_index(0);
+        }
+        self.entries.insert(address, hostname);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::structs::hostname_cache::HostnameCache;
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let a: IpAddr = "8.8.8.8".parse().unwrap();
+        let b: IpAddr = "1.1.1.1".parse().unwrap();
+        let c: IpAddr = "2001:4860:4860::8888".parse().unwrap();
+        let mut cache = HostnameCache::new(2);
+        cache.insert(a, Some("dns.google".to_string()));
+        cache.insert(b, None);
+        assert_eq!(cache.get(&a), Some(Some("dns.google".to_string())));
+        cache.insert(c, Some("dns.google".to_string()));
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(cache.get(&a), Some(Some("dns.google".to_string())));
+        assert_eq!(cache.get(&c), Some(Some("dns.google".to_string())));
+    }
+}
@@ -1,82 +1,397 @@
 //! Module defining the `InfoAddressPortPair` struct, useful to format the output report file and
 //! to keep track of statistics about the sniffed traffic.
 
-use chrono::{DateTime, Local};
-use std::fmt;
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
 use std::ops::Add;
 
+use crate::enums::tcp_state::TcpState;
 use crate::enums::traffic_type::TrafficType;
-use crate::utility::get_formatted_strings::get_formatted_bytes_string;
-use crate::AppProtocol;
+use crate::structs::address_port_pair::{AddressPortPair, MAX_IPV6_ADDRESS_LEN};
+use crate::structs::asn::Asn;
+use crate::structs::mac_address::MacAddress;
+use crate::utility::get_formatted_strings::{get_formatted_bytes_string, get_formatted_duration};
+use crate::{AppProtocol, UnitSystem};
 
 /// Struct useful to format the output report file and to keep track of statistics about the sniffed traffic.
 ///
 /// Each `InfoAddressPortPair` struct is associated to a single address:port pair.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct InfoAddressPortPair {
     /// Amount of bytes transmitted between the pair.
     pub transmitted_bytes: u128,
     /// Amount of packets transmitted between the pair.
     pub transmitted_packets: u128,
+    /// Amount of bytes sent by this host, out of the transmitted ones
+    #[serde(default)]
+    pub sent_bytes: u128,
+    /// Amount of bytes received by this host (or exchanged between other hosts), out of the transmitted ones
+    #[serde(default)]
+    pub received_bytes: u128,
+    /// Amount of packets sent by this host, out of the transmitted ones
+    #[serde(default)]
+    pub sent_packets: u128,
+    /// Amount of packets received by this host (or exchanged between other hosts), out of the transmitted ones
+    #[serde(default)]
+    pub received_packets: u128,
     /// First occurrence of information exchange featuring the associate address:port pair as a source or destination.
     pub initial_timestamp: DateTime<Local>,
     /// Last occurrence of information exchange featuring the associate address:port pair as a source or destination.
     pub final_timestamp: DateTime<Local>,
     /// Set of application layer protocols carried by the associated address:port pair.
     pub app_protocol: AppProtocol,
-    /// Check if source or destination is an IPv6 address longer than 25 bytes (used for Display
-    pub very_long_address: bool,
     /// Flag to determine which of the address is that of the sniffed adapter or remote
     pub traffic_type: TrafficType,
     /// Country of the remote IP address
     pub country: String,
-    /// Integer corresponding to the index inside the connections map
-    pub index: usize,
+    /// Latitude and longitude of the remote IP address, if a city database is available
+    #[serde(default)]
+    pub location: Option<(f32, f32)>,
+    /// Autonomous System of the remote IP address
+    pub asn: Asn,
+    /// Hostname of the remote IP address, filled in once resolved via reverse DNS
+    pub hostname: Option<String>,
+    /// Domain name of the most recent DNS (or mDNS) query exchanged by the associated address:port pair
+    pub dns_query: Option<String>,
+    /// Server name indicated by the most recent TLS ClientHello exchanged by the associated address:port pair
+    pub tls_sni: Option<String>,
+    /// Source MAC address of the most recent packet exchanged by the associated address:port pair (if available)
+    pub src_mac: Option<MacAddress>,
+    /// Destination MAC address of the most recent packet exchanged by the associated address:port pair (if available)
+    pub dst_mac: Option<MacAddress>,
+    /// VLAN identifier of the most recent 802.1Q tagged packet exchanged by the associated address:port pair
+    /// (the outer one for double tagged frames)
+    #[serde(default)]
+    pub vlan: Option<u16>,
+    /// Differentiated Services Code Point (the upper 6 bits of the IPv4 ToS or IPv6 traffic class)
+    /// of the most recent packet exchanged by the associated address:port pair
+    #[serde(default)]
+    pub dscp: u8,
+    /// Type and code of the most recent ICMP or ICMPv6 message exchanged by the associated address pair
+    pub icmp_type_code: Option<(u8, u8)>,
+    /// State of the TCP connection to which the associated address:port pair belongs (`None` for other protocols)
+    pub tcp_state: Option<TcpState>,
+    /// Name of the network adapter on which the associated address:port pair has been observed
+    pub interface: String,
+    /// Stable identifier of the connection, assigned when it's inserted into the connections map
+    /// (see `InfoTraffic::insert_connection`)
+    #[serde(skip)]
+    pub id: u64,
     /// Flag that indicates if this connection is marked as favourite
     pub is_favorite: bool,
+    /// Flag that indicates if the remote address is the default gateway of the network adapter
+    #[serde(default)]
+    pub is_gateway: bool,
+    /// Flag that indicates if either port is one of the configured notable ports (e.g. 22 for SSH),
+    /// to highlight the connection regardless of it being a favourite
+    #[serde(default)]
+    pub is_notable: bool,
+    /// Traffic snapshot taken at the last refresh, used to compute the rate of the connection
+    #[serde(skip)]
+    pub rate: RateSnapshot,
+}
+
+/// Snapshot of the traffic exchanged by a connection at the last refresh interval,
+/// used to compute the rate of the connection over the most recent interval.
+#[derive(Clone, Default)]
+pub struct RateSnapshot {
+    /// Time of the last refresh (`None` if the connection has never been refreshed)
+    timestamp: Option<DateTime<Local>>,
+    /// Amount of bytes transmitted at the time of the last refresh
+    transmitted_bytes: u128,
+    /// Amount of packets transmitted at the time of the last refresh
+    transmitted_packets: u128,
+    /// Bytes per second transmitted between the last two refreshes
+    bytes_per_second: f64,
+    /// Packets per second transmitted between the last two refreshes
+    packets_per_second: f64,
+}
+
+impl InfoAddressPortPair {
+    /// Constructs a connection first observed at the time of the call on the given network adapter,
+    /// which didn't exchange any traffic yet.
+    pub fn new(interface: String, traffic_type: TrafficType, app_protocol: AppProtocol) -> Self {
+        let now = Local::now();
+        InfoAddressPortPair {
+            transmitted_bytes: 0,
+            transmitted_packets: 0,
+            sent_bytes: 0,
+            received_bytes: 0,
+            sent_packets: 0,
+            received_packets: 0,
+            initial_timestamp: now,
+            final_timestamp: now,
+            app_protocol,
+            traffic_type,
+            country: String::new(),
+            location: None,
+            asn: Asn::default(),
+            hostname: None,
+            dns_query: None,
+            tls_sni: None,
+            src_mac: None,
+            dst_mac: None,
+            vlan: None,
+            dscp: 0,
+            icmp_type_code: None,
+            tcp_state: None,
+            interface,
+            id: 0,
+            is_favorite: false,
+            is_gateway: false,
+            is_notable: false,
+            rate: RateSnapshot::default(),
+        }
+    }
+}
+
+impl Default for InfoAddressPortPair {
+    fn default() -> Self {
+        InfoAddressPortPair::new(String::new(), TrafficType::Other, AppProtocol::Other)
+    }
 }
 
 impl InfoAddressPortPair {
-    pub fn print_gui(&self) -> String {
-        self.to_string()
+    /// Returns the row displayed in the connections report, expressing the bytes in the given unit system.
+    pub fn print_gui(&self, unit_system: UnitSystem) -> String {
+        self.format_row(unit_system)
             .get(0..37)
             .unwrap()
             .to_string()
             .replace('|', "")
-            .add(&*format!(" {} ", &self.country))
+            .add(&*format!(
+                "{:>8}    {} ",
+                get_formatted_duration(self.duration()),
+                &self.country
+            ))
+    }
+}
+
+impl InfoAddressPortPair {
+    /// Returns a description of the remote host, made up of its TLS server name, hostname and Autonomous System
+    /// (empty if none of them is known).
+    pub fn remote_host_description(&self) -> String {
+        let asn = self.asn.to_string();
+        let mut lines: Vec<&str> = Vec::new();
+        if let Some(tls_sni) = &self.tls_sni {
+            lines.push(tls_sni);
+        }
+        if let Some(hostname) = &self.hostname {
+            if self.tls_sni.as_ref() != Some(hostname) {
+                lines.push(hostname);
+            }
+        }
+        if !asn.is_empty() {
+            lines.push(&asn);
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+impl InfoAddressPortPair {
+    /// Constructs a connection observed on `eth0` (and not yet inserted into any map) which exchanged
+    /// the given traffic, first and last at the time of the call.
+    pub fn with_traffic(
+        traffic_type: TrafficType,
+        app_protocol: AppProtocol,
+        transmitted_bytes: u128,
+        transmitted_packets: u128,
+    ) -> Self {
+        let (sent_bytes, received_bytes, sent_packets, received_packets) =
+            if traffic_type == TrafficType::Outgoing {
+                (transmitted_bytes, 0, transmitted_packets, 0)
+            } else {
+                (0, transmitted_bytes, 0, transmitted_packets)
+            };
+        InfoAddressPortPair {
+            transmitted_bytes,
+            transmitted_packets,
+            sent_bytes,
+            received_bytes,
+            sent_packets,
+            received_packets,
+            ..InfoAddressPortPair::new("eth0".to_string(), traffic_type, app_protocol)
+        }
+    }
+}
+
+impl InfoAddressPortPair {
+    /// Updates the rate of the connection with the traffic exchanged since the previous refresh.
+    ///
+    /// The first interval of a connection starts at its `initial_timestamp`.
+    pub fn refresh_rate(&mut self, now: DateTime<Local>) {
+        let interval_start = self.rate.timestamp.unwrap_or(self.initial_timestamp);
+        #[allow(clippy::cast_precision_loss)]
+        let interval_secs = (now - interval_start).num_milliseconds() as f64 / 1000.0;
+        if interval_secs <= 0.0 {
+            return;
+        }
+        let new_bytes = self.transmitted_bytes - self.rate.transmitted_bytes;
+        let new_packets = self.transmitted_packets - self.rate.transmitted_packets;
+        #[allow(clippy::cast_precision_loss)]
+        let (new_bytes, new_packets) = (new_bytes as f64, new_packets as f64);
+        self.rate = RateSnapshot {
+            timestamp: Some(now),
+            transmitted_bytes: self.transmitted_bytes,
+            transmitted_packets: self.transmitted_packets,
+            bytes_per_second: new_bytes / interval_secs,
+            packets_per_second: new_packets / interval_secs,
+        };
+    }
+
+    /// Restarts the rate computation from the current traffic of the connection,
+    /// so that the traffic exchanged before `now` is not accounted in the next refresh interval.
+    pub fn resume_rate(&mut self, now: DateTime<Local>) {
+        self.rate = RateSnapshot {
+            timestamp: Some(now),
+            transmitted_bytes: self.transmitted_bytes,
+            transmitted_packets: self.transmitted_packets,
+            ..RateSnapshot::default()
+        };
+    }
+
+    /// Bytes per second exchanged by the connection in the most recent refresh interval.
+    pub fn bytes_per_second(&self) -> f64 {
+        self.rate.bytes_per_second
+    }
+
+    /// Packets per second exchanged by the connection in the most recent refresh interval.
+    pub fn packets_per_second(&self) -> f64 {
+        self.rate.packets_per_second
+    }
+
+    /// Total bytes exchanged by the connection, in both the directions.
+    pub fn total_bytes(&self) -> u128 {
+        self.sent_bytes + self.received_bytes
+    }
+
+    /// Time elapsed between the first and the last packet of the connection (zero for a single packet).
+    pub fn duration(&self) -> Duration {
+        self.final_timestamp - self.initial_timestamp
     }
 }
 
-impl fmt::Display for InfoAddressPortPair {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let bytes_string = get_formatted_bytes_string(self.transmitted_bytes);
+impl InfoAddressPortPair {
+    /// Returns the row of the report file for the connection identified by the given address:port pair,
+    /// with address columns `column_width` wide (see `AddressPortPair::address_column_width`).
+    ///
+    /// Rows are padded to the same length, regardless of the width of their address columns.
+    pub fn print_report(&self, key: &AddressPortPair, column_width: usize) -> String {
+        let padding = 2 * MAX_IPV6_ADDRESS_LEN
+            .max(column_width)
+            .saturating_sub(key.address_column_width(column_width));
+        format!(
+            "{}{}{}",
+            key.format_columns(column_width),
+            self.format_row(UnitSystem::default()),
+            " ".repeat(padding)
+        )
+    }
+}
+
+impl InfoAddressPortPair {
+    /// Formats the traffic of the connection as a row of the report, expressing the bytes in the given unit system.
+    fn format_row(&self, unit_system: UnitSystem) -> String {
+        let bytes_string = get_formatted_bytes_string(self.total_bytes(), unit_system);
 
         let app_string = match self.app_protocol {
             AppProtocol::Other => "Other".to_string(),
             _ => self.app_protocol.to_string(),
         };
 
-        if self.very_long_address {
-            write!(
-                f,
-                "{:^9}|{:>10}  |{:>10}  | {} | {} |",
-                app_string,
-                self.transmitted_packets,
-                bytes_string,
-                self.initial_timestamp.to_string().get(0..19).unwrap(),
-                self.final_timestamp.to_string().get(0..19).unwrap()
-            )
-        } else {
-            write!(
-                f,
-                "{:^9}|{:>10}  |{:>10}  | {} | {} |{}",
-                app_string,
-                self.transmitted_packets,
-                bytes_string,
-                self.initial_timestamp.to_string().get(0..19).unwrap(),
-                self.final_timestamp.to_string().get(0..19).unwrap(),
-                " ".repeat(40)
+        format!(
+            "{:^9}|{:>10}  |{:>10}  | {} | {} |",
+            app_string,
+            self.transmitted_packets,
+            bytes_string,
+            self.initial_timestamp.to_string().get(0..19).unwrap(),
+            self.final_timestamp.to_string().get(0..19).unwrap()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, TimeZone};
+
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::{AppProtocol, TransProtocol, UnitSystem};
+
+    #[test]
+    fn test_report_rows_have_the_same_length() {
+        let info =
+            InfoAddressPortPair::with_traffic(TrafficType::Incoming, AppProtocol::HTTPS, 1500, 1);
+        let row = |address: &str| {
+            info.print_report(
+                &AddressPortPair::new(
+                    address.to_string(),
+                    443,
+                    "192.168.1.10".to_string(),
+                    50000,
+                    TransProtocol::TCP,
+                ),
+                25,
             )
-        }
+        };
+        let short_row = row("93.184.216.34");
+        let long_row = row("2001:db8:85a3:8d3:1319:8a2e:370:7348");
+        assert_eq!(short_row.len(), long_row.len());
+        assert!(short_row.ends_with(&" ".repeat(40)));
+        assert!(long_row.ends_with('|'));
+    }
+
+    #[test]
+    fn test_duration() {
+        let mut info =
+            InfoAddressPortPair::with_traffic(TrafficType::Outgoing, AppProtocol::DNS, 80, 1);
+        // a single packet
+        assert_eq!(info.duration(), Duration::zero());
+        assert!(info.print_gui(UnitSystem::Decimal).contains("      0s"));
+
+        info.final_timestamp = info.initial_timestamp + Duration::seconds(185);
+        assert_eq!(info.duration(), Duration::seconds(185));
+        assert!(info.print_gui(UnitSystem::Decimal).contains("  3m 05s"));
+    }
+
+    #[test]
+    fn test_rate_reflects_most_recent_interval() {
+        let start = Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap();
+        let mut info = InfoAddressPortPair {
+            transmitted_bytes: 1000,
+            transmitted_packets: 10,
+            initial_timestamp: start,
+            final_timestamp: start,
+            app_protocol: AppProtocol::HTTPS,
+            traffic_type: TrafficType::Outgoing,
+            ..InfoAddressPortPair::default()
+        };
+        assert_eq!(info.bytes_per_second(), 0.0);
+
+        // first interval starts at the initial timestamp
+        info.refresh_rate(start + Duration::seconds(2));
+        assert_eq!(info.bytes_per_second(), 500.0);
+        assert_eq!(info.packets_per_second(), 5.0);
+
+        // only the traffic of the last interval is considered
+        info.transmitted_bytes += 300;
+        info.transmitted_packets += 1;
+        info.refresh_rate(start + Duration::seconds(3));
+        assert_eq!(info.bytes_per_second(), 300.0);
+        assert_eq!(info.packets_per_second(), 1.0);
+
+        // idle connection
+        info.refresh_rate(start + Duration::milliseconds(3500));
+        assert_eq!(info.bytes_per_second(), 0.0);
+        assert_eq!(info.packets_per_second(), 0.0);
+
+        // refreshing at the same instant doesn't alter the rate
+        info.transmitted_bytes += 100;
+        info.refresh_rate(start + Duration::milliseconds(3500));
+        assert_eq!(info.bytes_per_second(), 0.0);
+        info.refresh_rate(start + Duration::seconds(4));
+        assert_eq!(info.bytes_per_second(), 200.0);
     }
 }
@@ -1,16 +1,79 @@
 //! Module defining the `ReportInfo` struct, useful to format the output report file and
 //! to keep track of statistics about the sniffed traffic.
 
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
 
+use chrono::{DateTime, Local};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
+use crate::enums::metric::Metric;
+use crate::enums::sort_column::SortColumn;
 use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::discovery_traffic::DiscoveryTraffic;
 use crate::structs::info_address_port_pair::InfoAddressPortPair;
-use crate::AppProtocol;
+use crate::utility::anonymize::get_exported_connection;
+use crate::{AppProtocol, TransProtocol};
+
+/// Version of the JSON schema produced by `InfoTraffic::export_json`, to be incremented at every breaking change
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Key under which the traffic of the connections without a country (e.g. local ones) is aggregated by `country_breakdown`
+pub const LOCAL_COUNTRY: &str = "LOCAL";
+
+/// Number of buckets of the packet size histogram: the first one contains the packets up to 64 bytes,
+/// each of the following ones doubles the upper bound, and the last one contains the packets bigger than 1024 bytes
+pub const SIZE_BUCKETS: usize = 6;
+
+/// Returns the bucket of the packet size histogram in which a packet of the given size falls.
+pub fn get_size_bucket(bytes: u128) -> usize {
+    // number of bits needed to represent the bucket upper bound (64 bytes => 7 bits)
+    let bits = (u128::BITS - bytes.saturating_sub(1).leading_zeros()) as usize;
+    bits.saturating_sub(6).min(SIZE_BUCKETS - 1)
+}
+
+/// Traffic aggregated in a single summary entry instead of being tracked per connection
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct TrafficSummary {
+    /// Total amount of exchanged packets
+    pub packets: u128,
+    /// Total amount of exchanged bytes
+    pub bytes: u128,
+}
+
+/// Traffic sniffed on a single network adapter
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct InterfaceStats {
+    /// Packets sniffed on the adapter, including those not filtered
+    pub all_packets: u128,
+    /// Bytes sniffed on the adapter, including those not filtered
+    pub all_bytes: u128,
+    /// Filtered packets sent
+    pub sent_packets: u128,
+    /// Filtered bytes sent
+    pub sent_bytes: u128,
+    /// Filtered packets received (including the local traffic)
+    pub received_packets: u128,
+    /// Filtered bytes received (including the local traffic)
+    pub received_bytes: u128,
+}
 
 /// Struct to be shared between the threads in charge of parsing packets and update reports.
 pub struct InfoTraffic {
+    /// Time at which the current capture started
+    pub capture_started_at: DateTime<Local>,
+    /// Time at which the current capture has been stopped, if it has
+    pub capture_stopped_at: Option<DateTime<Local>>,
+    /// Name of the network adapter sniffed by the current capture
+    pub device_name: String,
+    /// Filters applied by the current capture (see `Filters::to_filter_string`)
+    pub capture_filters: String,
     /// Total amount of filtered bytes received.
     pub tot_received_bytes: u128,
     /// Total amount of filtered bytes sent.
@@ -23,33 +86,1299 @@ pub struct InfoTraffic {
     pub all_packets: u128,
     /// Total bytes including those not filtered
     pub all_bytes: u128,
+    /// Number of packets (including those not filtered) falling in each bucket of sizes (see `get_size_bucket`)
+    pub size_histogram: [u64; SIZE_BUCKETS],
+    /// ARP and IGMP packets, accounted apart instead of being reported per connection
+    pub discovery: DiscoveryTraffic,
+    /// Traffic sniffed on each network adapter, by its name
+    pub per_interface: HashMap<String, InterfaceStats>,
+    /// Packets dropped by the system because not read fast enough (according to the capture statistics)
+    pub dropped_packets: u128,
+    /// Packets dropped by the network adapters or their drivers (according to the capture statistics)
+    pub if_dropped_packets: u128,
+    /// Maximum delay between the capture of a packet and its processing since the last refresh (only for live captures)
+    pub capture_lag: Duration,
+    /// Network adapter and reason of the errors stalling the capture, if any
+    /// (set after several consecutive read errors, and reset as soon as packets are read again)
+    pub capture_error: Option<String>,
     /// Map of the filtered traffic
     pub map: IndexMap<AddressPortPair, InfoAddressPortPair>,
-    /// Set with the addresses of the last time interval
-    pub addresses_last_interval: HashSet<usize>,
-    /// Map of the application layer protocols with their packet count
-    pub app_protocols: HashMap<AppProtocol, u128>,
-    /// Collection of indexes of the favorite connections
-    pub favorite_connections: HashSet<usize>,
-    /// Collection of favorite connections that exchanged data in the last interval
-    pub favorites_last_interval: HashSet<usize>,
+    /// Identifiers of the connections that exchanged data in the last time interval
+    pub addresses_last_interval: HashSet<u64>,
+    /// Map of the application layer protocols with their packets and bytes count
+    pub app_protocols: HashMap<AppProtocol, TrafficSummary>,
+    /// Identifiers of the favorite connections
+    pub favorite_connections: HashSet<u64>,
+    /// Identifiers of the favorite connections that exchanged data in the last interval
+    pub favorites_last_interval: HashSet<u64>,
+    /// Codes of the countries of the remote addresses contacted so far
+    pub seen_countries: HashSet<String>,
+    /// Countries contacted for the first time in the last interval, with the identifier of the first connection featuring them
+    pub new_countries_last_interval: Vec<(String, u64)>,
+    /// Whether the broadcast and multicast traffic is aggregated in the summaries below
+    /// instead of being inserted in the map (it can be changed at any time)
+    pub aggregate_broadcast_multicast: bool,
+    /// Whether the two directions of each conversation with a remote host are combined in a single entry of the map
+    /// (see `get_map_key`)
+    pub combine_bidirectional_flows: bool,
+    /// Broadcast traffic aggregated while `aggregate_broadcast_multicast` is set
+    pub broadcast_summary: TrafficSummary,
+    /// Multicast traffic aggregated while `aggregate_broadcast_multicast` is set
+    pub multicast_summary: TrafficSummary,
+    /// Maximum number of connections in the map, if any: when it's reached,
+    /// the least recently active connection is evicted to make room for a new one (favorites are always kept)
+    pub max_connections: Option<usize>,
+    /// Identifier to be assigned to the next connection inserted into the map
+    next_connection_id: u64,
+    /// Current index inside the map of each connection, by its identifier
+    connection_indexes: HashMap<u64, usize>,
+    /// Identifiers of the connections ordered from the least to the most recently active (by their final timestamp)
+    recency: BTreeSet<(DateTime<Local>, u64)>,
 }
 
 impl InfoTraffic {
     /// Constructs a new `InfoTraffic` element.
     pub fn new() -> Self {
         InfoTraffic {
+            capture_started_at: Local::now(),
+            capture_stopped_at: None,
+            device_name: String::new(),
+            capture_filters: String::new(),
             tot_received_bytes: 0,
             tot_sent_bytes: 0,
             tot_received_packets: 0,
             tot_sent_packets: 0,
             all_packets: 0,
             all_bytes: 0,
+            size_histogram: [0; SIZE_BUCKETS],
+            discovery: DiscoveryTraffic::default(),
+            per_interface: HashMap::new(),
+            dropped_packets: 0,
+            if_dropped_packets: 0,
+            capture_lag: Duration::ZERO,
+            capture_error: None,
             map: IndexMap::new(),
             addresses_last_interval: HashSet::new(),
             app_protocols: HashMap::new(),
             favorite_connections: HashSet::new(),
             favorites_last_interval: HashSet::new(),
+            seen_countries: HashSet::new(),
+            new_countries_last_interval: Vec::new(),
+            aggregate_broadcast_multicast: false,
+            combine_bidirectional_flows: false,
+            broadcast_summary: TrafficSummary::default(),
+            multicast_summary: TrafficSummary::default(),
+            max_connections: None,
+            next_connection_id: 0,
+            connection_indexes: HashMap::new(),
+            recency: BTreeSet::new(),
+        }
+    }
+
+    /// Inserts a new connection into the map, assigning it the next identifier, which is returned.
+    ///
+    /// If the map already holds `max_connections`, the least recently active connections are evicted first.
+    pub fn insert_connection(
+        &mut self,
+        key: AddressPortPair,
+        mut info: InfoAddressPortPair,
+    ) -> u64 {
+        if let Some(max_connections) = self.max_connections {
+            while self.map.len() >= max_connections && self.evict_least_recently_active() {}
+        }
+        let id = self.next_connection_id;
+        self.next_connection_id += 1;
+        info.id = id;
+        self.recency.insert((info.final_timestamp, id));
+        let (index, _) = self.map.insert_full(key, info);
+        self.connection_indexes.insert(id, index);
+        id
+    }
+
+    /// Records that the connection with the given identifier exchanged traffic,
+    /// moving its final timestamp from `previous` to `current`.
+    pub fn record_activity(
+        &mut self,
+        id: u64,
+        previous: DateTime<Local>,
+        current: DateTime<Local>,
+    ) {
+        if previous != current && self.recency.remove(&(previous, id)) {
+            self.recency.insert((current, id));
+        }
+    }
+
+    /// Removes the least recently active connection not marked as favorite, returning whether one has been found.
+    ///
+    /// The last connection of the map takes the place of the evicted one, so that no other index is shifted.
+    fn evict_least_recently_active(&mut self) -> bool {
+        let Some(&(last_activity, id)) = self.recency.iter().find(|(_, id)| {
+            self.get_connection(*id)
+                .is_some_and(|(_, info)| !info.is_favorite)
+        }) else {
+            return false;
+        };
+        self.recency.remove(&(last_activity, id));
+        let index = self.connection_indexes.remove(&id).unwrap();
+        self.map.swap_remove_index(index);
+        if let Some((_, moved)) = self.map.get_index(index) {
+            // the moved connection has to be rewritten at its new position in the output report
+            let moved_id = moved.id;
+            self.connection_indexes.insert(moved_id, index);
+            self.addresses_last_interval.insert(moved_id);
+        }
+        self.addresses_last_interval.remove(&id);
+        self.new_countries_last_interval
+            .retain(|(_, country_id)| *country_id != id);
+        true
+    }
+
+    /// Returns the current index inside the map of the connection with the given identifier, if still present.
+    pub fn get_index_of_connection(&self, id: u64) -> Option<usize> {
+        self.connection_indexes.get(&id).copied()
+    }
+
+    /// Returns the time elapsed from the start of the capture to its stop (or to now, if it's still running).
+    pub fn capture_elapsed(&self) -> Duration {
+        let end = self.capture_stopped_at.unwrap_or_else(Local::now);
+        (end - self.capture_started_at)
+            .to_std()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Returns the filtered (sent and received) bytes per second, on average over the whole capture.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average_bytes_per_second(&self) -> f64 {
+        let elapsed = self.capture_elapsed().as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
         }
+        (self.tot_sent_bytes + self.tot_received_bytes) as f64 / elapsed
+    }
+
+    /// Returns the connection with the given identifier, if still present.
+    pub fn get_connection(&self, id: u64) -> Option<(&AddressPortPair, &InfoAddressPortPair)> {
+        self.map.get_index(self.get_index_of_connection(id)?)
+    }
+
+    /// Returns the connection with the given identifier as mutable, if still present.
+    pub fn get_connection_mut(&mut self, id: u64) -> Option<&mut InfoAddressPortPair> {
+        let index = self.get_index_of_connection(id)?;
+        self.map.get_index_mut(index).map(|(_, info)| info)
+    }
+}
+
+#[cfg(test)]
+impl InfoTraffic {
+    /// Serializes the aggregate counters and the connections to JSON, as written by `export_json`.
+    pub fn to_json(&self) -> String {
+        self.to_exported_json(None)
+    }
+
+    /// Serializes the traffic data to JSON, as written by `export_json` with the given anonymization salt.
+    pub fn to_exported_json(&self, anonymization_salt: Option<u64>) -> String {
+        serde_json::to_string(&self.get_json(anonymization_salt)).unwrap()
+    }
+
+    /// Constructs an `InfoTraffic` element holding the given connections, in order:
+    /// each one is assigned the next identifier, those marked as favorites are registered as such,
+    /// and their traffic is accounted in the total and per application protocol counters.
+    pub fn with_connections(
+        connections: impl IntoIterator<Item = (AddressPortPair, InfoAddressPortPair)>,
+    ) -> Self {
+        use crate::utility::manage_packets::update_protocols_and_totals;
+
+        let mut info_traffic = InfoTraffic::new();
+        for (key, info) in connections {
+            info_traffic.all_packets += info.transmitted_packets;
+            info_traffic.all_bytes += info.transmitted_bytes;
+            update_protocols_and_totals(
+                &mut info_traffic,
+                info.traffic_type,
+                info.app_protocol,
+                info.transmitted_packets,
+                info.transmitted_bytes,
+            );
+            let is_favorite = info.is_favorite;
+            let id = info_traffic.insert_connection(key, info);
+            if is_favorite {
+                info_traffic.favorite_connections.insert(id);
+            }
+        }
+        info_traffic
+    }
+
+    /// Merges the given packets into the map with a single flush, as the thread parsing the traffic
+    /// of the given network adapter does (with the embedded country database).
+    ///
+    /// Each packet is described by its connection, size, traffic type and application protocol.
+    pub fn flush_packets(
+        &mut self,
+        interface: &str,
+        packets: impl IntoIterator<
+            Item = (
+                AddressPortPair,
+                u128,
+                crate::enums::traffic_type::TrafficType,
+                AppProtocol,
+            ),
+        >,
+    ) {
+        use crate::structs::traffic_buffer::{FlushCadence, TrafficBuffer};
+        use crate::utility::countries::get_country_db_reader;
+
+        let mut buffer = TrafficBuffer::new(
+            interface.to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        for (key, exchanged_bytes, traffic_type, app_protocol) in packets {
+            buffer.add_packet(exchanged_bytes);
+            buffer.add_connection(key, exchanged_bytes, traffic_type, app_protocol);
+        }
+        let country_db_reader = get_country_db_reader(None);
+        buffer.flush(self, country_db_reader.as_ref(), None, None, None);
+    }
+}
+
+impl InfoTraffic {
+    /// Writes the aggregate counters and the connections to the given JSON file.
+    ///
+    /// The output is versioned with a `schema_version` field; each connection is an object
+    /// with the fields of both `AddressPortPair` and `InfoAddressPortPair`.
+    /// The connections are anonymized if a salt is specified (see `get_exported_connection`):
+    /// in that case the name of the sniffed network adapter is omitted as well.
+    pub fn export_json(&self, path: &Path, anonymization_salt: Option<u64>) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &self.get_json(anonymization_salt))
+            .map_err(|err| err.to_string())?;
+        writer.flush().map_err(|err| err.to_string())
+    }
+
+    /// Returns the JSON representation of the traffic data (see `export_json`).
+    fn get_json(&self, anonymization_salt: Option<u64>) -> JsonInfoTraffic<'_> {
+        JsonInfoTraffic {
+            schema_version: JSON_SCHEMA_VERSION,
+            capture_started_at: self.capture_started_at,
+            capture_stopped_at: self.capture_stopped_at,
+            capture_duration_seconds: self.capture_elapsed().as_secs_f64(),
+            device_name: if anonymization_salt.is_some() {
+                ""
+            } else {
+                &self.device_name
+            },
+            capture_filters: &self.capture_filters,
+            average_bytes_per_second: self.average_bytes_per_second(),
+            all_packets: self.all_packets,
+            all_bytes: self.all_bytes,
+            tot_sent_packets: self.tot_sent_packets,
+            tot_received_packets: self.tot_received_packets,
+            tot_sent_bytes: self.tot_sent_bytes,
+            tot_received_bytes: self.tot_received_bytes,
+            connections: self
+                .map
+                .iter()
+                .map(|(key, info)| {
+                    let (key, info) = get_exported_connection(key, info, anonymization_salt);
+                    JsonConnection { key, info }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl InfoTraffic {
+    /// Returns the indexes of the connections matching the query (all the connections if the query is empty).
+    ///
+    /// The query is case-insensitive, and it matches a connection if it's a substring of either of its addresses,
+    /// either of its port numbers, or its country code.
+    pub fn filter_connections(&self, query: &str) -> Vec<usize> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return (0..self.map.len()).collect();
+        }
+        let port = query.parse::<u16>().ok();
+        self.map
+            .iter()
+            .enumerate()
+            .filter(|(_, (key, info))| {
+                // addresses are always stored lowercase
+                key.address1.contains(&query)
+                    || key.address2.contains(&query)
+                    || port.is_some_and(|port| key.port1 == port || key.port2 == port)
+                    || info.country.eq_ignore_ascii_case(&query)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+impl InfoTraffic {
+    /// Returns the indexes of all the connections, ordered by the given field.
+    ///
+    /// Connections with the same value of the field keep the order in which they appeared.
+    pub fn sort_connections(&self, by: SortColumn, descending: bool) -> Vec<usize> {
+        let compare = |a: &InfoAddressPortPair, b: &InfoAddressPortPair| -> Ordering {
+            match by {
+                SortColumn::Bytes => a.transmitted_bytes.cmp(&b.transmitted_bytes),
+                SortColumn::Packets => a.transmitted_packets.cmp(&b.transmitted_packets),
+                SortColumn::Recency => a.final_timestamp.cmp(&b.final_timestamp),
+                SortColumn::Duration => a.duration().cmp(&b.duration()),
+                SortColumn::Country => a.country.cmp(&b.country),
+                SortColumn::AppProtocol => {
+                    let position = |info: &InfoAddressPortPair| {
+                        AppProtocol::ALL
+                            .iter()
+                            .position(|app_protocol| *app_protocol == info.app_protocol)
+                    };
+                    position(a).cmp(&position(b))
+                }
+            }
+        };
+        let mut indexes: Vec<usize> = (0..self.map.len()).collect();
+        // sort_by is stable: ties keep the ascending order of the indexes in both directions
+        indexes.sort_by(|a, b| {
+            let ordering = compare(&self.map[*a], &self.map[*b]);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        indexes
+    }
+}
+
+impl InfoTraffic {
+    /// Returns the `n` connections that exchanged the most traffic according to the given metric, in descending order.
+    ///
+    /// The two directions of each connection are aggregated: the returned entry has the key
+    /// of the direction that appeared first, and the sum of the traffic of both directions.
+    /// Connections exchanging the same traffic are returned in order of appearance.
+    pub fn top_talkers(&self, n: usize, by: Metric) -> Vec<(AddressPortPair, InfoAddressPortPair)> {
+        if n == 0 {
+            return Vec::new();
+        }
+        // index of the first direction of each connection, with the index of the opposite direction (if any)
+        let mut connections: Vec<(usize, Option<usize>)> = Vec::new();
+        let mut positions: HashMap<(&str, u16, &str, u16, TransProtocol), usize> = HashMap::new();
+        for (index, key) in self.map.keys().enumerate() {
+            let reverse = (
+                key.address2.as_str(),
+                key.port2,
+                key.address1.as_str(),
+                key.port1,
+                key.trans_protocol,
+            );
+            if let Some(position) = positions.get(&reverse) {
+                connections[*position].1 = Some(index);
+            } else {
+                positions.insert(
+                    (
+                        key.address1.as_str(),
+                        key.port1,
+                        key.address2.as_str(),
+                        key.port2,
+                        key.trans_protocol,
+                    ),
+                    connections.len(),
+                );
+                connections.push((index, None));
+            }
+        }
+
+        let metric = |info: &InfoAddressPortPair| match by {
+            Metric::Bytes => info.transmitted_bytes,
+            Metric::Packets => info.transmitted_packets,
+        };
+        // min-heap of the heaviest connections found so far: the root is the first to be evicted,
+        // that is the lightest one and, among those as light, the one appeared last
+        let mut heap: BinaryHeap<Reverse<(u128, Reverse<usize>)>> =
+            BinaryHeap::with_capacity(n + 1);
+        for (position, (index, reverse_index)) in connections.iter().enumerate() {
+            let traffic = metric(&self.map[*index])
+                + reverse_index.map_or(0, |reverse_index| metric(&self.map[reverse_index]));
+            heap.push(Reverse((traffic, Reverse(position))));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, Reverse(position)))| {
+                let (index, reverse_index) = connections[position];
+                let (key, info) = self.map.get_index(index).unwrap();
+                let mut info = info.clone();
+                if let Some(reverse_index) = reverse_index {
+                    let reverse_info = &self.map[reverse_index];
+                    info.transmitted_bytes += reverse_info.transmitted_bytes;
+                    info.transmitted_packets += reverse_info.transmitted_packets;
+                    info.sent_bytes += reverse_info.sent_bytes;
+                    info.received_bytes += reverse_info.received_bytes;
+                    info.sent_packets += reverse_info.sent_packets;
+                    info.received_packets += reverse_info.received_packets;
+                    info.initial_timestamp =
+                        info.initial_timestamp.min(reverse_info.initial_timestamp);
+                    info.final_timestamp = info.final_timestamp.max(reverse_info.final_timestamp);
+                }
+                (key.clone(), info)
+            })
+            .collect()
+    }
+}
+
+impl InfoTraffic {
+    /// Returns the total bytes and packets exchanged with each country, identified by its code.
+    ///
+    /// Connections without a country (e.g. private or local ones) are aggregated under `LOCAL_COUNTRY`.
+    pub fn country_breakdown(&self) -> HashMap<String, (u128, u128)> {
+        let mut breakdown: HashMap<String, (u128, u128)> = HashMap::new();
+        for info in self.map.values() {
+            let country = if info.country.is_empty() {
+                LOCAL_COUNTRY
+            } else {
+                &info.country
+            };
+            // avoid allocating a key for the countries already in the breakdown
+            if let Some((bytes, packets)) = breakdown.get_mut(country) {
+                *bytes += info.transmitted_bytes;
+                *packets += info.transmitted_packets;
+            } else {
+                breakdown.insert(
+                    country.to_string(),
+                    (info.transmitted_bytes, info.transmitted_packets),
+                );
+            }
+        }
+        breakdown
+    }
+
+    /// Returns the buckets of the packet size histogram, labeled with their range of sizes in bytes.
+    pub fn size_histogram_buckets(&self) -> Vec<(String, u64)> {
+        let mut lower_bound = 0;
+        self.size_histogram
+            .iter()
+            .enumerate()
+            .map(|(bucket, packets)| {
+                let label = if bucket == SIZE_BUCKETS - 1 {
+                    format!("{lower_bound}+")
+                } else {
+                    let upper_bound = 64 << bucket;
+                    let label = format!("{lower_bound}-{upper_bound}");
+                    lower_bound = upper_bound + 1;
+                    label
+                };
+                (label, *packets)
+            })
+            .collect()
+    }
+}
+
+impl InfoTraffic {
+    /// Removes the connections that haven't exchanged traffic since `cutoff` (favorite connections are always kept),
+    /// returning the number of evicted connections.
+    ///
+    /// The remaining connections keep their identifiers, while their indexes inside the map are shifted to fill the gaps.
+    pub fn evict_stale_connections(&mut self, cutoff: DateTime<Local>) -> usize {
+        let len = self.map.len();
+        self.map
+            .retain(|_, info| info.is_favorite || info.final_timestamp >= cutoff);
+        let evicted = len - self.map.len();
+        if evicted == 0 {
+            return 0;
+        }
+
+        self.connection_indexes = self
+            .map
+            .values()
+            .enumerate()
+            .map(|(index, info)| (info.id, index))
+            .collect();
+        // all the remaining connections have moved in the output report
+        self.addresses_last_interval = self.connection_indexes.keys().copied().collect();
+        let connection_indexes = &self.connection_indexes;
+        self.new_countries_last_interval
+            .retain(|(_, id)| connection_indexes.contains_key(id));
+        self.recency
+            .retain(|(_, id)| connection_indexes.contains_key(id));
+        evicted
+    }
+}
+
+impl InfoTraffic {
+    /// Saves the traffic session to a JSON file, so that it can be restored with `load_session`.
+    pub fn save_session(&self, path: &Path) -> Result<(), String> {
+        self.export_json(path, None)
+    }
+
+    /// Restores a traffic session previously saved with `save_session`.
+    ///
+    /// Returns an error if the file cannot be read or parsed, or if it has been written
+    /// with a different schema version.
+    pub fn load_session(path: &Path) -> Result<InfoTraffic, String> {
+        let json = std::fs::read(path).map_err(|err| err.to_string())?;
+        Self::from_json(&json)
+    }
+
+    /// Rebuilds the traffic data from their JSON representation produced by `export_json`.
+    ///
+    /// The timestamps of the connections are preserved, while their rate is restarted,
+    /// so that the restored traffic doesn't result in a spike.
+    fn from_json(json: &[u8]) -> Result<InfoTraffic, String> {
+        let header: JsonSessionHeader =
+            serde_json::from_slice(json).map_err(|err| err.to_string())?;
+        if header.schema_version != JSON_SCHEMA_VERSION {
+            return Err(format!(
+                "schema version {} doesn't match the current one ({JSON_SCHEMA_VERSION})",
+                header.schema_version
+            ));
+        }
+        let session: JsonSession = serde_json::from_slice(json).map_err(|err| err.to_string())?;
+        let keys: JsonSessionKeys = serde_json::from_slice(json).map_err(|err| err.to_string())?;
+        let mut info_traffic = InfoTraffic {
+            capture_started_at: session.capture_started_at.unwrap_or_else(Local::now),
+            capture_stopped_at: session.capture_stopped_at,
+            device_name: session.device_name,
+            capture_filters: session.capture_filters,
+            tot_received_bytes: session.tot_received_bytes,
+            tot_sent_bytes: session.tot_sent_bytes,
+            tot_received_packets: session.tot_received_packets,
+            tot_sent_packets: session.tot_sent_packets,
+            all_packets: session.all_packets,
+            all_bytes: session.all_bytes,
+            ..InfoTraffic::new()
+        };
+        let now = Local::now();
+        for (key, mut info) in keys.connections.into_iter().zip(session.connections) {
+            if info_traffic.map.contains_key(&key) {
+                continue;
+            }
+            info.resume_rate(now);
+            if !info.country.is_empty() {
+                info_traffic.seen_countries.insert(info.country.clone());
+            }
+            let app_protocol_summary = info_traffic
+                .app_protocols
+                .entry(info.app_protocol)
+                .or_default();
+            app_protocol_summary.packets += info.transmitted_packets;
+            app_protocol_summary.bytes += info.transmitted_bytes;
+            let is_favorite = info.is_favorite;
+            let id = info_traffic.insert_connection(key, info);
+            if is_favorite {
+                info_traffic.favorite_connections.insert(id);
+            }
+        }
+        Ok(info_traffic)
+    }
+}
+
+/// JSON representation of `InfoTraffic`
+#[derive(Serialize)]
+struct JsonInfoTraffic<'a> {
+    schema_version: u32,
+    capture_started_at: DateTime<Local>,
+    capture_stopped_at: Option<DateTime<Local>>,
+    capture_duration_seconds: f64,
+    device_name: &'a str,
+    capture_filters: &'a str,
+    average_bytes_per_second: f64,
+    all_packets: u128,
+    all_bytes: u128,
+    tot_sent_packets: u128,
+    tot_received_packets: u128,
+    tot_sent_bytes: u128,
+    tot_received_bytes: u128,
+    connections: Vec<JsonConnection<'a>>,
+}
+
+/// JSON representation of a connection
+#[derive(Serialize)]
+struct JsonConnection<'a> {
+    #[serde(flatten)]
+    key: Cow<'a, AddressPortPair>,
+    #[serde(flatten)]
+    info: Cow<'a, InfoAddressPortPair>,
+}
+
+/// Fields of the JSON representation of `InfoTraffic` checked before restoring a session
+#[derive(Deserialize)]
+struct JsonSessionHeader {
+    schema_version: u32,
+}
+
+/// JSON representation of `InfoTraffic`, as restored from a session file
+///
+/// The keys of the connections are read separately (see `JsonSessionKeys`),
+/// since flattened fields can't be deserialized as `u128`.
+#[derive(Deserialize)]
+struct JsonSession {
+    /// Missing in the sessions saved before the capture metadata were exported
+    #[serde(default)]
+    capture_started_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    capture_stopped_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    device_name: String,
+    #[serde(default)]
+    capture_filters: String,
+    all_packets: u128,
+    all_bytes: u128,
+    tot_sent_packets: u128,
+    tot_received_packets: u128,
+    tot_sent_bytes: u128,
+    tot_received_bytes: u128,
+    connections: Vec<InfoAddressPortPair>,
+}
+
+/// Keys of the connections of a session file
+#[derive(Deserialize)]
+struct JsonSessionKeys {
+    connections: Vec<AddressPortPair>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{Local, TimeZone};
+
+    use crate::enums::metric::Metric;
+    use crate::enums::sort_column::SortColumn;
+    use crate::enums::tcp_state::TcpState;
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::asn::Asn;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::structs::info_traffic::{
+        get_size_bucket, TrafficSummary, LOCAL_COUNTRY, SIZE_BUCKETS,
+    };
+    use crate::structs::mac_address::MacAddress;
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    #[test]
+    fn test_to_json() {
+        let timestamp = Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap();
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.all_packets = 8;
+        info_traffic.all_bytes = 1500;
+        info_traffic.tot_sent_packets = 5;
+        info_traffic.tot_sent_bytes = 1234;
+        info_traffic.capture_started_at = timestamp;
+        info_traffic.capture_stopped_at = Some(timestamp + chrono::Duration::seconds(2));
+        info_traffic.device_name = "eth0".to_string();
+        info_traffic.capture_filters = "transport=UDP".to_string();
+        info_traffic.map.insert(
+            AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                50000,
+                "8.8.8.8".to_string(),
+                53,
+                TransProtocol::UDP,
+            ),
+            InfoAddressPortPair {
+                transmitted_bytes: 1234,
+                transmitted_packets: 5,
+                sent_bytes: 1234,
+                sent_packets: 5,
+                initial_timestamp: timestamp,
+                final_timestamp: timestamp,
+                app_protocol: AppProtocol::DNS,
+                traffic_type: TrafficType::Outgoing,
+                country: "US".to_string(),
+                location: Some((37.75, -97.5)),
+                asn: Asn {
+                    number: 15169,
+                    org: "GOOGLE".to_string(),
+                },
+                hostname: Some("dns.google".to_string()),
+                interface: "eth0".to_string(),
+                ..InfoAddressPortPair::default()
+            },
+        );
+        let stopped_at = (timestamp + chrono::Duration::seconds(2)).to_rfc3339();
+        let timestamp = timestamp.to_rfc3339();
+        assert_eq!(
+            info_traffic.to_json(),
+            format!(
+                "{{\"schema_version\":1,\"capture_started_at\":\"{timestamp}\",\"capture_stopped_at\":\"{stopped_at}\",\
+\"capture_duration_seconds\":2.0,\"device_name\":\"eth0\",\"capture_filters\":\"transport=UDP\",\"average_bytes_per_second\":617.0,\
+\"all_packets\":8,\"all_bytes\":1500,\
+\"tot_sent_packets\":5,\"tot_received_packets\":0,\"tot_sent_bytes\":1234,\"tot_received_bytes\":0,\
+\"connections\":[{{\"address1\":\"192.168.1.10\",\"port1\":50000,\"address2\":\"8.8.8.8\",\"port2\":53,\
+\"trans_protocol\":\"UDP\",\"transmitted_bytes\":1234,\"transmitted_packets\":5,\"sent_bytes\":1234,\"received_bytes\":0,\"sent_packets\":5,\"received_packets\":0,\
+\"initial_timestamp\":\"{timestamp}\",\"final_timestamp\":\"{timestamp}\",\"app_protocol\":\"DNS\",\
+\"traffic_type\":\"Outgoing\",\"country\":\"US\",\"location\":[37.75,-97.5],\"asn\":{{\"number\":15169,\"org\":\"GOOGLE\"}},\
+\"hostname\":\"dns.google\",\"dns_query\":null,\"tls_sni\":null,\"src_mac\":null,\"dst_mac\":null,\"vlan\":null,\"dscp\":0,\"icmp_type_code\":null,\"tcp_state\":null,\"interface\":\"eth0\",\"is_favorite\":false,\"is_gateway\":false,\"is_notable\":false}}]}}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let timestamp = Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap();
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.all_packets = 20;
+        info_traffic.all_bytes = 9000;
+        info_traffic.tot_received_packets = 12;
+        info_traffic.tot_received_bytes = 8000;
+        info_traffic.capture_started_at = timestamp;
+        info_traffic.capture_stopped_at = Some(timestamp + chrono::Duration::seconds(100));
+        info_traffic.device_name = "eth0".to_string();
+        for (index, (address, app_protocol, country)) in [
+            (
+                "2001:db8:85a3:8d3:1319:8a2e:370:7348",
+                AppProtocol::HTTPS,
+                "US",
+            ),
+            ("10.0.0.1", AppProtocol::SSH, ""),
+            ("1.1.1.1", AppProtocol::HTTPS, "AU"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            info_traffic.map.insert(
+                AddressPortPair::new(
+                    address.to_string(),
+                    443,
+                    "192.168.1.10".to_string(),
+                    50000,
+                    TransProtocol::TCP,
+                ),
+                InfoAddressPortPair {
+                    transmitted_bytes: 2000,
+                    transmitted_packets: 4,
+                    initial_timestamp: timestamp,
+                    final_timestamp: timestamp + chrono::Duration::seconds(90),
+                    app_protocol,
+                    traffic_type: TrafficType::Incoming,
+                    country: country.to_string(),
+                    dns_query: Some("www.example.com".to_string()),
+                    src_mac: Some(MacAddress([0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6])),
+                    tcp_state: Some(TcpState::Established),
+                    interface: "eth0".to_string(),
+                    is_favorite: index == 1,
+                    ..InfoAddressPortPair::default()
+                },
+            );
+        }
+
+        let path = std::env::temp_dir().join("sniffnet_test_session.json");
+        info_traffic.save_session(&path).unwrap();
+        let restored = InfoTraffic::load_session(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.to_json(), info_traffic.to_json());
+        assert_eq!(restored.all_packets, 20);
+        assert_eq!(restored.capture_started_at, timestamp);
+        assert_eq!(restored.device_name, "eth0");
+        assert!((restored.average_bytes_per_second() - 80.0).abs() < f64::EPSILON);
+        assert_eq!(restored.tot_received_bytes, 8000);
+        assert_eq!(restored.favorite_connections, [1].into());
+        assert_eq!(
+            restored.seen_countries,
+            ["US".to_string(), "AU".to_string()].into()
+        );
+        assert_eq!(
+            restored.app_protocols,
+            [
+                (
+                    AppProtocol::HTTPS,
+                    TrafficSummary {
+                        packets: 8,
+                        bytes: 4000
+                    }
+                ),
+                (
+                    AppProtocol::SSH,
+                    TrafficSummary {
+                        packets: 4,
+                        bytes: 2000
+                    }
+                )
+            ]
+            .into()
+        );
+        for (index, info) in restored.map.values().enumerate() {
+            assert_eq!(restored.get_index_of_connection(info.id), Some(index));
+            assert_eq!(info.initial_timestamp, timestamp);
+        }
+        // traffic exchanged before the restore is not accounted in the rate
+        let mut info = restored.map[0].clone();
+        info.refresh_rate(Local::now() + chrono::Duration::seconds(1));
+        assert_eq!(info.bytes_per_second(), 0.0);
+    }
+
+    /// Builds a connection with the given traffic, last seen `seconds` after a fixed time.
+    fn connection(
+        bytes: u128,
+        packets: u128,
+        seconds: i64,
+        country: &str,
+        app_protocol: AppProtocol,
+    ) -> InfoAddressPortPair {
+        let timestamp = Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap();
+        InfoAddressPortPair {
+            transmitted_bytes: bytes,
+            transmitted_packets: packets,
+            initial_timestamp: timestamp,
+            final_timestamp: timestamp + chrono::Duration::seconds(seconds),
+            app_protocol,
+            traffic_type: TrafficType::Outgoing,
+            country: country.to_string(),
+            ..InfoAddressPortPair::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_connections() {
+        let mut info_traffic = InfoTraffic::new();
+        for (port, info) in [
+            connection(500, 5, 30, "US", AppProtocol::HTTPS),
+            connection(100, 9, 10, "IT", AppProtocol::DNS),
+            connection(500, 1, 20, "", AppProtocol::Other),
+            connection(900, 5, 20, "US", AppProtocol::SSH),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            info_traffic.map.insert(
+                AddressPortPair::new(
+                    "10.0.0.1".to_string(),
+                    u16::try_from(port).unwrap(),
+                    "1.1.1.1".to_string(),
+                    443,
+                    TransProtocol::TCP,
+                ),
+                info,
+            );
+        }
+
+        // ties keep the order of appearance in both directions
+        for (by, ascending) in [
+            (SortColumn::Bytes, [1, 0, 2, 3]),
+            (SortColumn::Packets, [2, 0, 3, 1]),
+            (SortColumn::Recency, [1, 2, 3, 0]),
+            (SortColumn::Country, [2, 1, 0, 3]),
+            (SortColumn::AppProtocol, [2, 1, 0, 3]),
+        ] {
+            assert_eq!(
+                info_traffic.sort_connections(by, false),
+                ascending,
+                "{by:?}"
+            );
+        }
+        for (by, descending) in [
+            (SortColumn::Bytes, [3, 0, 2, 1]),
+            (SortColumn::Packets, [1, 0, 3, 2]),
+            (SortColumn::Recency, [0, 2, 3, 1]),
+            (SortColumn::Country, [0, 3, 1, 2]),
+            (SortColumn::AppProtocol, [3, 0, 1, 2]),
+        ] {
+            assert_eq!(
+                info_traffic.sort_connections(by, true),
+                descending,
+                "{by:?}"
+            );
+        }
+        assert!(InfoTraffic::new()
+            .sort_connections(SortColumn::Bytes, true)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_top_talkers() {
+        let mut info_traffic = InfoTraffic::new();
+        for i in 0..100_000_u32 {
+            let address = format!("10.{}.{}.{}", i >> 16, (i >> 8) & 0xff, i & 0xff);
+            // pseudo-random traffic, with plenty of ties
+            let bytes = u128::from(i.wrapping_mul(2_654_435_761) % 50_000);
+            let packets = u128::from(i % 1000);
+            info_traffic.map.insert(
+                AddressPortPair::new(
+                    "192.168.1.10".to_string(),
+                    443,
+                    address,
+                    50000,
+                    TransProtocol::TCP,
+                ),
+                connection(bytes, packets, 0, "", AppProtocol::HTTPS),
+            );
+        }
+
+        for (by, sort_column) in [
+            (Metric::Bytes, SortColumn::Bytes),
+            (Metric::Packets, SortColumn::Packets),
+        ] {
+            let expected: Vec<(AddressPortPair, u128, u128)> = info_traffic
+                .sort_connections(sort_column, true)
+                .into_iter()
+                .take(10)
+                .map(|index| {
+                    let (key, info) = info_traffic.map.get_index(index).unwrap();
+                    (
+                        key.clone(),
+                        info.transmitted_bytes,
+                        info.transmitted_packets,
+                    )
+                })
+                .collect();
+            let top_talkers: Vec<(AddressPortPair, u128, u128)> = info_traffic
+                .top_talkers(10, by)
+                .into_iter()
+                .map(|(key, info)| (key, info.transmitted_bytes, info.transmitted_packets))
+                .collect();
+            assert_eq!(top_talkers.len(), 10);
+            assert!(top_talkers == expected, "{by:?}");
+        }
+        assert!(info_traffic.top_talkers(0, Metric::Bytes).is_empty());
+        assert!(InfoTraffic::new().top_talkers(10, Metric::Bytes).is_empty());
+    }
+
+    #[test]
+    fn test_top_talkers_aggregates_directions() {
+        let mut info_traffic = InfoTraffic::new();
+        for (address1, port1, address2, port2, info) in [
+            (
+                "10.0.0.1",
+                50000,
+                "1.1.1.1",
+                443,
+                connection(300, 3, 10, "US", AppProtocol::HTTPS),
+            ),
+            (
+                "10.0.0.1",
+                50001,
+                "8.8.8.8",
+                53,
+                connection(700, 7, 10, "US", AppProtocol::DNS),
+            ),
+            (
+                "1.1.1.1",
+                443,
+                "10.0.0.1",
+                50000,
+                connection(600, 2, 20, "US", AppProtocol::HTTPS),
+            ),
+        ] {
+            info_traffic.map.insert(
+                AddressPortPair::new(
+                    address1.to_string(),
+                    port1,
+                    address2.to_string(),
+                    port2,
+                    TransProtocol::TCP,
+                ),
+                info,
+            );
+        }
+
+        let top_bytes = info_traffic.top_talkers(5, Metric::Bytes);
+        assert_eq!(top_bytes.len(), 2);
+        let (key, info) = &top_bytes[0];
+        assert_eq!(key.address2, "1.1.1.1");
+        assert_eq!(info.transmitted_bytes, 900);
+        assert_eq!(info.transmitted_packets, 5);
+        assert_eq!(
+            info.final_timestamp,
+            info_traffic.map.get_index(2).unwrap().1.final_timestamp
+        );
+        assert_eq!(top_bytes[1].1.transmitted_bytes, 700);
+
+        let top_packets = info_traffic.top_talkers(1, Metric::Packets);
+        assert_eq!(top_packets.len(), 1);
+        assert_eq!(top_packets[0].0.address2, "8.8.8.8");
+    }
+
+    #[test]
+    fn test_country_breakdown() {
+        let mut info_traffic = InfoTraffic::new();
+        assert!(info_traffic.country_breakdown().is_empty());
+        for (port, info) in [
+            connection(500, 5, 0, "US", AppProtocol::HTTPS),
+            connection(100, 9, 0, "IT", AppProtocol::DNS),
+            connection(50, 1, 0, "", AppProtocol::Other),
+            connection(900, 5, 0, "US", AppProtocol::SSH),
+            connection(25, 2, 0, "", AppProtocol::mDNS),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            info_traffic.map.insert(
+                AddressPortPair::new(
+                    "10.0.0.1".to_string(),
+                    u16::try_from(port).unwrap(),
+                    "1.1.1.1".to_string(),
+                    443,
+                    TransProtocol::TCP,
+                ),
+                info,
+            );
+        }
+
+        let breakdown = info_traffic.country_breakdown();
+        assert_eq!(breakdown.len(), 3);
+        assert_eq!(breakdown["US"], (1400, 10));
+        assert_eq!(breakdown["IT"], (100, 9));
+        assert_eq!(breakdown[LOCAL_COUNTRY], (75, 3));
+    }
+
+    #[test]
+    fn test_evict_stale_connections() {
+        let mut info_traffic = InfoTraffic::new();
+        // connections active 10, 100, 20, 200 and 5 seconds after the fixed time
+        for (port, seconds) in [10, 100, 20, 200, 5].into_iter().enumerate() {
+            let mut info = connection(100, 1, seconds, "US", AppProtocol::HTTPS);
+            info.is_favorite = port == 4;
+            info_traffic.insert_connection(
+                AddressPortPair::new(
+                    "10.0.0.1".to_string(),
+                    u16::try_from(port).unwrap(),
+                    "1.1.1.1".to_string(),
+                    443,
+                    TransProtocol::TCP,
+                ),
+                info,
+            );
+        }
+        info_traffic.favorite_connections = HashSet::from([4]);
+        info_traffic.favorites_last_interval = HashSet::from([4]);
+        info_traffic.new_countries_last_interval =
+            vec![("US".to_string(), 0), ("IT".to_string(), 3)];
+        let cutoff = info_traffic.map[0].initial_timestamp + chrono::Duration::seconds(50);
+
+        assert_eq!(info_traffic.evict_stale_connections(cutoff), 2);
+        // only the stale connections not marked as favorites are evicted
+        let ports: Vec<u16> = info_traffic.map.keys().map(|key| key.port1).collect();
+        assert_eq!(ports, vec![1, 3, 4]);
+        // the remaining connections keep their identifiers
+        for (index, (key, info)) in info_traffic.map.iter().enumerate() {
+            assert_eq!(info.id, u64::from(key.port1));
+            assert_eq!(info_traffic.get_index_of_connection(info.id), Some(index));
+        }
+        assert!(info_traffic.get_connection(0).is_none());
+        assert_eq!(info_traffic.get_connection(4).unwrap().0.port1, 4);
+        assert_eq!(info_traffic.favorite_connections, HashSet::from([4]));
+        assert_eq!(info_traffic.favorites_last_interval, HashSet::from([4]));
+        assert_eq!(
+            info_traffic.addresses_last_interval,
+            HashSet::from([1, 3, 4])
+        );
+        assert_eq!(
+            info_traffic.new_countries_last_interval,
+            vec![("IT".to_string(), 3)]
+        );
+
+        // nothing else to evict
+        assert_eq!(info_traffic.evict_stale_connections(cutoff), 0);
+        assert_eq!(info_traffic.map.len(), 3);
+        // identifiers are never reused
+        let key = AddressPortPair::new(
+            "10.0.0.1".to_string(),
+            5,
+            "1.1.1.1".to_string(),
+            443,
+            TransProtocol::TCP,
+        );
+        let info = connection(100, 1, 0, "US", AppProtocol::HTTPS);
+        assert_eq!(info_traffic.insert_connection(key, info), 5);
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let key = |port: u16| {
+            AddressPortPair::new(
+                "10.0.0.1".to_string(),
+                port,
+                "1.1.1.1".to_string(),
+                443,
+                TransProtocol::TCP,
+            )
+        };
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.max_connections = Some(3);
+        // connections active 10, 20, 30 seconds after the fixed time; the second one is a favorite
+        for (port, seconds) in [(0, 10), (1, 20), (2, 30)] {
+            let mut info = connection(100, 1, seconds, "US", AppProtocol::HTTPS);
+            info.is_favorite = port == 1;
+            info_traffic.insert_connection(key(port), info);
+        }
+        // the last connection exchanges traffic again
+        let last = info_traffic.get_connection_mut(2).unwrap();
+        let previous = last.final_timestamp;
+        last.final_timestamp = previous + chrono::Duration::seconds(100);
+        let current = last.final_timestamp;
+        info_traffic.record_activity(2, previous, current);
+        info_traffic.addresses_last_interval.clear();
+
+        for (port, seconds) in [(3, 40), (4, 50)] {
+            let info = connection(100, 1, seconds, "US", AppProtocol::HTTPS);
+            info_traffic.insert_connection(key(port), info);
+            assert_eq!(info_traffic.map.len(), 3);
+        }
+        // the least recently active connections are evicted, except the favorite one
+        let ports: Vec<u16> = info_traffic.map.keys().map(|key| key.port1).collect();
+        assert_eq!(ports, vec![2, 1, 4]);
+        for (index, info) in info_traffic.map.values().enumerate() {
+            assert_eq!(info_traffic.get_index_of_connection(info.id), Some(index));
+        }
+        assert!(info_traffic.get_connection(0).is_none());
+        assert!(info_traffic.get_connection(3).is_none());
+        // the connection moved in place of an evicted one has to be rewritten in the output report
+        assert_eq!(info_traffic.addresses_last_interval, HashSet::from([2]));
+
+        // favorites are kept even if they exceed the limit
+        for info in info_traffic.map.values_mut() {
+            info.is_favorite = true;
+        }
+        let info = connection(100, 1, 60, "US", AppProtocol::HTTPS);
+        info_traffic.insert_connection(key(5), info);
+        assert_eq!(info_traffic.map.len(), 4);
+    }
+
+    #[test]
+    fn test_with_connections() {
+        let key = |port: u16| {
+            AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                port,
+                "1.1.1.1".to_string(),
+                443,
+                TransProtocol::TCP,
+            )
+        };
+        let mut favorite =
+            InfoAddressPortPair::with_traffic(TrafficType::Incoming, AppProtocol::HTTPS, 3000, 3);
+        favorite.is_favorite = true;
+        let info_traffic = InfoTraffic::with_connections([
+            (
+                key(50000),
+                InfoAddressPortPair::with_traffic(
+                    TrafficType::Outgoing,
+                    AppProtocol::HTTPS,
+                    1000,
+                    2,
+                ),
+            ),
+            (key(50001), favorite),
+        ]);
+
+        assert_eq!(info_traffic.map.len(), 2);
+        assert_eq!(info_traffic.get_connection(1).unwrap().0.port1, 50001);
+        assert_eq!(info_traffic.favorite_connections, HashSet::from([1]));
+        assert_eq!(info_traffic.all_packets, 5);
+        assert_eq!(info_traffic.all_bytes, 4000);
+        assert_eq!(
+            (info_traffic.tot_sent_packets, info_traffic.tot_sent_bytes),
+            (2, 1000)
+        );
+        assert_eq!(
+            (
+                info_traffic.tot_received_packets,
+                info_traffic.tot_received_bytes
+            ),
+            (3, 3000)
+        );
+        assert_eq!(
+            info_traffic.app_protocols[&AppProtocol::HTTPS],
+            TrafficSummary {
+                packets: 5,
+                bytes: 4000
+            }
+        );
+    }
+
+    #[test]
+    fn test_filter_connections() {
+        let mut info_traffic = InfoTraffic::new();
+        for (address, port, country) in [
+            ("192.168.1.10", 443, "US"),
+            ("2001:db8::abcd", 53, "IT"),
+            ("10.0.0.1", 22, ""),
+        ] {
+            let info_template = InfoAddressPortPair {
+                initial_timestamp: Local::now(),
+                final_timestamp: Local::now(),
+                traffic_type: TrafficType::Outgoing,
+                country: country.to_string(),
+                ..InfoAddressPortPair::default()
+            };
+            info_traffic.map.insert(
+                AddressPortPair::new(
+                    "172.16.0.5".to_string(),
+                    50000,
+                    address.to_string(),
+                    port,
+                    TransProtocol::TCP,
+                ),
+                info_template,
+            );
+        }
+
+        assert_eq!(info_traffic.filter_connections(""), vec![0, 1, 2]);
+        assert_eq!(info_traffic.filter_connections("  "), vec![0, 1, 2]);
+        assert_eq!(info_traffic.filter_connections("172.16"), vec![0, 1, 2]);
+        assert_eq!(info_traffic.filter_connections("168.1"), vec![0]);
+        assert_eq!(info_traffic.filter_connections("DB8::ABCD"), vec![1]);
+        // ports match exactly, not as substrings
+        assert_eq!(info_traffic.filter_connections("53"), vec![1]);
+        assert_eq!(info_traffic.filter_connections("50000"), vec![0, 1, 2]);
+        assert_eq!(info_traffic.filter_connections("4"), Vec::<usize>::new());
+        // "10" is both part of an address and a port number
+        assert_eq!(info_traffic.filter_connections("10"), vec![0, 2]);
+        assert_eq!(info_traffic.filter_connections("it"), vec![1]);
+        assert_eq!(info_traffic.filter_connections(" us "), vec![0]);
+        assert_eq!(info_traffic.filter_connections("u"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_session_schema_mismatch() {
+        let json = InfoTraffic::new()
+            .to_json()
+            .replace("\"schema_version\":1", "\"schema_version\":0");
+        assert!(InfoTraffic::from_json(json.as_bytes())
+            .err()
+            .unwrap()
+            .contains("schema version 0"));
+        assert!(InfoTraffic::from_json(b"{\"schema_version\":1}").is_err());
+        assert!(InfoTraffic::from_json(InfoTraffic::new().to_json().as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_size_buckets() {
+        for (bytes, bucket) in [
+            (0, 0),
+            (1, 0),
+            (64, 0),
+            (65, 1),
+            (128, 1),
+            (129, 2),
+            (512, 3),
+            (1024, 4),
+            (1025, 5),
+            (1500, 5),
+            (65535, 5),
+        ] {
+            assert_eq!(get_size_bucket(bytes), bucket, "{bytes} bytes");
+        }
+
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.size_histogram = [1, 2, 3, 4, 5, 6];
+        let buckets = info_traffic.size_histogram_buckets();
+        assert_eq!(buckets.len(), SIZE_BUCKETS);
+        assert_eq!(
+            buckets,
+            vec![
+                ("0-64".to_string(), 1),
+                ("65-128".to_string(), 2),
+                ("129-256".to_string(), 3),
+                ("257-512".to_string(), 4),
+                ("513-1024".to_string(), 5),
+                ("1025+".to_string(), 6),
+            ]
+        );
     }
 }
@@ -0,0 +1,80 @@
+//! Module defining the `MacAddress` struct, which represents the link layer address of an Ethernet interface.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Struct representing a MAC address, displayed as six colon separated hexadecimal bytes.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+impl Serialize for MacAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = String;
+
+    fn from_str(mac: &str) -> Result<Self, Self::Err> {
+        let mut bytes = [0; 6];
+        let mut parts = mac.split(':');
+        for byte in &mut bytes {
+            *byte = parts
+                .next()
+                .filter(|part| part.len() == 2)
+                .and_then(|part| u8::from_str_radix(part, 16).ok())
+                .ok_or_else(|| format!("invalid MAC address \"{mac}\""))?;
+        }
+        if parts.next().is_some() {
+            return Err(format!("invalid MAC address \"{mac}\""));
+        }
+        Ok(MacAddress(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for MacAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::mac_address::MacAddress;
+
+    #[test]
+    fn test_mac_address_format() {
+        let mac = MacAddress([0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6]);
+        assert_eq!(mac.to_string(), "00:1b:63:84:45:e6");
+        assert_eq!(
+            serde_json::to_string(&mac).unwrap(),
+            "\"00:1b:63:84:45:e6\""
+        );
+        assert_eq!(MacAddress::default().to_string(), "00:00:00:00:00:00");
+        assert_eq!(
+            serde_json::from_str::<MacAddress>("\"00:1b:63:84:45:e6\"").unwrap(),
+            mac
+        );
+        for invalid in [
+            "00:1b:63:84:45",
+            "00:1b:63:84:45:e6:01",
+            "00:1b:63:84:45:g6",
+            "0:1b:63:84:45:e6",
+        ] {
+            assert!(invalid.parse::<MacAddress>().is_err(), "{invalid}");
+        }
+    }
+}
@@ -1,11 +1,23 @@
 pub mod address_port_pair;
+pub mod asn;
+pub mod capture_options;
+pub mod cli_args;
 pub mod configs;
+pub mod discovery_traffic;
+pub mod favorite_rule;
 pub mod filters;
+pub mod hostname_cache;
 pub mod info_address_port_pair;
 pub mod info_traffic;
+pub mod mac_address;
 pub mod notifications;
 pub mod palette;
+pub mod pcap_export;
 pub mod runtime_data;
+pub mod saved_favorites;
 pub mod sniffer;
 pub mod style_tuple;
+pub mod tcp_connection;
+pub mod throughput_history;
+pub mod traffic_buffer;
 pub mod traffic_chart;
@@ -1,14 +1,65 @@
+use crate::enums::notification_direction::NotificationDirection;
+use crate::enums::threshold_mode::ThresholdMode;
 use crate::enums::{byte_multiple::from_char_to_multiple, sound::Sound};
-use crate::ByteMultiple;
+use crate::{ByteMultiple, UnitSystem};
 use serde::{Deserialize, Serialize};
 
 /// Used to contain the notifications configuration set by the user
-#[derive(Clone, Serialize, Deserialize, Copy)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Notifications {
     pub volume: u8,
     pub packets_notification: PacketsNotification,
     pub bytes_notification: BytesNotification,
     pub favorite_notification: FavoriteNotification,
+    /// Threshold of bytes per second exchanged by a single connection; if exceeded a notification is emitted
+    #[serde(default = "default_connection_rate_notification")]
+    pub connection_rate_notification: BytesNotification,
+    #[serde(default)]
+    pub new_country_notification: NewCountryNotification,
+    /// Country codes (e.g. `US`) of the expected destinations, for which no new country notification is emitted
+    #[serde(default)]
+    pub allowed_countries: Vec<String>,
+    /// URL of a webhook (e.g. Slack or a generic endpoint) to which emitted notifications are posted
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Maximum number of notifications kept in the log; when exceeded, the oldest ones are dropped
+    #[serde(default = "default_max_logged_notifications")]
+    pub max_logged_notifications: usize,
+    /// Time window (in seconds) during which repeated notifications for the same favorite connection are suppressed
+    #[serde(default = "default_favorite_debounce_seconds")]
+    pub favorite_debounce_seconds: u64,
+    /// Time window (in seconds) after a packets or bytes threshold notification, during which further notifications
+    /// of the same type are suppressed even if the threshold keeps being exceeded; if 0, they're logged at every interval
+    #[serde(default = "default_threshold_cooldown_seconds")]
+    pub threshold_cooldown_seconds: u64,
+    /// Delay (in seconds) between the capture of the packets and their processing beyond which a warning is logged,
+    /// signaling that the capture is falling behind; if 0, the delay is not monitored
+    #[serde(default = "default_capture_lag_threshold_seconds")]
+    pub capture_lag_threshold_seconds: u64,
+}
+
+fn default_connection_rate_notification() -> BytesNotification {
+    BytesNotification {
+        byte_multiple: ByteMultiple::MB,
+        previous_threshold: 10_000_000,
+        ..BytesNotification::default()
+    }
+}
+
+fn default_max_logged_notifications() -> usize {
+    30
+}
+
+fn default_favorite_debounce_seconds() -> u64 {
+    10
+}
+
+fn default_threshold_cooldown_seconds() -> u64 {
+    30
+}
+
+fn default_capture_lag_threshold_seconds() -> u64 {
+    5
 }
 
 impl Default for Notifications {
@@ -18,18 +69,29 @@ impl Default for Notifications {
             packets_notification: PacketsNotification::default(),
             bytes_notification: BytesNotification::default(),
             favorite_notification: FavoriteNotification::default(),
+            connection_rate_notification: default_connection_rate_notification(),
+            new_country_notification: NewCountryNotification::default(),
+            allowed_countries: Vec::new(),
+            webhook_url: None,
+            max_logged_notifications: default_max_logged_notifications(),
+            favorite_debounce_seconds: default_favorite_debounce_seconds(),
+            threshold_cooldown_seconds: default_threshold_cooldown_seconds(),
+            capture_lag_threshold_seconds: default_capture_lag_threshold_seconds(),
         }
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Copy)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct PacketsNotification {
-    /// Threshold of received + sent bytes; if exceeded a notification is emitted
+    /// Threshold of received + sent packets; if exceeded a notification is emitted
     pub threshold: Option<u32>,
     /// The sound to emit
     pub sound: Sound,
     /// The last used Some value for the threshold field
     pub previous_threshold: u32,
+    /// Whether the threshold is compared with the received, the sent, or the received + sent packets
+    #[serde(default)]
+    pub direction: NotificationDirection,
 }
 
 impl Default for PacketsNotification {
@@ -38,6 +100,7 @@ impl Default for PacketsNotification {
             threshold: None,
             sound: Sound::Gulp,
             previous_threshold: 750,
+            direction: NotificationDirection::default(),
         }
     }
 }
@@ -60,7 +123,7 @@ impl PacketsNotification {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Copy)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct BytesNotification {
     /// Threshold of received + sent bytes; if exceeded a notification is emitted
     pub threshold: Option<u64>,
@@ -70,6 +133,14 @@ pub struct BytesNotification {
     pub sound: Sound,
     /// The last used Some value for the threshold field
     pub previous_threshold: u64,
+    /// Whether the threshold is a rate per second or an amount per refresh interval
+    /// (the connection rate threshold is always a rate per second)
+    #[serde(default)]
+    pub threshold_mode: ThresholdMode,
+    /// Whether the threshold is compared with the received, the sent, or the received + sent bytes
+    /// (the connection rate threshold is always compared with the received + sent bytes)
+    #[serde(default)]
+    pub direction: NotificationDirection,
 }
 
 impl Default for BytesNotification {
@@ -79,6 +150,8 @@ impl Default for BytesNotification {
             byte_multiple: ByteMultiple::KB,
             sound: Sound::Pop,
             previous_threshold: 800_000,
+            threshold_mode: ThresholdMode::default(),
+            direction: NotificationDirection::default(),
         }
     }
 }
@@ -122,9 +195,18 @@ impl BytesNotification {
             ..default
         }
     }
+
+    /// Returns the threshold in bytes, interpreting its multiple in the given unit system
+    /// (e.g. `800K` are 800 000 bytes in the decimal system, 819 200 in the binary one).
+    pub fn threshold_in(&self, unit_system: UnitSystem) -> Option<u128> {
+        let multiplier = u128::from(self.byte_multiple.get_multiplier());
+        self.threshold.map(|threshold| {
+            u128::from(threshold) / multiplier * self.byte_multiple.get_divisor(unit_system)
+        })
+    }
 }
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Copy)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct FavoriteNotification {
     /// Flag to determine if this notification is enabled
     pub notify_on_favorite: bool,
@@ -159,6 +241,50 @@ impl FavoriteNotification {
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct NewCountryNotification {
+    /// Flag to determine if this notification is enabled
+    pub notify_on_new_country: bool,
+    /// The sound to emit
+    pub sound: Sound,
+}
+
+impl Default for NewCountryNotification {
+    fn default() -> Self {
+        NewCountryNotification {
+            notify_on_new_country: false,
+            sound: Sound::Gulp,
+        }
+    }
+}
+
+impl NewCountryNotification {
+    /// Constructor when the notification is in use
+    pub fn on(sound: Sound) -> Self {
+        NewCountryNotification {
+            notify_on_new_country: true,
+            sound,
+        }
+    }
+
+    /// Constructor when the notification is not in use. Note that sound is used here for caching, although it won't actively be used.
+    pub fn off(sound: Sound) -> Self {
+        NewCountryNotification {
+            notify_on_new_country: false,
+            sound,
+        }
+    }
+}
+
+impl Notifications {
+    /// Checks if the country with the given code is among the expected ones (case insensitive).
+    pub fn is_country_allowed(&self, country: &str) -> bool {
+        self.allowed_countries
+            .iter()
+            .any(|allowed| allowed.trim().eq_ignore_ascii_case(country))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,6 +331,23 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case("1500", UnitSystem::Binary, 1500)]
+    #[case("800k", UnitSystem::Decimal, 800_000)]
+    #[case("800k", UnitSystem::Binary, 819_200)]
+    #[case("2M", UnitSystem::Binary, 2_097_152)]
+    #[case("3g", UnitSystem::Binary, 3_221_225_472)]
+    fn test_threshold_in_unit_system(
+        #[case] input: &str,
+        #[case] unit_system: UnitSystem,
+        #[case] expected: u128,
+    ) {
+        assert_eq!(
+            BytesNotification::from(input, None).threshold_in(unit_system),
+            Some(expected)
+        );
+    }
+
     #[test]
     fn test_can_instanciate_favourite_notification() {
         assert_eq!(
@@ -237,6 +380,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_allowed_countries() {
+        let notifications = Notifications {
+            allowed_countries: vec!["it".to_string(), " US ".to_string()],
+            ..Notifications::default()
+        };
+        assert!(notifications.is_country_allowed("IT"));
+        assert!(notifications.is_country_allowed("US"));
+        assert!(!notifications.is_country_allowed("FR"));
+        assert!(!Notifications::default().is_country_allowed("IT"));
+    }
+
     #[rstest]
     #[case("123", PacketsNotification { 
         previous_threshold: 123,
@@ -1,6 +1,10 @@
 //! Module defining the `Colors` struct, which defines the colors in use in the GUI.
 
-use crate::utility::style_constants::{DAY_STYLE, DEEP_SEA_STYLE, MON_AMOUR_STYLE, NIGHT_STYLE};
+use std::sync::OnceLock;
+
+use crate::utility::style_constants::{
+    COLORBLIND_STYLE, DAY_STYLE, DEEP_SEA_STYLE, MON_AMOUR_STYLE, NIGHT_STYLE,
+};
 use crate::StyleType;
 use iced::Color;
 use plotters::style::RGBColor;
@@ -14,6 +18,7 @@ use plotters::style::RGBColor;
 /// - `incoming` and `outgoing` should be complementary colors if possible
 /// - `text_headers` should be black or white and must have a strong contrast with `secondary`
 /// - `text_body` should be black or white and must have a strong contrast with `primary`
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Palette {
     /// Main color of the GUI (background, hovered buttons, active tab)
     pub primary: Color,
@@ -33,12 +38,96 @@ pub struct Palette {
     pub round_borders: Color,
 }
 
+/// Palette defined by the user in the configuration file, used by `StyleType::Custom`
+static CUSTOM_PALETTE: OnceLock<Palette> = OnceLock::new();
+
 pub fn get_colors(style: StyleType) -> Palette {
     match style {
         StyleType::Night => NIGHT_STYLE,
         StyleType::Day => DAY_STYLE,
         StyleType::DeepSea => DEEP_SEA_STYLE,
         StyleType::MonAmour => MON_AMOUR_STYLE,
+        StyleType::Colorblind => COLORBLIND_STYLE,
+        StyleType::Custom => CUSTOM_PALETTE.get().copied().unwrap_or(NIGHT_STYLE),
+    }
+}
+
+/// Sets the palette used by `StyleType::Custom`; only the first call has effect.
+pub fn set_custom_palette(palette: Palette) {
+    CUSTOM_PALETTE.get_or_init(|| palette);
+}
+
+impl Palette {
+    /// Builds a palette from colors in the `#RRGGBB` format.
+    ///
+    /// Header and body texts are black or white, whichever has the strongest contrast
+    /// with `secondary` and `primary` respectively.
+    pub fn from_hex(
+        primary: &str,
+        secondary: &str,
+        buttons: &str,
+        incoming: &str,
+        outgoing: &str,
+    ) -> Result<Self, String> {
+        let parse = |name: &str, hex: &str| {
+            color_from_hex(hex).map_err(|err| format!("invalid {name} color ({err})"))
+        };
+        let primary = parse("primary", primary)?;
+        let secondary = parse("secondary", secondary)?;
+        Ok(Palette {
+            primary,
+            secondary,
+            buttons: parse("buttons", buttons)?,
+            incoming: parse("incoming", incoming)?,
+            outgoing: parse("outgoing", outgoing)?,
+            text_headers: get_text_color(secondary),
+            text_body: get_text_color(primary),
+            round_borders: Color::BLACK,
+        })
+    }
+}
+
+/// Parses a color in the `#RRGGBB` format.
+pub fn color_from_hex(hex: &str) -> Result<Color, String> {
+    let digits = hex
+        .trim()
+        .strip_prefix('#')
+        .filter(|digits| digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| format!("\"{hex}\" is not in the #RRGGBB format"))?;
+    let channel = |i: usize| f32::from(u8::from_str_radix(&digits[i..i + 2], 16).unwrap()) / 255.0;
+    Ok(Color {
+        r: channel(0),
+        g: channel(2),
+        b: channel(4),
+        a: 1.0,
+    })
+}
+
+/// Relative luminance of a color, as defined by the WCAG (0 for black, 1 for white)
+pub fn relative_luminance(color: Color) -> f32 {
+    let linear = |c: f32| {
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.r) + 0.7152 * linear(color.g) + 0.0722 * linear(color.b)
+}
+
+/// Contrast ratio between two colors, as defined by the WCAG (from 1 to 21)
+pub fn contrast_ratio(color1: Color, color2: Color) -> f32 {
+    let luminance1 = relative_luminance(color1);
+    let luminance2 = relative_luminance(color2);
+    (luminance1.max(luminance2) + 0.05) / (luminance1.min(luminance2) + 0.05)
+}
+
+/// Returns black or white, whichever has the strongest contrast with the given background.
+fn get_text_color(background: Color) -> Color {
+    if contrast_ratio(background, Color::WHITE) >= contrast_ratio(background, Color::BLACK) {
+        Color::WHITE
+    } else {
+        Color::BLACK
     }
 }
 
@@ -67,3 +156,61 @@ impl Default for Palette {
         get_colors(StyleType::Night)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use iced::Color;
+
+    use crate::structs::palette::{color_from_hex, contrast_ratio, get_colors, Palette};
+    use crate::StyleType;
+
+    #[test]
+    fn test_color_from_hex() {
+        assert_eq!(color_from_hex("#000000"), Ok(Color::BLACK));
+        assert_eq!(color_from_hex(" #FFffFF "), Ok(Color::WHITE));
+        assert_eq!(
+            color_from_hex("#336699"),
+            Ok(Color::from_rgb8(0x33, 0x66, 0x99))
+        );
+        for invalid in ["336699", "#36c", "#3366990", "#33669g", "#", ""] {
+            assert!(color_from_hex(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_palette_from_hex() {
+        let palette =
+            Palette::from_hex("#1e1e2e", "#f5c2e7", "#313244", "#89b4fa", "#fab387").unwrap();
+        assert_eq!(palette.primary, Color::from_rgb8(0x1e, 0x1e, 0x2e));
+        assert_eq!(palette.outgoing, Color::from_rgb8(0xfa, 0xb3, 0x87));
+        assert_eq!(palette.text_body, Color::WHITE);
+        assert_eq!(palette.text_headers, Color::BLACK);
+
+        let err = Palette::from_hex("#ffffff", "#0000ff", "#dddddd", "blue", "#ff8800");
+        assert_eq!(
+            err,
+            Err("invalid incoming color (\"blue\" is not in the #RRGGBB format)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio(Color::BLACK, Color::WHITE) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(Color::WHITE, Color::WHITE) - 1.0).abs() < 0.01);
+        let grey = Color::from_rgb8(0x77, 0x77, 0x77);
+        assert!((contrast_ratio(grey, Color::WHITE) - 4.48).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_colorblind_palette_contrast() {
+        let palette = get_colors(StyleType::Colorblind);
+        // WCAG AAA for body text, AA for headers and for graphical objects
+        assert!(contrast_ratio(palette.text_body, palette.primary) >= 7.0);
+        assert!(contrast_ratio(palette.text_body, palette.buttons) >= 7.0);
+        assert!(contrast_ratio(palette.text_headers, palette.secondary) >= 4.5);
+        assert!(contrast_ratio(palette.incoming, palette.primary) >= 4.5);
+        assert!(contrast_ratio(palette.outgoing, palette.primary) >= 4.5);
+        // incoming and outgoing differ in lightness too, not only in hue
+        assert!(contrast_ratio(palette.incoming, palette.outgoing) >= 1.5);
+    }
+}
@@ -0,0 +1,163 @@
+//! Module defining the `PcapExport` struct, which specifies where the packets accepted by the filters are saved,
+//! and the `PcapExportWriter` struct, in charge of writing them to pcap files.
+
+use std::path::{Path, PathBuf};
+
+use pcap::{Capture, Linktype, Packet, Savefile};
+use serde::{Deserialize, Serialize};
+
+/// Size in bytes of the global header of a pcap file
+const PCAP_GLOBAL_HEADER_SIZE: u64 = 24;
+/// Size in bytes of the header of each packet record in a pcap file
+const PCAP_RECORD_HEADER_SIZE: u64 = 16;
+
+/// Destination of the pcap export of the packets accepted by the filters
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PcapExport {
+    /// Path of the (first) pcap file
+    pub path: PathBuf,
+    /// Maximum size in bytes of each pcap file, after which a new file is started;
+    /// if not specified, all the packets are saved in the same file
+    pub max_file_size: Option<u64>,
+}
+
+impl PcapExport {
+    /// Returns the settings of the export of the packets sniffed on an additional interface,
+    /// saved to files named after the original ones with the interface name appended to the file stem.
+    pub fn for_interface(&self, interface: &str) -> Self {
+        let interface: String = interface
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        PcapExport {
+            path: append_to_file_stem(&self.path, &interface),
+            max_file_size: self.max_file_size,
+        }
+    }
+}
+
+/// Writes packets to pcap files, rotating them according to the `PcapExport` settings.
+///
+/// Rotated files are named after the original one, with an increasing index appended to the file stem
+/// (`capture.pcap`, `capture_1.pcap`, `capture_2.pcap`, ...).
+pub struct PcapExportWriter {
+    /// Settings of the export
+    export: PcapExport,
+    /// Link type of the sniffed packets
+    linktype: Linktype,
+    /// File currently written
+    savefile: Savefile,
+    /// Size in bytes of the file currently written
+    file_size: u64,
+    /// Index of the file currently written
+    file_index: usize,
+}
+
+impl PcapExportWriter {
+    /// Creates the first pcap file of the export, for packets of the given link type.
+    pub fn new(export: &PcapExport, linktype: Linktype) -> Result<Self, pcap::Error> {
+        Ok(PcapExportWriter {
+            export: export.clone(),
+            linktype,
+            savefile: open_savefile(&export.path, linktype)?,
+            file_size: PCAP_GLOBAL_HEADER_SIZE,
+            file_index: 0,
+        })
+    }
+
+    /// Writes a packet, starting a new file first if the current one would exceed the maximum size.
+    pub fn write(&mut self, packet: &Packet) -> Result<(), pcap::Error> {
+        let record_size = PCAP_RECORD_HEADER_SIZE + u64::from(packet.header.caplen);
+        if let Some(max_file_size) = self.export.max_file_size {
+            // a file always contains at least a packet, even if bigger than the maximum size
+            if self.file_size > PCAP_GLOBAL_HEADER_SIZE
+                && self.file_size + record_size > max_file_size
+            {
+                self.savefile.flush()?;
+                self.file_index += 1;
+                let path = get_rotated_path(&self.export.path, self.file_index);
+                self.savefile = open_savefile(&path, self.linktype)?;
+                self.file_size = PCAP_GLOBAL_HEADER_SIZE;
+            }
+        }
+        self.savefile.write(packet);
+        self.file_size += record_size;
+        Ok(())
+    }
+
+    /// Flushes the packets written so far to the current file.
+    pub fn flush(&mut self) -> Result<(), pcap::Error> {
+        self.savefile.flush()
+    }
+}
+
+/// Opens a new pcap file for packets of the given link type.
+///
+/// A dead capture is used, so that files can be opened independently of the capture in progress.
+fn open_savefile(path: &Path, linktype: Linktype) -> Result<Savefile, pcap::Error> {
+    Capture::dead(linktype)?.savefile(path)
+}
+
+/// Returns the path of the file with the given rotation index.
+fn get_rotated_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    append_to_file_stem(path, &index.to_string())
+}
+
+/// Appends an underscore followed by `suffix` to the file stem of the path.
+fn append_to_file_stem(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{stem}_{suffix}.{}", extension.to_string_lossy()),
+        None => format!("{stem}_{suffix}"),
+    };
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::structs::pcap_export::{get_rotated_path, PcapExport};
+
+    #[test]
+    fn test_rotated_paths() {
+        let path = Path::new("/tmp/sniffnet/capture.pcap");
+        assert_eq!(get_rotated_path(path, 0), path);
+        assert_eq!(
+            get_rotated_path(path, 1),
+            PathBuf::from("/tmp/sniffnet/capture_1.pcap")
+        );
+        assert_eq!(
+            get_rotated_path(path, 12),
+            PathBuf::from("/tmp/sniffnet/capture_12.pcap")
+        );
+        assert_eq!(
+            get_rotated_path(Path::new("capture"), 3),
+            PathBuf::from("capture_3")
+        );
+    }
+    #[test]
+    fn test_interface_paths() {
+        let export = PcapExport {
+            path: PathBuf::from("/tmp/sniffnet/capture.pcap"),
+            max_file_size: Some(1000),
+        };
+        let interface_export = export.for_interface("eth1");
+        assert_eq!(
+            interface_export.path,
+            PathBuf::from("/tmp/sniffnet/capture_eth1.pcap")
+        );
+        assert_eq!(interface_export.max_file_size, Some(1000));
+        assert_eq!(
+            get_rotated_path(&interface_export.path, 2),
+            PathBuf::from("/tmp/sniffnet/capture_eth1_2.pcap")
+        );
+        assert_eq!(
+            export.for_interface("\\Device\\NPF_{1234}").path,
+            PathBuf::from("/tmp/sniffnet/capture__Device_NPF__1234_.pcap")
+        );
+    }
+}
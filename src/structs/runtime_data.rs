@@ -1,30 +1,61 @@
 //! Module defining the `RunTimeData` struct, useful to to generate charts and to display statistics about network traffic
 //!
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use tracing::warn;
 
 use crate::enums::logged_notification::LoggedNotification;
 use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::discovery_traffic::DiscoveryTraffic;
 use crate::structs::info_address_port_pair::InfoAddressPortPair;
+use crate::structs::info_traffic::{InterfaceStats, TrafficSummary};
+use crate::structs::throughput_history::{ThroughputHistory, DEFAULT_THROUGHPUT_HISTORY_SECONDS};
 use crate::AppProtocol;
 
+/// Default time elapsed between two consecutive refreshes of the running page, in milliseconds
+pub const DEFAULT_REFRESH_INTERVAL_MS: u64 = 1000;
+
+/// Shortest refresh interval accepted, in milliseconds
+pub const MIN_REFRESH_INTERVAL_MS: u64 = 100;
+
+/// Longest refresh interval accepted, in milliseconds
+pub const MAX_REFRESH_INTERVAL_MS: u64 = 60_000;
+
 /// Struct containing useful data to generate charts and to display statistics about network traffic
 pub struct RunTimeData {
     /// Total number of bytes (filtered and not filtered)
     pub all_bytes: u128,
-    /// Sent bytes filtered and their time occurrence
-    pub sent_bytes: VecDeque<(u32, i64)>,
-    /// Received bytes filtered and their time occurrence
-    pub received_bytes: VecDeque<(u32, i64)>,
     /// Total number of packets (filtered and not filtered)
     pub all_packets: u128,
-    /// Sent packets filtered and their time occurrence
-    pub sent_packets: VecDeque<(u32, i64)>,
-    /// Received packets filtered and their time occurrence
-    pub received_packets: VecDeque<(u32, i64)>,
-    /// Application protocol with the respective number of filtered packets
-    pub app_protocols: HashMap<AppProtocol, u128>,
+    /// Rates of the filtered sent and received bytes and packets in the last intervals
+    pub throughput_history: ThroughputHistory,
+    /// Packets dropped by the system because not read fast enough
+    pub dropped_packets: u128,
+    /// Packets dropped by the network adapters or their drivers
+    pub if_dropped_packets: u128,
+    /// Broadcast traffic aggregated instead of being reported per connection
+    pub broadcast_summary: TrafficSummary,
+    /// Multicast traffic aggregated instead of being reported per connection
+    pub multicast_summary: TrafficSummary,
+    /// Application protocol with the respective number of filtered packets and bytes
+    pub app_protocols: HashMap<AppProtocol, TrafficSummary>,
+    /// Application protocol with the respective number of filtered packets and bytes before the current time interval
+    pub app_protocols_prev: HashMap<AppProtocol, TrafficSummary>,
+    /// Filtered packets of each application protocol and their time occurrence
+    pub app_protocol_packets: HashMap<AppProtocol, VecDeque<(u32, i64)>>,
     /// Connection entries to be displayed in report column
     pub report_vec: Vec<(AddressPortPair, InfoAddressPortPair)>,
+    /// Connections that exchanged the most traffic (according to the displayed chart kind)
+    pub top_talkers: Vec<(AddressPortPair, InfoAddressPortPair)>,
+    /// Total bytes and packets exchanged with each country
+    pub country_breakdown: HashMap<String, (u128, u128)>,
+    /// Number of packets in each bucket of sizes, labeled with its range
+    pub size_histogram: Vec<(String, u64)>,
+    /// ARP and IGMP packets observed so far
+    pub discovery_traffic: DiscoveryTraffic,
+    /// Traffic sniffed on each network adapter, by its name
+    pub per_interface: HashMap<String, InterfaceStats>,
     /// Total sent bytes filtered
     pub tot_sent_bytes: u128,
     /// Total received bytes filtered
@@ -41,20 +72,42 @@ pub struct RunTimeData {
     pub tot_sent_packets_prev: u128,
     /// Total received packets filtered before the current time interval
     pub tot_received_packets_prev: u128,
-    /// Minimum number of sent bytes per time interval (computed on last 30 intervals)
+    /// Minimum number of sent bytes per second (computed on the intervals displayed in the chart)
     pub min_sent_bytes: i64,
-    /// Minimum number of received bytes per time interval (computed on last 30 intervals)
+    /// Maximum number of received bytes per second (computed on the intervals displayed in the chart)
     pub max_received_bytes: i64,
-    /// Minimum number of sent packets per time interval (computed on last 30 intervals)
+    /// Minimum number of sent packets per second (computed on the intervals displayed in the chart)
     pub min_sent_packets: i64,
-    /// Minimum number of received packets per time interval (computed on last 30 intervals)
+    /// Maximum number of received packets per second (computed on the intervals displayed in the chart)
     pub max_received_packets: i64,
-    /// Collection of favorite connections that exchanged data in the last interval
-    pub favorites_last_interval: HashSet<usize>,
+    /// Identifiers of the favorite connections that exchanged data in the last interval
+    pub favorites_last_interval: HashSet<u64>,
+    /// Identifiers of the connections whose rate exceeded the threshold in the last interval
+    pub connections_over_rate: HashSet<u64>,
+    /// Maximum delay between the capture of a packet and its processing in the last interval
+    pub capture_lag: Duration,
+    /// Whether the capture lag exceeded the threshold in the last interval
+    pub capture_lagging: bool,
+    /// Network adapter and reason of the errors stalling the capture, if any
+    pub capture_error: Option<String>,
+    /// Countries contacted for the first time in the last interval, with the identifier of the first connection featuring them
+    pub new_countries_last_interval: Vec<(String, u64)>,
+    /// Last time a notification has been logged for each favorite connection (by its identifier)
+    pub favorites_last_notified: HashMap<u64, Instant>,
     /// Log of the received notifications
     pub logged_notifications: VecDeque<LoggedNotification>,
+    /// Last time a packets threshold notification has been logged
+    pub packets_threshold_last_notified: Option<Instant>,
+    /// Last time a bytes threshold notification has been logged
+    pub bytes_threshold_last_notified: Option<Instant>,
+    /// Last time the notifications webhook has been called
+    pub last_webhook_call: Option<Instant>,
+    /// Last time a notification sound has been emitted
+    pub last_sound_emitted: Option<Instant>,
     /// Current time interval number
     pub ticks: u32,
+    /// Time elapsed between two consecutive refreshes: the time base of the per interval deltas
+    pub refresh_interval: Duration,
 }
 
 impl RunTimeData {
@@ -62,13 +115,21 @@ impl RunTimeData {
     pub fn new() -> Self {
         RunTimeData {
             all_bytes: 0,
-            sent_bytes: VecDeque::default(),
-            received_bytes: VecDeque::default(),
             all_packets: 0,
-            sent_packets: VecDeque::default(),
-            received_packets: VecDeque::default(),
+            throughput_history: ThroughputHistory::new(DEFAULT_THROUGHPUT_HISTORY_SECONDS),
+            dropped_packets: 0,
+            if_dropped_packets: 0,
+            broadcast_summary: TrafficSummary::default(),
+            multicast_summary: TrafficSummary::default(),
             app_protocols: HashMap::default(),
+            app_protocols_prev: HashMap::new(),
+            app_protocol_packets: HashMap::new(),
             report_vec: Vec::default(),
+            top_talkers: Vec::default(),
+            country_breakdown: HashMap::new(),
+            size_histogram: Vec::new(),
+            discovery_traffic: DiscoveryTraffic::default(),
+            per_interface: HashMap::new(),
             tot_sent_bytes: 0,
             tot_received_bytes: 0,
             tot_sent_packets: 0,
@@ -82,8 +143,101 @@ impl RunTimeData {
             min_sent_packets: 0,
             max_received_packets: 0,
             favorites_last_interval: HashSet::new(),
+            connections_over_rate: HashSet::new(),
+            capture_lag: Duration::ZERO,
+            capture_lagging: false,
+            capture_error: None,
+            new_countries_last_interval: Vec::new(),
+            favorites_last_notified: HashMap::new(),
             logged_notifications: VecDeque::default(),
+            packets_threshold_last_notified: None,
+            bytes_threshold_last_notified: None,
+            last_webhook_call: None,
+            last_sound_emitted: None,
             ticks: 0,
+            refresh_interval: Duration::from_millis(DEFAULT_REFRESH_INTERVAL_MS),
         }
     }
+
+    /// Converts an amount of traffic exchanged in the last refresh interval into the corresponding rate per second,
+    /// so that it doesn't depend on the refresh interval in use.
+    pub fn per_second(&self, amount: u128) -> u128 {
+        amount * 1000 / self.refresh_interval.as_millis().max(1)
+    }
+
+    /// Number of refresh intervals spanning the given time window (at least one).
+    pub fn intervals_in(&self, window_seconds: usize) -> usize {
+        let window_ms = u128::try_from(window_seconds)
+            .unwrap_or(u128::MAX)
+            .saturating_mul(1000);
+        usize::try_from(window_ms / self.refresh_interval.as_millis().max(1))
+            .unwrap_or(usize::MAX)
+            .max(1)
+    }
+}
+
+/// Returns the refresh interval specified in the configuration, or the default one if not specified.
+///
+/// Intervals out of the accepted range are clamped to it, with a warning.
+pub fn get_refresh_interval(refresh_interval_ms: Option<u64>) -> Duration {
+    let refresh_interval_ms = match refresh_interval_ms {
+        Some(ms) if !(MIN_REFRESH_INTERVAL_MS..=MAX_REFRESH_INTERVAL_MS).contains(&ms) => {
+            let clamped = ms.clamp(MIN_REFRESH_INTERVAL_MS, MAX_REFRESH_INTERVAL_MS);
+            warn!(refresh_interval_ms = %ms, min_ms = %MIN_REFRESH_INTERVAL_MS, max_ms = %MAX_REFRESH_INTERVAL_MS, used_ms = %clamped, "refresh interval out of range, clamped");
+            clamped
+        }
+        Some(ms) => ms,
+        None => DEFAULT_REFRESH_INTERVAL_MS,
+    };
+    Duration::from_millis(refresh_interval_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::structs::runtime_data::{
+        get_refresh_interval, DEFAULT_REFRESH_INTERVAL_MS, MAX_REFRESH_INTERVAL_MS,
+        MIN_REFRESH_INTERVAL_MS,
+    };
+    use crate::RunTimeData;
+
+    #[test]
+    fn test_get_refresh_interval() {
+        assert_eq!(
+            get_refresh_interval(None),
+            Duration::from_millis(DEFAULT_REFRESH_INTERVAL_MS)
+        );
+        assert_eq!(
+            get_refresh_interval(Some(2500)),
+            Duration::from_millis(2500)
+        );
+        assert_eq!(
+            get_refresh_interval(Some(0)),
+            Duration::from_millis(MIN_REFRESH_INTERVAL_MS)
+        );
+        assert_eq!(
+            get_refresh_interval(Some(u64::MAX)),
+            Duration::from_millis(MAX_REFRESH_INTERVAL_MS)
+        );
+    }
+
+    #[test]
+    fn test_rates_are_independent_of_refresh_interval() {
+        let mut runtime_data = RunTimeData::new();
+        assert_eq!(runtime_data.per_second(300), 300);
+        assert_eq!(runtime_data.intervals_in(30), 30);
+
+        runtime_data.refresh_interval = Duration::from_millis(2000);
+        assert_eq!(runtime_data.per_second(300), 150);
+        assert_eq!(runtime_data.intervals_in(30), 15);
+
+        runtime_data.refresh_interval = Duration::from_millis(250);
+        assert_eq!(runtime_data.per_second(300), 1200);
+        assert_eq!(runtime_data.intervals_in(30), 120);
+
+        // a window shorter than the interval still holds the most recent sample
+        runtime_data.refresh_interval = Duration::from_secs(60);
+        assert_eq!(runtime_data.intervals_in(30), 1);
+    }
 }
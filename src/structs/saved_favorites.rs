@@ -0,0 +1,232 @@
+//! Module defining the `SavedFavorites` struct, which persists the connections starred in the GUI
+//! so that they are marked as favorites again after a restart.
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::favorite_rule::FavoriteMatcher;
+
+/// Address pair of a starred connection.
+///
+/// Ports are not saved, since the local ones are usually ephemeral and change at every restart:
+/// any connection between the two addresses (in either direction) matches.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FavoritePair {
+    pub address1: String,
+    pub address2: String,
+}
+
+impl FavoritePair {
+    /// Constructs the pair with the addresses sorted, so that both the directions of a conversation
+    /// are saved as the same pair.
+    fn new(address1: String, address2: String) -> Self {
+        if address1 <= address2 {
+            FavoritePair { address1, address2 }
+        } else {
+            FavoritePair {
+                address1: address2,
+                address2: address1,
+            }
+        }
+    }
+
+    /// Returns the address pair of the connection.
+    fn from_key(key: &AddressPortPair) -> Self {
+        FavoritePair::new(key.address1.clone(), key.address2.clone())
+    }
+
+    /// Parses the addresses of the pair, returning `None` if either is not valid.
+    fn to_matcher(&self) -> Option<FavoriteMatcher> {
+        let address1: IpAddr = self.address1.parse().ok()?;
+        let address2: IpAddr = self.address2.parse().ok()?;
+        Some(FavoriteMatcher::address_pair(address1, address2))
+    }
+}
+
+/// Connections starred in the GUI, saved to file at every change
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SavedFavorites {
+    /// Address pairs of the starred connections, without duplicates
+    pub connections: Vec<FavoritePair>,
+}
+
+impl SavedFavorites {
+    /// Saves the address pair of a starred connection.
+    ///
+    /// Returns `false` if the pair is not saved because it's already saved (e.g. for the other
+    /// direction of the conversation) or because the connection is already matched by one of
+    /// the configured `favorite_rules`, which mark it at every start anyway.
+    pub fn add(&mut self, key: &AddressPortPair, favorite_rules: &[FavoriteMatcher]) -> bool {
+        let pair = FavoritePair::from_key(key);
+        if self.connections.contains(&pair) || favorite_rules.iter().any(|rule| rule.matches(key)) {
+            return false;
+        }
+        self.connections.push(pair);
+        true
+    }
+
+    /// Removes the address pair of a connection that is no longer a favorite.
+    ///
+    /// Returns `false` if the pair was not saved.
+    pub fn remove(&mut self, key: &AddressPortPair) -> bool {
+        let pair = FavoritePair::from_key(key);
+        let len = self.connections.len();
+        self.connections.retain(|saved| *saved != pair);
+        self.connections.len() != len
+    }
+
+    /// Returns the matchers of the saved pairs, to be applied together with the configured rules.
+    ///
+    /// Invalid pairs (e.g. edited by hand) are discarded with a warning.
+    pub fn to_matchers(&self) -> Vec<FavoriteMatcher> {
+        self.connections
+            .iter()
+            .filter_map(|pair| {
+                let matcher = pair.to_matcher();
+                if matcher.is_none() {
+                    warn!(address1 = %pair.address1, address2 = %pair.address2, "saved favorite ignored, invalid address pair");
+                }
+                matcher
+            })
+            .collect()
+    }
+
+    /// Removes the duplicated pairs (e.g. saved in both the orders by hand), keeping the first occurrence.
+    fn dedup(&mut self) {
+        let mut unique: Vec<FavoritePair> = Vec::with_capacity(self.connections.len());
+        for pair in self.connections.drain(..) {
+            let pair = FavoritePair::new(pair.address1, pair.address2);
+            if !unique.contains(&pair) {
+                unique.push(pair);
+            }
+        }
+        self.connections = unique;
+    }
+}
+
+/// Returns the path of the file where the starred connections are saved, next to the configuration files.
+pub fn get_favorites_path() -> Option<PathBuf> {
+    confy::get_configuration_file_path("sniffnet", "favorites").ok()
+}
+
+/// Loads the starred connections saved in the given file.
+///
+/// Returns no connection if the file doesn't exist yet, or (with a warning) if it cannot be read.
+pub fn load_favorites(path: &Path) -> SavedFavorites {
+    if !path.exists() {
+        return SavedFavorites::default();
+    }
+    match confy::load_path::<SavedFavorites>(path) {
+        Ok(mut favorites) => {
+            favorites.dedup();
+            favorites
+        }
+        Err(err) => {
+            warn!(path = %path.display(), error = %err, "cannot load saved favorites");
+            SavedFavorites::default()
+        }
+    }
+}
+
+/// Saves the starred connections to the given file.
+pub fn save_favorites(path: &Path, favorites: &SavedFavorites) -> Result<(), confy::ConfyError> {
+    confy::store_path(path, favorites)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::favorite_rule::{get_favorite_matchers, FavoriteRule};
+    use crate::structs::saved_favorites::{
+        load_favorites, save_favorites, FavoritePair, SavedFavorites,
+    };
+    use crate::TransProtocol;
+
+    fn key(address1: &str, port1: u16, address2: &str, port2: u16) -> AddressPortPair {
+        AddressPortPair::new(
+            address1.to_string(),
+            port1,
+            address2.to_string(),
+            port2,
+            TransProtocol::TCP,
+        )
+    }
+
+    fn pair(address1: &str, address2: &str) -> FavoritePair {
+        FavoritePair {
+            address1: address1.to_string(),
+            address2: address2.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove_favorites() {
+        let favorite_rules = get_favorite_matchers(&[FavoriteRule {
+            network: Some("10.0.0.0/8".to_string()),
+            port: None,
+        }]);
+        let mut favorites = SavedFavorites::default();
+        assert!(favorites.add(&key("192.168.1.10", 50000, "8.8.8.8", 443), &favorite_rules));
+        // the other direction and other ports of the same conversation are duplicates
+        assert!(!favorites.add(&key("8.8.8.8", 443, "192.168.1.10", 50000), &favorite_rules));
+        assert!(!favorites.add(&key("192.168.1.10", 50001, "8.8.8.8", 53), &favorite_rules));
+        // connections matched by the configured rules are not saved
+        assert!(!favorites.add(&key("192.168.1.10", 50000, "10.1.2.3", 22), &favorite_rules));
+        assert_eq!(favorites.connections, vec![pair("192.168.1.10", "8.8.8.8")]);
+
+        let matchers = favorites.to_matchers();
+        assert!(matchers.iter().any(|matcher| matcher.matches(&key(
+            "8.8.8.8",
+            853,
+            "192.168.1.10",
+            40000
+        ))));
+        assert!(!matchers.iter().any(|matcher| matcher.matches(&key(
+            "192.168.1.10",
+            50000,
+            "8.8.4.4",
+            443
+        ))));
+
+        assert!(favorites.remove(&key("8.8.8.8", 443, "192.168.1.10", 50000)));
+        assert!(!favorites.remove(&key("8.8.8.8", 443, "192.168.1.10", 50000)));
+        assert!(favorites.connections.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load_favorites() {
+        let path = std::env::temp_dir().join("sniffnet_test_favorites.toml");
+        fs::remove_file(&path).unwrap_or(());
+        assert_eq!(load_favorites(&path), SavedFavorites::default());
+
+        let favorites = SavedFavorites {
+            connections: vec![
+                pair("192.168.1.10", "8.8.8.8"),
+                // duplicate in the opposite order, e.g. added by hand
+                pair("8.8.8.8", "192.168.1.10"),
+                pair("fe80::1", "2001:db8::1"),
+                pair("not an address", "8.8.8.8"),
+            ],
+        };
+        save_favorites(&path, &favorites).unwrap();
+        let loaded = load_favorites(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.connections,
+            vec![
+                pair("192.168.1.10", "8.8.8.8"),
+                pair("2001:db8::1", "fe80::1"),
+                pair("8.8.8.8", "not an address"),
+            ]
+        );
+        // invalid pairs are discarded
+        assert_eq!(loaded.to_matchers().len(), 2);
+    }
+}
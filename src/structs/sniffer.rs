@@ -1,19 +1,50 @@
 //! Module defining the `Sniffer` struct, which trace gui's component statuses and permits
 //! to share data among the different threads.
 
-use pcap::Device;
+use chrono::Local;
+use pcap::{Active, Capture, Device};
 use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{info_span, warn};
 
+use crate::enums::app_protocol::get_port_protocol_overrides;
 use crate::enums::language::Language;
 use crate::enums::my_overlay::MyOverlay;
 use crate::enums::report_type::ReportType;
 use crate::enums::running_page::RunningPage;
+use crate::enums::sort_column::SortColumn;
 use crate::enums::status::Status;
+use crate::structs::capture_options::CaptureOptions;
+use crate::structs::cli_args::CliArgs;
+use crate::structs::configs::{ConfigCapture, ConfigMmdb};
+use crate::structs::favorite_rule::{get_favorite_matchers, FavoriteMatcher};
 use crate::structs::filters::Filters;
 use crate::structs::notifications::Notifications;
-use crate::{ConfigDevice, ConfigSettings, InfoTraffic, RunTimeData, StyleType, TrafficChart};
+use crate::structs::runtime_data::get_refresh_interval;
+use crate::structs::saved_favorites::{
+    get_favorites_path, load_favorites, save_favorites, SavedFavorites,
+};
+use crate::structs::throughput_history::{ThroughputHistory, DEFAULT_THROUGHPUT_HISTORY_SECONDS};
+use crate::thread_parse_packets::parse_packets_loop;
+use crate::thread_resolve_hostnames::resolve_hostnames_loop;
+#[cfg(feature = "metrics")]
+use crate::thread_serve_metrics::serve_metrics_loop;
+use crate::thread_write_sql::write_sql_loop;
+use crate::utility::anonymize::get_anonymization_salt;
+use crate::utility::countries::MmdbReaders;
+use crate::utility::logging::lock_or_recover;
+use crate::utility::manage_charts_data::update_charts_data;
+use crate::utility::manage_notifications::notify_and_log;
+use crate::utility::manage_packets::get_capture_result;
+use crate::{
+    ConfigDevice, ConfigSettings, InfoTraffic, RunTimeData, StyleType, TrafficChart, UnitSystem,
+};
 
 /// Struct on which the gui is based
 ///
@@ -21,6 +52,10 @@ use crate::{ConfigDevice, ConfigSettings, InfoTraffic, RunTimeData, StyleType, T
 pub struct Sniffer {
     /// Capture number, incremented at every new run
     pub current_capture_id: Arc<Mutex<u16>>,
+    /// Flag set to stop the threads parsing packets of the current run
+    pub capture_stop_flag: Arc<AtomicBool>,
+    /// Flag set while the current run is paused: sniffed packets are discarded
+    pub capture_pause_flag: Arc<AtomicBool>,
     /// Capture data updated by thread parsing packets
     pub info_traffic: Arc<Mutex<InfoTraffic>>,
     /// Status of the application (init or running) and the associated condition variable
@@ -43,6 +78,11 @@ pub struct Sniffer {
     pub traffic_chart: TrafficChart,
     /// Report type to be displayed
     pub report_type: ReportType,
+    /// Column selected in the report header, with the sort direction (true if descending);
+    /// if None, connections are sorted according to the report type
+    pub report_sort: Option<(SortColumn, bool)>,
+    /// Query filtering the connections displayed in the report
+    pub search: String,
     /// Currently displayed overlay; None if no overlay is displayed
     pub overlay: Option<MyOverlay>,
     /// Remembers the last opened setting page
@@ -53,9 +93,57 @@ pub struct Sniffer {
     pub running_page: RunningPage,
     /// Language used in the GUI
     pub language: Language,
+    /// Paths of the user supplied MMDB files
+    pub config_mmdb: ConfigMmdb,
+    /// Whether the hostnames of the remote addresses have to be resolved via reverse DNS
+    pub resolve_hostnames: bool,
+    /// System in which the multiples of bytes are expressed (and the bytes thresholds interpreted)
+    pub unit_system: UnitSystem,
+    /// Path of the JSON file where the traffic data are dumped on exit, if any
+    pub export_json_path: Option<PathBuf>,
+    /// Salt of the hashes replacing the addresses in the exported connections, if they have to be anonymized
+    pub anonymization_salt: Option<u64>,
+    /// Names of the network adapters sniffed together with `device`
+    pub additional_device_names: Vec<String>,
+    /// Path of the file where the traffic session is saved on exit, if any
+    pub session_path: Option<PathBuf>,
+    /// Traffic session restored at startup, continued by the first capture
+    pub restored_session: Option<InfoTraffic>,
+    /// Path of the SQLite database to which the connections are logged, if any
+    pub sqlite_path: Option<PathBuf>,
+    /// Thread logging the connections of the current capture to the SQLite database, if any,
+    /// with the flag to stop it (distinct from the capture one, so that it's stopped after the capture threads)
+    pub sql_writer: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+    /// Address on which the metrics are served, if any
+    pub metrics_address: Option<String>,
+    /// Thread serving the metrics of the current capture, if any
+    pub metrics_server: Option<JoinHandle<()>>,
+    /// Threads parsing the packets of the current capture, one for each sniffed adapter
+    pub capture_threads: Vec<JoinHandle<()>>,
+    /// Time after which the connections not exchanging traffic are evicted, if any
+    pub connection_ttl: Option<Duration>,
+    /// Maximum number of connections kept in memory, if any
+    pub max_connections: Option<usize>,
+    /// Options applied when opening the captures on the network adapters
+    pub capture_options: CaptureOptions,
+    /// Whether the broadcast and multicast traffic is aggregated instead of being reported per connection
+    pub aggregate_broadcast_multicast: bool,
+    /// Whether the two directions of each conversation are combined in a single connection
+    pub combine_bidirectional_flows: bool,
+    /// Time elapsed between two consecutive refreshes of the running page
+    pub refresh_interval: Duration,
+    /// Duration of the time window covered by the throughput history, in seconds
+    pub throughput_history_seconds: usize,
+    /// Favorite rules specified in the configuration
+    pub configured_favorite_rules: Vec<FavoriteMatcher>,
+    /// Connections starred in the GUI, marked as favorites at every start
+    pub saved_favorites: SavedFavorites,
+    /// File where the starred connections are saved, if available
+    pub favorites_path: Option<PathBuf>,
 }
 
 impl Sniffer {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         current_capture_id: Arc<Mutex<u16>>,
         info_traffic: Arc<Mutex<InfoTraffic>>,
@@ -63,15 +151,57 @@ impl Sniffer {
         status_pair: Arc<(Mutex<Status>, Condvar)>,
         config_settings: &ConfigSettings,
         config_device: &ConfigDevice,
+        config_mmdb: ConfigMmdb,
+        config_capture: &ConfigCapture,
+        cli_args: CliArgs,
     ) -> Self {
+        let configured_filters = match config_capture.filter.as_deref().map(Filters::parse) {
+            Some(Ok(filters)) => filters,
+            Some(Err(err)) => {
+                warn!(error = %err, "filter ignored");
+                Filters::default()
+            }
+            None => Filters::default(),
+        };
+        let configured_favorite_rules = get_favorite_matchers(&config_capture.favorite_rules);
+        let favorites_path = get_favorites_path();
+        let saved_favorites = favorites_path
+            .as_deref()
+            .map(load_favorites)
+            .unwrap_or_default();
         Self {
             current_capture_id,
+            capture_stop_flag: Arc::new(AtomicBool::new(false)),
+            capture_pause_flag: Arc::new(AtomicBool::new(false)),
             info_traffic,
             status_pair,
             runtime_data: runtime_data.clone(),
             device: config_device.to_pcap_device(),
             last_device_name_sniffed: config_device.device_name.clone(),
-            filters: Filters::default(),
+            filters: Filters {
+                pcap_export: config_capture.pcap_export.clone(),
+                bpf: config_capture.bpf.clone(),
+                direction_filter: config_capture
+                    .direction_filter
+                    .or(configured_filters.direction_filter),
+                port_blocklist: config_capture.port_blocklist.clone(),
+                port_allowlist: [
+                    configured_filters.port_allowlist,
+                    config_capture.port_allowlist.clone(),
+                ]
+                .concat(),
+                favorite_rules: [
+                    configured_favorite_rules.clone(),
+                    saved_favorites.to_matchers(),
+                ]
+                .concat(),
+                notable_ports: config_capture.notable_ports.clone(),
+                port_protocol_overrides: get_port_protocol_overrides(
+                    &config_capture.port_protocol_overrides,
+                ),
+                byte_accounting: config_capture.byte_accounting,
+                ..configured_filters
+            },
             pcap_error: None,
             style: config_settings.style,
             waiting: ".".to_string(),
@@ -79,13 +209,418 @@ impl Sniffer {
                 runtime_data,
                 config_settings.style,
                 config_settings.language,
+                config_settings.unit_system,
             ),
             report_type: ReportType::MostRecent,
+            report_sort: None,
+            search: String::new(),
             overlay: None,
             last_opened_setting: MyOverlay::SettingsNotifications,
-            notifications: config_settings.notifications,
+            notifications: config_settings.notifications.clone(),
             running_page: RunningPage::Overview,
             language: config_settings.language,
+            config_mmdb,
+            resolve_hostnames: config_settings.resolve_hostnames,
+            unit_system: config_settings.unit_system,
+            export_json_path: cli_args.export_json,
+            anonymization_salt: cli_args.anonymize.then(get_anonymization_salt),
+            additional_device_names: config_capture.additional_devices.clone(),
+            session_path: config_capture.session_path.clone(),
+            restored_session: config_capture
+                .session_path
+                .as_deref()
+                .and_then(restore_session),
+            sqlite_path: config_capture.sqlite_path.clone(),
+            sql_writer: None,
+            metrics_address: config_capture.metrics_address.clone(),
+            metrics_server: None,
+            capture_threads: Vec::new(),
+            connection_ttl: config_capture
+                .connection_ttl_seconds
+                .map(Duration::from_secs),
+            max_connections: config_capture.max_connections,
+            capture_options: CaptureOptions::from_config(config_capture),
+            aggregate_broadcast_multicast: config_capture.aggregate_broadcast_multicast,
+            combine_bidirectional_flows: config_capture.combine_bidirectional_flows,
+            refresh_interval: get_refresh_interval(config_capture.refresh_interval_ms),
+            throughput_history_seconds: config_capture
+                .throughput_history_seconds
+                .unwrap_or(DEFAULT_THROUGHPUT_HISTORY_SECONDS),
+            configured_favorite_rules,
+            saved_favorites,
+            favorites_path,
         }
     }
 }
+
+/// Loads the traffic session saved in the given file, if any.
+///
+/// Sessions that cannot be restored (e.g. because saved with a different schema version)
+/// are discarded with a warning.
+fn restore_session(path: &Path) -> Option<InfoTraffic> {
+    if !path.exists() {
+        return None;
+    }
+    InfoTraffic::load_session(path)
+        .map_err(|err| {
+            warn!(path = %path.display(), %err, "traffic session discarded");
+        })
+        .ok()
+}
+
+impl Sniffer {
+    /// Pauses the current run: packets are still read from the network adapters, but discarded.
+    pub fn pause(&self) {
+        self.capture_pause_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes the current run: only the packets sniffed from now on are accounted.
+    pub fn resume(&self) {
+        self.capture_pause_flag.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the current run is paused
+    pub fn is_paused(&self) -> bool {
+        self.capture_pause_flag.load(Ordering::Relaxed)
+    }
+
+    /// Starts a new capture on the selected network adapter (and on the additional ones, if any),
+    /// continuing the restored session if this is the first one.
+    ///
+    /// If the capture cannot be opened, the error is stored in `pcap_error` and no thread is started.
+    pub fn start_capture(&mut self) {
+        let device = self.device.clone();
+        let (pcap_error, cap) =
+            get_capture_result(&device, self.filters.bpf.as_deref(), &self.capture_options);
+        self.pcap_error = pcap_error.clone();
+        *lock_or_recover(&self.status_pair.0, "starting capture") = Status::Running;
+        let info_traffic_mutex = self.info_traffic.clone();
+        // the first capture continues the restored session, if any
+        let mut info_traffic = self
+            .restored_session
+            .take()
+            .unwrap_or_else(InfoTraffic::new);
+        info_traffic.aggregate_broadcast_multicast = self.aggregate_broadcast_multicast;
+        info_traffic.combine_bidirectional_flows = self.combine_bidirectional_flows;
+        info_traffic.max_connections = self.max_connections;
+        // a continued session is accounted as a new capture, with its own metadata
+        info_traffic.capture_started_at = Local::now();
+        info_traffic.capture_stopped_at = None;
+        info_traffic.device_name.clone_from(&device.name);
+        info_traffic.capture_filters = self.filters.to_filter_string();
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.refresh_interval = self.refresh_interval;
+        runtime_data.throughput_history = ThroughputHistory::new(self.throughput_history_seconds);
+        // the restored traffic must not be accounted in the first interval of the charts
+        // (the refresh interval only changes between captures, so its first interval starts from here as well)
+        runtime_data.tot_sent_bytes_prev = info_traffic.tot_sent_bytes;
+        runtime_data.tot_received_bytes_prev = info_traffic.tot_received_bytes;
+        runtime_data.tot_sent_packets_prev = info_traffic.tot_sent_packets;
+        runtime_data.tot_received_packets_prev = info_traffic.tot_received_packets;
+        runtime_data.app_protocols_prev = info_traffic.app_protocols.clone();
+        *lock_or_recover(&info_traffic_mutex, "starting capture") = info_traffic;
+        self.runtime_data = Rc::new(RefCell::new(runtime_data));
+
+        if pcap_error.is_none() {
+            // no pcap error
+            self.capture_stop_flag = Arc::new(AtomicBool::new(false));
+            self.capture_pause_flag = Arc::new(AtomicBool::new(false));
+            self.capture_threads = Vec::new();
+            // the events of the capture threads are emitted in the span of the capture
+            let capture_span = info_span!(
+                "capture",
+                device = %device.name,
+                filters = %self.filters.to_filter_string()
+            );
+            let mut captures = vec![(device, cap.unwrap(), self.filters.clone())];
+            captures.extend(self.get_additional_captures());
+            let hostname_sender = if self.resolve_hostnames {
+                let (hostname_sender, hostname_receiver) = mpsc::channel();
+                let info_traffic_mutex = info_traffic_mutex.clone();
+                let capture_span = capture_span.clone();
+                thread::Builder::new()
+                    .name("thread_resolve_hostnames".to_string())
+                    .spawn(move || {
+                        let _entered = capture_span.entered();
+                        resolve_hostnames_loop(&hostname_receiver, &info_traffic_mutex);
+                    })
+                    .unwrap();
+                Some(hostname_sender)
+            } else {
+                None
+            };
+            if let Some(sqlite_path) = self.sqlite_path.clone() {
+                self.start_sql_writer(sqlite_path, &info_traffic_mutex);
+            }
+            if let Some(metrics_address) = self.metrics_address.clone() {
+                self.start_metrics_server(metrics_address, &info_traffic_mutex);
+            }
+            self.status_pair.1.notify_all();
+            // one thread for each sniffed adapter, all of them updating the same InfoTraffic
+            for (device, cap, filters) in captures {
+                let stop_flag = self.capture_stop_flag.clone();
+                let pause_flag = self.capture_pause_flag.clone();
+                let config_mmdb = self.config_mmdb.clone();
+                let info_traffic_mutex = info_traffic_mutex.clone();
+                let hostname_sender = hostname_sender.clone();
+                let capture_span = capture_span.clone();
+                let capture_thread = thread::Builder::new()
+                    .name("thread_parse_packets".to_string())
+                    .spawn(move || {
+                        let _entered = capture_span.entered();
+                        parse_packets_loop(
+                            &stop_flag,
+                            &pause_flag,
+                            device,
+                            cap,
+                            &filters,
+                            &MmdbReaders::open(&config_mmdb),
+                            &info_traffic_mutex,
+                            hostname_sender,
+                        );
+                    })
+                    .unwrap();
+                self.capture_threads.push(capture_thread);
+            }
+        }
+    }
+
+    /// Copies the traffic data of the last refresh interval from the shared `InfoTraffic`
+    /// (evicting the stale connections), then checks the notifications thresholds and updates the charts data.
+    ///
+    /// Returns `false`, skipping the thresholds and the charts, if no filtered packet has been observed yet:
+    /// from then on the charts advance at every call, even if no new packet has been sniffed in the meantime.
+    pub fn refresh_runtime_data(&self) -> bool {
+        let mut info_traffic_lock = lock_or_recover(&self.info_traffic, "refreshing runtime data");
+        let mut runtime_data = self.runtime_data.borrow_mut();
+        runtime_data.all_packets = info_traffic_lock.all_packets;
+        runtime_data.dropped_packets = info_traffic_lock.dropped_packets;
+        runtime_data.if_dropped_packets = info_traffic_lock.if_dropped_packets;
+        runtime_data.capture_lag = std::mem::take(&mut info_traffic_lock.capture_lag);
+        runtime_data.capture_error = info_traffic_lock.capture_error.clone();
+        runtime_data.broadcast_summary = info_traffic_lock.broadcast_summary;
+        runtime_data.multicast_summary = info_traffic_lock.multicast_summary;
+        if info_traffic_lock.tot_received_packets + info_traffic_lock.tot_sent_packets == 0 {
+            return false;
+        }
+        runtime_data.tot_sent_packets = info_traffic_lock.tot_sent_packets;
+        runtime_data.tot_received_packets = info_traffic_lock.tot_received_packets;
+        runtime_data.all_bytes = info_traffic_lock.all_bytes;
+        runtime_data.tot_received_bytes = info_traffic_lock.tot_received_bytes;
+        runtime_data.tot_sent_bytes = info_traffic_lock.tot_sent_bytes;
+        runtime_data.app_protocols = info_traffic_lock.app_protocols.clone();
+        let now = Local::now();
+        if let Some(ttl) = self
+            .connection_ttl
+            .and_then(|ttl| chrono::Duration::from_std(ttl).ok())
+        {
+            info_traffic_lock.evict_stale_connections(now - ttl);
+        }
+        runtime_data.favorites_last_interval =
+            std::mem::take(&mut info_traffic_lock.favorites_last_interval);
+        runtime_data.new_countries_last_interval =
+            std::mem::take(&mut info_traffic_lock.new_countries_last_interval);
+        for info in info_traffic_lock.map.values_mut() {
+            info.refresh_rate(now);
+        }
+        drop(info_traffic_lock);
+        notify_and_log(
+            &mut runtime_data,
+            &self.notifications,
+            &self.info_traffic,
+            self.unit_system,
+        );
+        drop(runtime_data);
+        update_charts_data(
+            self.runtime_data.borrow_mut(),
+            self.traffic_chart.window_seconds,
+        );
+        true
+    }
+
+    /// Stops the threads parsing packets of the current capture, waiting for them to terminate
+    /// (so that the packets they export are written to file).
+    pub fn stop_capture(&mut self) {
+        self.capture_stop_flag.store(true, Ordering::Relaxed);
+        lock_or_recover(&self.info_traffic, "stopping capture").capture_stopped_at =
+            Some(Local::now());
+        self.join_capture_threads();
+        // the address is released, to be bound again by the next capture
+        if let Some(metrics_server) = self.metrics_server.take() {
+            metrics_server.join().unwrap_or(());
+        }
+    }
+
+    /// Waits for the threads parsing packets to terminate (the stop flag must have been set),
+    /// so that the traffic they still buffered is merged into the shared data, then stops the SQL logging
+    /// after its last transaction, which includes that traffic as well.
+    fn join_capture_threads(&mut self) {
+        for capture_thread in self.capture_threads.drain(..) {
+            capture_thread.join().unwrap_or(());
+        }
+        self.stop_sql_writer();
+    }
+
+    /// Starts the thread logging the connections of the current capture to the SQLite database, until it's stopped.
+    pub fn start_sql_writer(
+        &mut self,
+        sqlite_path: PathBuf,
+        info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    ) {
+        // the logging of a previous capture (reset without waiting for its threads) is terminated
+        self.stop_sql_writer();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let info_traffic_mutex = info_traffic_mutex.clone();
+        let sql_writer = {
+            let stop_flag = stop_flag.clone();
+            thread::Builder::new()
+                .name("thread_write_sql".to_string())
+                .spawn(move || {
+                    write_sql_loop(&stop_flag, &info_traffic_mutex, &sqlite_path);
+                })
+                .unwrap()
+        };
+        self.sql_writer = Some((stop_flag, sql_writer));
+    }
+
+    /// Stops the thread logging the connections to the SQLite database, if any, waiting for its last transaction.
+    fn stop_sql_writer(&mut self) {
+        if let Some((stop_flag, sql_writer)) = self.sql_writer.take() {
+            stop_flag.store(true, Ordering::Relaxed);
+            sql_writer.join().unwrap_or(());
+        }
+    }
+
+    /// Starts the thread serving the metrics of the current capture, until it's stopped.
+    #[cfg(feature = "metrics")]
+    fn start_metrics_server(
+        &mut self,
+        metrics_address: String,
+        info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    ) {
+        let stop_flag = self.capture_stop_flag.clone();
+        let info_traffic_mutex = info_traffic_mutex.clone();
+        self.metrics_server = Some(
+            thread::Builder::new()
+                .name("thread_serve_metrics".to_string())
+                .spawn(move || {
+                    serve_metrics_loop(&stop_flag, &info_traffic_mutex, &metrics_address);
+                })
+                .unwrap(),
+        );
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn start_metrics_server(
+        &mut self,
+        metrics_address: String,
+        _info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    ) {
+        warn!(
+            %metrics_address,
+            "metrics not served, Sniffnet has been built without the metrics feature"
+        );
+    }
+
+    /// Stars or unstars the connection with the given identifier, saving the change to file
+    /// so that the connections between the same addresses are marked as favorites after a restart as well.
+    ///
+    /// The saved favorites are applied to the connections of the next captures.
+    pub fn set_favorite(&mut self, id: u64, is_favorite: bool) {
+        let mut info_traffic = lock_or_recover(&self.info_traffic, "updating favorite");
+        let key = match info_traffic.get_connection(id) {
+            Some((key, _)) => key.clone(),
+            None => return,
+        };
+        if let Some(info) = info_traffic.get_connection_mut(id) {
+            info.is_favorite = is_favorite;
+        }
+        if is_favorite {
+            info_traffic.favorite_connections.insert(id);
+        } else {
+            info_traffic.favorite_connections.remove(&id);
+        }
+        drop(info_traffic);
+
+        let changed = if is_favorite {
+            self.saved_favorites
+                .add(&key, &self.configured_favorite_rules)
+        } else {
+            self.saved_favorites.remove(&key)
+        };
+        if !changed {
+            return;
+        }
+        self.filters.favorite_rules = [
+            self.configured_favorite_rules.clone(),
+            self.saved_favorites.to_matchers(),
+        ]
+        .concat();
+        if let Some(path) = &self.favorites_path {
+            if let Err(err) = save_favorites(path, &self.saved_favorites) {
+                warn!(path = %path.display(), %err, "cannot save favorites");
+            }
+        }
+    }
+
+    /// Writes the connections still to be logged to the SQLite database, and the traffic data to the JSON export
+    /// and to the session file (if configured), before quitting.
+    pub fn save_on_exit(&mut self) {
+        // let the traffic of the current capture be flushed and logged before quitting
+        self.capture_stop_flag.store(true, Ordering::Relaxed);
+        self.join_capture_threads();
+        if let Some(path) = &self.export_json_path {
+            if let Err(err) = lock_or_recover(&self.info_traffic, "exporting JSON")
+                .export_json(path, self.anonymization_salt)
+            {
+                warn!(path = %path.display(), %err, "cannot export traffic data");
+            }
+        }
+        // a restored session not continued by any capture is left untouched
+        if let (Some(path), None) = (&self.session_path, &self.restored_session) {
+            if let Err(err) =
+                lock_or_recover(&self.info_traffic, "saving session").save_session(path)
+            {
+                warn!(path = %path.display(), %err, "cannot save traffic session");
+            }
+        }
+    }
+
+    /// Opens the captures on the additional network adapters, returning them
+    /// with the filters to be applied (the pcap export, if any, is saved to files specific to each adapter).
+    ///
+    /// Adapters that are not available or whose capture can't be opened are skipped with a warning.
+    pub fn get_additional_captures(&self) -> Vec<(Device, Capture<Active>, Filters)> {
+        if self.additional_device_names.is_empty() {
+            return Vec::new();
+        }
+        let mut captures = Vec::new();
+        let devices = Device::list().unwrap_or_default();
+        for name in &self.additional_device_names {
+            if name.eq(&self.device.name) {
+                continue;
+            }
+            let Some(device) = devices.iter().find(|device| device.name.eq(name)) else {
+                warn!(interface = %name, "network adapter not found");
+                continue;
+            };
+            match get_capture_result(device, self.filters.bpf.as_deref(), &self.capture_options) {
+                (None, Some(cap)) => {
+                    let filters = Filters {
+                        pcap_export: self
+                            .filters
+                            .pcap_export
+                            .as_ref()
+                            .map(|pcap_export| pcap_export.for_interface(name)),
+                        ..self.filters.clone()
+                    };
+                    captures.push((device.clone(), cap, filters));
+                }
+                (err, _) => {
+                    warn!(interface = %name, error = %err.unwrap_or_default(), "cannot sniff network adapter")
+                }
+            }
+        }
+        captures
+    }
+}
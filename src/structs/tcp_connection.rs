@@ -0,0 +1,142 @@
+//! Module defining the `TcpConnection` struct, used to follow the state of a TCP connection
+//! across the segments sent by both its endpoints.
+
+use crate::enums::tcp_state::TcpState;
+use crate::structs::address_port_pair::AddressPortPair;
+
+/// Flags of a TCP header relevant to the state of the connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpFlags {
+    pub syn: bool,
+    pub ack: bool,
+    pub fin: bool,
+    pub rst: bool,
+}
+
+/// State of a TCP connection, shared by its two directions.
+#[derive(Default)]
+pub struct TcpConnection {
+    /// Current state (`None` if no segment has been seen yet)
+    state: Option<TcpState>,
+    /// Whether a FIN has been sent by the first and by the second endpoint of the connection
+    fin_sent: [bool; 2],
+}
+
+impl TcpConnection {
+    /// Returns the current state of the connection (`None` if no segment has been seen yet).
+    pub fn state(&self) -> Option<TcpState> {
+        self.state
+    }
+
+    /// Updates the state of the connection with a segment sent by its first endpoint
+    /// (if `from_first_endpoint` is true) or by its second endpoint, returning the new state.
+    ///
+    /// Connections whose handshake has not been sniffed are considered established at their first segment.
+    pub fn update(&mut self, flags: TcpFlags, from_first_endpoint: bool) -> TcpState {
+        let state = if flags.rst {
+            TcpState::Closed
+        } else if flags.syn {
+            // a new connection may reuse the same address:port pairs of a closed one
+            self.fin_sent = [false, false];
+            TcpState::SynSeen
+        } else if flags.fin {
+            self.fin_sent[usize::from(!from_first_endpoint)] = true;
+            match self.state {
+                Some(TcpState::Closed) => TcpState::Closed,
+                _ => TcpState::Closing,
+            }
+        } else {
+            match self.state {
+                None | Some(TcpState::SynSeen | TcpState::Established) => TcpState::Established,
+                // last acknowledgement, following the FIN of both the endpoints
+                Some(TcpState::Closing) if self.fin_sent == [true, true] => TcpState::Closed,
+                Some(state) => state,
+            }
+        };
+        self.state = Some(state);
+        state
+    }
+}
+
+/// Returns the key identifying the connection to which the segment with the given key belongs,
+/// regardless of its direction, and whether the segment has been sent by the first endpoint of the connection.
+pub fn get_tcp_connection_key(key: &AddressPortPair) -> (AddressPortPair, bool) {
+    if (&key.address1, key.port1) <= (&key.address2, key.port2) {
+        (key.clone(), true)
+    } else {
+        (get_reverse_key(key), false)
+    }
+}
+
+/// Returns the key of the opposite direction of the given address:port pair.
+pub fn get_reverse_key(key: &AddressPortPair) -> AddressPortPair {
+    AddressPortPair::new(
+        key.address2.clone(),
+        key.port2,
+        key.address1.clone(),
+        key.port1,
+        key.trans_protocol,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::tcp_state::TcpState;
+    use crate::structs::tcp_connection::{TcpConnection, TcpFlags};
+
+    const SYN: TcpFlags = TcpFlags {
+        syn: true,
+        ack: false,
+        fin: false,
+        rst: false,
+    };
+    const SYN_ACK: TcpFlags = TcpFlags { ack: true, ..SYN };
+    const ACK: TcpFlags = TcpFlags {
+        syn: false,
+        ack: true,
+        fin: false,
+        rst: false,
+    };
+    const FIN_ACK: TcpFlags = TcpFlags { fin: true, ..ACK };
+    const RST: TcpFlags = TcpFlags {
+        syn: false,
+        ack: false,
+        fin: false,
+        rst: true,
+    };
+
+    #[test]
+    fn test_handshake_then_teardown() {
+        let mut connection = TcpConnection::default();
+        let sequence = [
+            (SYN, true, TcpState::SynSeen),
+            (SYN_ACK, false, TcpState::SynSeen),
+            (ACK, true, TcpState::Established),
+            (ACK, false, TcpState::Established),
+            (FIN_ACK, true, TcpState::Closing),
+            (ACK, false, TcpState::Closing),
+            (FIN_ACK, false, TcpState::Closing),
+            (ACK, true, TcpState::Closed),
+        ];
+        for (flags, from_first_endpoint, expected) in sequence {
+            assert_eq!(connection.update(flags, from_first_endpoint), expected);
+        }
+        // retransmissions don't reopen the connection
+        assert_eq!(connection.update(FIN_ACK, false), TcpState::Closed);
+        assert_eq!(connection.update(ACK, true), TcpState::Closed);
+        // a new handshake on the same address:port pairs does
+        assert_eq!(connection.update(SYN, true), TcpState::SynSeen);
+    }
+
+    #[test]
+    fn test_connection_reset_and_not_sniffed_handshake() {
+        let mut connection = TcpConnection::default();
+        assert_eq!(connection.update(ACK, false), TcpState::Established);
+        assert_eq!(connection.update(RST, true), TcpState::Closed);
+
+        let mut half_closed = TcpConnection::default();
+        assert_eq!(half_closed.update(FIN_ACK, true), TcpState::Closing);
+        // only one of the endpoints closed the connection
+        assert_eq!(half_closed.update(ACK, true), TcpState::Closing);
+    }
+}
@@ -0,0 +1,183 @@
+//! Module defining the `ThroughputHistory` struct, which keeps the traffic rates of the last refresh intervals
+//! to compute statistics about them (e.g. the 95th percentile), independently of how they're displayed.
+
+use std::collections::VecDeque;
+
+/// Default duration of the time window covered by the throughput history, in seconds
+pub const DEFAULT_THROUGHPUT_HISTORY_SECONDS: usize = 300;
+
+/// Traffic rates (per second) measured in a refresh interval
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThroughputSample {
+    /// Sent bytes per second
+    pub sent_bytes: u128,
+    /// Received bytes per second
+    pub received_bytes: u128,
+    /// Sent packets per second
+    pub sent_packets: u128,
+    /// Received packets per second
+    pub received_packets: u128,
+}
+
+/// Ring buffer of the traffic rates measured in the last refresh intervals
+pub struct ThroughputHistory {
+    /// Samples labeled with the number of the interval they've been taken at, from the oldest
+    samples: VecDeque<(u32, ThroughputSample)>,
+    /// Duration of the time window covered by the history, in seconds
+    pub window_seconds: usize,
+}
+
+impl ThroughputHistory {
+    pub fn new(window_seconds: usize) -> Self {
+        ThroughputHistory {
+            samples: VecDeque::new(),
+            window_seconds,
+        }
+    }
+
+    /// Appends the sample taken at interval `tick`, evicting the samples that fall out of the time window
+    /// (spanning `window_intervals` intervals).
+    pub fn push(&mut self, tick: u32, sample: ThroughputSample, window_intervals: usize) {
+        while self.samples.front().is_some_and(|(sample_tick, _)| {
+            usize::try_from(tick - sample_tick).unwrap_or(usize::MAX) >= window_intervals
+        }) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((tick, sample));
+    }
+
+    /// Returns the number of samples in the history.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns whether no sample has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the samples taken in the last `intervals` intervals, from the oldest.
+    pub fn recent(&self, intervals: usize) -> impl Iterator<Item = &(u32, ThroughputSample)> {
+        let last_tick = self.samples.back().map_or(0, |(tick, _)| *tick);
+        self.samples.iter().filter(move |(tick, _)| {
+            usize::try_from(last_tick - tick).unwrap_or(usize::MAX) < intervals
+        })
+    }
+
+    /// Returns the series of a quantity in the last `intervals` intervals, labeled with the interval of each sample.
+    pub fn series(
+        &self,
+        intervals: usize,
+        value: impl Fn(&ThroughputSample) -> i64,
+    ) -> Vec<(u32, i64)> {
+        self.recent(intervals)
+            .map(|(tick, sample)| (*tick, value(sample)))
+            .collect()
+    }
+
+    /// Returns the `p`-th percentile (from 0 to 100) of each quantity in the history,
+    /// computed with the nearest-rank method, or `None` if the history is empty.
+    ///
+    /// Each quantity is ranked on its own, so the returned rates may come from different intervals.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn percentile(&self, p: f64) -> Option<ThroughputSample> {
+        if self.is_empty() {
+            return None;
+        }
+        let len = self.len();
+        let rank = ((p.clamp(0.0, 100.0) / 100.0 * len as f64).ceil() as usize).clamp(1, len);
+        let nth = |value: fn(&ThroughputSample) -> u128| {
+            let mut values: Vec<u128> = self.samples.iter().map(|(_, s)| value(s)).collect();
+            *values.select_nth_unstable(rank - 1).1
+        };
+        Some(ThroughputSample {
+            sent_bytes: nth(|s| s.sent_bytes),
+            received_bytes: nth(|s| s.received_bytes),
+            sent_packets: nth(|s| s.sent_packets),
+            received_packets: nth(|s| s.received_packets),
+        })
+    }
+
+    /// Returns the highest rate of each quantity in the history, or `None` if the history is empty.
+    pub fn peak(&self) -> Option<ThroughputSample> {
+        self.samples
+            .iter()
+            .map(|(_, sample)| *sample)
+            .reduce(|peak, sample| ThroughputSample {
+                sent_bytes: peak.sent_bytes.max(sample.sent_bytes),
+                received_bytes: peak.received_bytes.max(sample.received_bytes),
+                sent_packets: peak.sent_packets.max(sample.sent_packets),
+                received_packets: peak.received_packets.max(sample.received_packets),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::throughput_history::{ThroughputHistory, ThroughputSample};
+
+    fn sample(bytes: u128, packets: u128) -> ThroughputSample {
+        ThroughputSample {
+            sent_bytes: bytes,
+            received_bytes: 2 * bytes,
+            sent_packets: packets,
+            received_packets: 0,
+        }
+    }
+
+    /// History with the bytes 1..=100 (shuffled) and the packets 100..=1 in 100 intervals
+    fn history() -> ThroughputHistory {
+        let mut history = ThroughputHistory::new(100);
+        for tick in 0..100 {
+            let bytes = u128::from(tick * 37 % 100 + 1);
+            history.push(tick, sample(bytes, u128::from(100 - tick)), 100);
+        }
+        history
+    }
+
+    #[test]
+    fn test_percentile() {
+        let history = history();
+        assert_eq!(history.len(), 100);
+        assert_eq!(history.percentile(95.0), Some(sample(95, 95)));
+        assert_eq!(history.percentile(50.0), Some(sample(50, 50)));
+        assert_eq!(history.percentile(100.0), Some(sample(100, 100)));
+        // the lowest ranks are the minimum
+        assert_eq!(history.percentile(0.0), Some(sample(1, 1)));
+        assert_eq!(history.percentile(-5.0), Some(sample(1, 1)));
+        assert_eq!(history.percentile(0.5), Some(sample(1, 1)));
+        assert_eq!(history.percentile(1.5), Some(sample(2, 2)));
+
+        let mut history = ThroughputHistory::new(30);
+        history.push(0, sample(10, 1), 30);
+        assert_eq!(history.percentile(95.0), Some(sample(10, 1)));
+        assert_eq!(ThroughputHistory::new(30).percentile(95.0), None);
+    }
+
+    #[test]
+    fn test_peak() {
+        assert_eq!(history().peak(), Some(sample(100, 100)));
+        assert_eq!(history().peak(), history().percentile(100.0));
+        assert_eq!(ThroughputHistory::new(30).peak(), None);
+    }
+
+    #[test]
+    fn test_history_is_bounded_by_window() {
+        let mut history = ThroughputHistory::new(10);
+        for tick in 0..25 {
+            history.push(tick, sample(u128::from(tick), 0), 10);
+        }
+        assert_eq!(history.len(), 10);
+        // the peak only considers the samples in the window
+        assert_eq!(history.peak(), Some(sample(24, 0)));
+        assert_eq!(history.percentile(0.0), Some(sample(15, 0)));
+        assert_eq!(
+            history.series(3, |s| -i64::try_from(s.sent_bytes).unwrap()),
+            vec![(22, -22), (23, -23), (24, -24)]
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Module defining the `TrafficBuffer` struct, used by the thread in charge of parsing packets
+//! to accumulate updates before merging them into the shared `InfoTraffic`.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use indexmap::map::Entry;
+use indexmap::IndexMap;
+
+use crate::enums::tcp_state::TcpState;
+use crate::enums::traffic_type::TrafficType;
+use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::discovery_traffic::{DiscoveryPacket, DiscoveryTraffic};
+use crate::structs::favorite_rule::FavoriteMatcher;
+use crate::structs::info_address_port_pair::InfoAddressPortPair;
+use crate::structs::info_traffic::{get_size_bucket, InterfaceStats, SIZE_BUCKETS};
+use crate::structs::tcp_connection::{get_tcp_connection_key, TcpConnection, TcpFlags};
+use crate::utility::countries::MmdbReader;
+use crate::utility::manage_packets::{get_map_key, get_remote_address, modify_or_insert_in_map};
+use crate::{AppProtocol, InfoTraffic};
+
+/// Time after which the state of a TCP connection not exchanging segments is forgotten
+const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Determines how often the buffered traffic is flushed into the shared `InfoTraffic`.
+///
+/// A flush takes place as soon as either of the two limits is reached.
+#[derive(Clone, Copy)]
+pub struct FlushCadence {
+    /// Maximum time elapsed between two consecutive flushes
+    pub interval: Duration,
+    /// Maximum number of packets buffered before a flush
+    pub max_packets: u128,
+}
+
+impl Default for FlushCadence {
+    fn default() -> Self {
+        FlushCadence {
+            interval: Duration::from_millis(500),
+            max_packets: 1000,
+        }
+    }
+}
+
+/// Thread-local buffer of the sniffed traffic not yet merged into the shared `InfoTraffic`.
+pub struct TrafficBuffer {
+    /// How often the buffer has to be flushed
+    cadence: FlushCadence,
+    /// Time of the last flush
+    last_flush: Instant,
+    /// Packets buffered since the last flush, including those not filtered
+    all_packets: u128,
+    /// Bytes buffered since the last flush, including those not filtered
+    all_bytes: u128,
+    /// Packet size histogram of the packets buffered since the last flush, including those not filtered
+    size_histogram: [u64; SIZE_BUCKETS],
+    /// LAN discovery packets buffered since the last flush
+    discovery: DiscoveryTraffic,
+    /// Filtered traffic buffered since the last flush, in order of first occurrence
+    map: IndexMap<AddressPortPair, InfoAddressPortPair>,
+    /// Rules to mark the new connections as favorites
+    favorite_rules: Vec<FavoriteMatcher>,
+    /// Name of the network adapter on which the traffic is sniffed
+    interface: String,
+    /// Addresses of the default gateways of the network adapter
+    gateways: Vec<String>,
+    /// State of the TCP connections being followed, identified by their direction-independent key,
+    /// with the time of their last segment
+    tcp_connections: HashMap<AddressPortPair, (TcpConnection, Instant)>,
+}
+
+impl TrafficBuffer {
+    /// Constructs a new empty `TrafficBuffer` element for the traffic sniffed on `interface`,
+    /// flushed with the given cadence.
+    ///
+    /// Connections matching any of `favorite_rules` are marked as favorites when they first appear,
+    /// and those featuring any of the `gateways` addresses are flagged accordingly.
+    pub fn new(
+        interface: String,
+        cadence: FlushCadence,
+        favorite_rules: Vec<FavoriteMatcher>,
+        gateways: Vec<String>,
+    ) -> Self {
+        TrafficBuffer {
+            cadence,
+            last_flush: Instant::now(),
+            all_packets: 0,
+            all_bytes: 0,
+            size_histogram: [0; SIZE_BUCKETS],
+            discovery: DiscoveryTraffic::default(),
+            map: IndexMap::new(),
+            favorite_rules,
+            interface,
+            gateways,
+            tcp_connections: HashMap::new(),
+        }
+    }
+
+    /// Buffers a sniffed packet, whether or not it has been filtered.
+    pub fn add_packet(&mut self, exchanged_bytes: u128) {
+        self.all_packets += 1;
+        self.all_bytes += exchanged_bytes;
+        self.size_histogram[get_size_bucket(exchanged_bytes)] += 1;
+    }
+
+    /// Buffers a LAN discovery packet (ARP or IGMP), whatever the filters.
+    pub fn add_discovery_packet(&mut self, packet: DiscoveryPacket) {
+        self.discovery.add(packet);
+    }
+
+    /// Buffers the connection of a packet that has been accepted by the filters.
+    ///
+    /// Returns the buffered entry of the connection, so that the details decoded from the packet
+    /// (e.g. the DNS query or the TLS server name) can be recorded on it.
+    pub fn add_connection(
+        &mut self,
+        key: AddressPortPair,
+        exchanged_bytes: u128,
+        traffic_type: TrafficType,
+        application_protocol: AppProtocol,
+    ) -> &mut InfoAddressPortPair {
+        let now = Local::now();
+        // all the packets buffered under a key flow in the same direction
+        let (sent_bytes, received_bytes, sent_packets, received_packets) =
+            if traffic_type == TrafficType::Outgoing {
+                (exchanged_bytes, 0, 1, 0)
+            } else {
+                (0, exchanged_bytes, 0, 1)
+            };
+        match self.map.entry(key) {
+            Entry::Occupied(entry) => {
+                let info = entry.into_mut();
+                info.transmitted_bytes += exchanged_bytes;
+                info.transmitted_packets += 1;
+                info.sent_bytes += sent_bytes;
+                info.received_bytes += received_bytes;
+                info.sent_packets += sent_packets;
+                info.received_packets += received_packets;
+                info.final_timestamp = now;
+                if info.app_protocol == AppProtocol::Other {
+                    // the protocol of the first packets may not be detected from their payload (e.g. TCP handshake)
+                    info.app_protocol = application_protocol;
+                }
+                info
+            }
+            Entry::Vacant(entry) => {
+                let is_favorite = self
+                    .favorite_rules
+                    .iter()
+                    .any(|rule| rule.matches(entry.key()));
+                let is_gateway = self.gateways.iter().any(|gateway| {
+                    gateway.eq(&entry.key().address1) || gateway.eq(&entry.key().address2)
+                });
+                entry.insert(InfoAddressPortPair {
+                    transmitted_bytes: exchanged_bytes,
+                    transmitted_packets: 1,
+                    sent_bytes,
+                    received_bytes,
+                    sent_packets,
+                    received_packets,
+                    initial_timestamp: now,
+                    final_timestamp: now,
+                    is_favorite,
+                    is_gateway,
+                    ..InfoAddressPortPair::new(
+                        self.interface.clone(),
+                        traffic_type,
+                        application_protocol,
+                    )
+                })
+            }
+        }
+    }
+
+    /// Updates the state of the TCP connection to which the segment with the given key and flags belongs,
+    /// returning the new state.
+    pub fn update_tcp_state(&mut self, key: &AddressPortPair, flags: TcpFlags) -> TcpState {
+        let (connection_key, from_first_endpoint) = get_tcp_connection_key(key);
+        let now = Instant::now();
+        let (connection, last_segment) = self
+            .tcp_connections
+            .entry(connection_key)
+            .or_insert_with(|| (TcpConnection::default(), now));
+        *last_segment = now;
+        connection.update(flags, from_first_endpoint)
+    }
+
+    /// Forgets the state of the TCP connections already closed and of those not exchanging segments
+    /// for longer than `idle_timeout`, so that it doesn't pile up during long captures.
+    fn forget_tcp_connections(&mut self, idle_timeout: Duration) {
+        self.tcp_connections
+            .retain(|_, (connection, last_segment)| {
+                connection.state() != Some(TcpState::Closed)
+                    && last_segment.elapsed() < idle_timeout
+            });
+    }
+
+    /// Name of the network adapter whose traffic is buffered
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// Number of packets and bytes buffered since the last flush, and number of connections they belong to
+    pub fn buffered(&self) -> (u128, u128, usize) {
+        (self.all_packets, self.all_bytes, self.map.len())
+    }
+
+    /// Determines if the buffered packets have to be flushed, according to the cadence.
+    pub fn is_flush_due(&self) -> bool {
+        (self.all_packets > 0 || !self.discovery.is_empty())
+            && (self.all_packets >= self.cadence.max_packets
+                || self.last_flush.elapsed() >= self.cadence.interval)
+    }
+
+    /// Merges the buffered traffic into the shared `InfoTraffic` and empties the buffer.
+    ///
+    /// It must be called while holding the lock on `info_traffic`, so that a single critical section
+    /// is needed for the whole batch.
+    ///
+    /// If `hostname_sender` is specified, the remote addresses of the new connections are sent through it
+    /// to have their hostname resolved.
+    pub fn flush(
+        &mut self,
+        info_traffic: &mut InfoTraffic,
+        country_db_reader: Option<&MmdbReader>,
+        asn_db_reader: Option<&MmdbReader>,
+        city_db_reader: Option<&MmdbReader>,
+        hostname_sender: Option<&Sender<(AddressPortPair, IpAddr)>>,
+    ) {
+        let mut interface_stats = InterfaceStats {
+            all_packets: self.all_packets,
+            all_bytes: self.all_bytes,
+            ..InterfaceStats::default()
+        };
+        for (key, mut buffered) in self.map.drain(..) {
+            // local traffic is accounted as received, as in the totals
+            if buffered.traffic_type == TrafficType::Outgoing {
+                interface_stats.sent_packets += buffered.transmitted_packets;
+                interface_stats.sent_bytes += buffered.transmitted_bytes;
+            } else {
+                interface_stats.received_packets += buffered.transmitted_packets;
+                interface_stats.received_bytes += buffered.transmitted_bytes;
+            }
+            if buffered.tcp_state.is_some() {
+                // the connection may have changed state after the last segment in this direction
+                let (connection_key, _) = get_tcp_connection_key(&key);
+                buffered.tcp_state = self
+                    .tcp_connections
+                    .get(&connection_key)
+                    .and_then(|(connection, _)| connection.state());
+            }
+            if let Some(sender) = hostname_sender {
+                let (map_key, traffic_type) = get_map_key(
+                    key.clone(),
+                    buffered.traffic_type,
+                    info_traffic.combine_bidirectional_flows,
+                );
+                if !info_traffic.map.contains_key(&map_key) {
+                    if let Some(address) = get_remote_address(traffic_type, &map_key) {
+                        // the resolver thread may have already terminated: nothing to do in that case
+                        sender.send((map_key, address)).unwrap_or(());
+                    }
+                }
+            }
+            modify_or_insert_in_map(
+                info_traffic,
+                key,
+                buffered,
+                country_db_reader,
+                asn_db_reader,
+                city_db_reader,
+            );
+        }
+
+        //increment number of sniffed packets and bytes
+        info_traffic.all_packets += self.all_packets;
+        info_traffic.all_bytes += self.all_bytes;
+        for (total, buffered) in info_traffic
+            .size_histogram
+            .iter_mut()
+            .zip(self.size_histogram)
+        {
+            *total += buffered;
+        }
+
+        info_traffic.discovery.merge(&mut self.discovery);
+        if interface_stats != InterfaceStats::default() {
+            let total = info_traffic
+                .per_interface
+                .entry(self.interface.clone())
+                .or_default();
+            total.all_packets += interface_stats.all_packets;
+            total.all_bytes += interface_stats.all_bytes;
+            total.sent_packets += interface_stats.sent_packets;
+            total.sent_bytes += interface_stats.sent_bytes;
+            total.received_packets += interface_stats.received_packets;
+            total.received_bytes += interface_stats.received_bytes;
+        }
+
+        self.all_packets = 0;
+        self.all_bytes = 0;
+        self.size_histogram = [0; SIZE_BUCKETS];
+        self.last_flush = Instant::now();
+        // the states of the flushed connections have already been merged
+        self.forget_tcp_connections(TCP_IDLE_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::enums::tcp_state::TcpState;
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::tcp_connection::TcpFlags;
+    use crate::structs::traffic_buffer::{FlushCadence, TrafficBuffer};
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    const ACK: TcpFlags = TcpFlags {
+        syn: false,
+        ack: true,
+        fin: false,
+        rst: false,
+    };
+    const FIN_ACK: TcpFlags = TcpFlags { fin: true, ..ACK };
+
+    fn key(port: u16) -> AddressPortPair {
+        AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            port,
+            "8.8.8.8".to_string(),
+            443,
+            TransProtocol::TCP,
+        )
+    }
+
+    /// Buffers a segment sent from the local endpoint of the connection with the given local port.
+    fn add_segment(buffer: &mut TrafficBuffer, port: u16, flags: TcpFlags) {
+        let tcp_state = buffer.update_tcp_state(&key(port), flags);
+        buffer.add_packet(20);
+        buffer
+            .add_connection(key(port), 20, TrafficType::Outgoing, AppProtocol::HTTPS)
+            .tcp_state = Some(tcp_state);
+    }
+
+    #[test]
+    fn test_tcp_connections_forgotten() {
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        add_segment(&mut buffer, 50000, ACK);
+        add_segment(&mut buffer, 50001, ACK);
+        add_segment(&mut buffer, 50001, FIN_ACK);
+        buffer.flush(&mut info_traffic, None, None, None, None);
+        assert_eq!(buffer.tcp_connections.len(), 2);
+
+        // the other endpoint closes the connection as well (its segment is not buffered in this test)
+        let reverse_key = AddressPortPair::new(
+            "8.8.8.8".to_string(),
+            443,
+            "192.168.1.10".to_string(),
+            50001,
+            TransProtocol::TCP,
+        );
+        buffer.update_tcp_state(&reverse_key, FIN_ACK);
+        add_segment(&mut buffer, 50001, ACK);
+        buffer.flush(&mut info_traffic, None, None, None, None);
+        // the closed connection is forgotten, once its final state is merged
+        assert_eq!(buffer.tcp_connections.len(), 1);
+        assert_eq!(info_traffic.map[1].tcp_state, Some(TcpState::Closed));
+        assert_eq!(info_traffic.map[0].tcp_state, Some(TcpState::Established));
+
+        // idle connections are forgotten as well
+        buffer.forget_tcp_connections(Duration::from_secs(60));
+        assert_eq!(buffer.tcp_connections.len(), 1);
+        buffer.forget_tcp_connections(Duration::ZERO);
+        assert!(buffer.tcp_connections.is_empty());
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
 use iced::alignment::{Horizontal, Vertical};
 use iced::widget::{Column, Container};
@@ -11,9 +12,23 @@ use plotters_iced::{Chart, ChartBuilder, ChartWidget, DrawingBackend};
 
 use crate::enums::message::Message;
 use crate::structs::palette::to_rgb_color;
+use crate::structs::throughput_history::ThroughputSample;
+use crate::utility::get_formatted_strings::format_bytes;
+use crate::utility::manage_charts_data::{
+    received_bytes_value, received_packets_value, sent_bytes_value, sent_packets_value,
+};
 use crate::utility::style_constants::{get_color_mix_chart, CHARTS_LINE_BORDER, INCONSOLATA_BOLD};
 use crate::utility::translations::{incoming_translation, outgoing_translation};
-use crate::{get_colors, ChartType, Language, RunTimeData, StyleType};
+use crate::{
+    get_colors, AppProtocol, ByteMultiple, ChartType, Language, RunTimeData, StyleType, UnitSystem,
+};
+
+/// Default duration of the time window displayed in the chart, in seconds
+pub const CHART_WINDOW_SECONDS: usize = 30;
+
+/// Factor applied to the logarithms of the values plotted in logarithmic scale,
+/// so that they can be represented with integer coordinates without losing precision
+const LOG_SCALE_FACTOR: f64 = 1000.0;
 
 /// Struct defining the chart to be displayed in gui run page
 pub struct TrafficChart {
@@ -24,6 +39,15 @@ pub struct TrafficChart {
     color_font: RGBColor,
     pub chart_type: ChartType,
     pub language: Language,
+    /// Duration of the sliding time window displayed, in seconds
+    pub window_seconds: usize,
+    /// Whether the values are plotted in logarithmic scale
+    pub log_scale: bool,
+    /// Whether the packets chart displays the composition of the traffic by application protocol
+    /// instead of the incoming and outgoing traffic
+    pub per_app_protocol: bool,
+    /// System in which the multiples of bytes are expressed in the labels
+    pub unit_system: UnitSystem,
 }
 
 impl TrafficChart {
@@ -31,6 +55,7 @@ impl TrafficChart {
         charts_data: Rc<RefCell<RunTimeData>>,
         style: StyleType,
         language: Language,
+        unit_system: UnitSystem,
     ) -> Self {
         TrafficChart {
             charts_data,
@@ -40,6 +65,10 @@ impl TrafficChart {
             color_font: to_rgb_color(get_colors(style).text_body),
             chart_type: ChartType::Packets,
             language,
+            window_seconds: CHART_WINDOW_SECONDS,
+            log_scale: false,
+            per_app_protocol: false,
+            unit_system,
         }
     }
 
@@ -72,6 +101,140 @@ impl TrafficChart {
         self.color_outgoing = to_rgb_color(get_colors(style).outgoing);
         self.color_mix = get_color_mix_chart(style);
     }
+
+    /// Maps a value to the coordinate at which it's plotted.
+    ///
+    /// In logarithmic scale the sign is preserved (sent traffic is negative),
+    /// and values with absolute value lower than 1 are clamped to 1.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn scale_value(&self, value: i64) -> i64 {
+        if !self.log_scale {
+            return value;
+        }
+        let log = (value.unsigned_abs().max(1) as f64).log10() * LOG_SCALE_FACTOR;
+        value.signum() * log.round() as i64
+    }
+
+    /// Maps a coordinate of the chart back to the value it represents.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn unscale_value(&self, value: i64) -> i64 {
+        if !self.log_scale {
+            return value;
+        }
+        let abs = 10_f64.powf(value.unsigned_abs() as f64 / LOG_SCALE_FACTOR);
+        value.signum() * abs.round() as i64
+    }
+
+    /// Returns the samples of a series, mapped to the coordinates at which they're plotted.
+    fn scale_series<'a>(
+        &self,
+        series: impl IntoIterator<Item = &'a (u32, i64)>,
+    ) -> Vec<(u32, i64)> {
+        series
+            .into_iter()
+            .map(|(time, value)| (*time, self.scale_value(*value)))
+            .collect()
+    }
+
+    /// Returns the series of a quantity in the time window displayed, mapped to the coordinates at which
+    /// it's plotted.
+    fn displayed_series(&self, value: fn(&ThroughputSample) -> i64) -> Vec<(u32, i64)> {
+        let charts_data = self.charts_data.borrow();
+        let intervals = charts_data.intervals_in(self.window_seconds);
+        self.scale_series(&charts_data.throughput_history.series(intervals, value))
+    }
+
+    /// Returns the stacked series of the packets of each application protocol that exchanged traffic
+    /// in the time window, from the one with the most packets: each series is the sum of the packets of
+    /// its protocol and of the protocols preceding it.
+    fn stacked_app_protocol_series(&self) -> Vec<(AppProtocol, Vec<(u32, i64)>)> {
+        let charts_data = self.charts_data.borrow();
+        let times: Vec<u32> = charts_data
+            .throughput_history
+            .recent(charts_data.intervals_in(self.window_seconds))
+            .map(|(time, _)| *time)
+            .collect();
+        let mut app_protocols: Vec<(AppProtocol, i64)> = charts_data
+            .app_protocol_packets
+            .iter()
+            .map(|(app_protocol, series)| {
+                (
+                    *app_protocol,
+                    series.iter().map(|(_, packets)| packets).sum(),
+                )
+            })
+            .filter(|(_, packets)| *packets > 0)
+            .collect();
+        app_protocols
+            .sort_by(|(p1, a), (p2, b)| b.cmp(a).then_with(|| p1.to_string().cmp(&p2.to_string())));
+
+        let mut stacked = vec![0; times.len()];
+        app_protocols
+            .into_iter()
+            .map(|(app_protocol, _)| {
+                for (time, packets) in &charts_data.app_protocol_packets[&app_protocol] {
+                    if let Ok(index) = times.binary_search(time) {
+                        stacked[index] += packets;
+                    }
+                }
+                (
+                    app_protocol,
+                    times.iter().copied().zip(stacked.clone()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Draws the packets of each application protocol as stacked areas.
+    fn build_app_protocols_chart<DB: DrawingBackend>(
+        &self,
+        mut chart: ChartBuilder<DB>,
+        x_range: std::ops::Range<u32>,
+        x_label_formatter: &dyn Fn(&u32) -> String,
+    ) {
+        use plotters::prelude::*;
+
+        let stacked_series = self.stacked_app_protocol_series();
+        let max = stacked_series
+            .last()
+            .and_then(|(_, series)| series.iter().map(|(_, packets)| *packets).max())
+            .unwrap_or(0);
+        let mut chart = chart
+            .margin_right(30)
+            .set_label_area_size(LabelAreaPosition::Left, 60)
+            .set_label_area_size(LabelAreaPosition::Bottom, 50)
+            .build_cartesian_2d(x_range, 0..self.scale_value(max))
+            .expect("Error drawing graph");
+
+        chart
+            .configure_mesh()
+            .label_style(("notosans", 13).into_font().color(&self.color_font))
+            .x_label_formatter(&x_label_formatter)
+            .y_label_formatter(&|packets| self.unscale_value(*packets).to_string())
+            .draw()
+            .unwrap();
+        // the highest areas are drawn first, so that each protocol only covers the ones stacked above it
+        for (index, (app_protocol, series)) in stacked_series.iter().enumerate().rev() {
+            let color = Palette99::pick(index);
+            chart
+                .draw_series(
+                    AreaSeries::new(self.scale_series(series), 0, color.mix(self.color_mix))
+                        .border_style(ShapeStyle::from(&color).stroke_width(CHARTS_LINE_BORDER)),
+                )
+                .expect("Error drawing graph")
+                .label(app_protocol.to_string())
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 25, y + 5)], color.filled())
+                });
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .border_style(BLACK)
+            .label_font(("notosans", 15).into_font().color(&self.color_font))
+            .draw()
+            .expect("Error drawing graph");
+    }
 }
 
 impl Chart<Message> for TrafficChart {
@@ -84,11 +247,14 @@ impl Chart<Message> for TrafficChart {
             return;
         }
         let tot_seconds = self.charts_data.borrow().ticks - 1;
-        let first_time_displayed = if self.charts_data.borrow().ticks > 30 {
-            self.charts_data.borrow().ticks - 30
-        } else {
-            0
-        };
+        let refresh_interval = self.charts_data.borrow().refresh_interval;
+        let window_seconds =
+            u32::try_from(self.charts_data.borrow().intervals_in(self.window_seconds))
+                .unwrap_or(u32::MAX);
+        let first_time_displayed = tot_seconds.saturating_sub(window_seconds.saturating_sub(1));
+        // x labels are relative to the most recent sample
+        let x_label_formatter =
+            |tick: &u32| get_time_ago_label(tot_seconds.saturating_sub(*tick), refresh_interval);
 
         let color_incoming = self.color_incoming;
         let color_outgoing = self.color_outgoing;
@@ -102,39 +268,26 @@ impl Chart<Message> for TrafficChart {
                     .set_label_area_size(LabelAreaPosition::Bottom, 50)
                     .build_cartesian_2d(
                         first_time_displayed..tot_seconds,
-                        self.charts_data.borrow().min_sent_bytes
-                            ..self.charts_data.borrow().max_received_bytes,
+                        self.scale_value(self.charts_data.borrow().min_sent_bytes)
+                            ..self.scale_value(self.charts_data.borrow().max_received_bytes),
                     )
                     .expect("Error drawing graph");
 
                 chart
                     .configure_mesh()
                     .label_style(("notosans", 13).into_font().color(&self.color_font))
+                    .x_label_formatter(&x_label_formatter)
                     .y_label_formatter(&|bytes| {
-                        let bytes_abs = bytes.abs();
-                        #[allow(clippy::cast_precision_loss)]
-                        let bytes_abs_float = bytes_abs as f32;
-                        match bytes_abs {
-                            0..=999 => {
-                                format!("{bytes_abs}")
-                            }
-                            1000..=999_999 => {
-                                format!("{:.1} {}", bytes_abs_float / 1_000_f32, "K")
-                            }
-                            1_000_000..=999_999_999 => {
-                                format!("{:.1} {}", bytes_abs_float / 1_000_000_f32, "M")
-                            }
-                            _ => {
-                                format!("{:.1} {}", bytes_abs_float / 1_000_000_000_f32, "G")
-                            }
-                        }
+                        let bytes_abs = u128::from(self.unscale_value(*bytes).unsigned_abs());
+                        let multiple = ByteMultiple::fitting(bytes_abs, self.unit_system);
+                        format_bytes(bytes_abs, multiple, self.unit_system)
                     })
                     .draw()
                     .unwrap();
                 chart
                     .draw_series(
                         AreaSeries::new(
-                            self.charts_data.borrow().received_bytes.iter().copied(),
+                            self.displayed_series(received_bytes_value),
                             0,
                             color_incoming.mix(self.color_mix),
                         )
@@ -150,7 +303,7 @@ impl Chart<Message> for TrafficChart {
                 chart
                     .draw_series(
                         AreaSeries::new(
-                            self.charts_data.borrow().sent_bytes.iter().copied(),
+                            self.displayed_series(sent_bytes_value),
                             0,
                             color_outgoing.mix(self.color_mix),
                         )
@@ -172,6 +325,15 @@ impl Chart<Message> for TrafficChart {
                     .expect("Error drawing graph");
             }
 
+            ChartType::Packets if self.per_app_protocol => {
+                //display packets chart by application protocol
+                self.build_app_protocols_chart(
+                    chart,
+                    first_time_displayed..tot_seconds,
+                    &x_label_formatter,
+                );
+            }
+
             ChartType::Packets => {
                 //display packets chart
                 let mut chart = chart
@@ -180,21 +342,22 @@ impl Chart<Message> for TrafficChart {
                     .set_label_area_size(LabelAreaPosition::Bottom, 50)
                     .build_cartesian_2d(
                         first_time_displayed..tot_seconds,
-                        self.charts_data.borrow().min_sent_packets
-                            ..self.charts_data.borrow().max_received_packets,
+                        self.scale_value(self.charts_data.borrow().min_sent_packets)
+                            ..self.scale_value(self.charts_data.borrow().max_received_packets),
                     )
                     .expect("Error drawing graph");
 
                 chart
                     .configure_mesh()
                     .label_style(("notosans", 13).into_font().color(&self.color_font))
-                    .y_label_formatter(&|packets| packets.abs().to_string())
+                    .x_label_formatter(&x_label_formatter)
+                    .y_label_formatter(&|packets| self.unscale_value(*packets).abs().to_string())
                     .draw()
                     .unwrap();
                 chart
                     .draw_series(
                         AreaSeries::new(
-                            self.charts_data.borrow().received_packets.iter().copied(),
+                            self.displayed_series(received_packets_value),
                             0,
                             color_incoming.mix(self.color_mix),
                         )
@@ -210,7 +373,7 @@ impl Chart<Message> for TrafficChart {
                 chart
                     .draw_series(
                         AreaSeries::new(
-                            self.charts_data.borrow().sent_packets.iter().copied(),
+                            self.displayed_series(sent_packets_value),
                             0,
                             color_outgoing.mix(self.color_mix),
                         )
@@ -234,3 +397,91 @@ impl Chart<Message> for TrafficChart {
         }
     }
 }
+
+/// Formats the label of the sample taken the given number of refresh intervals ago (e.g. `-1.5s`).
+fn get_time_ago_label(intervals_ago: u32, refresh_interval: Duration) -> String {
+    if intervals_ago == 0 {
+        return "0s".to_string();
+    }
+    // computed in milliseconds to avoid rounding errors (e.g. 3 * 0.1s)
+    let ms_ago = u128::from(intervals_ago) * refresh_interval.as_millis();
+    #[allow(clippy::cast_precision_loss)]
+    let seconds_ago = ms_ago as f64 / 1000.0;
+    format!("-{seconds_ago}s")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use crate::structs::throughput_history::ThroughputSample;
+    use crate::structs::traffic_chart::{get_time_ago_label, TrafficChart};
+    use crate::{AppProtocol, Language, RunTimeData, StyleType, UnitSystem};
+
+    #[test]
+    fn test_log_scale_values() {
+        let mut chart = TrafficChart::new(
+            Rc::new(RefCell::new(RunTimeData::new())),
+            StyleType::Night,
+            Language::EN,
+            UnitSystem::Decimal,
+        );
+        assert_eq!(chart.scale_value(-1500), -1500);
+        assert_eq!(chart.unscale_value(1500), 1500);
+
+        chart.log_scale = true;
+        assert_eq!(chart.scale_value(0), 0);
+        assert_eq!(chart.scale_value(1), 0);
+        assert_eq!(chart.scale_value(1000), 3000);
+        assert_eq!(chart.scale_value(-1_000_000), -6000);
+        for value in [0, 7, -42, 1500, -65_535, 123_456_789] {
+            let restored = chart.unscale_value(chart.scale_value(value));
+            // precision of the restored values is within 0.12%
+            assert!((restored - value).abs() <= value.abs() / 800 + 1, "{value}");
+        }
+        assert_eq!(chart.unscale_value(2000), 100);
+        assert_eq!(chart.unscale_value(-3000), -1000);
+    }
+
+    #[test]
+    fn test_stacked_app_protocol_series() {
+        let mut charts_data = RunTimeData::new();
+        for tick in 3..=5 {
+            charts_data
+                .throughput_history
+                .push(tick, ThroughputSample::default(), 30);
+        }
+        charts_data.app_protocol_packets = [
+            (AppProtocol::DNS, [(3, 1), (4, 2), (5, 3)].into()),
+            (AppProtocol::HTTPS, [(4, 10), (5, 0)].into()),
+            (AppProtocol::SSH, [(3, 0), (4, 0), (5, 0)].into()),
+        ]
+        .into();
+        let chart = TrafficChart::new(
+            Rc::new(RefCell::new(charts_data)),
+            StyleType::Night,
+            Language::EN,
+            UnitSystem::Decimal,
+        );
+        // protocols without traffic in the window are not displayed
+        assert_eq!(
+            chart.stacked_app_protocol_series(),
+            vec![
+                (AppProtocol::HTTPS, vec![(3, 0), (4, 10), (5, 0)]),
+                (AppProtocol::DNS, vec![(3, 1), (4, 12), (5, 3)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_ago_labels() {
+        let second = Duration::from_secs(1);
+        assert_eq!(get_time_ago_label(0, second), "0s");
+        assert_eq!(get_time_ago_label(5, second), "-5s");
+        assert_eq!(get_time_ago_label(5, Duration::from_secs(2)), "-10s");
+        assert_eq!(get_time_ago_label(3, Duration::from_millis(100)), "-0.3s");
+        assert_eq!(get_time_ago_label(3, Duration::from_millis(1500)), "-4.5s");
+    }
+}
@@ -1,32 +1,71 @@
 //! Module containing functions executed by the thread in charge of parsing sniffed packets and
 //! inserting them in the shared map.
 
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use etherparse::PacketHeaders;
-use pcap::{Active, Capture, Device};
+use pcap::{Activated, Capture, Device, PacketHeader, Stat};
+use tracing::{debug, info, instrument, trace, warn};
 
-use crate::enums::traffic_type::TrafficType;
 use crate::structs::address_port_pair::AddressPortPair;
 use crate::structs::filters::Filters;
-use crate::utility::countries::COUNTRY_MMDB;
+use crate::structs::mac_address::MacAddress;
+use crate::structs::pcap_export::PcapExportWriter;
+use crate::structs::traffic_buffer::{FlushCadence, TrafficBuffer};
+use crate::utility::countries::{MmdbReader, MmdbReaders};
+use crate::utility::dns::get_dns_query_name;
+use crate::utility::gateway::get_default_gateways;
+use crate::utility::logging::lock_or_recover;
 use crate::utility::manage_packets::{
-    analyze_network_header, analyze_transport_header, is_broadcast_address, is_multicast_address,
-    modify_or_insert_in_map,
+    analyze_network_header, analyze_transport_header, get_broadcast_addresses,
+    get_discovery_packet, get_tls_sni, get_traffic_type, get_transport_header, get_vlan_id,
+    is_in_network_scope, is_supported_linktype, parse_packet_headers,
 };
 use crate::{AppProtocol, InfoTraffic, IpVersion, TransProtocol};
 
+/// Number of consecutive read errors after which the capture is reported as stalled
+const CAPTURE_ERROR_THRESHOLD: u32 = 10;
+
+/// Interval between two checks of the capture statistics, independent of the traffic being buffered
+const STATS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 /// The calling thread enters in a loop in which it waits for network packets, parses them according
 /// to the user specified filters, and inserts them into the shared map variable.
-pub fn parse_packets_loop(
-    current_capture_id: &Arc<Mutex<u16>>,
+///
+/// Packets can be read both from a live device and from a pcap file (`Capture<Offline>`):
+/// in the latter case the loop ends when the end of the file is reached.
+/// The loop also ends as soon as `stop_flag` is set: live captures must be opened with a timeout,
+/// so that the flag is checked periodically even when no traffic is sniffed.
+/// In both cases the traffic still buffered is merged into the shared map (and exported) before returning.
+/// The addresses of `device` are used to determine the traffic direction.
+/// The remote addresses of the connections are looked up in the available `mmdb_readers`.
+///
+/// While `pause_flag` is set, packets keep being read from the capture (so that the kernel buffer
+/// doesn't overflow), but they are discarded: on resume, only the traffic sniffed from then on is accounted.
+///
+/// To reduce contention on the shared map, packets are accumulated in a thread-local buffer,
+/// merged into the shared map with the default `FlushCadence`.
+/// At every merge, the packets dropped by the capture (if its statistics are available) are accounted as well.
+///
+/// If `hostname_sender` is specified, the remote addresses of the new connections are sent through it
+/// to have their hostname resolved.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "parse", skip_all, fields(interface = %device.name))]
+pub fn parse_packets_loop<T: Activated + ?Sized>(
+    stop_flag: &Arc<AtomicBool>,
+    pause_flag: &Arc<AtomicBool>,
     device: Device,
-    mut cap: Capture<Active>,
+    mut cap: Capture<T>,
     filters: &Filters,
+    mmdb_readers: &MmdbReaders,
     info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    hostname_sender: Option<Sender<(AddressPortPair, IpAddr)>>,
 ) {
-    let capture_id = *current_capture_id.lock().unwrap();
-
+    let interface = device.name.clone();
+    let broadcast_addresses = get_broadcast_addresses(&device.addresses);
     let mut my_interface_addresses = Vec::new();
     for address in device.addresses {
         my_interface_addresses.push(address.addr.to_string());
@@ -35,136 +74,1429 @@ pub fn parse_packets_loop(
     let network_layer_filter = filters.ip;
     let transport_layer_filter = filters.transport;
     let app_layer_filter = filters.application;
+    let scope_filter = filters.scope;
+    let direction_filter = filters.direction_filter;
 
     let mut port1 = 0;
     let mut port2 = 0;
-    let mut exchanged_bytes: u128 = 0;
+    let mut payload_len: u128 = 0;
+    let mut dscp = 0;
     let mut network_protocol;
     let mut transport_protocol;
     let mut application_protocol;
     let mut traffic_type;
+    let mut icmp_type_code;
+    let mut tcp_flags;
     let mut skip_packet;
-    let mut reported_packet;
 
-    let country_db_reader = maxminddb::Reader::from_source(COUNTRY_MMDB).unwrap();
+    let MmdbReaders {
+        country: country_db_reader,
+        asn: asn_db_reader,
+        city: city_db_reader,
+    } = mmdb_readers;
+
+    let gateways = get_default_gateways(&interface)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let mut buffer = TrafficBuffer::new(
+        interface,
+        FlushCadence::default(),
+        filters.favorite_rules.clone(),
+        gateways,
+    );
+
+    let mut pcap_writer = filters.pcap_export.as_ref().and_then(|pcap_export| {
+        PcapExportWriter::new(pcap_export, cap.get_datalink())
+            .map_err(|err| {
+                warn!(path = %pcap_export.path.display(), %err, "cannot export packets");
+            })
+            .ok()
+    });
+
+    info!(ip = %filters.ip, transport = %filters.transport, application = %filters.application, scope = %filters.scope, bpf = %filters.bpf.as_deref().unwrap_or_default(), "capture started");
+
+    // loopback adapters of some systems don't encapsulate packets in Ethernet frames
+    let linktype = cap.get_datalink();
+    if !is_supported_linktype(linktype) {
+        // all the packets would be dropped
+        let linktype_name = linktype
+            .get_name()
+            .unwrap_or_else(|_| linktype.0.to_string());
+        warn!(linktype = %linktype_name, "link type not supported, packets ignored");
+        let mut info_traffic = lock_or_recover(info_traffic_mutex, "updating capture error");
+        info_traffic.capture_error = Some(format!(
+            "{}: unsupported link type {linktype_name}",
+            buffer.interface()
+        ));
+    }
+
+    // capture statistics at the time of the last check; None if not available on this platform or capture
+    let mut last_stats = cap.stats().ok();
+    let mut last_stats_poll = Instant::now();
+    // the timestamps of the packets read from a file are unrelated to the current time:
+    // the processing lag is only measured on live captures (recognized by their statistics)
+    let measure_lag = last_stats.is_some();
+    // maximum processing lag since the last merge into the shared data
+    let mut processing_lag = Duration::ZERO;
+    let mut error_streak = ErrorStreak::default();
 
     loop {
-        match cap.next_packet() {
+        if stop_flag.load(Ordering::Relaxed) {
+            // capture stopped: packets still buffered must not be lost
+            flush_buffer(
+                &mut buffer,
+                &mut pcap_writer,
+                info_traffic_mutex,
+                country_db_reader.as_ref(),
+                asn_db_reader.as_ref(),
+                city_db_reader.as_ref(),
+                hostname_sender.as_ref(),
+            );
+            return;
+        }
+        if buffer.is_flush_due() {
+            flush_buffer(
+                &mut buffer,
+                &mut pcap_writer,
+                info_traffic_mutex,
+                country_db_reader.as_ref(),
+                asn_db_reader.as_ref(),
+                city_db_reader.as_ref(),
+                hostname_sender.as_ref(),
+            );
+            if processing_lag > Duration::ZERO {
+                let mut info_traffic = lock_or_recover(info_traffic_mutex, "updating capture lag");
+                info_traffic.capture_lag = info_traffic.capture_lag.max(processing_lag);
+                processing_lag = Duration::ZERO;
+            }
+        }
+        // polled even when nothing is buffered (e.g. while paused, or dropping all the packets)
+        if let Some(previous) = last_stats.as_mut() {
+            if last_stats_poll.elapsed() >= STATS_POLL_INTERVAL {
+                update_dropped_packets(previous, cap.stats(), info_traffic_mutex);
+                last_stats_poll = Instant::now();
+            }
+        }
+        let next_packet = cap.next_packet();
+        if let Some(capture_error) = error_streak.update(&next_packet) {
+            let mut info_traffic = lock_or_recover(info_traffic_mutex, "updating capture error");
+            info_traffic.capture_error =
+                capture_error.map(|reason| format!("{}: {reason}", buffer.interface()));
+        }
+        match next_packet {
+            Err(pcap::Error::NoMorePackets) => {
+                // end of the pcap file: packets still buffered must not be lost
+                flush_buffer(
+                    &mut buffer,
+                    &mut pcap_writer,
+                    info_traffic_mutex,
+                    country_db_reader.as_ref(),
+                    asn_db_reader.as_ref(),
+                    city_db_reader.as_ref(),
+                    hostname_sender.as_ref(),
+                );
+                return;
+            }
             Err(_) => {
-                if *current_capture_id.lock().unwrap() != capture_id {
-                    return;
-                }
+                // timeout expired or read error: the stop flag is checked again
                 continue;
             }
-            Ok(packet) => {
-                if *current_capture_id.lock().unwrap() != capture_id {
-                    return;
+            Ok(_) if pause_flag.load(Ordering::Relaxed) => {
+                // capture paused: the packet is drained and discarded
+                continue;
+            }
+            Ok(packet) => match parse_packet_headers(linktype, &packet) {
+                None => {
+                    continue;
                 }
-                match PacketHeaders::from_ethernet_slice(&packet) {
-                    Err(_) => {
+                Some(value) => {
+                    if measure_lag {
+                        processing_lag = processing_lag.max(get_processing_lag(
+                            get_capture_time(packet.header),
+                            SystemTime::now(),
+                        ));
+                    }
+                    let mut address1 = String::new();
+                    let mut address2 = String::new();
+                    network_protocol = IpVersion::Other;
+                    transport_protocol = TransProtocol::Other;
+                    application_protocol = AppProtocol::Other;
+                    icmp_type_code = None;
+                    tcp_flags = None;
+                    skip_packet = false;
+
+                    // ARP and IGMP packets are accounted apart, instead of being reported per connection
+                    if let Some(discovery_packet) = get_discovery_packet(&value) {
+                        buffer.add_discovery_packet(discovery_packet);
                         continue;
                     }
-                    Ok(value) => {
-                        let mut address1 = String::new();
-                        let mut address2 = String::new();
-                        network_protocol = IpVersion::Other;
-                        transport_protocol = TransProtocol::Other;
-                        application_protocol = AppProtocol::Other;
-                        traffic_type = TrafficType::Other;
-                        skip_packet = false;
-                        reported_packet = false;
-
-                        analyze_network_header(
-                            value.ip,
-                            &mut exchanged_bytes,
-                            &mut network_protocol,
-                            &mut address1,
-                            &mut address2,
-                            &mut skip_packet,
-                        );
-                        if skip_packet {
-                            continue;
-                        }
 
-                        analyze_transport_header(
-                            value.transport,
-                            &mut port1,
-                            &mut port2,
-                            &mut application_protocol,
-                            &mut transport_protocol,
-                            &mut skip_packet,
-                        );
-                        if skip_packet {
-                            continue;
-                        }
+                    let (transport_header, transport_payload) =
+                        get_transport_header(&value.ip, value.transport, value.payload).unzip();
 
-                        if my_interface_addresses.contains(&address1) {
-                            traffic_type = TrafficType::Outgoing;
-                        } else if my_interface_addresses.contains(&address2) {
-                            traffic_type = TrafficType::Incoming;
-                        } else if is_multicast_address(&address2) {
-                            traffic_type = TrafficType::Multicast;
-                        } else if is_broadcast_address(&address2) {
-                            traffic_type = TrafficType::Broadcast;
-                        }
+                    analyze_network_header(
+                        value.ip,
+                        &mut payload_len,
+                        &mut network_protocol,
+                        &mut dscp,
+                        &mut address1,
+                        &mut address2,
+                        &mut skip_packet,
+                    );
+                    if skip_packet {
+                        continue;
+                    }
 
-                        let key: AddressPortPair = AddressPortPair::new(
-                            address1,
-                            port1,
-                            address2,
-                            port2,
-                            transport_protocol,
-                        );
+                    analyze_transport_header(
+                        transport_header,
+                        transport_payload.unwrap_or_default(),
+                        &filters.port_protocol_overrides,
+                        &mut port1,
+                        &mut port2,
+                        &mut application_protocol,
+                        &mut transport_protocol,
+                        &mut icmp_type_code,
+                        &mut tcp_flags,
+                        &mut skip_packet,
+                    );
+                    if skip_packet {
+                        continue;
+                    }
+
+                    traffic_type = get_traffic_type(
+                        &address1,
+                        &address2,
+                        &my_interface_addresses,
+                        &broadcast_addresses,
+                    );
+
+                    let key: AddressPortPair =
+                        AddressPortPair::new(address1, port1, address2, port2, transport_protocol);
 
-                        if (network_layer_filter.eq(&IpVersion::Other)
-                            || network_layer_filter.eq(&network_protocol))
-                            && (transport_layer_filter.eq(&TransProtocol::Other)
-                                || transport_layer_filter.eq(&transport_protocol))
-                            && (app_layer_filter.eq(&AppProtocol::Other)
-                                || app_layer_filter.eq(&application_protocol))
+                    // length on the wire, even if the packet has been truncated by the snaplen
+                    let frame_len = u128::from(packet.header.len);
+                    let exchanged_bytes = filters
+                        .byte_accounting
+                        .exchanged_bytes(frame_len, payload_len);
+                    buffer.add_packet(exchanged_bytes);
+
+                    if (network_layer_filter.eq(&IpVersion::Other)
+                        || network_layer_filter.eq(&network_protocol))
+                        && (transport_layer_filter.eq(&TransProtocol::Other)
+                            || transport_layer_filter.eq(&transport_protocol))
+                        && (app_layer_filter.eq(&AppProtocol::Other)
+                            || app_layer_filter.eq(&application_protocol))
+                        && is_in_network_scope(scope_filter, &key.address1, &key.address2)
+                        && direction_filter.is_none_or(|direction| direction == traffic_type)
+                        && filters.accepts_ports(port1, port2)
+                    {
+                        let tcp_state = tcp_flags.map(|flags| buffer.update_tcp_state(&key, flags));
+                        let info = buffer.add_connection(
+                            key,
+                            exchanged_bytes,
+                            traffic_type,
+                            application_protocol,
+                        );
+                        info.dscp = dscp;
+                        if icmp_type_code.is_some() {
+                            info.icmp_type_code = icmp_type_code;
+                        }
+                        if tcp_state.is_some() {
+                            info.tcp_state = tcp_state;
+                        }
+                        if filters.notable_ports.contains(&port1)
+                            || filters.notable_ports.contains(&port2)
                         {
-                            // if (port1 >= lowest_port && port1 <= highest_port)
-                            //     || (port2 >= lowest_port && port2 <= highest_port) {
-                            modify_or_insert_in_map(
-                                info_traffic_mutex,
-                                key,
-                                exchanged_bytes,
-                                traffic_type,
-                                application_protocol,
-                                &country_db_reader,
-                            );
-                            reported_packet = true;
-                            // }
+                            info.is_notable = true;
                         }
-
-                        let mut info_traffic = info_traffic_mutex
-                            .lock()
-                            .expect("Error acquiring mutex\n\r");
-                        //increment number of sniffed packets and bytes
-                        info_traffic.all_packets += 1;
-                        info_traffic.all_bytes += exchanged_bytes;
-
-                        if reported_packet {
-                            //increment the packet count for the sniffed app protocol
-                            info_traffic
-                                .app_protocols
-                                .entry(application_protocol)
-                                .and_modify(|n| *n += 1)
-                                .or_insert(1);
-
-                            if traffic_type == TrafficType::Outgoing {
-                                //increment number of sent packets and bytes
-                                info_traffic.tot_sent_packets += 1;
-                                info_traffic.tot_sent_bytes += exchanged_bytes;
-                            } else {
-                                //increment number of received packets and bytes
-                                info_traffic.tot_received_packets += 1;
-                                info_traffic.tot_received_bytes += exchanged_bytes;
+                        if let Some(link) = &value.link {
+                            info.src_mac = Some(MacAddress(link.source));
+                            info.dst_mac = Some(MacAddress(link.destination));
+                        }
+                        if let Some(vlan) = &value.vlan {
+                            info.vlan = Some(get_vlan_id(vlan));
+                        }
+                        if let Some(payload) = transport_payload {
+                            // mDNS messages have the same format of DNS messages
+                            if matches!(application_protocol, AppProtocol::DNS | AppProtocol::mDNS)
+                            {
+                                if let Some(dns_query) =
+                                    get_dns_query_name(payload, transport_protocol)
+                                {
+                                    info.dns_query = Some(dns_query);
+                                }
+                            } else if application_protocol == AppProtocol::HTTPS
+                                && transport_protocol == TransProtocol::TCP
+                            {
+                                if let Some(tls_sni) = get_tls_sni(payload) {
+                                    info.tls_sni = Some(tls_sni);
+                                }
+                            }
+                        }
+                        if let Some(writer) = pcap_writer.as_mut() {
+                            if let Err(err) = writer.write(&packet) {
+                                warn!(%err, "cannot export packets, export interrupted");
+                                pcap_writer = None;
                             }
                         }
+                    } else {
+                        trace!(address1 = %key.address1, port1 = %key.port1, address2 = %key.address2, port2 = %key.port2, ip = %network_protocol, transport = %transport_protocol, application = %application_protocol, "packet rejected by filters");
                     }
                 }
+            },
+        }
+    }
+}
+
+/// Returns the packets dropped by the system and by the network adapter between two capture statistics.
+///
+/// The counters of the statistics may wrap around.
+fn get_dropped_since(previous: &Stat, current: &Stat) -> (u32, u32) {
+    (
+        current.dropped.wrapping_sub(previous.dropped),
+        current.if_dropped.wrapping_sub(previous.if_dropped),
+    )
+}
+
+/// Adds the packets dropped since the previous capture statistics to the shared data.
+///
+/// A failed check keeps the previous statistics, so that the next one still counts from them.
+fn update_dropped_packets(
+    previous: &mut Stat,
+    current: Result<Stat, pcap::Error>,
+    info_traffic_mutex: &Mutex<InfoTraffic>,
+) {
+    let Ok(current) = current else {
+        return;
+    };
+    let (dropped, if_dropped) = get_dropped_since(previous, &current);
+    *previous = current;
+    if dropped > 0 || if_dropped > 0 {
+        let mut info_traffic = lock_or_recover(info_traffic_mutex, "updating dropped packets");
+        info_traffic.dropped_packets += u128::from(dropped);
+        info_traffic.if_dropped_packets += u128::from(if_dropped);
+    }
+}
+
+/// Streak of consecutive errors returned by a capture, used to detect when it's stalled
+/// (e.g. the network adapter went down or the permissions have been revoked).
+#[derive(Default)]
+struct ErrorStreak {
+    /// Consecutive read errors, excluding the timeouts
+    errors: u32,
+}
+
+impl ErrorStreak {
+    /// Accounts the outcome of a read, returning the capture error to be reported if it changed:
+    /// `Some(Some(reason))` when the errors reach `CAPTURE_ERROR_THRESHOLD`,
+    /// and `Some(None)` when the packets are read again after that.
+    ///
+    /// Timeouts (and the end of pcap files) are benign, and they don't affect the streak.
+    fn update<T>(&mut self, result: &Result<T, pcap::Error>) -> Option<Option<String>> {
+        match result {
+            Err(pcap::Error::TimeoutExpired | pcap::Error::NoMorePackets) => None,
+            Err(err) => {
+                self.errors = self.errors.saturating_add(1);
+                (self.errors == CAPTURE_ERROR_THRESHOLD).then(|| Some(err.to_string()))
+            }
+            Ok(_) => {
+                let was_stalled = self.errors >= CAPTURE_ERROR_THRESHOLD;
+                self.errors = 0;
+                was_stalled.then_some(None)
+            }
+        }
+    }
+}
+
+/// Merges the buffered traffic into the shared map and flushes the pcap export, if any.
+#[instrument(name = "flush", level = "debug", skip_all)]
+fn flush_buffer(
+    buffer: &mut TrafficBuffer,
+    pcap_writer: &mut Option<PcapExportWriter>,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    country_db_reader: Option<&MmdbReader>,
+    asn_db_reader: Option<&MmdbReader>,
+    city_db_reader: Option<&MmdbReader>,
+    hostname_sender: Option<&Sender<(AddressPortPair, IpAddr)>>,
+) {
+    let (packets, bytes, connections) = buffer.buffered();
+    debug!(%packets, %bytes, %connections, "flushing buffered traffic");
+    let mut info_traffic = lock_or_recover(info_traffic_mutex, "merging buffered traffic");
+    buffer.flush(
+        &mut info_traffic,
+        country_db_reader,
+        asn_db_reader,
+        city_db_reader,
+        hostname_sender,
+    );
+    drop(info_traffic);
+    if let Some(writer) = pcap_writer.as_mut() {
+        if let Err(err) = writer.flush() {
+            warn!(%err, "cannot export packets, export interrupted");
+            *pcap_writer = None;
+        }
+    }
+}
+
+/// Returns the time at which a packet has been captured, according to its header.
+fn get_capture_time(header: &PacketHeader) -> SystemTime {
+    UNIX_EPOCH
+        + Duration::from_secs(u64::try_from(header.ts.tv_sec).unwrap_or_default())
+        + Duration::from_micros(u64::try_from(header.ts.tv_usec).unwrap_or_default())
+}
+
+/// Returns the delay between the capture of a packet and its processing
+/// (zero if the packet seems to be captured in the future, e.g. because the clock has been adjusted).
+fn get_processing_lag(captured: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(captured).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant, SystemTime};
+
+    use pcap::{Address, Capture, Device, Linktype, Stat};
+
+    use crate::enums::byte_accounting::ByteAccounting;
+    use crate::enums::tcp_state::TcpState;
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::configs::ConfigMmdb;
+    use crate::structs::filters::Filters;
+    use crate::thread_parse_packets::{
+        get_dropped_since, get_processing_lag, parse_packets_loop, update_dropped_packets,
+        ErrorStreak, CAPTURE_ERROR_THRESHOLD,
+    };
+    use crate::utility::countries::{open_embedded_country_db, MmdbReaders};
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    /// Builds an Ethernet frame carrying an IPv4 UDP datagram with 4 bytes of payload.
+    fn udp_frame(source: [u8; 4], destination: [u8; 4], ports: (u16, u16)) -> Vec<u8> {
+        udp_frame_with_payload(source, destination, ports, &[1, 2, 3, 4])
+    }
+
+    /// Builds an Ethernet frame carrying an IPv4 UDP datagram with the given payload.
+    fn udp_frame_with_payload(
+        source: [u8; 4],
+        destination: [u8; 4],
+        ports: (u16, u16),
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let udp_len = u16::try_from(8 + payload.len()).unwrap();
+        let mut frame = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0x08, 0x00];
+        frame.extend_from_slice(&[0x45, 0]);
+        frame.extend_from_slice(&(20 + udp_len).to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 64, 17, 0, 0]);
+        frame.extend_from_slice(&source);
+        frame.extend_from_slice(&destination);
+        frame.extend_from_slice(&ports.0.to_be_bytes());
+        frame.extend_from_slice(&ports.1.to_be_bytes());
+        frame.extend_from_slice(&udp_len.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// Builds an Ethernet frame tagged with the given VLAN identifiers (outermost first),
+    /// carrying an IPv4 TCP SYN segment.
+    fn vlan_tcp_frame(
+        vlan_ids: &[u16],
+        source: [u8; 4],
+        destination: [u8; 4],
+        ports: (u16, u16),
+    ) -> Vec<u8> {
+        let mut frame = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        for (i, vlan_id) in vlan_ids.iter().enumerate() {
+            // service tag for the outer header of double tagged frames, customer tag otherwise
+            let tpid: u16 = if i + 1 < vlan_ids.len() {
+                0x88a8
+            } else {
+                0x8100
+            };
+            frame.extend_from_slice(&tpid.to_be_bytes());
+            frame.extend_from_slice(&vlan_id.to_be_bytes());
+        }
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(&[0x45, 0, 0, 40, 0, 0, 0x40, 0, 64, 6, 0, 0]);
+        frame.extend_from_slice(&source);
+        frame.extend_from_slice(&destination);
+        frame.extend_from_slice(&ports.0.to_be_bytes());
+        frame.extend_from_slice(&ports.1.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xff, 0xff, 0, 0, 0, 0]);
+        frame
+    }
+
+    /// Builds a pcap file (Ethernet link type) containing the given frames.
+    fn pcap_file(frames: &[Vec<u8>]) -> Vec<u8> {
+        pcap_file_with_linktype(frames, Linktype::ETHERNET)
+    }
+
+    /// Builds a pcap file with the given link type containing the given frames.
+    fn pcap_file_with_linktype(frames: &[Vec<u8>], linktype: Linktype) -> Vec<u8> {
+        let mut file = Vec::new();
+        let linktype = u32::try_from(linktype.0).unwrap();
+        for field in [0xa1b2_c3d4_u32, 0x0004_0002, 0, 0, 65535, linktype] {
+            file.extend_from_slice(&field.to_ne_bytes());
+        }
+        for (i, frame) in frames.iter().enumerate() {
+            let len = u32::try_from(frame.len()).unwrap();
+            for field in [1_672_531_200 + u32::try_from(i).unwrap(), 0, len, len] {
+                file.extend_from_slice(&field.to_ne_bytes());
+            }
+            file.extend_from_slice(frame);
+        }
+        file
+    }
+
+    #[test]
+    fn test_offline_capture_until_end_of_file() {
+        let local = [192, 168, 1, 10];
+        let remote = [8, 8, 8, 8];
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            frames.push(udp_frame(local, remote, (50000, 53)));
+            frames.push(udp_frame(remote, local, (53, 50000)));
+        }
+        frames.push(udp_frame(local, [192, 168, 1, 1], (50001, 123)));
+        // DNS query for "sniffnet.net" over a new connection
+        let mut dns_query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        dns_query.extend_from_slice(b"\x08sniffnet\x03net\x00\x00\x01\x00\x01");
+        frames.push(udp_frame_with_payload(
+            local,
+            remote,
+            (50002, 53),
+            &dns_query,
+        ));
+        let path = std::env::temp_dir().join("sniffnet_test_offline_capture.pcap");
+        fs::write(&path, pcap_file(&frames)).unwrap();
+
+        let device = Device {
+            addresses: vec![Address {
+                addr: "192.168.1.10".parse().unwrap(),
+                netmask: None,
+                broadcast_addr: None,
+                dst_addr: None,
+            }],
+            ..Device::from("offline")
+        };
+        let filters = Filters {
+            transport: TransProtocol::UDP,
+            application: AppProtocol::DNS,
+            ..Filters::default()
+        };
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(false)),
+            device,
+            Capture::from_file(&path).unwrap(),
+            &filters,
+            &MmdbReaders::open(&ConfigMmdb::default()),
+            &info_traffic_mutex,
+            None,
+        );
+        fs::remove_file(&path).unwrap();
+
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        let dns_query_bytes = 8 + 30;
+        assert_eq!(info_traffic.all_packets, 8);
+        assert_eq!(info_traffic.all_bytes, 7 * 12 + dns_query_bytes);
+        assert_eq!(info_traffic.size_histogram, [8, 0, 0, 0, 0, 0]);
+        assert_eq!(info_traffic.tot_sent_packets, 4);
+        assert_eq!(info_traffic.tot_received_packets, 3);
+        assert_eq!(info_traffic.tot_sent_bytes, 3 * 12 + dns_query_bytes);
+        assert_eq!(info_traffic.map.len(), 3);
+        let dns_summary = info_traffic.app_protocols[&AppProtocol::DNS];
+        assert_eq!(dns_summary.packets, 7);
+        assert_eq!(dns_summary.bytes, 6 * 12 + dns_query_bytes);
+        let (key, info) = info_traffic.map.get_index(0).unwrap();
+        assert_eq!(key.address2, "8.8.8.8");
+        assert_eq!(info.transmitted_packets, 3);
+        assert_eq!(info.country, "US");
+        assert_eq!(info.interface, "offline");
+        assert_eq!(info.src_mac.unwrap().to_string(), "06:07:08:09:0a:0b");
+        assert_eq!(info.dst_mac.unwrap().to_string(), "00:01:02:03:04:05");
+        // the payload of the other packets is not a valid DNS message
+        assert_eq!(info.dns_query, None);
+        let (key, info) = info_traffic.map.get_index(2).unwrap();
+        assert_eq!(key.port1, 50002);
+        assert_eq!(info.dns_query.as_deref(), Some("sniffnet.net"));
+        // packets read from a file (captured in 2023) are not considered lagging
+        assert_eq!(info_traffic.capture_lag, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_offline_per_interface_traffic() {
+        let local = [192, 168, 1, 10];
+        let remote = [8, 8, 8, 8];
+        let sent = udp_frame(local, remote, (50000, 53));
+        let received = udp_frame(remote, local, (53, 50000));
+        // not matching the filters
+        let other = udp_frame(local, remote, (50001, 123));
+
+        let filters = Filters {
+            application: AppProtocol::DNS,
+            ..Filters::default()
+        };
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        for (name, frames) in [
+            ("eth0", vec![sent.clone(), sent.clone(), received, other]),
+            ("wlan0", vec![sent.clone(), sent]),
+        ] {
+            let path = std::env::temp_dir()
+                .join(format!("sniffnet_test_offline_per_interface_{name}.pcap"));
+            fs::write(&path, pcap_file(&frames)).unwrap();
+            let device = Device {
+                addresses: vec![Address {
+                    addr: "192.168.1.10".parse().unwrap(),
+                    netmask: None,
+                    broadcast_addr: None,
+                    dst_addr: None,
+                }],
+                ..Device::from(name)
+            };
+            parse_packets_loop(
+                &Arc::new(AtomicBool::new(false)),
+                &Arc::new(AtomicBool::new(false)),
+                device,
+                Capture::from_file(&path).unwrap(),
+                &filters,
+                &MmdbReaders::open(&ConfigMmdb::default()),
+                &info_traffic_mutex,
+                None,
+            );
+            fs::remove_file(&path).unwrap();
+        }
+
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        assert_eq!(info_traffic.per_interface.len(), 2);
+        // UDP header and payload
+        let packet_bytes = 12;
+        let eth0 = info_traffic.per_interface["eth0"];
+        assert_eq!(eth0.all_packets, 4);
+        assert_eq!(eth0.all_bytes, 4 * packet_bytes);
+        assert_eq!((eth0.sent_packets, eth0.received_packets), (2, 1));
+        assert_eq!(
+            (eth0.sent_bytes, eth0.received_bytes),
+            (2 * packet_bytes, packet_bytes)
+        );
+        let wlan0 = info_traffic.per_interface["wlan0"];
+        assert_eq!(wlan0.all_packets, 2);
+        assert_eq!((wlan0.sent_packets, wlan0.received_packets), (2, 0));
+        // the totals sum up the traffic of all the adapters
+        assert_eq!(info_traffic.all_packets, 6);
+        assert_eq!(info_traffic.tot_sent_packets, 4);
+    }
+
+    #[test]
+    fn test_offline_direction_filter() {
+        let local = [192, 168, 1, 10];
+        let remote = [8, 8, 8, 8];
+        // another host of the local network
+        let peer = [192, 168, 1, 20];
+        let frames = [
+            (TrafficType::Incoming, udp_frame(remote, local, (53, 50000))),
+            (TrafficType::Outgoing, udp_frame(local, remote, (50000, 53))),
+            (TrafficType::Local, udp_frame(local, local, (50000, 50001))),
+            (
+                TrafficType::Multicast,
+                udp_frame(peer, [224, 0, 0, 251], (5353, 5353)),
+            ),
+            (
+                TrafficType::Broadcast,
+                udp_frame(peer, [255, 255, 255, 255], (68, 67)),
+            ),
+            (
+                TrafficType::Other,
+                udp_frame([10, 0, 0, 1], [10, 0, 0, 2], (50000, 80)),
+            ),
+        ];
+        let path = std::env::temp_dir().join("sniffnet_test_offline_direction_filter.pcap");
+        fs::write(
+            &path,
+            pcap_file(
+                &frames
+                    .iter()
+                    .map(|(_, frame)| frame.clone())
+                    .collect::<Vec<_>>(),
+            ),
+        )
+        .unwrap();
+
+        for (direction, _) in &frames {
+            let filters = Filters {
+                direction_filter: Some(*direction),
+                ..Filters::default()
+            };
+            let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+            let device = Device {
+                addresses: vec![Address {
+                    addr: "192.168.1.10".parse().unwrap(),
+                    netmask: None,
+                    broadcast_addr: None,
+                    dst_addr: None,
+                }],
+                ..Device::from("eth0")
+            };
+            parse_packets_loop(
+                &Arc::new(AtomicBool::new(false)),
+                &Arc::new(AtomicBool::new(false)),
+                device,
+                Capture::from_file(&path).unwrap(),
+                &filters,
+                &MmdbReaders::open(&ConfigMmdb::default()),
+                &info_traffic_mutex,
+                None,
+            );
+
+            let info_traffic = info_traffic_mutex.lock().unwrap();
+            // only the connection in the filtered direction is reported...
+            assert_eq!(info_traffic.map.len(), 1, "{direction}");
+            assert_eq!(
+                info_traffic.map.values().next().unwrap().traffic_type,
+                *direction
+            );
+            // ...but all the packets are counted
+            assert_eq!(info_traffic.all_packets, 6);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_offline_discovery_traffic() {
+        let arp = |operation: u8| {
+            let mut frame = vec![0xff; 6];
+            frame.extend_from_slice(&[0, 1, 2, 3, 4, 5, 0x08, 0x06, 0, 1, 0x08, 0, 6, 4, 0]);
+            frame.extend_from_slice(&[operation, 0, 1, 2, 3, 4, 5, 192, 168, 1, 10]);
+            frame.extend_from_slice(&[0, 0, 0, 0, 0, 0, 192, 168, 1, 1]);
+            frame
+        };
+        let mut igmp_report = vec![1, 0, 0x5e, 0, 0, 0xfb, 0, 1, 2, 3, 4, 5, 0x08, 0x00];
+        igmp_report.extend_from_slice(&[0x45, 0, 0, 28, 0, 0, 0x40, 0, 1, 2, 0, 0]);
+        igmp_report.extend_from_slice(&[192, 168, 1, 10, 224, 0, 0, 251]);
+        igmp_report.extend_from_slice(&[0x16, 0, 0, 0, 224, 0, 0, 251]);
+        let frames = vec![arp(1), arp(1), arp(2), igmp_report];
+        let path = std::env::temp_dir().join("sniffnet_test_offline_discovery.pcap");
+        fs::write(&path, pcap_file(&frames)).unwrap();
+
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(false)),
+            Device::from("offline"),
+            Capture::from_file(&path).unwrap(),
+            &Filters::default(),
+            &MmdbReaders::open(&ConfigMmdb::default()),
+            &info_traffic_mutex,
+            None,
+        );
+        fs::remove_file(&path).unwrap();
+
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        // discovery packets are not reported per connection
+        assert_eq!(info_traffic.all_packets, 0);
+        assert!(info_traffic.map.is_empty());
+        let discovery = &info_traffic.discovery;
+        assert_eq!(discovery.arp_requests, 2);
+        assert_eq!(discovery.arp_replies, 1);
+        assert_eq!(
+            discovery.most_requested(3),
+            vec![("192.168.1.1".parse().unwrap(), 2)]
+        );
+        assert_eq!(discovery.igmp_queries, 0);
+        assert_eq!(discovery.igmp_reports, 1);
+    }
+
+    #[test]
+    fn test_processing_lag() {
+        let now = SystemTime::now();
+        assert_eq!(
+            get_processing_lag(now - Duration::from_millis(1500), now),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(get_processing_lag(now, now), Duration::ZERO);
+        // packets timestamped in the future (e.g. after a clock adjustment) are not lagging
+        assert_eq!(
+            get_processing_lag(now + Duration::from_secs(10), now),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_offline_loopback_capture() {
+        let localhost = [127, 0, 0, 1];
+        // BSD loopback encapsulation: protocol family (AF_INET) in place of the Ethernet header
+        let loopback_frame = |source, destination, ports| {
+            let mut frame = 2_u32.to_ne_bytes().to_vec();
+            frame.extend_from_slice(&udp_frame(source, destination, ports)[14..]);
+            frame
+        };
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            frames.push(loopback_frame(localhost, localhost, (50000, 8125)));
+        }
+        for _ in 0..2 {
+            frames.push(loopback_frame(localhost, localhost, (8125, 50000)));
+        }
+        // truncated packet, shorter than the protocol family header
+        frames.push(vec![2, 0]);
+        let path = std::env::temp_dir().join("sniffnet_test_loopback_capture.pcap");
+        fs::write(&path, pcap_file_with_linktype(&frames, Linktype::NULL)).unwrap();
+
+        let device = Device {
+            addresses: vec![Address {
+                addr: "127.0.0.1".parse().unwrap(),
+                netmask: Some("255.0.0.0".parse().unwrap()),
+                broadcast_addr: None,
+                dst_addr: None,
+            }],
+            ..Device::from("lo0")
+        };
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(false)),
+            device,
+            Capture::from_file(&path).unwrap(),
+            &Filters::default(),
+            &MmdbReaders::open(&ConfigMmdb::default()),
+            &info_traffic_mutex,
+            None,
+        );
+        fs::remove_file(&path).unwrap();
+
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        assert_eq!(info_traffic.all_packets, 5);
+        // each packet is accounted once, even if both its endpoints are local
+        assert_eq!(
+            info_traffic.tot_sent_packets + info_traffic.tot_received_packets,
+            5
+        );
+        assert_eq!(
+            info_traffic.tot_sent_bytes + info_traffic.tot_received_bytes,
+            5 * 12
+        );
+        assert_eq!(info_traffic.map.len(), 2);
+        assert!(info_traffic
+            .map
+            .values()
+            .all(|info| info.traffic_type == TrafficType::Local && info.src_mac.is_none()));
+        let (key, info) = info_traffic.map.get_index(0).unwrap();
+        assert_eq!((key.port1, key.port2), (50000, 8125));
+        assert_eq!(info.transmitted_packets, 3);
+    }
+
+    /// Replays the frames through the parsing loop, as read from a file with the given link type
+    /// and sniffed by the adapter whose address is 192.168.1.10.
+    fn replay_frames_with_linktype(
+        frames: &[Vec<u8>],
+        linktype: Linktype,
+        file_name: &str,
+    ) -> InfoTraffic {
+        let path = std::env::temp_dir().join(file_name);
+        fs::write(&path, pcap_file_with_linktype(frames, linktype)).unwrap();
+        let device = Device {
+            addresses: vec![Address {
+                addr: "192.168.1.10".parse().unwrap(),
+                netmask: None,
+                broadcast_addr: None,
+                dst_addr: None,
+            }],
+            ..Device::from("wlan0mon")
+        };
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(false)),
+            device,
+            Capture::from_file(&path).unwrap(),
+            &Filters::default(),
+            &MmdbReaders::open(&ConfigMmdb::default()),
+            &info_traffic_mutex,
+            None,
+        );
+        fs::remove_file(&path).unwrap();
+        Arc::try_unwrap(info_traffic_mutex)
+            .ok()
+            .unwrap()
+            .into_inner()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_offline_wifi_monitor_capture() {
+        let local = [192, 168, 1, 10];
+        let remote = [8, 8, 8, 8];
+        // radiotap header (version, padding, length and an empty presence bitmap) and 802.11 header
+        let wifi_frame = |frame_control: [u8; 2], ip_packet: &[u8]| {
+            let mut frame = vec![0, 0, 8, 0, 0, 0, 0, 0];
+            frame.extend_from_slice(&frame_control);
+            // duration, three addresses and sequence control
+            frame.extend_from_slice(&[0; 22]);
+            if frame_control[0] & 0x80 != 0 {
+                // QoS control
+                frame.extend_from_slice(&[0, 0]);
             }
+            frame.extend_from_slice(&[0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x00]);
+            frame.extend_from_slice(ip_packet);
+            frame
+        };
+        let sent = udp_frame(local, remote, (50000, 53));
+        let received = udp_frame(remote, local, (53, 50000));
+        let frames = [
+            // data frame sent to the access point
+            wifi_frame([0x08, 0x01], &sent[14..]),
+            // QoS data frame received from the access point
+            wifi_frame([0x88, 0x02], &received[14..]),
+            // beacon (management frame)
+            wifi_frame([0x80, 0x00], &sent[14..]),
+            // null data frame
+            wifi_frame([0x48, 0x01], &[]),
+            // protected data frame
+            wifi_frame([0x08, 0x41], &sent[14..]),
+            // truncated radiotap header
+            vec![0, 0, 8],
+        ];
+        let info_traffic = replay_frames_with_linktype(
+            &frames,
+            Linktype::IEEE802_11_RADIOTAP,
+            "sniffnet_test_wifi_monitor_capture.pcap",
+        );
+        assert_eq!(info_traffic.capture_error, None);
+        assert_eq!(info_traffic.all_packets, 2);
+        assert_eq!(info_traffic.tot_sent_packets, 1);
+        assert_eq!(info_traffic.tot_received_packets, 1);
+        assert_eq!(info_traffic.map.len(), 2);
+        let (key, info) = info_traffic.map.get_index(0).unwrap();
+        assert_eq!((key.port1, key.port2), (50000, 53));
+        assert_eq!(info.app_protocol, AppProtocol::DNS);
+        assert_eq!(info.traffic_type, TrafficType::Outgoing);
+    }
+
+    #[test]
+    fn test_offline_linux_cooked_capture() {
+        let local = [192, 168, 1, 10];
+        let remote = [93, 184, 216, 34];
+        let syn = vlan_tcp_frame(&[], local, remote, (50000, 443));
+        let syn_ack = vlan_tcp_frame(&[], remote, local, (443, 50000));
+        // packet type, ARPHRD_ETHER, address length, address (padded to 8 bytes) and protocol
+        let sll_frame = |packet_type: u8, ether_type: [u8; 2], ip_packet: &[u8]| {
+            let mut frame = vec![0, packet_type, 0, 1, 0, 6, 0, 1, 2, 3, 4, 5, 0, 0];
+            frame.extend_from_slice(&ether_type);
+            frame.extend_from_slice(ip_packet);
+            frame
+        };
+        // protocol, reserved, interface index, ARPHRD_ETHER, packet type, address length and address
+        let sll2_frame = |packet_type: u8, ether_type: [u8; 2], ip_packet: &[u8]| {
+            let mut frame = ether_type.to_vec();
+            frame.extend_from_slice(&[0, 0, 0, 0, 0, 2, 0, 1, packet_type, 6]);
+            frame.extend_from_slice(&[0, 1, 2, 3, 4, 5, 0, 0]);
+            frame.extend_from_slice(ip_packet);
+            frame
+        };
+        for (linktype, cooked_frame) in [
+            (
+                Linktype::LINUX_SLL,
+                sll_frame as fn(u8, [u8; 2], &[u8]) -> Vec<u8>,
+            ),
+            (Linktype::LINUX_SLL2, sll2_frame),
+        ] {
+            let frames = [
+                // sent by this host
+                cooked_frame(4, [0x08, 0x00], &syn[14..]),
+                // to this host
+                cooked_frame(0, [0x08, 0x00], &syn_ack[14..]),
+                // ARP
+                cooked_frame(1, [0x08, 0x06], &[0; 28]),
+                // truncated header
+                cooked_frame(0, [0x08, 0x00], &[])[..10].to_vec(),
+            ];
+            let info_traffic = replay_frames_with_linktype(
+                &frames,
+                linktype,
+                "sniffnet_test_linux_cooked_capture.pcap",
+            );
+            assert_eq!(info_traffic.capture_error, None);
+            assert_eq!(info_traffic.all_packets, 2, "{linktype:?}");
+            assert_eq!(info_traffic.map.len(), 2);
+            let (key, info) = info_traffic.map.get_index(0).unwrap();
+            assert_eq!(key.trans_protocol, TransProtocol::TCP);
+            assert_eq!(
+                (key.address2.as_str(), key.port1, key.port2),
+                ("93.184.216.34", 50000, 443)
+            );
+            assert_eq!(info.traffic_type, TrafficType::Outgoing);
+            assert_eq!(info.app_protocol, AppProtocol::HTTPS);
+            assert!(info.tcp_state.is_some());
+            assert_eq!(info.src_mac, None);
         }
     }
+
+    #[test]
+    fn test_offline_unsupported_linktype() {
+        let frames = vec![udp_frame([192, 168, 1, 10], [8, 8, 8, 8], (50000, 53)); 3];
+        // DLT_USER0, reserved for private use
+        let info_traffic = replay_frames_with_linktype(
+            &frames,
+            Linktype(147),
+            "sniffnet_test_unsupported_linktype.pcap",
+        );
+        assert_eq!(info_traffic.all_packets, 0);
+        // named after libpcap, if it knows the link type
+        assert!(info_traffic
+            .capture_error
+            .unwrap()
+            .starts_with("wlan0mon: unsupported link type "));
+    }
+
+    #[test]
+    fn test_loop_stops_when_flag_is_set() {
+        let frames = vec![udp_frame([192, 168, 1, 10], [8, 8, 8, 8], (50000, 53)); 100_000];
+        let path = std::env::temp_dir().join("sniffnet_test_stop_flag.pcap");
+        fs::write(&path, pcap_file(&frames)).unwrap();
+        let cap = Capture::from_file(&path).unwrap();
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        let handle = {
+            let stop_flag = stop_flag.clone();
+            let info_traffic_mutex = info_traffic_mutex.clone();
+            thread::spawn(move || {
+                parse_packets_loop(
+                    &stop_flag,
+                    &Arc::new(AtomicBool::new(false)),
+                    Device::from("offline"),
+                    cap,
+                    &Filters::default(),
+                    &MmdbReaders::open(&ConfigMmdb::default()),
+                    &info_traffic_mutex,
+                    None,
+                );
+            })
+        };
+        stop_flag.store(true, Ordering::Relaxed);
+        let stopped_at = Instant::now();
+        handle.join().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(stopped_at.elapsed() < Duration::from_secs(1));
+        // the loop exited without parsing all the packets of the file
+        assert!(info_traffic_mutex.lock().unwrap().all_packets < 100_000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_buffered_packets_kept_when_flag_is_set() {
+        use std::fs::File;
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+
+        let frame = udp_frame([192, 168, 1, 10], [8, 8, 8, 8], (50000, 53));
+        let file = pcap_file(&vec![frame; 11]);
+        // all the records have the same length, after the global header
+        let record_len = (file.len() - 24) / 11;
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut writer = unsafe { File::from_raw_fd(fds[1]) };
+        writer.write_all(&file[..24 + 10 * record_len]).unwrap();
+        let cap = unsafe { Capture::from_raw_fd(fds[0]) }.unwrap();
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        let handle = {
+            let stop_flag = stop_flag.clone();
+            let info_traffic_mutex = info_traffic_mutex.clone();
+            thread::spawn(move || {
+                parse_packets_loop(
+                    &stop_flag,
+                    &Arc::new(AtomicBool::new(false)),
+                    Device::from("offline"),
+                    cap,
+                    &Filters::default(),
+                    &MmdbReaders::open(&ConfigMmdb::default()),
+                    &info_traffic_mutex,
+                    None,
+                );
+            })
+        };
+        // the loop reads the first packets and waits for the next one, far from its flush cadence
+        thread::sleep(Duration::from_millis(100));
+        stop_flag.store(true, Ordering::Relaxed);
+        writer.write_all(&file[24 + 10 * record_len..]).unwrap();
+        handle.join().unwrap();
+        drop(writer);
+
+        // the packets buffered when the loop stopped are accounted as well
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        assert_eq!(info_traffic.all_packets, 11);
+        assert_eq!(info_traffic.map.len(), 1);
+        assert_eq!(info_traffic.map[0].transmitted_packets, 11);
+    }
+
+    #[test]
+    fn test_paused_capture_discards_packets() {
+        let frames = vec![udp_frame([192, 168, 1, 10], [8, 8, 8, 8], (50000, 53)); 10];
+        let path = std::env::temp_dir().join("sniffnet_test_pause_flag.pcap");
+        fs::write(&path, pcap_file(&frames)).unwrap();
+
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        // the loop still reads the whole file, without accounting its packets
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(true)),
+            Device::from("offline"),
+            Capture::from_file(&path).unwrap(),
+            &Filters::default(),
+            &MmdbReaders::open(&ConfigMmdb::default()),
+            &info_traffic_mutex,
+            None,
+        );
+        fs::remove_file(&path).unwrap();
+
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        assert_eq!(info_traffic.all_packets, 0);
+        assert!(info_traffic.map.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_packets_between_stats() {
+        let stat = |dropped, if_dropped| Stat {
+            received: 0,
+            dropped,
+            if_dropped,
+        };
+        assert_eq!(get_dropped_since(&stat(0, 0), &stat(0, 0)), (0, 0));
+        assert_eq!(get_dropped_since(&stat(10, 2), &stat(25, 3)), (15, 1));
+        // counters wrapped around
+        assert_eq!(
+            get_dropped_since(&stat(u32::MAX - 1, 0), &stat(3, 0)),
+            (5, 0)
+        );
+    }
+
+    #[test]
+    fn test_dropped_packets_after_failed_stats() {
+        let stat = |dropped, if_dropped| Stat {
+            received: 0,
+            dropped,
+            if_dropped,
+        };
+        let info_traffic_mutex = Mutex::new(InfoTraffic::new());
+        let mut last_stats = stat(10, 2);
+        update_dropped_packets(&mut last_stats, Ok(stat(25, 3)), &info_traffic_mutex);
+        // a failed check doesn't stop the following ones and doesn't lose the packets dropped meanwhile
+        update_dropped_packets(
+            &mut last_stats,
+            Err(pcap::Error::TimeoutExpired),
+            &info_traffic_mutex,
+        );
+        assert_eq!(last_stats.dropped, 25);
+        update_dropped_packets(&mut last_stats, Ok(stat(40, 3)), &info_traffic_mutex);
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        assert_eq!(info_traffic.dropped_packets, 30);
+        assert_eq!(info_traffic.if_dropped_packets, 1);
+    }
+
+    #[test]
+    fn test_offline_vlan_tagged_packets() {
+        let local = [192, 168, 1, 10];
+        let remote = [8, 8, 8, 8];
+        let frames = vec![
+            vlan_tcp_frame(&[100], local, remote, (50000, 443)),
+            // double tagged (QinQ) frame
+            vlan_tcp_frame(&[200, 100], local, remote, (50001, 80)),
+            vlan_tcp_frame(&[], local, remote, (50002, 22)),
+        ];
+        let path = std::env::temp_dir().join("sniffnet_test_offline_vlan.pcap");
+        fs::write(&path, pcap_file(&frames)).unwrap();
+        let device = Device {
+            addresses: vec![Address {
+                addr: "192.168.1.10".parse().unwrap(),
+                netmask: None,
+                broadcast_addr: None,
+                dst_addr: None,
+            }],
+            ..Device::from("eth0")
+        };
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(false)),
+            device,
+            Capture::from_file(&path).unwrap(),
+            &Filters::default(),
+            &MmdbReaders::open(&ConfigMmdb::default()),
+            &info_traffic_mutex,
+            None,
+        );
+        fs::remove_file(&path).unwrap();
+
+        let info_traffic = info_traffic_mutex.lock().unwrap();
+        assert_eq!(info_traffic.map.len(), 3);
+        for (port, app_protocol, vlan) in [
+            (443, AppProtocol::HTTPS, Some(100)),
+            (80, AppProtocol::HTTP, Some(200)),
+            (22, AppProtocol::SSH, None),
+        ] {
+            let (key, info) = info_traffic
+                .map
+                .iter()
+                .find(|(key, _)| key.port2 == port)
+                .unwrap();
+            // the inner headers are parsed after the tags
+            assert_eq!(key.trans_protocol, TransProtocol::TCP);
+            assert_eq!(key.address2, "8.8.8.8");
+            assert_eq!(info.app_protocol, app_protocol);
+            assert_eq!(info.vlan, vlan);
+        }
+    }
+
+    #[test]
+    fn test_error_streak() {
+        let mut error_streak = ErrorStreak::default();
+        let error: Result<(), pcap::Error> = Err(pcap::Error::PcapError("device gone".to_string()));
+        // timeouts are benign
+        for _ in 0..2 * CAPTURE_ERROR_THRESHOLD {
+            assert_eq!(
+                error_streak.update(&Err::<(), _>(pcap::Error::TimeoutExpired)),
+                None
+            );
+        }
+        for _ in 1..CAPTURE_ERROR_THRESHOLD {
+            assert_eq!(error_streak.update(&error), None);
+        }
+        // a single packet resets the streak
+        assert_eq!(error_streak.update(&Ok(())), None);
+        for _ in 1..CAPTURE_ERROR_THRESHOLD {
+            assert_eq!(error_streak.update(&error), None);
+        }
+        // timeouts don't interrupt the streak either
+        assert_eq!(
+            error_streak.update(&Err::<(), _>(pcap::Error::TimeoutExpired)),
+            None
+        );
+        assert_eq!(
+            error_streak.update(&error),
+            Some(Some("libpcap error: device gone".to_string()))
+        );
+        // reported only once
+        assert_eq!(error_streak.update(&error), None);
+        // the error is reset as soon as packets resume
+        assert_eq!(error_streak.update(&Ok(())), Some(None));
+        assert_eq!(error_streak.update(&Ok(())), None);
+    }
+
+    /// Replays one of the pcap fixtures under `tests/pcaps` through the parsing loop, with no filters,
+    /// as sniffed by the adapter whose address is 192.168.1.10.
+    fn replay_fixture(name: &str) -> InfoTraffic {
+        replay_fixture_with_filters(name, &Filters::default())
+    }
+
+    /// Replays one of the pcap fixtures like `replay_fixture`, applying the given filters.
+    fn replay_fixture_with_filters(name: &str, filters: &Filters) -> InfoTraffic {
+        replay_fixture_with_readers(name, filters, &MmdbReaders::open(&ConfigMmdb::default()))
+    }
+
+    /// Replays one of the pcap fixtures like `replay_fixture`, applying the given filters
+    /// and looking up the connections in the given databases.
+    fn replay_fixture_with_readers(
+        name: &str,
+        filters: &Filters,
+        mmdb_readers: &MmdbReaders,
+    ) -> InfoTraffic {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("pcaps")
+            .join(name);
+        let device = Device {
+            addresses: vec![Address {
+                addr: "192.168.1.10".parse().unwrap(),
+                netmask: None,
+                broadcast_addr: None,
+                dst_addr: None,
+            }],
+            ..Device::from("offline")
+        };
+        let info_traffic_mutex = Arc::new(Mutex::new(InfoTraffic::new()));
+        parse_packets_loop(
+            &Arc::new(AtomicBool::new(false)),
+            &Arc::new(AtomicBool::new(false)),
+            device,
+            Capture::from_file(&path).unwrap(),
+            filters,
+            mmdb_readers,
+            &info_traffic_mutex,
+            None,
+        );
+        Arc::try_unwrap(info_traffic_mutex)
+            .ok()
+            .unwrap()
+            .into_inner()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fixture_http_flow() {
+        let info_traffic = replay_fixture("http.pcap");
+        // handshake, request, response and teardown of a single TCP connection
+        assert_eq!(info_traffic.all_packets, 10);
+        assert_eq!(info_traffic.all_bytes, 8 * 20 + 57 + 63);
+        assert_eq!(info_traffic.tot_sent_packets, 6);
+        assert_eq!(info_traffic.tot_sent_bytes, 5 * 20 + 57);
+        assert_eq!(info_traffic.tot_received_packets, 4);
+        assert_eq!(info_traffic.tot_received_bytes, 3 * 20 + 63);
+        assert_eq!(info_traffic.map.len(), 2);
+        assert_eq!(info_traffic.app_protocols[&AppProtocol::HTTP].packets, 10);
+        let (key, info) = info_traffic.map.get_index(0).unwrap();
+        assert_eq!(
+            (
+                key.address1.as_str(),
+                key.port1,
+                key.address2.as_str(),
+                key.port2
+            ),
+            ("192.168.1.10", 50000, "93.184.216.34", 80)
+        );
+        assert_eq!(key.trans_protocol, TransProtocol::TCP);
+        assert_eq!(info.traffic_type, TrafficType::Outgoing);
+        assert_eq!(info.app_protocol, AppProtocol::HTTP);
+        assert_eq!(info.tcp_state, Some(TcpState::Closed));
+        let (key, info) = info_traffic.map.get_index(1).unwrap();
+        assert_eq!((key.port1, key.port2), (80, 50000));
+        assert_eq!(info.traffic_type, TrafficType::Incoming);
+        assert_eq!(info.transmitted_packets, 4);
+        assert_eq!(info.tcp_state, Some(TcpState::Closed));
+    }
+
+    #[test]
+    fn test_fixture_frame_accounting() {
+        let frame_filters = Filters {
+            byte_accounting: ByteAccounting::Frame,
+            ..Filters::default()
+        };
+        let payload = replay_fixture("dns.pcap");
+        let frame = replay_fixture_with_filters("dns.pcap", &frame_filters);
+        // Ethernet and IPv4 headers are accounted as well
+        assert_eq!(frame.all_bytes, payload.all_bytes + 2 * (14 + 20));
+        assert_eq!(frame.tot_sent_bytes, 38 + 14 + 20);
+        assert_eq!(frame.tot_received_bytes, 54 + 14 + 20);
+
+        for fixture in ["http.pcap", "dns.pcap", "icmp.pcap"] {
+            let payload = replay_fixture(fixture);
+            let frame = replay_fixture_with_filters(fixture, &frame_filters);
+            assert_eq!(frame.all_packets, payload.all_packets);
+            assert!(frame.all_bytes > payload.all_bytes);
+            for ((_, frame_info), (_, payload_info)) in frame.map.iter().zip(&payload.map) {
+                assert!(frame_info.transmitted_bytes >= payload_info.transmitted_bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixture_notable_ports() {
+        // no connection is notable by default
+        let info_traffic = replay_fixture("http.pcap");
+        assert!(info_traffic.map.values().all(|info| !info.is_notable));
+
+        let filters = Filters {
+            notable_ports: vec![22, 80],
+            ..Filters::default()
+        };
+        let info_traffic = replay_fixture_with_filters("http.pcap", &filters);
+        // both the directions of the connection to the HTTP server are notable, but not favorites
+        assert_eq!(info_traffic.map.len(), 2);
+        for info in info_traffic.map.values() {
+            assert!(info.is_notable);
+            assert!(!info.is_favorite);
+        }
+        let info_traffic = replay_fixture_with_filters("dns.pcap", &filters);
+        assert!(info_traffic.map.values().all(|info| !info.is_notable));
+    }
+
+    #[test]
+    fn test_fixture_dns_exchange() {
+        let info_traffic = replay_fixture("dns.pcap");
+        assert_eq!(info_traffic.all_packets, 2);
+        assert_eq!(info_traffic.all_bytes, 38 + 54);
+        assert_eq!(info_traffic.tot_sent_bytes, 38);
+        assert_eq!(info_traffic.tot_received_bytes, 54);
+        assert_eq!(info_traffic.map.len(), 2);
+        assert_eq!(info_traffic.app_protocols[&AppProtocol::DNS].packets, 2);
+        for (key, info) in &info_traffic.map {
+            assert_eq!(key.trans_protocol, TransProtocol::UDP);
+            assert_eq!(info.app_protocol, AppProtocol::DNS);
+            assert_eq!(info.dns_query.as_deref(), Some("sniffnet.net"));
+            assert_eq!(info.country, "US");
+        }
+    }
+
+    #[test]
+    fn test_fixture_corrupt_databases() {
+        let path = std::env::temp_dir().join("sniffnet_test_corrupt.mmdb");
+        fs::write(&path, b"not a database").unwrap();
+        let config_mmdb = ConfigMmdb {
+            country_path: Some(path.clone()),
+            asn_path: Some(path.clone()),
+            city_path: Some(path.clone()),
+        };
+        let info_traffic = replay_fixture_with_readers(
+            "dns.pcap",
+            &Filters::default(),
+            &MmdbReaders::open(&config_mmdb),
+        );
+        fs::remove_file(&path).unwrap();
+        // the packets are processed anyway, with the country from the embedded database
+        assert_eq!(info_traffic.all_packets, 2);
+        assert_eq!(info_traffic.map.len(), 2);
+        for info in info_traffic.map.values() {
+            assert_eq!(info.country, "US");
+            assert_eq!(info.location, None);
+        }
+    }
+
+    #[test]
+    fn test_fixture_corrupt_embedded_database() {
+        let mmdb_readers = MmdbReaders {
+            country: open_embedded_country_db(b"not a database"),
+            asn: None,
+            city: None,
+        };
+        assert!(mmdb_readers.country.is_none());
+        let info_traffic =
+            replay_fixture_with_readers("dns.pcap", &Filters::default(), &mmdb_readers);
+        // the packets are processed anyway, without their country
+        assert_eq!(info_traffic.all_packets, 2);
+        assert_eq!(info_traffic.all_bytes, 38 + 54);
+        assert_eq!(info_traffic.map.len(), 2);
+        for info in info_traffic.map.values() {
+            assert_eq!(info.app_protocol, AppProtocol::DNS);
+            assert_eq!(info.dns_query.as_deref(), Some("sniffnet.net"));
+            assert_eq!(info.country, "");
+        }
+    }
+
+    #[test]
+    fn test_fixture_icmp_ping() {
+        let info_traffic = replay_fixture("icmp.pcap");
+        // two echo requests, each followed by its reply
+        assert_eq!(info_traffic.all_packets, 4);
+        assert_eq!(info_traffic.all_bytes, 4 * 40);
+        assert_eq!(info_traffic.tot_sent_packets, 2);
+        assert_eq!(info_traffic.tot_received_packets, 2);
+        assert_eq!(info_traffic.map.len(), 2);
+        assert_eq!(info_traffic.app_protocols[&AppProtocol::Other].packets, 4);
+        let (key, info) = info_traffic.map.get_index(0).unwrap();
+        assert_eq!(key.trans_protocol, TransProtocol::ICMP);
+        assert_eq!((key.port1, key.port2), (0, 0));
+        assert_eq!(info.traffic_type, TrafficType::Outgoing);
+        assert_eq!(info.icmp_type_code, Some((8, 0)));
+        let (_, info) = info_traffic.map.get_index(1).unwrap();
+        assert_eq!(info.traffic_type, TrafficType::Incoming);
+        assert_eq!(info.icmp_type_code, Some((0, 0)));
+        assert_eq!(info.tcp_state, None);
+    }
 }
@@ -0,0 +1,123 @@
+//! Module containing functions executed by the thread in charge of resolving the hostnames
+//! of the remote addresses via reverse DNS lookups.
+
+use std::net::IpAddr;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::hostname_cache::HostnameCache;
+use crate::utility::logging::lock_or_recover;
+use crate::InfoTraffic;
+
+/// Maximum number of addresses whose hostname is kept in memory
+const HOSTNAME_CACHE_CAPACITY: usize = 4096;
+
+/// The calling thread enters in a loop in which it waits for the remote addresses of new connections,
+/// resolves their hostname and updates the corresponding entries of the shared map.
+///
+/// The loop ends when the thread parsing packets terminates.
+pub fn resolve_hostnames_loop(
+    receiver: &Receiver<(AddressPortPair, IpAddr)>,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+) {
+    #[cfg(not(unix))]
+    tracing::warn!(
+        "reverse DNS lookups are not supported on this platform, hostnames won't be resolved"
+    );
+    let mut cache = HostnameCache::new(HOSTNAME_CACHE_CAPACITY);
+    for (key, address) in receiver {
+        let hostname = cache.get(&address).unwrap_or_else(|| {
+            let hostname = lookup_hostname(address);
+            cache.insert(address, hostname.clone());
+            hostname
+        });
+        if hostname.is_some() {
+            let mut info_traffic =
+                lock_or_recover(info_traffic_mutex, "storing a resolved hostname");
+            if let Some(info) = info_traffic.map.get_mut(&key) {
+                info.hostname = hostname;
+            }
+        }
+    }
+}
+
+/// Performs a reverse DNS (PTR) lookup of the given address.
+#[cfg(unix)]
+fn lookup_hostname(address: IpAddr) -> Option<String> {
+    use std::ffi::CStr;
+    use std::mem;
+
+    let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+    // SAFETY: the socket address structs are fully initialized before being passed to getnameinfo
+    // (the fields not set explicitly are zeroed), together with their actual size,
+    // and the host buffer is large enough for any hostname
+    let result = unsafe {
+        match address {
+            IpAddr::V4(ipv4) => {
+                let mut socket_address: libc::sockaddr_in = mem::zeroed();
+                socket_address.sin_family = libc::AF_INET as libc::sa_family_t;
+                socket_address.sin_addr.s_addr = u32::from_ne_bytes(ipv4.octets());
+                // BSD derived systems also expect the length of the struct in its first field
+                #[cfg(any(
+                    target_os = "macos",
+                    target_os = "ios",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd",
+                    target_os = "dragonfly"
+                ))]
+                {
+                    socket_address.sin_len = mem::size_of::<libc::sockaddr_in>() as u8;
+                }
+                libc::getnameinfo(
+                    (&socket_address as *const libc::sockaddr_in).cast(),
+                    mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+            IpAddr::V6(ipv6) => {
+                let mut socket_address: libc::sockaddr_in6 = mem::zeroed();
+                socket_address.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                socket_address.sin6_addr.s6_addr = ipv6.octets();
+                #[cfg(any(
+                    target_os = "macos",
+                    target_os = "ios",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd",
+                    target_os = "dragonfly"
+                ))]
+                {
+                    socket_address.sin6_len = mem::size_of::<libc::sockaddr_in6>() as u8;
+                }
+                libc::getnameinfo(
+                    (&socket_address as *const libc::sockaddr_in6).cast(),
+                    mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+        }
+    };
+    if result != 0 {
+        return None;
+    }
+    // SAFETY: on success getnameinfo writes a null terminated string in the host buffer
+    let hostname = unsafe { CStr::from_ptr(host.as_ptr()) };
+    hostname.to_str().ok().map(ToString::to_string)
+}
+
+/// Reverse DNS lookups are only supported on Unix systems
+/// (a warning is logged when the resolving thread starts on the other ones).
+#[cfg(not(unix))]
+fn lookup_hostname(_address: IpAddr) -> Option<String> {
+    None
+}
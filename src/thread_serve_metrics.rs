@@ -0,0 +1,304 @@
+//! Module containing functions executed by the thread in charge of exposing the traffic statistics
+//! as Prometheus metrics, served over HTTP at `/metrics` (available with the `metrics` feature).
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::warn;
+
+use crate::utility::logging::lock_or_recover;
+use crate::InfoTraffic;
+
+/// Interval between two consecutive checks of the stop flag (while waiting for incoming requests)
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Content type of the Prometheus text exposition format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The calling thread serves the metrics on the given address (e.g. `127.0.0.1:9100`)
+/// until the stop flag is set, i.e. until the capture is stopped.
+pub fn serve_metrics_loop(
+    stop_flag: &Arc<AtomicBool>,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    address: &str,
+) {
+    let server = match Server::http(address) {
+        Ok(server) => server,
+        Err(err) => {
+            warn!(%address, error = %err, "cannot serve metrics");
+            return;
+        }
+    };
+    accept_loop(stop_flag, info_traffic_mutex, &server);
+}
+
+/// Answers the requests received by the server until the stop flag is set.
+///
+/// The requests are read by the server on a thread per client,
+/// so that a slow client doesn't delay the requests of the others.
+fn accept_loop(
+    stop_flag: &Arc<AtomicBool>,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    server: &Server,
+) {
+    while !stop_flag.load(Ordering::Relaxed) {
+        match server.recv_timeout(STOP_POLL_INTERVAL) {
+            Ok(Some(request)) => {
+                if let Err(err) = answer_request(request, info_traffic_mutex) {
+                    warn!(error = %err, "cannot answer metrics request");
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                warn!(error = %err, "cannot receive metrics request");
+                std::thread::sleep(STOP_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Writes the response to a request, closing the connection.
+fn answer_request(
+    request: Request,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+) -> std::io::Result<()> {
+    let (status_code, content_type, body) =
+        get_response(request.method(), request.url(), info_traffic_mutex);
+    let content_type =
+        Header::from_bytes("Content-Type", content_type).expect("Invalid metrics content type\n\r");
+    request.respond(
+        Response::from_string(body)
+            .with_status_code(status_code)
+            .with_header(content_type),
+    )
+}
+
+/// Computes the status code, content type and body of the response to the given request (e.g. `GET /metrics`).
+fn get_response(
+    method: &Method,
+    url: &str,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+) -> (u16, &'static str, String) {
+    match (method, url) {
+        (Method::Get, "/metrics") => {
+            let info_traffic = lock_or_recover(info_traffic_mutex, "serving metrics");
+            let metrics = get_metrics(&info_traffic);
+            drop(info_traffic);
+            (200, METRICS_CONTENT_TYPE, metrics)
+        }
+        (Method::Get, _) => (404, "text/plain", "Not Found\n".to_string()),
+        _ => (405, "text/plain", "Method Not Allowed\n".to_string()),
+    }
+}
+
+/// Returns the traffic statistics in the Prometheus text exposition format.
+fn get_metrics(info_traffic: &InfoTraffic) -> String {
+    let mut metrics = String::new();
+    let mut push_metric = |name: &str, kind: &str, help: &str, samples: &[(String, u128)]| {
+        let _ = writeln!(metrics, "# HELP {name} {help}");
+        let _ = writeln!(metrics, "# TYPE {name} {kind}");
+        for (labels, value) in samples {
+            let _ = writeln!(metrics, "{name}{labels} {value}");
+        }
+    };
+    push_metric(
+        "sniffnet_bytes",
+        "gauge",
+        "Filtered bytes exchanged in the current capture.",
+        &[
+            (
+                "{direction=\"sent\"}".to_string(),
+                info_traffic.tot_sent_bytes,
+            ),
+            (
+                "{direction=\"received\"}".to_string(),
+                info_traffic.tot_received_bytes,
+            ),
+        ],
+    );
+    push_metric(
+        "sniffnet_packets",
+        "gauge",
+        "Filtered packets exchanged in the current capture.",
+        &[
+            (
+                "{direction=\"sent\"}".to_string(),
+                info_traffic.tot_sent_packets,
+            ),
+            (
+                "{direction=\"received\"}".to_string(),
+                info_traffic.tot_received_packets,
+            ),
+        ],
+    );
+    push_metric(
+        "sniffnet_all_bytes",
+        "gauge",
+        "Bytes sniffed in the current capture, including those not filtered.",
+        &[(String::new(), info_traffic.all_bytes)],
+    );
+    push_metric(
+        "sniffnet_all_packets",
+        "gauge",
+        "Packets sniffed in the current capture, including those not filtered.",
+        &[(String::new(), info_traffic.all_packets)],
+    );
+    // sorted by protocol, so that consecutive scrapes list the samples in the same order
+    let mut app_protocols: Vec<(String, u128, u128)> = info_traffic
+        .app_protocols
+        .iter()
+        .map(|(app_protocol, summary)| (app_protocol.to_string(), summary.bytes, summary.packets))
+        .collect();
+    app_protocols.sort();
+    let label = |app_protocol: &str| format!("{{protocol=\"{app_protocol}\"}}");
+    push_metric(
+        "sniffnet_app_protocol_bytes_total",
+        "counter",
+        "Filtered bytes exchanged by each application protocol.",
+        &app_protocols
+            .iter()
+            .map(|(app_protocol, bytes, _)| (label(app_protocol), *bytes))
+            .collect::<Vec<_>>(),
+    );
+    push_metric(
+        "sniffnet_app_protocol_packets_total",
+        "counter",
+        "Filtered packets exchanged by each application protocol.",
+        &app_protocols
+            .iter()
+            .map(|(app_protocol, _, packets)| (label(app_protocol), *packets))
+            .collect::<Vec<_>>(),
+    );
+    push_metric(
+        "sniffnet_connections",
+        "gauge",
+        "Connections currently kept in memory.",
+        &[(String::new(), info_traffic.map.len() as u128)],
+    );
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use tiny_http::{Method, Server};
+
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::thread_serve_metrics::{accept_loop, get_metrics, get_response};
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    fn info_traffic() -> InfoTraffic {
+        let key = |port: u16| {
+            AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                port,
+                "1.1.1.1".to_string(),
+                443,
+                TransProtocol::TCP,
+            )
+        };
+        let mut info_traffic = InfoTraffic::with_connections([
+            (
+                key(50000),
+                InfoAddressPortPair::with_traffic(
+                    TrafficType::Outgoing,
+                    AppProtocol::HTTPS,
+                    500,
+                    2,
+                ),
+            ),
+            (
+                key(50001),
+                InfoAddressPortPair::with_traffic(TrafficType::Incoming, AppProtocol::DNS, 100, 1),
+            ),
+        ]);
+        info_traffic.all_bytes = 700;
+        info_traffic.all_packets = 4;
+        info_traffic
+    }
+
+    #[test]
+    fn test_metrics() {
+        let metrics = get_metrics(&info_traffic());
+        for line in [
+            "# TYPE sniffnet_bytes gauge",
+            "sniffnet_all_bytes 700",
+            "sniffnet_all_packets 4",
+            "# TYPE sniffnet_app_protocol_bytes_total counter",
+            "sniffnet_app_protocol_bytes_total{protocol=\"DNS\"} 100",
+            "sniffnet_app_protocol_bytes_total{protocol=\"HTTPS\"} 500",
+            "sniffnet_app_protocol_packets_total{protocol=\"HTTPS\"} 2",
+            "sniffnet_connections 2",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing {line}");
+        }
+        // every sample is preceded by the description of its metric
+        assert!(metrics
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .all(|line| line.starts_with("sniffnet_")));
+    }
+
+    #[test]
+    fn test_response() {
+        let info_traffic = Arc::new(Mutex::new(info_traffic()));
+        let (status_code, content_type, body) =
+            get_response(&Method::Get, "/metrics", &info_traffic);
+        assert_eq!(status_code, 200);
+        assert!(content_type.starts_with("text/plain; version=0.0.4"));
+        assert!(body.ends_with("sniffnet_connections 2\n"));
+        assert_eq!(get_response(&Method::Get, "/", &info_traffic).0, 404);
+        assert_eq!(
+            get_response(&Method::Post, "/metrics", &info_traffic).0,
+            405
+        );
+    }
+
+    /// Sends a scrape request to the address, returning the response.
+    fn scrape(address: SocketAddr) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_metrics_served_until_stopped() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let info_traffic = Arc::new(Mutex::new(info_traffic()));
+        let server = {
+            let stop_flag = stop_flag.clone();
+            thread::spawn(move || accept_loop(&stop_flag, &info_traffic, &server))
+        };
+
+        // a client that never completes its request doesn't block the others
+        let mut slow_client = TcpStream::connect(address).unwrap();
+        slow_client.write_all(b"GET /metrics HTTP/1.1\r\n").unwrap();
+        for _ in 0..2 {
+            let response = scrape(address);
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(
+                response.contains("sniffnet_app_protocol_bytes_total{protocol=\"HTTPS\"} 500\n")
+            );
+        }
+
+        stop_flag.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+        drop(slow_client);
+    }
+}
@@ -9,8 +9,12 @@ use std::time::Duration;
 
 use crate::enums::status::Status;
 use crate::utility::get_formatted_strings::get_report_path;
+use crate::utility::logging::{lock_or_recover, unwrap_or_log_poisoned};
 use crate::InfoTraffic;
 
+/// Width of the address columns of the report file, matching its header
+const ADDRESS_COLUMN_WIDTH: usize = 25;
+
 /// The calling thread enters in a loop in which it sleeps for 1 second and then
 /// updates the output report containing detailed traffic information
 pub fn sleep_and_write_report_loop(
@@ -22,7 +26,9 @@ pub fn sleep_and_write_report_loop(
 
     let path_report = get_report_path();
 
-    let mut capture_id = *current_capture_id.lock().unwrap();
+    let mut capture_id = *lock_or_recover(current_capture_id, "reading capture id");
+    // number of connections in the report, to truncate it when stale connections are evicted
+    let mut report_len = 0;
 
     let mut output =
         BufWriter::new(File::create(path_report.clone()).expect("Error creating output file\n\r"));
@@ -34,9 +40,10 @@ pub fn sleep_and_write_report_loop(
         // sleep 1 second
         thread::sleep(Duration::from_secs(1));
 
-        let current_capture_id_lock = current_capture_id.lock().unwrap();
+        let current_capture_id_lock = lock_or_recover(current_capture_id, "reading capture id");
         if *current_capture_id_lock != capture_id {
             capture_id = *current_capture_id_lock;
+            report_len = 0;
             output = BufWriter::new(
                 File::create(path_report.clone()).expect("Error creating output file\n\r"),
             );
@@ -46,31 +53,46 @@ pub fn sleep_and_write_report_loop(
         }
         drop(current_capture_id_lock);
 
-        let mut status = status_pair.0.lock().expect("Error acquiring mutex\n\r");
+        let mut status = lock_or_recover(&status_pair.0, "reading capture status");
 
         if *status == Status::Running {
             drop(status);
 
-            let mut info_traffic = info_traffic_mutex
-                .lock()
-                .expect("Error acquiring mutex\n\r");
+            let mut info_traffic =
+                lock_or_recover(info_traffic_mutex, "writing connections to the report");
 
-            for index in &info_traffic.addresses_last_interval {
-                let key_val = info_traffic.map.get_index(*index).unwrap();
-                let seek_pos = 166 * 3 + 206 * (*index) as u64;
+            for id in &info_traffic.addresses_last_interval {
+                let Some(index) = info_traffic.get_index_of_connection(*id) else {
+                    continue;
+                };
+                let key_val = info_traffic.map.get_index(index).unwrap();
+                let seek_pos = 166 * 3 + 206 * index as u64;
                 output.seek(SeekFrom::Start(seek_pos)).unwrap();
-                writeln!(output, "{}{}", key_val.0, key_val.1)
-                    .expect("Error writing output file\n\r");
+                writeln!(
+                    output,
+                    "{}",
+                    key_val.1.print_report(key_val.0, ADDRESS_COLUMN_WIDTH)
+                )
+                .expect("Error writing output file\n\r");
             }
             info_traffic.addresses_last_interval = HashSet::new(); // empty set
 
             output.flush().expect("Error writing output file\n\r");
+            let len = info_traffic.map.len();
+            if len < report_len {
+                output
+                    .get_ref()
+                    .set_len(166 * 3 + 206 * len as u64)
+                    .expect("Error writing output file\n\r");
+            }
+            report_len = len;
 
             drop(info_traffic);
         } else {
             //status is Init
             while *status == Status::Init {
-                status = cvar.wait(status).expect("Error acquiring mutex\n\r");
+                status =
+                    unwrap_or_log_poisoned(cvar.wait(status), "waiting for a capture to start");
             }
         }
     }
@@ -0,0 +1,282 @@
+//! Module containing functions executed by the thread in charge of logging the connections
+//! to a SQLite database (e.g. to be queried with `sqlite3 traffic.db`).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use rusqlite::{params, Connection};
+use tracing::warn;
+
+use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::info_address_port_pair::InfoAddressPortPair;
+use crate::utility::logging::lock_or_recover;
+use crate::InfoTraffic;
+
+/// Interval between two consecutive transactions committed to the database
+const SQL_WRITE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between two consecutive checks of the stop flag
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Statement creating the table in which the connections are logged
+const SQL_CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS connections (\
+timestamp TEXT NOT NULL, address1 TEXT NOT NULL, port1 INTEGER NOT NULL, \
+address2 TEXT NOT NULL, port2 INTEGER NOT NULL, trans_protocol TEXT NOT NULL, \
+app_protocol TEXT NOT NULL, transmitted_bytes INTEGER NOT NULL, transmitted_packets INTEGER NOT NULL, \
+initial_timestamp TEXT NOT NULL, final_timestamp TEXT NOT NULL, traffic_type TEXT NOT NULL, \
+country TEXT NOT NULL, asn_number INTEGER NOT NULL, asn_org TEXT NOT NULL, hostname TEXT, \
+dns_query TEXT, tls_sni TEXT, src_mac TEXT, dst_mac TEXT, interface TEXT NOT NULL);";
+
+/// Statement inserting a snapshot of a connection, with the columns in the order of `SQL_CREATE_TABLE`
+const SQL_INSERT: &str = "INSERT INTO connections VALUES \
+(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21);";
+
+/// The calling thread enters in a loop in which, at every interval, it commits to the database a transaction
+/// with a row for each connection that exchanged traffic since the previous one.
+///
+/// Each row is a snapshot of the connection, labeled with the time it has been taken.
+/// The loop ends (after committing a last transaction) when the stop flag is set.
+pub fn write_sql_loop(
+    stop_flag: &Arc<AtomicBool>,
+    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    path: &Path,
+) {
+    let mut database = match open_database(path) {
+        Ok(database) => database,
+        Err(err) => {
+            warn!(path = %path.display(), %err, "cannot log connections");
+            return;
+        }
+    };
+    // packets count of each connection (by its identifier) when last logged
+    let mut logged_packets: HashMap<u64, u128> = HashMap::new();
+
+    loop {
+        let stop = wait_interval(stop_flag);
+
+        let now = Local::now();
+        let info_traffic = lock_or_recover(info_traffic_mutex, "reading connections to be logged");
+        let mut connections = Vec::new();
+        // connections evicted from the map are forgotten
+        logged_packets = info_traffic
+            .map
+            .iter()
+            .map(|(key, info)| {
+                if logged_packets.get(&info.id) != Some(&info.transmitted_packets) {
+                    connections.push((key.clone(), info.clone()));
+                }
+                (info.id, info.transmitted_packets)
+            })
+            .collect();
+        drop(info_traffic);
+
+        if let Err(err) = insert_connections(&mut database, now, &connections) {
+            warn!(path = %path.display(), %err, "cannot log connections");
+            return;
+        }
+
+        if stop {
+            return;
+        }
+    }
+}
+
+/// Opens the database, creating it and its connections table if they don't exist yet.
+fn open_database(path: &Path) -> rusqlite::Result<Connection> {
+    let database = Connection::open(path)?;
+    database.execute_batch(SQL_CREATE_TABLE)?;
+    Ok(database)
+}
+
+/// Sleeps for an interval, returning early (with `true`) as soon as the stop flag is set.
+fn wait_interval(stop_flag: &Arc<AtomicBool>) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < SQL_WRITE_INTERVAL {
+        if stop_flag.load(Ordering::Relaxed) {
+            return true;
+        }
+        thread::sleep(STOP_POLL_INTERVAL);
+    }
+    stop_flag.load(Ordering::Relaxed)
+}
+
+/// Inserts the snapshots of the connections taken at the given time, in a single transaction.
+fn insert_connections(
+    database: &mut Connection,
+    timestamp: DateTime<Local>,
+    connections: &[(AddressPortPair, InfoAddressPortPair)],
+) -> rusqlite::Result<()> {
+    if connections.is_empty() {
+        return Ok(());
+    }
+    let iso = |timestamp: &DateTime<Local>| timestamp.to_rfc3339_opts(SecondsFormat::Millis, false);
+    let timestamp = iso(&timestamp);
+    let transaction = database.transaction()?;
+    {
+        let mut statement = transaction.prepare_cached(SQL_INSERT)?;
+        for (key, info) in connections {
+            statement.execute(params![
+                timestamp,
+                key.address1,
+                key.port1,
+                key.address2,
+                key.port2,
+                key.trans_protocol.to_string(),
+                info.app_protocol.to_string(),
+                to_sql_integer(info.transmitted_bytes),
+                to_sql_integer(info.transmitted_packets),
+                iso(&info.initial_timestamp),
+                iso(&info.final_timestamp),
+                info.traffic_type.to_string(),
+                info.country,
+                info.asn.number,
+                info.asn.org,
+                info.hostname,
+                info.dns_query,
+                info.tls_sni,
+                info.src_mac.map(|mac| mac.to_string()),
+                info.dst_mac.map(|mac| mac.to_string()),
+                info.interface,
+            ])?;
+        }
+    }
+    transaction.commit()
+}
+
+/// Converts a counter to a SQLite integer, saturating at its maximum value.
+fn to_sql_integer(value: u128) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    use chrono::{Local, TimeZone};
+    use rusqlite::types::Value;
+    use rusqlite::Connection;
+
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::asn::Asn;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::thread_write_sql::{insert_connections, write_sql_loop, SQL_CREATE_TABLE};
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    fn connection() -> (AddressPortPair, InfoAddressPortPair) {
+        let timestamp = Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap();
+        (
+            AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                50000,
+                "8.8.8.8".to_string(),
+                53,
+                TransProtocol::UDP,
+            ),
+            InfoAddressPortPair {
+                transmitted_bytes: 1234,
+                transmitted_packets: 5,
+                initial_timestamp: timestamp,
+                final_timestamp: timestamp,
+                app_protocol: AppProtocol::DNS,
+                traffic_type: TrafficType::Outgoing,
+                country: "US".to_string(),
+                asn: Asn {
+                    number: 15169,
+                    org: "Google's LLC".to_string(),
+                },
+                dns_query: Some("example.com".to_string()),
+                interface: "eth0".to_string(),
+                ..InfoAddressPortPair::default()
+            },
+        )
+    }
+
+    /// Reads the logged rows (timestamp excluded), in insertion order.
+    fn logged_rows(database: &Connection) -> Vec<Vec<Value>> {
+        let mut statement = database
+            .prepare("SELECT * FROM connections ORDER BY rowid")
+            .unwrap();
+        let rows = statement
+            .query_map([], |row| (1..21).map(|i| row.get(i)).collect())
+            .unwrap();
+        rows.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_insert_connections() {
+        let (key, info) = connection();
+        let mut database = Connection::open_in_memory().unwrap();
+        database.execute_batch(SQL_CREATE_TABLE).unwrap();
+        insert_connections(&mut database, Local::now(), &[]).unwrap();
+        assert!(logged_rows(&database).is_empty());
+
+        let timestamp = Local.with_ymd_and_hms(2023, 1, 31, 10, 31, 0).unwrap();
+        insert_connections(&mut database, timestamp, &[(key.clone(), info.clone())]).unwrap();
+        let iso = |timestamp: chrono::DateTime<Local>| {
+            Value::Text(timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, false))
+        };
+        let text = |value: &str| Value::Text(value.to_string());
+        assert_eq!(
+            logged_rows(&database),
+            vec![vec![
+                text("192.168.1.10"),
+                Value::Integer(50000),
+                text("8.8.8.8"),
+                Value::Integer(53),
+                text("UDP"),
+                text("DNS"),
+                Value::Integer(1234),
+                Value::Integer(5),
+                iso(info.initial_timestamp),
+                iso(info.final_timestamp),
+                text("Outgoing"),
+                text("US"),
+                Value::Integer(15169),
+                text("Google's LLC"),
+                Value::Null,
+                text("example.com"),
+                Value::Null,
+                Value::Null,
+                Value::Null,
+                text("eth0"),
+            ]]
+        );
+        let logged_at: String = database
+            .query_row("SELECT timestamp FROM connections", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(
+            logged_at,
+            timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+        );
+    }
+
+    #[test]
+    fn test_write_sql_loop_commits_on_stop() {
+        let (key, info) = connection();
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.map.insert(key, info);
+        let path = std::env::temp_dir().join("sniffnet_test_write_sql_loop.sqlite");
+        fs::remove_file(&path).unwrap_or(());
+        let info_traffic_mutex = Arc::new(Mutex::new(info_traffic));
+        // the capture is already stopped: the loop commits a single transaction and returns
+        write_sql_loop(&Arc::new(AtomicBool::new(true)), &info_traffic_mutex, &path);
+        // the database is reopened by the next captures, appending their rows
+        write_sql_loop(&Arc::new(AtomicBool::new(true)), &info_traffic_mutex, &path);
+        let database = Connection::open(&path).unwrap();
+        let rows = logged_rows(&database);
+        drop(database);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], rows[1]);
+        assert_eq!(rows[0][0], Value::Text("192.168.1.10".to_string()));
+    }
+}
@@ -0,0 +1,163 @@
+//! Module containing functions to anonymize the exported connections, so that they can be shared
+//! without disclosing the hosts that exchanged the traffic.
+
+use std::borrow::Cow;
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::info_address_port_pair::InfoAddressPortPair;
+
+/// Prefix of the tokens replacing the anonymized addresses
+const ANONYMIZED_ADDRESS_PREFIX: &str = "anon-";
+
+/// Returns a random salt, so that the tokens of the anonymized addresses can't be computed in advance
+/// for the known addresses (e.g. the private ranges).
+pub fn get_anonymization_salt() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Replaces an address with a token derived from its salted hash: the same address is always
+/// replaced by the same token when anonymized with the same salt, so that the hosts can still be told apart.
+pub fn anonymize_address(address: &str, salt: u64) -> String {
+    let mut hasher = DefaultHasher::new();
+    salt.hash(&mut hasher);
+    address.hash(&mut hasher);
+    format!(
+        "{ANONYMIZED_ADDRESS_PREFIX}{:012x}",
+        hasher.finish() & 0xffff_ffff_ffff
+    )
+}
+
+/// Returns the connection to be exported, anonymized if a salt is specified.
+///
+/// Besides the addresses, the details that would reveal the hosts involved (hostname, DNS query,
+/// TLS server name, MAC addresses, geographic coordinates and name of the network adapter) are dropped,
+/// while the country, the Autonomous System and the protocols are preserved.
+/// The connection in memory is never altered.
+pub fn get_exported_connection<'a>(
+    key: &'a AddressPortPair,
+    info: &'a InfoAddressPortPair,
+    anonymization_salt: Option<u64>,
+) -> (Cow<'a, AddressPortPair>, Cow<'a, InfoAddressPortPair>) {
+    let Some(salt) = anonymization_salt else {
+        return (Cow::Borrowed(key), Cow::Borrowed(info));
+    };
+    let key = AddressPortPair {
+        address1: anonymize_address(&key.address1, salt),
+        address2: anonymize_address(&key.address2, salt),
+        ..key.clone()
+    };
+    let info = InfoAddressPortPair {
+        hostname: None,
+        dns_query: None,
+        tls_sni: None,
+        src_mac: None,
+        dst_mac: None,
+        location: None,
+        interface: String::new(),
+        ..info.clone()
+    };
+    (Cow::Owned(key), Cow::Owned(info))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::structs::mac_address::MacAddress;
+    use crate::utility::anonymize::{anonymize_address, get_exported_connection};
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    #[test]
+    fn test_anonymize_address() {
+        let token = anonymize_address("192.168.1.10", 42);
+        assert!(token.starts_with("anon-"));
+        assert_eq!(token.len(), "anon-".len() + 12);
+        assert!(!token.contains("192.168"));
+        // consistent for the same salt
+        assert_eq!(anonymize_address("192.168.1.10", 42), token);
+        assert_ne!(anonymize_address("192.168.1.11", 42), token);
+        assert_ne!(anonymize_address("192.168.1.10", 43), token);
+    }
+
+    #[test]
+    fn test_exported_connection() {
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "8.8.8.8".to_string(),
+            53,
+            TransProtocol::UDP,
+        );
+        let mut info =
+            InfoAddressPortPair::with_traffic(TrafficType::Outgoing, AppProtocol::DNS, 80, 1);
+        info.country = "US".to_string();
+        info.hostname = Some("dns.google".to_string());
+        info.dns_query = Some("dns.google".to_string());
+        info.tls_sni = Some("dns.google".to_string());
+        info.src_mac = Some(MacAddress([0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6]));
+        info.location = Some((37.75, -97.5));
+
+        let (exported_key, exported_info) = get_exported_connection(&key, &info, None);
+        assert!(matches!(exported_key, Cow::Borrowed(_)));
+        assert!(matches!(exported_info, Cow::Borrowed(_)));
+
+        let (exported_key, exported_info) = get_exported_connection(&key, &info, Some(7));
+        assert_eq!(exported_key.address1, anonymize_address("192.168.1.10", 7));
+        assert_eq!(exported_key.address2, anonymize_address("8.8.8.8", 7));
+        assert_eq!((exported_key.port1, exported_key.port2), (50000, 53));
+        assert_eq!(exported_key.trans_protocol, TransProtocol::UDP);
+        assert_eq!(exported_info.country, "US");
+        assert_eq!(exported_info.app_protocol, AppProtocol::DNS);
+        assert_eq!(exported_info.hostname, None);
+        assert_eq!(exported_info.dns_query, None);
+        assert_eq!(exported_info.tls_sni, None);
+        assert!(exported_info.src_mac.is_none());
+        assert_eq!(exported_info.location, None);
+        assert_eq!(exported_info.interface, "");
+        // the original connection is untouched
+        assert_eq!(key.address1, "192.168.1.10");
+        assert_eq!(info.hostname.as_deref(), Some("dns.google"));
+    }
+
+    #[test]
+    fn test_anonymized_json_export() {
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "104.21.1.1".to_string(),
+            443,
+            TransProtocol::TCP,
+        );
+        let mut info =
+            InfoAddressPortPair::with_traffic(TrafficType::Outgoing, AppProtocol::HTTPS, 500, 2);
+        info.hostname = Some("host.example.com".to_string());
+        info.dns_query = Some("query.example.com".to_string());
+        info.tls_sni = Some("sni.example.com".to_string());
+        info.src_mac = Some(MacAddress([0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6]));
+        info.interface = "wlp3s0".to_string();
+        let mut info_traffic = InfoTraffic::with_connections([(key, info)]);
+        info_traffic.device_name = "wlp3s0".to_string();
+
+        let json = info_traffic.to_exported_json(None);
+        assert!(json.contains("sni.example.com") && json.contains("00:1b:63:84:45:e6"));
+        assert!(json.contains("wlp3s0"));
+        let json = info_traffic.to_exported_json(Some(42));
+        for disclosed in [
+            "192.168.1.10",
+            "104.21.1.1",
+            "host.example.com",
+            "query.example.com",
+            "sni.example.com",
+            "00:1b:63:84:45:e6",
+            "wlp3s0",
+        ] {
+            assert!(!json.contains(disclosed), "{disclosed} disclosed");
+        }
+        assert!(json.contains(&anonymize_address("104.21.1.1", 42)));
+    }
+}
@@ -1,23 +1,99 @@
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
 use crate::enums::traffic_type::TrafficType;
 use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::asn::Asn;
+use crate::structs::configs::ConfigMmdb;
 use iced::widget::{image::Handle, Image};
 use iced::Length;
 use maxminddb::{geoip2, MaxMindDBError, Reader};
 
 pub const COUNTRY_MMDB: &[u8] = include_bytes!("../../resources/DB/GeoLite2-Country.mmdb");
 
+/// Name of the ASN database file, looked up in the same folder containing the configuration files
+pub const ASN_MMDB_FILE_NAME: &str = "GeoLite2-ASN.mmdb";
+
+/// Name of the city database file, looked up in the same folder containing the configuration files
+pub const CITY_MMDB_FILE_NAME: &str = "GeoLite2-City.mmdb";
+
+/// Reader of a MMDB file, either embedded in the executable or loaded from a user supplied path
+pub type MmdbReader = Reader<Cow<'static, [u8]>>;
+
+/// Returns the remote address of a connection in the form to be looked up in the MMDB databases.
+///
+/// IPv4-mapped and IPv4-compatible IPv6 addresses are converted to IPv4,
+/// while `None` is returned for private, loopback and link-local addresses (both IPv4 and IPv6).
+fn get_address_to_lookup(traffic_type: TrafficType, key: &AddressPortPair) -> Option<IpAddr> {
+    let address = match traffic_type {
+        TrafficType::Outgoing => &key.address2,
+        // both the addresses belong to this host
+        TrafficType::Local => return None,
+        _ => &key.address1,
+    };
+    let address = match address.parse().ok()? {
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            // IPv4-compatible addresses (::a.b.c.d), excluding the unspecified and loopback ones
+            None if ipv6.segments()[..6] == [0; 6]
+                && !ipv6.is_unspecified()
+                && !ipv6.is_loopback() =>
+            {
+                IpAddr::V4(ipv6.to_ipv4()?)
+            }
+            None => IpAddr::V6(ipv6),
+        },
+        ipv4 => ipv4,
+    };
+    let is_local = match address {
+        IpAddr::V4(ipv4) => ipv4.is_private() || ipv4.is_loopback() || ipv4.is_link_local(),
+        IpAddr::V6(ipv6) => {
+            let first_segment = ipv6.segments()[0];
+            ipv6.is_loopback()
+                // unique local addresses (fc00::/7) and link-local addresses (fe80::/10)
+                || first_segment & 0xfe00 == 0xfc00
+                || first_segment & 0xffc0 == 0xfe80
+        }
+    };
+    (!is_local).then_some(address)
+}
+
+/// Readers of the MMDB databases used to look up the remote addresses of the connections
+pub struct MmdbReaders {
+    pub country: Option<MmdbReader>,
+    pub asn: Option<MmdbReader>,
+    pub city: Option<MmdbReader>,
+}
+
+impl MmdbReaders {
+    /// Opens the databases specified in the configuration (see `get_country_db_reader`,
+    /// `get_asn_db_reader` and `get_city_db_reader`).
+    pub fn open(config_mmdb: &ConfigMmdb) -> Self {
+        Self {
+            country: get_country_db_reader(config_mmdb.country_path.as_deref()),
+            asn: get_asn_db_reader(config_mmdb.asn_path.as_deref()),
+            city: get_city_db_reader(config_mmdb.city_path.as_deref()),
+        }
+    }
+}
+
+/// Returns the country code of the remote address of a connection.
+///
+/// An empty string is returned when the address is private or not found in the database.
 pub fn get_country_code(
     traffic_type: TrafficType,
     key: &AddressPortPair,
-    country_db_reader: &Reader<&[u8]>,
+    country_db_reader: &MmdbReader,
 ) -> String {
-    let address_to_lookup = match traffic_type {
-        TrafficType::Outgoing => &key.address2,
-        _ => &key.address1,
+    let Some(address_to_lookup) = get_address_to_lookup(traffic_type, key) else {
+        return String::new();
     };
 
     let country_result: Result<geoip2::Country, MaxMindDBError> =
-        country_db_reader.lookup(address_to_lookup.parse().unwrap());
+        country_db_reader.lookup(address_to_lookup);
     if let Ok(res1) = country_result {
         if let Some(res2) = res1.country {
             if let Some(res3) = res2.iso_code {
@@ -28,6 +104,418 @@ pub fn get_country_code(
     String::new()
 }
 
+/// Opens the country database at the given path, falling back to the embedded one
+/// if no path is specified or if the user supplied file is not a valid country database
+///
+/// Returns `None` (disabling the country lookup of the connections) if neither can be opened.
+pub fn get_country_db_reader(country_db_path: Option<&Path>) -> Option<MmdbReader> {
+    if let Some(path) = country_db_path {
+        match open_mmdb(path, &["Country", "City", "Enterprise"]) {
+            Ok(reader) => return Some(reader),
+            Err(err) => warn!(
+                path = %path.display(),
+                %err,
+                "cannot use the country database, using the embedded one"
+            ),
+        }
+    }
+    open_embedded_country_db(COUNTRY_MMDB)
+}
+
+/// Opens the country database embedded in the executable, given its content.
+///
+/// Returns `None`, with a warning, if the content is not a valid database (e.g. a corrupt build):
+/// the capture goes on anyway, without the country of the connections.
+pub fn open_embedded_country_db(source: &'static [u8]) -> Option<MmdbReader> {
+    match Reader::from_source(Cow::Borrowed(source)) {
+        Ok(reader) => Some(reader),
+        Err(err) => {
+            warn!(error = %err, "cannot use the embedded country database, country lookup disabled");
+            None
+        }
+    }
+}
+
+/// Opens the ASN database at the given path or, if no path is specified,
+/// the one placed by the user in the same folder containing the configuration files
+pub fn get_asn_db_reader(asn_db_path: Option<&Path>) -> Option<MmdbReader> {
+    if let Some(path) = asn_db_path {
+        return match open_mmdb(path, &["ASN", "ISP"]) {
+            Ok(reader) => Some(reader),
+            Err(err) => {
+                warn!(path = %path.display(), %err, "cannot use the ASN database, ASN lookup disabled");
+                None
+            }
+        };
+    }
+    open_mmdb(&get_default_mmdb_path(ASN_MMDB_FILE_NAME)?, &["ASN", "ISP"]).ok()
+}
+
+/// Opens the city database at the given path or, if no path is specified,
+/// the one placed by the user in the same folder containing the configuration files
+///
+/// Returns `None` (disabling the geolocation of the connections) if no city database is available.
+pub fn get_city_db_reader(city_db_path: Option<&Path>) -> Option<MmdbReader> {
+    if let Some(path) = city_db_path {
+        return match open_mmdb(path, &["City", "Enterprise"]) {
+            Ok(reader) => Some(reader),
+            Err(err) => {
+                warn!(path = %path.display(), %err, "cannot use the city database, geolocation disabled");
+                None
+            }
+        };
+    }
+    open_mmdb(
+        &get_default_mmdb_path(CITY_MMDB_FILE_NAME)?,
+        &["City", "Enterprise"],
+    )
+    .ok()
+}
+
+/// Returns the path of the MMDB file with the given name in the folder containing the configuration files
+fn get_default_mmdb_path(file_name: &str) -> Option<PathBuf> {
+    let mut default_path = confy::get_configuration_file_path("sniffnet", "file").ok()?;
+    default_path.pop();
+    default_path.push(file_name);
+    Some(default_path)
+}
+
+/// Opens the MMDB file at the given path, checking that its database type
+/// contains one of the expected schema names
+fn open_mmdb(path: &Path, expected_types: &[&str]) -> Result<MmdbReader, String> {
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    let reader = Reader::from_source(Cow::Owned(bytes)).map_err(|err| err.to_string())?;
+    let database_type = &reader.metadata.database_type;
+    if expected_types
+        .iter()
+        .any(|expected| database_type.contains(expected))
+    {
+        Ok(reader)
+    } else {
+        Err(format!("incompatible database type '{database_type}'"))
+    }
+}
+
+/// Returns the Autonomous System of the remote address of a connection.
+///
+/// An empty `Asn` is returned when the address is private or not found in the database.
+pub fn get_asn(
+    traffic_type: TrafficType,
+    key: &AddressPortPair,
+    asn_db_reader: &MmdbReader,
+) -> Asn {
+    if let Some(address) = get_address_to_lookup(traffic_type, key) {
+        let asn_result: Result<geoip2::Asn, MaxMindDBError> = asn_db_reader.lookup(address);
+        if let Ok(res) = asn_result {
+            return Asn {
+                number: res.autonomous_system_number.unwrap_or(0),
+                org: res
+                    .autonomous_system_organization
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+        }
+    }
+    Asn::default()
+}
+
+/// Returns the latitude and longitude of the remote address of a connection.
+///
+/// `None` is returned when the address is private, not found in the database or not geolocated.
+pub fn get_geo_point(
+    traffic_type: TrafficType,
+    key: &AddressPortPair,
+    city_db_reader: &MmdbReader,
+) -> Option<(f64, f64)> {
+    let address = get_address_to_lookup(traffic_type, key)?;
+    let city_result: Result<geoip2::City, MaxMindDBError> = city_db_reader.lookup(address);
+    let location = city_result.ok()?.location?;
+    Some((location.latitude?, location.longitude?))
+}
+
+/// Name displayed for the connections without a country code (e.g. local ones) or with an unknown one
+pub const UNKNOWN_COUNTRY_NAME: &str = "Local/Unknown";
+
+/// Flag displayed for the connections without a country code (e.g. local ones) or with an unknown one
+pub const UNKNOWN_COUNTRY_FLAG: &str = "\u{1F3F3}";
+
+/// ISO 3166-1 alpha-2 country codes with the respective (short) country names, sorted by code
+const COUNTRY_NAMES: &[(&str, &str)] = &[
+    ("AD", "Andorra"),
+    ("AE", "United Arab Emirates"),
+    ("AF", "Afghanistan"),
+    ("AG", "Antigua and Barbuda"),
+    ("AI", "Anguilla"),
+    ("AL", "Albania"),
+    ("AM", "Armenia"),
+    ("AO", "Angola"),
+    ("AQ", "Antarctica"),
+    ("AR", "Argentina"),
+    ("AS", "American Samoa"),
+    ("AT", "Austria"),
+    ("AU", "Australia"),
+    ("AW", "Aruba"),
+    ("AX", "Åland Islands"),
+    ("AZ", "Azerbaijan"),
+    ("BA", "Bosnia and Herzegovina"),
+    ("BB", "Barbados"),
+    ("BD", "Bangladesh"),
+    ("BE", "Belgium"),
+    ("BF", "Burkina Faso"),
+    ("BG", "Bulgaria"),
+    ("BH", "Bahrain"),
+    ("BI", "Burundi"),
+    ("BJ", "Benin"),
+    ("BL", "Saint Barthélemy"),
+    ("BM", "Bermuda"),
+    ("BN", "Brunei Darussalam"),
+    ("BO", "Bolivia"),
+    ("BQ", "Bonaire, Sint Eustatius and Saba"),
+    ("BR", "Brazil"),
+    ("BS", "Bahamas"),
+    ("BT", "Bhutan"),
+    ("BV", "Bouvet Island"),
+    ("BW", "Botswana"),
+    ("BY", "Belarus"),
+    ("BZ", "Belize"),
+    ("CA", "Canada"),
+    ("CC", "Cocos (Keeling) Islands"),
+    ("CD", "Congo, The Democratic Republic of the"),
+    ("CF", "Central African Republic"),
+    ("CG", "Congo"),
+    ("CH", "Switzerland"),
+    ("CI", "Côte d'Ivoire"),
+    ("CK", "Cook Islands"),
+    ("CL", "Chile"),
+    ("CM", "Cameroon"),
+    ("CN", "China"),
+    ("CO", "Colombia"),
+    ("CR", "Costa Rica"),
+    ("CU", "Cuba"),
+    ("CV", "Cabo Verde"),
+    ("CW", "Curaçao"),
+    ("CX", "Christmas Island"),
+    ("CY", "Cyprus"),
+    ("CZ", "Czechia"),
+    ("DE", "Germany"),
+    ("DJ", "Djibouti"),
+    ("DK", "Denmark"),
+    ("DM", "Dominica"),
+    ("DO", "Dominican Republic"),
+    ("DZ", "Algeria"),
+    ("EC", "Ecuador"),
+    ("EE", "Estonia"),
+    ("EG", "Egypt"),
+    ("EH", "Western Sahara"),
+    ("ER", "Eritrea"),
+    ("ES", "Spain"),
+    ("ET", "Ethiopia"),
+    ("FI", "Finland"),
+    ("FJ", "Fiji"),
+    ("FK", "Falkland Islands (Malvinas)"),
+    ("FM", "Micronesia, Federated States of"),
+    ("FO", "Faroe Islands"),
+    ("FR", "France"),
+    ("GA", "Gabon"),
+    ("GB", "United Kingdom"),
+    ("GD", "Grenada"),
+    ("GE", "Georgia"),
+    ("GF", "French Guiana"),
+    ("GG", "Guernsey"),
+    ("GH", "Ghana"),
+    ("GI", "Gibraltar"),
+    ("GL", "Greenland"),
+    ("GM", "Gambia"),
+    ("GN", "Guinea"),
+    ("GP", "Guadeloupe"),
+    ("GQ", "Equatorial Guinea"),
+    ("GR", "Greece"),
+    ("GS", "South Georgia and the South Sandwich Islands"),
+    ("GT", "Guatemala"),
+    ("GU", "Guam"),
+    ("GW", "Guinea-Bissau"),
+    ("GY", "Guyana"),
+    ("HK", "Hong Kong"),
+    ("HM", "Heard Island and McDonald Islands"),
+    ("HN", "Honduras"),
+    ("HR", "Croatia"),
+    ("HT", "Haiti"),
+    ("HU", "Hungary"),
+    ("ID", "Indonesia"),
+    ("IE", "Ireland"),
+    ("IL", "Israel"),
+    ("IM", "Isle of Man"),
+    ("IN", "India"),
+    ("IO", "British Indian Ocean Territory"),
+    ("IQ", "Iraq"),
+    ("IR", "Iran"),
+    ("IS", "Iceland"),
+    ("IT", "Italy"),
+    ("JE", "Jersey"),
+    ("JM", "Jamaica"),
+    ("JO", "Jordan"),
+    ("JP", "Japan"),
+    ("KE", "Kenya"),
+    ("KG", "Kyrgyzstan"),
+    ("KH", "Cambodia"),
+    ("KI", "Kiribati"),
+    ("KM", "Comoros"),
+    ("KN", "Saint Kitts and Nevis"),
+    ("KP", "North Korea"),
+    ("KR", "South Korea"),
+    ("KW", "Kuwait"),
+    ("KY", "Cayman Islands"),
+    ("KZ", "Kazakhstan"),
+    ("LA", "Laos"),
+    ("LB", "Lebanon"),
+    ("LC", "Saint Lucia"),
+    ("LI", "Liechtenstein"),
+    ("LK", "Sri Lanka"),
+    ("LR", "Liberia"),
+    ("LS", "Lesotho"),
+    ("LT", "Lithuania"),
+    ("LU", "Luxembourg"),
+    ("LV", "Latvia"),
+    ("LY", "Libya"),
+    ("MA", "Morocco"),
+    ("MC", "Monaco"),
+    ("MD", "Moldova"),
+    ("ME", "Montenegro"),
+    ("MF", "Saint Martin (French part)"),
+    ("MG", "Madagascar"),
+    ("MH", "Marshall Islands"),
+    ("MK", "North Macedonia"),
+    ("ML", "Mali"),
+    ("MM", "Myanmar"),
+    ("MN", "Mongolia"),
+    ("MO", "Macao"),
+    ("MP", "Northern Mariana Islands"),
+    ("MQ", "Martinique"),
+    ("MR", "Mauritania"),
+    ("MS", "Montserrat"),
+    ("MT", "Malta"),
+    ("MU", "Mauritius"),
+    ("MV", "Maldives"),
+    ("MW", "Malawi"),
+    ("MX", "Mexico"),
+    ("MY", "Malaysia"),
+    ("MZ", "Mozambique"),
+    ("NA", "Namibia"),
+    ("NC", "New Caledonia"),
+    ("NE", "Niger"),
+    ("NF", "Norfolk Island"),
+    ("NG", "Nigeria"),
+    ("NI", "Nicaragua"),
+    ("NL", "Netherlands"),
+    ("NO", "Norway"),
+    ("NP", "Nepal"),
+    ("NR", "Nauru"),
+    ("NU", "Niue"),
+    ("NZ", "New Zealand"),
+    ("OM", "Oman"),
+    ("PA", "Panama"),
+    ("PE", "Peru"),
+    ("PF", "French Polynesia"),
+    ("PG", "Papua New Guinea"),
+    ("PH", "Philippines"),
+    ("PK", "Pakistan"),
+    ("PL", "Poland"),
+    ("PM", "Saint Pierre and Miquelon"),
+    ("PN", "Pitcairn"),
+    ("PR", "Puerto Rico"),
+    ("PS", "Palestine, State of"),
+    ("PT", "Portugal"),
+    ("PW", "Palau"),
+    ("PY", "Paraguay"),
+    ("QA", "Qatar"),
+    ("RE", "Réunion"),
+    ("RO", "Romania"),
+    ("RS", "Serbia"),
+    ("RU", "Russian Federation"),
+    ("RW", "Rwanda"),
+    ("SA", "Saudi Arabia"),
+    ("SB", "Solomon Islands"),
+    ("SC", "Seychelles"),
+    ("SD", "Sudan"),
+    ("SE", "Sweden"),
+    ("SG", "Singapore"),
+    ("SH", "Saint Helena, Ascension and Tristan da Cunha"),
+    ("SI", "Slovenia"),
+    ("SJ", "Svalbard and Jan Mayen"),
+    ("SK", "Slovakia"),
+    ("SL", "Sierra Leone"),
+    ("SM", "San Marino"),
+    ("SN", "Senegal"),
+    ("SO", "Somalia"),
+    ("SR", "Suriname"),
+    ("SS", "South Sudan"),
+    ("ST", "Sao Tome and Principe"),
+    ("SV", "El Salvador"),
+    ("SX", "Sint Maarten (Dutch part)"),
+    ("SY", "Syria"),
+    ("SZ", "Eswatini"),
+    ("TC", "Turks and Caicos Islands"),
+    ("TD", "Chad"),
+    ("TF", "French Southern Territories"),
+    ("TG", "Togo"),
+    ("TH", "Thailand"),
+    ("TJ", "Tajikistan"),
+    ("TK", "Tokelau"),
+    ("TL", "Timor-Leste"),
+    ("TM", "Turkmenistan"),
+    ("TN", "Tunisia"),
+    ("TO", "Tonga"),
+    ("TR", "Türkiye"),
+    ("TT", "Trinidad and Tobago"),
+    ("TV", "Tuvalu"),
+    ("TW", "Taiwan"),
+    ("TZ", "Tanzania"),
+    ("UA", "Ukraine"),
+    ("UG", "Uganda"),
+    ("UM", "United States Minor Outlying Islands"),
+    ("US", "United States"),
+    ("UY", "Uruguay"),
+    ("UZ", "Uzbekistan"),
+    ("VA", "Holy See (Vatican City State)"),
+    ("VC", "Saint Vincent and the Grenadines"),
+    ("VE", "Venezuela"),
+    ("VG", "Virgin Islands, British"),
+    ("VI", "Virgin Islands, U.S."),
+    ("VN", "Vietnam"),
+    ("VU", "Vanuatu"),
+    ("WF", "Wallis and Futuna"),
+    ("WS", "Samoa"),
+    ("XK", "Kosovo"),
+    ("YE", "Yemen"),
+    ("YT", "Mayotte"),
+    ("ZA", "South Africa"),
+    ("ZM", "Zambia"),
+    ("ZW", "Zimbabwe"),
+];
+
+/// Returns the name of the country with the given ISO 3166-1 alpha-2 code,
+/// or `UNKNOWN_COUNTRY_NAME` if the code is empty or unknown.
+pub fn country_name(code: &str) -> &'static str {
+    COUNTRY_NAMES
+        .binary_search_by_key(&code, |(code, _)| code)
+        .map_or(UNKNOWN_COUNTRY_NAME, |index| COUNTRY_NAMES[index].1)
+}
+
+/// Returns the flag emoji of the country with the given ISO 3166-1 alpha-2 code
+/// (the pair of regional indicator symbols corresponding to its letters),
+/// or `UNKNOWN_COUNTRY_FLAG` if the code is empty or unknown.
+pub fn country_flag_emoji(code: &str) -> String {
+    if COUNTRY_NAMES
+        .binary_search_by_key(&code, |(code, _)| code)
+        .is_err()
+    {
+        return UNKNOWN_COUNTRY_FLAG.to_string();
+    }
+    code.chars()
+        .filter_map(|letter| char::from_u32(0x1F1E6 + u32::from(letter) - u32::from('A')))
+        .collect()
+}
+
 pub const FLAGS_WIDTH: f32 = 15.0;
 
 pub const AD: &[u8] =
@@ -759,3 +1247,130 @@ pub fn get_flag(country: &str) -> Image {
     })))
     .width(Length::Fixed(FLAGS_WIDTH))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::utility::countries::{
+        country_flag_emoji, country_name, get_city_db_reader, get_country_code,
+        get_country_db_reader, get_geo_point, open_embedded_country_db, COUNTRY_MMDB,
+        COUNTRY_NAMES, UNKNOWN_COUNTRY_FLAG, UNKNOWN_COUNTRY_NAME,
+    };
+    use crate::TransProtocol;
+
+    fn country_of(remote_address: &str) -> String {
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            remote_address.to_string(),
+            443,
+            TransProtocol::TCP,
+        );
+        get_country_code(
+            TrafficType::Outgoing,
+            &key,
+            &get_country_db_reader(None).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_ipv4_mapped_addresses_country() {
+        let country = country_of("8.8.8.8");
+        assert!(!country.is_empty());
+        assert_eq!(country_of("::ffff:8.8.8.8"), country);
+        assert_eq!(country_of("::ffff:808:808"), country);
+        assert_eq!(country_of("::8.8.8.8"), country);
+    }
+
+    #[test]
+    fn test_local_addresses_have_no_country() {
+        for address in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "127.0.0.1",
+            "169.254.1.1",
+            "::ffff:192.168.1.1",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd12:3456:789a::1",
+            "fc00::1",
+            "not an address",
+        ] {
+            assert_eq!(country_of(address), "", "{address}");
+        }
+    }
+
+    #[test]
+    fn test_missing_city_database() {
+        let path = std::env::temp_dir().join("sniffnet_test_missing_city.mmdb");
+        std::fs::remove_file(&path).unwrap_or(());
+        assert!(get_city_db_reader(Some(&path)).is_none());
+
+        // a country database is not accepted as city database
+        let path = std::env::temp_dir().join("sniffnet_test_country_as_city.mmdb");
+        std::fs::write(&path, COUNTRY_MMDB).unwrap();
+        assert!(get_city_db_reader(Some(&path)).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_country_database() {
+        assert!(open_embedded_country_db(b"not a database").is_none());
+        assert!(open_embedded_country_db(&COUNTRY_MMDB[..COUNTRY_MMDB.len() / 2]).is_none());
+        assert!(open_embedded_country_db(COUNTRY_MMDB).is_some());
+
+        // a corrupt user supplied database falls back to the embedded one
+        let path = std::env::temp_dir().join("sniffnet_test_corrupt_country.mmdb");
+        std::fs::write(&path, b"not a database").unwrap();
+        assert!(get_country_db_reader(Some(&path)).is_some());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_geo_point_not_available() {
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "8.8.8.8".to_string(),
+            443,
+            TransProtocol::TCP,
+        );
+        // records without coordinates are not geolocated
+        let reader = get_country_db_reader(None).unwrap();
+        assert_eq!(get_geo_point(TrafficType::Outgoing, &key, &reader), None);
+        // local traffic is never geolocated
+        assert_eq!(get_geo_point(TrafficType::Local, &key, &reader), None);
+    }
+
+    #[test]
+    fn test_country_name() {
+        assert_eq!(country_name("IT"), "Italy");
+        assert_eq!(country_name("US"), "United States");
+        assert_eq!(country_name("AD"), "Andorra");
+        assert_eq!(country_name("ZW"), "Zimbabwe");
+        assert_eq!(country_name(""), UNKNOWN_COUNTRY_NAME);
+        assert_eq!(country_name("//"), UNKNOWN_COUNTRY_NAME);
+        assert_eq!(country_name("it"), UNKNOWN_COUNTRY_NAME);
+        assert_eq!(country_name("ITA"), UNKNOWN_COUNTRY_NAME);
+    }
+
+    #[test]
+    fn test_country_names_are_sorted() {
+        assert!(COUNTRY_NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(COUNTRY_NAMES
+            .iter()
+            .all(|(code, _)| code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase())));
+    }
+
+    #[test]
+    fn test_country_flag_emoji() {
+        assert_eq!(country_flag_emoji("IT"), "\u{1F1EE}\u{1F1F9}");
+        assert_eq!(country_flag_emoji("US"), "\u{1F1FA}\u{1F1F8}");
+        assert_eq!(country_flag_emoji(""), UNKNOWN_COUNTRY_FLAG);
+        assert_eq!(country_flag_emoji("//"), UNKNOWN_COUNTRY_FLAG);
+        assert_eq!(country_flag_emoji("QQ"), UNKNOWN_COUNTRY_FLAG);
+    }
+}
@@ -0,0 +1,248 @@
+//! Module containing functions to decode the DNS messages carried by the sniffed packets.
+
+use crate::TransProtocol;
+
+/// Length of the DNS message header
+const DNS_HEADER_LEN: usize = 12;
+
+/// Maximum length of a domain name in its textual representation
+const MAX_NAME_LEN: usize = 253;
+
+/// Maximum number of compression pointers followed while decoding a name (to prevent loops)
+const MAX_POINTERS: usize = 16;
+
+/// Extracts the domain name of the first question of a DNS message.
+///
+/// Over TCP, DNS messages are prefixed by their 2-byte length.
+/// Returns `None` if the message is truncated, malformed, or doesn't contain any question.
+pub fn get_dns_query_name(payload: &[u8], transport_protocol: TransProtocol) -> Option<String> {
+    let message = match transport_protocol {
+        TransProtocol::TCP => payload.get(2..)?,
+        _ => payload,
+    };
+    let header = message.get(..DNS_HEADER_LEN)?;
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+    if questions == 0 {
+        return None;
+    }
+    decode_name(message, DNS_HEADER_LEN)
+}
+
+/// Checks if the data look like a DNS message with a single question (as almost all the queries and responses),
+/// without decoding it: used to recognize DNS on nonstandard ports.
+///
+/// Besides the header fields, the labels of the question name must be printable and the name must be
+/// followed by its type and class (Internet, or any class).
+pub fn is_dns_message(message: &[u8]) -> bool {
+    let Some(header) = message.get(..DNS_HEADER_LEN) else {
+        return false;
+    };
+    let field = |index: usize| u16::from_be_bytes([header[index], header[index + 1]]);
+    let is_response = header[2] & 0x80 != 0;
+    let opcode = (header[2] >> 3) & 0x0f;
+    let reserved = header[3] & 0x40;
+    let rcode = header[3] & 0x0f;
+    let (questions, answers, authorities) = (field(4), field(6), field(8));
+    let valid_header = if is_response {
+        rcode <= 5
+    } else {
+        // queries don't carry answers, and their response code is always 0
+        rcode == 0 && answers == 0 && authorities == 0
+    };
+    if opcode != 0 || reserved != 0 || questions != 1 || !valid_header {
+        return false;
+    }
+    // the question name is not compressed, since it's the first one in the message
+    let mut offset = DNS_HEADER_LEN;
+    let mut name_len = 0;
+    loop {
+        let Some(&len) = message.get(offset) else {
+            return false;
+        };
+        if len == 0 {
+            break;
+        }
+        let Some(label) = message.get(offset + 1..offset + 1 + usize::from(len)) else {
+            return false;
+        };
+        name_len += usize::from(len) + 1;
+        if len > 63 || name_len > MAX_NAME_LEN + 1 || !label.iter().all(u8::is_ascii_graphic) {
+            return false;
+        }
+        offset += 1 + usize::from(len);
+    }
+    let Some(&[_, _, class_high, class_low]) = message.get(offset + 1..offset + 5) else {
+        return false;
+    };
+    // the top bit of the class is the unicast response bit of mDNS
+    matches!(
+        u16::from_be_bytes([class_high, class_low]) & 0x7fff,
+        1 | 255
+    )
+}
+
+/// Decodes the domain name starting at `offset` of the DNS message, following compression pointers.
+fn decode_name(message: &[u8], mut offset: usize) -> Option<String> {
+    let mut name = String::new();
+    let mut pointers = 0;
+    loop {
+        let len = *message.get(offset)?;
+        match len {
+            0 => break,
+            // compression pointer
+            len if len & 0xc0 == 0xc0 => {
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return None;
+                }
+                let low = *message.get(offset + 1)?;
+                offset = usize::from(u16::from_be_bytes([len & 0x3f, low]));
+            }
+            // reserved label types
+            len if len & 0xc0 != 0 => return None,
+            len => {
+                let label = message.get(offset + 1..offset + 1 + usize::from(len))?;
+                if !label.iter().all(|c| c.is_ascii_graphic() && *c != b'.') {
+                    return None;
+                }
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.extend(label.iter().map(|c| char::from(c.to_ascii_lowercase())));
+                if name.len() > MAX_NAME_LEN {
+                    return None;
+                }
+                offset += 1 + usize::from(len);
+            }
+        }
+    }
+    if name.is_empty() {
+        // query for the root domain
+        Some(".".to_string())
+    } else {
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utility::dns::{get_dns_query_name, is_dns_message};
+    use crate::TransProtocol;
+
+    /// Builds a DNS query message for the given labels.
+    fn dns_query(labels: &[&str]) -> Vec<u8> {
+        let mut message = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in labels {
+            message.push(u8::try_from(label.len()).unwrap());
+            message.extend_from_slice(label.as_bytes());
+        }
+        message.extend_from_slice(&[0, 0, 1, 0, 1]);
+        message
+    }
+
+    #[test]
+    fn test_dns_query_name_over_udp() {
+        assert_eq!(
+            get_dns_query_name(&dns_query(&["www", "Example", "com"]), TransProtocol::UDP),
+            Some("www.example.com".to_string())
+        );
+        assert_eq!(
+            get_dns_query_name(&dns_query(&[]), TransProtocol::UDP),
+            Some(".".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dns_query_name_over_tcp() {
+        let query = dns_query(&["sniffnet", "net"]);
+        let mut message = u16::try_from(query.len()).unwrap().to_be_bytes().to_vec();
+        message.extend_from_slice(&query);
+        assert_eq!(
+            get_dns_query_name(&message, TransProtocol::TCP),
+            Some("sniffnet.net".to_string())
+        );
+    }
+
+    #[test]
+    fn test_mdns_service_query_name() {
+        assert_eq!(
+            get_dns_query_name(
+                &dns_query(&["_googlecast", "_tcp", "local"]),
+                TransProtocol::UDP
+            ),
+            Some("_googlecast._tcp.local".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dns_query_name_with_compression_pointer() {
+        // response quoting the question name through a pointer to the answer section
+        let mut message = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        message.extend_from_slice(&[0xc0, 18, 0, 1, 0, 1]);
+        message.extend_from_slice(&[3, b'a', b'b', b'c', 0]);
+        assert_eq!(
+            get_dns_query_name(&message, TransProtocol::UDP),
+            Some("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_malformed_dns_messages() {
+        let query = dns_query(&["www", "example", "com"]);
+        // truncated messages
+        for len in [0, 5, 12, 15, query.len() - 6] {
+            assert_eq!(get_dns_query_name(&query[..len], TransProtocol::UDP), None);
+        }
+        // no questions
+        let mut no_questions = query.clone();
+        no_questions[5] = 0;
+        assert_eq!(get_dns_query_name(&no_questions, TransProtocol::UDP), None);
+        // pointer loop
+        let mut pointer_loop = query[..12].to_vec();
+        pointer_loop.extend_from_slice(&[0xc0, 12]);
+        assert_eq!(get_dns_query_name(&pointer_loop, TransProtocol::UDP), None);
+        // reserved label type
+        let mut reserved = query.clone();
+        reserved[12] = 0x43;
+        assert_eq!(get_dns_query_name(&reserved, TransProtocol::UDP), None);
+        // non printable characters
+        let mut binary = query;
+        binary[13] = 0;
+        assert_eq!(get_dns_query_name(&binary, TransProtocol::UDP), None);
+    }
+
+    #[test]
+    fn test_is_dns_message() {
+        let query = dns_query(&["www", "example", "com"]);
+        assert!(is_dns_message(&query));
+        assert!(is_dns_message(&dns_query(&[])));
+        // response with an answer
+        let mut response = query.clone();
+        response[2..4].copy_from_slice(&[0x81, 0x80]);
+        response[7] = 1;
+        response.extend_from_slice(&[
+            0xc0, 12, 0, 1, 0, 1, 0, 0, 0x0e, 0x10, 0, 4, 93, 184, 216, 34,
+        ]);
+        assert!(is_dns_message(&response));
+
+        // truncated question
+        assert!(!is_dns_message(&query[..query.len() - 1]));
+        // query with an answer
+        let mut query_with_answer = query.clone();
+        query_with_answer[7] = 1;
+        assert!(!is_dns_message(&query_with_answer));
+        // two questions
+        let mut two_questions = query.clone();
+        two_questions[5] = 2;
+        assert!(!is_dns_message(&two_questions));
+        // unknown class
+        let mut chaos = query.clone();
+        *chaos.last_mut().unwrap() = 3;
+        assert!(!is_dns_message(&chaos));
+        // not a DNS message at all
+        assert!(!is_dns_message(
+            b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n"
+        ));
+        assert!(!is_dns_message(&[0; 40]));
+    }
+}
@@ -0,0 +1,159 @@
+//! Module containing functions to export the connections map to files for external processing.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::{Duration, SecondsFormat};
+
+use crate::utility::anonymize::get_exported_connection;
+use crate::utility::manage_packets::get_dscp_name;
+use crate::InfoTraffic;
+
+/// Header of the CSV file containing the connections
+const CSV_HEADER: &str = "address1,port1,address2,port2,transport_protocol,app_protocol,\
+transmitted_bytes,transmitted_packets,country,traffic_type,initial_timestamp,final_timestamp,\
+bytes_per_second,packets_per_second,duration_seconds,sent_bytes,received_bytes,sent_packets,received_packets,dscp";
+
+/// Writes a CSV file at the given path, with a row for each connection of the map.
+///
+/// Timestamps are formatted according to ISO 8601 (RFC 3339),
+/// DSCP markings by name if standard (e.g. `EF`) or by value otherwise.
+/// If a salt is specified, the connections are anonymized (see `get_exported_connection`).
+pub fn export_connections_csv(
+    path: &Path,
+    info_traffic: &InfoTraffic,
+    anonymization_salt: Option<u64>,
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{CSV_HEADER}")?;
+    for (key, val) in &info_traffic.map {
+        let (key, val) = get_exported_connection(key, val, anonymization_salt);
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{:.2},{:.2},{:.3},{},{},{},{},{}",
+            escape_csv_field(&key.address1),
+            key.port1,
+            escape_csv_field(&key.address2),
+            key.port2,
+            key.trans_protocol,
+            val.app_protocol,
+            val.transmitted_bytes,
+            val.transmitted_packets,
+            escape_csv_field(&val.country),
+            val.traffic_type,
+            val.initial_timestamp
+                .to_rfc3339_opts(SecondsFormat::Millis, false),
+            val.final_timestamp
+                .to_rfc3339_opts(SecondsFormat::Millis, false),
+            val.bytes_per_second(),
+            val.packets_per_second(),
+            get_duration_seconds(val.duration()),
+            val.sent_bytes,
+            val.received_bytes,
+            val.sent_packets,
+            val.received_packets,
+            get_dscp_name(val.dscp).map_or_else(|| val.dscp.to_string(), ToString::to_string),
+        )?;
+    }
+    writer.flush()
+}
+
+/// Converts a duration to seconds, with millisecond precision.
+fn get_duration_seconds(duration: Duration) -> f64 {
+    #[allow(clippy::cast_precision_loss)]
+    let milliseconds = duration.num_milliseconds() as f64;
+    milliseconds / 1000.0
+}
+
+/// Quotes a CSV field if it contains separators, quotes or line breaks, doubling the inner quotes.
+fn escape_csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{Local, TimeZone};
+
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::utility::anonymize::anonymize_address;
+    use crate::utility::export_connections::{escape_csv_field, export_connections_csv};
+    use crate::{AppProtocol, InfoTraffic, TransProtocol};
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("192.168.1.1"), "192.168.1.1");
+        assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_export_connections_csv() {
+        let timestamp = Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap();
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.map.insert(
+            AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                50000,
+                "8.8.8.8".to_string(),
+                53,
+                TransProtocol::UDP,
+            ),
+            InfoAddressPortPair {
+                transmitted_bytes: 1234,
+                transmitted_packets: 5,
+                sent_bytes: 1234,
+                sent_packets: 5,
+                initial_timestamp: timestamp,
+                final_timestamp: timestamp,
+                app_protocol: AppProtocol::DNS,
+                traffic_type: TrafficType::Outgoing,
+                country: "US".to_string(),
+                dscp: 46,
+                ..InfoAddressPortPair::default()
+            },
+        );
+        let path = std::env::temp_dir().join("sniffnet_test_export_connections.csv");
+        export_connections_csv(&path, &info_traffic, None).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let iso_timestamp = timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, false);
+        let mut lines = content.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "address1,port1,address2,port2,transport_protocol,app_protocol,transmitted_bytes,\
+transmitted_packets,country,traffic_type,initial_timestamp,final_timestamp,bytes_per_second,packets_per_second,\
+duration_seconds,sent_bytes,received_bytes,sent_packets,received_packets,dscp"
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            format!(
+                "192.168.1.10,50000,8.8.8.8,53,UDP,DNS,1234,5,US,Outgoing,{iso_timestamp},{iso_timestamp},0.00,0.00,0.000,1234,0,5,0,EF"
+            )
+        );
+        assert!(iso_timestamp.starts_with("2023-01-31T10:30:00.000"));
+        assert_eq!(lines.next(), None);
+
+        export_connections_csv(&path, &info_traffic, Some(42)).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let row = content.lines().nth(1).unwrap();
+        assert!(row.starts_with(&format!(
+            "{},50000,{},53,UDP,DNS,1234,5,US,",
+            anonymize_address("192.168.1.10", 42),
+            anonymize_address("8.8.8.8", 42)
+        )));
+        assert!(!row.contains("192.168.1.10") && !row.contains("8.8.8.8"));
+    }
+}
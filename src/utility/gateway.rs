@@ -0,0 +1,106 @@
+//! Module containing functions to detect the default gateways of the network adapters.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Flag of the routes whose destination is reached through a gateway (see `route(8)`)
+const RTF_GATEWAY: u32 = 0x2;
+
+/// Returns the addresses of the default gateways (IPv4 and IPv6) of the given network adapter,
+/// read from the kernel routing tables.
+///
+/// Returns an empty vector if the routing tables are not available (e.g. on systems other than Linux).
+pub fn get_default_gateways(interface: &str) -> Vec<IpAddr> {
+    let mut gateways = Vec::new();
+    if let Ok(routes) = std::fs::read_to_string("/proc/net/route") {
+        gateways.extend(parse_ipv4_default_gateways(&routes, interface));
+    }
+    if let Ok(routes) = std::fs::read_to_string("/proc/net/ipv6_route") {
+        gateways.extend(parse_ipv6_default_gateways(&routes, interface));
+    }
+    gateways
+}
+
+/// Extracts the default gateways of the network adapter from the content of `/proc/net/route`.
+///
+/// Each line after the header describes a route: addresses are hexadecimal in host byte order (little-endian).
+fn parse_ipv4_default_gateways(routes: &str, interface: &str) -> Vec<IpAddr> {
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|route| {
+            let fields: Vec<&str> = route.split_whitespace().collect();
+            let [iface, destination, gateway, flags, _, _, _, mask, ..] = fields[..] else {
+                return None;
+            };
+            let hex = |field: &str| u32::from_str_radix(field, 16).ok();
+            let is_default = hex(destination)? == 0 && hex(mask)? == 0;
+            let gateway = hex(gateway)?;
+            if iface != interface || !is_default || hex(flags)? & RTF_GATEWAY == 0 || gateway == 0 {
+                return None;
+            }
+            Some(IpAddr::V4(Ipv4Addr::from(gateway.to_le_bytes())))
+        })
+        .collect()
+}
+
+/// Extracts the default gateways of the network adapter from the content of `/proc/net/ipv6_route`.
+///
+/// Each line describes a route: addresses are hexadecimal in network byte order (big-endian).
+fn parse_ipv6_default_gateways(routes: &str, interface: &str) -> Vec<IpAddr> {
+    routes
+        .lines()
+        .filter_map(|route| {
+            let fields: Vec<&str> = route.split_whitespace().collect();
+            let [destination, prefix_len, _, _, next_hop, _, _, _, flags, iface] = fields[..]
+            else {
+                return None;
+            };
+            let is_default = u128::from_str_radix(destination, 16).ok()? == 0
+                && u8::from_str_radix(prefix_len, 16).ok()? == 0;
+            let next_hop = u128::from_str_radix(next_hop, 16).ok()?;
+            let flags = u32::from_str_radix(flags, 16).ok()?;
+            if iface != interface || !is_default || flags & RTF_GATEWAY == 0 || next_hop == 0 {
+                return None;
+            }
+            Some(IpAddr::V6(Ipv6Addr::from(next_hop)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::utility::gateway::{parse_ipv4_default_gateways, parse_ipv6_default_gateways};
+
+    #[test]
+    fn test_ipv4_default_gateways() {
+        let routes =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n\
+wlan0\t00000000\t0100000A\t0003\t0\t0\t600\t00000000\t0\t0\t0\n";
+        assert_eq!(
+            parse_ipv4_default_gateways(routes, "eth0"),
+            vec!["192.168.1.1".parse::<IpAddr>().unwrap()]
+        );
+        assert_eq!(
+            parse_ipv4_default_gateways(routes, "wlan0"),
+            vec!["10.0.0.1".parse::<IpAddr>().unwrap()]
+        );
+        assert!(parse_ipv4_default_gateways(routes, "eth1").is_empty());
+        assert!(parse_ipv4_default_gateways("", "eth0").is_empty());
+    }
+
+    #[test]
+    fn test_ipv6_default_gateways() {
+        let routes = "fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0\n\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe80000000000000021122fffe334455 00000400 00000001 00000000 00000003     eth0\n\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo\n";
+        assert_eq!(
+            parse_ipv6_default_gateways(routes, "eth0"),
+            vec!["fe80::211:22ff:fe33:4455".parse::<IpAddr>().unwrap()]
+        );
+        assert!(parse_ipv6_default_gateways(routes, "lo").is_empty());
+    }
+}
@@ -1,14 +1,37 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use chrono::{DateTime, Local};
 use iced::Color;
 use thousands::Separable;
 
+use crate::enums::network_scope::NetworkScope;
 use crate::enums::traffic_type::TrafficType;
+use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::discovery_traffic::DiscoveryTraffic;
 use crate::structs::filters::Filters;
-use crate::utility::translations::{active_filters_translation, none_translation};
-use crate::{get_colors, AppProtocol, IpVersion, Language, StyleType, TransProtocol};
+use crate::structs::info_address_port_pair::InfoAddressPortPair;
+use crate::structs::info_traffic::{InterfaceStats, TrafficSummary};
+use crate::structs::throughput_history::ThroughputHistory;
+use crate::utility::translations::{
+    active_filters_translation, arp_counts_translation, igmp_counts_translation,
+    interface_traffic_translation, none_translation,
+};
+use crate::{
+    get_colors, AppProtocol, ByteMultiple, ChartType, IpVersion, Language, StyleType,
+    TransProtocol, UnitSystem,
+};
+
+/// Number of countries listed in the traffic per country summary
+const TOP_COUNTRIES: usize = 5;
+
+/// Number of addresses listed in the LAN discovery traffic summary, among the most requested via ARP
+const TOP_ARP_TARGETS: usize = 3;
+
+/// Percentile of the traffic rates reported in the throughput summary
+const THROUGHPUT_PERCENTILE: f64 = 95.0;
 
 /// Application version number (to be displayed in gui footer)
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -26,11 +49,205 @@ pub fn get_percentage_string(observed: u128, filtered: u128) -> String {
     }
 }
 
+/// Computes the String listing the connections that exchanged the most traffic, one per line
+pub fn get_top_talkers_string(
+    top_talkers: &[(AddressPortPair, InfoAddressPortPair)],
+    chart_type: ChartType,
+    unit_system: UnitSystem,
+) -> String {
+    top_talkers
+        .iter()
+        .map(|(key, info)| {
+            let traffic = match chart_type {
+                ChartType::Packets => info.transmitted_packets.separate_with_spaces(),
+                ChartType::Bytes => get_formatted_bytes_string(info.transmitted_bytes, unit_system)
+                    .trim()
+                    .to_string(),
+            };
+            format!("   {} - {}   {traffic}", key.address1, key.address2)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Computes the String listing the countries with which the most traffic was exchanged, one per line
+pub fn get_country_breakdown_string(
+    country_breakdown: &HashMap<String, (u128, u128)>,
+    chart_type: ChartType,
+    unit_system: UnitSystem,
+) -> String {
+    let mut sorted_breakdown: Vec<(&String, u128)> = country_breakdown
+        .iter()
+        .map(|(country, (bytes, packets))| match chart_type {
+            ChartType::Packets => (country, *packets),
+            ChartType::Bytes => (country, *bytes),
+        })
+        .collect();
+    sorted_breakdown.sort_by(|(c1, a), (c2, b)| b.cmp(a).then_with(|| c1.cmp(c2)));
+    sorted_breakdown
+        .into_iter()
+        .take(TOP_COUNTRIES)
+        .map(|(country, traffic)| {
+            let traffic = match chart_type {
+                ChartType::Packets => traffic.separate_with_spaces(),
+                ChartType::Bytes => get_formatted_bytes_string(traffic, unit_system)
+                    .trim()
+                    .to_string(),
+            };
+            format!("   {country:<5}   {traffic}")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Computes the String representing the traffic of a summary entry (packets or bytes, according to the chart kind)
+pub fn get_traffic_summary_string(
+    summary: &TrafficSummary,
+    chart_type: ChartType,
+    unit_system: UnitSystem,
+) -> String {
+    match chart_type {
+        ChartType::Packets => summary.packets.separate_with_spaces(),
+        ChartType::Bytes => get_formatted_bytes_string(summary.bytes, unit_system)
+            .trim()
+            .to_string(),
+    }
+}
+
+/// Computes the String listing the number of packets in each bucket of the packet size histogram, one per line
+pub fn get_size_histogram_string(size_histogram: &[(String, u64)]) -> String {
+    size_histogram
+        .iter()
+        .map(|(label, packets)| format!("   {label:<9} B   {}", packets.separate_with_spaces()))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Computes the String summarizing the LAN discovery traffic: the ARP requests and replies
+/// (with the most requested addresses) and the IGMP queries and reports, one per line
+pub fn get_discovery_traffic_string(discovery: &DiscoveryTraffic, language: Language) -> String {
+    let mut lines = vec![format!(
+        "   {}",
+        arp_counts_translation(
+            language,
+            &discovery.arp_requests.separate_with_spaces(),
+            &discovery.arp_replies.separate_with_spaces()
+        )
+    )];
+    for (target, requests) in discovery.most_requested(TOP_ARP_TARGETS) {
+        lines.push(format!(
+            "      who-has {target}   {}",
+            requests.separate_with_spaces()
+        ));
+    }
+    lines.push(format!(
+        "   {}",
+        igmp_counts_translation(
+            language,
+            &discovery.igmp_queries.separate_with_spaces(),
+            &discovery.igmp_reports.separate_with_spaces()
+        )
+    ));
+    lines.join("\n")
+}
+
+/// Computes the String summarizing the traffic sniffed on each network adapter (packets or bytes,
+/// according to the chart kind), one per line in alphabetical order
+pub fn get_per_interface_string(
+    per_interface: &HashMap<String, InterfaceStats>,
+    chart_type: ChartType,
+    unit_system: UnitSystem,
+    language: Language,
+) -> String {
+    let mut sorted_interfaces: Vec<(&String, &InterfaceStats)> = per_interface.iter().collect();
+    sorted_interfaces.sort_by_key(|(interface, _)| *interface);
+    sorted_interfaces
+        .into_iter()
+        .map(|(interface, stats)| {
+            let (received, sent) = match chart_type {
+                ChartType::Packets => (
+                    stats.received_packets.separate_with_spaces(),
+                    stats.sent_packets.separate_with_spaces(),
+                ),
+                ChartType::Bytes => (
+                    get_formatted_bytes_string(stats.received_bytes, unit_system)
+                        .trim()
+                        .to_string(),
+                    get_formatted_bytes_string(stats.sent_bytes, unit_system)
+                        .trim()
+                        .to_string(),
+                ),
+            };
+            format!(
+                "   {interface}   {}",
+                interface_traffic_translation(language, &received, &sent)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Computes the String summarizing the incoming and outgoing throughput in the time window covered by the history
+/// (peak and 95th percentile of the bytes per second), or `None` if no interval has been recorded
+pub fn get_throughput_summary_string(
+    history: &ThroughputHistory,
+    refresh_interval: Duration,
+    unit_system: UnitSystem,
+) -> Option<String> {
+    let peak = history.peak()?;
+    let percentile = history.percentile(THROUGHPUT_PERCENTILE)?;
+    let rate = |bytes: u128| {
+        let multiple = ByteMultiple::fitting(bytes, unit_system);
+        let separator = if multiple == ByteMultiple::B { " " } else { "" };
+        format!(
+            "{}{separator}B/s",
+            format_bytes(bytes, multiple, unit_system)
+        )
+    };
+    let covered = refresh_interval * u32::try_from(history.len()).unwrap_or(u32::MAX);
+    Some(format!(
+        "Throughput of the last {} (incoming / outgoing)\n   peak: {} / {}\n   {THROUGHPUT_PERCENTILE}th percentile: {} / {}",
+        get_formatted_duration(chrono::Duration::from_std(covered).unwrap_or(chrono::Duration::zero())),
+        rate(peak.received_bytes),
+        rate(peak.sent_bytes),
+        rate(percentile.received_bytes),
+        rate(percentile.sent_bytes),
+    ))
+}
+
+/// Computes the String representing the time of day (hours, minutes and seconds) of the given timestamp
+pub fn get_formatted_time(timestamp: DateTime<Local>) -> String {
+    timestamp.format("%H:%M:%S").to_string()
+}
+
+/// Computes the String representing a delay expressed in milliseconds, in seconds with a decimal digit (e.g. `7.2 s`)
+pub fn get_formatted_lag(lag_ms: u64) -> String {
+    format!("{}.{} s", lag_ms / 1000, lag_ms % 1000 / 100)
+}
+
+/// Computes the String representing a duration with its two most significant units (e.g. `3m 05s`);
+/// a zero duration is represented as `0s`
+pub fn get_formatted_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let (days, hours, minutes) = (seconds / 86_400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {:02}s", seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
 /// Computes the String representing the active filters
 pub fn get_active_filters_string(filters: &Filters, language: Language) -> String {
     if filters.ip.eq(&IpVersion::Other)
         && filters.application.eq(&AppProtocol::Other)
         && filters.transport.eq(&TransProtocol::Other)
+        && filters.scope.eq(&NetworkScope::Other)
+        && filters.direction_filter.is_none()
     {
         format!(
             "{}\n   {}",
@@ -48,6 +265,12 @@ pub fn get_active_filters_string(filters: &Filters, language: Language) -> Strin
         if filters.application.ne(&AppProtocol::Other) {
             ret_val.push_str(&format!("\n   {}", filters.application));
         }
+        if filters.scope.ne(&NetworkScope::Other) {
+            ret_val.push_str(&format!("\n   {}", filters.scope.get_radio_label(language)));
+        }
+        if let Some(direction) = filters.direction_filter {
+            ret_val.push_str(&format!("\n   {direction}"));
+        }
         ret_val
     }
 }
@@ -64,6 +287,12 @@ pub fn get_active_filters_string_nobr(filters: &Filters, language: Language) ->
     if filters.application.ne(&AppProtocol::Other) {
         ret_val.push_str(&format!(" {}", filters.application));
     }
+    if filters.scope.ne(&NetworkScope::Other) {
+        ret_val.push_str(&format!(" {}", filters.scope.get_radio_label(language)));
+    }
+    if let Some(direction) = filters.direction_filter {
+        ret_val.push_str(&format!(" {direction}"));
+    }
     ret_val
 }
 
@@ -82,24 +311,30 @@ pub fn get_connection_color(traffic_type: TrafficType, style: StyleType) -> Colo
 ///
 /// # Arguments
 ///
-/// * `app_count` - Map of app layer protocols with the relative sniffed packets count
+/// * `app_count` - Map of app layer protocols with the relative sniffed packets and bytes count
 ///
 /// * `tot_packets` - Total number of sniffed packets
-pub fn get_app_count_string(app_count: &HashMap<AppProtocol, u128>, tot_packets: u128) -> String {
+///
+/// * `unit_system` - System in which the multiples of bytes are expressed
+pub fn get_app_count_string(
+    app_count: &HashMap<AppProtocol, TrafficSummary>,
+    tot_packets: u128,
+    unit_system: UnitSystem,
+) -> String {
     let mut ret_val = String::new();
 
     if app_count.is_empty() {
         return ret_val;
     }
 
-    let mut sorted_app_count: Vec<(&AppProtocol, &u128)> = app_count.iter().collect();
+    let mut sorted_app_count: Vec<(&AppProtocol, &TrafficSummary)> = app_count.iter().collect();
     sorted_app_count.sort_by(|&(p1, a), &(p2, b)| {
         if p1.eq(&AppProtocol::Other) {
             Ordering::Greater
         } else if p2.eq(&AppProtocol::Other) {
             Ordering::Less
         } else {
-            b.cmp(a)
+            b.packets.cmp(&a.packets)
         }
     });
 
@@ -108,13 +343,14 @@ pub fn get_app_count_string(app_count: &HashMap<AppProtocol, u128>, tot_packets:
         .get(0)
         .unwrap()
         .1
+        .packets
         .separate_with_spaces()
         .len();
     match app_count.get(&AppProtocol::Other) {
         None => {}
         Some(x) => {
-            if x.separate_with_spaces().len() > longest_num {
-                longest_num = x.separate_with_spaces().len();
+            if x.packets.separate_with_spaces().len() > longest_num {
+                longest_num = x.packets.separate_with_spaces().len();
             }
         }
     }
@@ -122,10 +358,11 @@ pub fn get_app_count_string(app_count: &HashMap<AppProtocol, u128>, tot_packets:
     for entry in sorted_app_count {
         let app_proto_string = format!("{:?}", entry.0);
 
-        let num_string = entry.1.separate_with_spaces().to_string();
+        let num_string = entry.1.packets.separate_with_spaces().to_string();
+        let bytes_string = get_formatted_bytes_string(entry.1.bytes, unit_system);
 
         #[allow(clippy::cast_precision_loss)]
-        let num_app_float = *entry.1 as f32;
+        let num_app_float = entry.1.packets as f32;
         #[allow(clippy::cast_precision_loss)]
         let num_tot_float = tot_packets as f32;
         let percentage_string = if format!("{:.1}", 100.0 * num_app_float / num_tot_float).eq("0.0")
@@ -142,45 +379,33 @@ pub fn get_app_count_string(app_count: &HashMap<AppProtocol, u128>, tot_packets:
         let spaces_string_2 = " ".to_string().repeat(10 - percentage_string.len());
 
         ret_val.push_str(&format!(
-            "   {app_proto_string}:{spaces_string_1}{num_string}{spaces_string_2}{percentage_string}  \n",
+            "   {app_proto_string}:{spaces_string_1}{num_string}{spaces_string_2}{percentage_string}   {bytes_string}  \n",
         ));
     }
     ret_val
 }
 
-/// Returns a String representing a quantity of bytes with their proper multiple (KB, MB, GB, TB)
-pub fn get_formatted_bytes_string(bytes: u128) -> String {
-    let mut multiple_transmitted = String::new();
-    #[allow(clippy::cast_precision_loss)]
-    let mut n = bytes as f32;
-
-    match bytes {
-        0..=999 => {}
-        1_000..=999_999 => {
-            n /= 1000_f32;
-            multiple_transmitted.push('K');
-        } // kilo
-        1_000_000..=999_999_999 => {
-            n /= 1_000_000_f32;
-            multiple_transmitted.push('M');
-        } // mega
-        1_000_000_000..=999_999_999_999 => {
-            n /= 1_000_000_000_f32;
-            multiple_transmitted.push('G');
-        } // giga
-        _ => {
-            n /= 1_000_000_000_000_f32;
-            multiple_transmitted.push('T');
-        } // tera
-    }
-
-    if multiple_transmitted.is_empty() {
-        // no multiple
-        format!("{n}   ")
+/// Returns a String representing a quantity of bytes with their proper multiple (KB, MB, GB, TB),
+/// padded to be aligned in columns
+pub fn get_formatted_bytes_string(bytes: u128, unit_system: UnitSystem) -> String {
+    let multiple = ByteMultiple::fitting(bytes, unit_system);
+    let padding = if multiple == ByteMultiple::B {
+        "   "
     } else {
-        // with multiple
-        format!("{n:.1} {multiple_transmitted} ")
+        " "
+    };
+    format!("{}{padding}", format_bytes(bytes, multiple, unit_system))
+}
+
+/// Returns a String representing a quantity of bytes in the given multiple, with one decimal digit
+/// (e.g. `1.5 K` in the decimal system, `1.5 Ki` in the binary one); the unit is left to the caller
+pub fn format_bytes(bytes: u128, multiple: ByteMultiple, unit_system: UnitSystem) -> String {
+    if multiple == ByteMultiple::B {
+        return bytes.to_string();
     }
+    #[allow(clippy::cast_precision_loss)]
+    let n = bytes as f64 / multiple.get_divisor(unit_system) as f64;
+    format!("{n:.1} {}", multiple.get_prefix(unit_system))
 }
 
 pub fn get_report_path() -> PathBuf {
@@ -195,6 +420,19 @@ pub fn get_report_path() -> PathBuf {
     }
 }
 
+/// Returns the path of the CSV file where the connections are exported.
+pub fn get_csv_path() -> PathBuf {
+    if let Ok(mut config_path) = confy::get_configuration_file_path("sniffnet", "file") {
+        config_path.pop();
+        config_path.push("connections.csv");
+        config_path
+    } else {
+        let mut csv_path = PathBuf::from(std::env::var_os("HOME").unwrap());
+        csv_path.push("sniffnet_connections.csv");
+        csv_path
+    }
+}
+
 pub fn print_cli_welcome_message() {
     print!(
         r"
@@ -220,3 +458,110 @@ pub fn print_cli_welcome_message() {
     "
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::structs::throughput_history::{ThroughputHistory, ThroughputSample};
+    use crate::utility::get_formatted_strings::{
+        format_bytes, get_formatted_bytes_string, get_formatted_duration,
+        get_throughput_summary_string,
+    };
+    use crate::{ByteMultiple, UnitSystem};
+
+    fn fitting(bytes: u128, unit_system: UnitSystem) -> String {
+        format_bytes(
+            bytes,
+            ByteMultiple::fitting(bytes, unit_system),
+            unit_system,
+        )
+    }
+
+    #[test]
+    fn test_format_bytes_boundaries() {
+        assert_eq!(fitting(1023, UnitSystem::Decimal), "1.0 K");
+        assert_eq!(fitting(1023, UnitSystem::Binary), "1023");
+        assert_eq!(fitting(1024, UnitSystem::Decimal), "1.0 K");
+        assert_eq!(fitting(1024, UnitSystem::Binary), "1.0 Ki");
+        assert_eq!(fitting(1000, UnitSystem::Decimal), "1.0 K");
+        assert_eq!(fitting(1000, UnitSystem::Binary), "1000");
+        assert_eq!(fitting(999, UnitSystem::Decimal), "999");
+        assert_eq!(fitting(1_000_000, UnitSystem::Decimal), "1.0 M");
+        assert_eq!(fitting(1_000_000, UnitSystem::Binary), "976.6 Ki");
+        assert_eq!(fitting(1_048_576, UnitSystem::Binary), "1.0 Mi");
+    }
+
+    #[test]
+    fn test_format_bytes_in_given_multiple() {
+        assert_eq!(
+            format_bytes(1_500_000, ByteMultiple::KB, UnitSystem::Decimal),
+            "1500.0 K"
+        );
+        assert_eq!(
+            format_bytes(1_500_000, ByteMultiple::MB, UnitSystem::Decimal),
+            "1.5 M"
+        );
+        assert_eq!(
+            format_bytes(1_536, ByteMultiple::KB, UnitSystem::Binary),
+            "1.5 Ki"
+        );
+        assert_eq!(
+            format_bytes(1_536, ByteMultiple::B, UnitSystem::Binary),
+            "1536"
+        );
+        assert_eq!(
+            format_bytes(3_000_000_000_000, ByteMultiple::TB, UnitSystem::Decimal),
+            "3.0 T"
+        );
+    }
+
+    #[test]
+    fn test_formatted_bytes_string_is_padded() {
+        assert_eq!(
+            get_formatted_bytes_string(999, UnitSystem::Decimal),
+            "999   "
+        );
+        assert_eq!(
+            get_formatted_bytes_string(1500, UnitSystem::Decimal),
+            "1.5 K "
+        );
+    }
+
+    #[test]
+    fn test_formatted_duration() {
+        let format = |seconds| get_formatted_duration(chrono::Duration::seconds(seconds));
+        assert_eq!(get_formatted_duration(chrono::Duration::zero()), "0s");
+        assert_eq!(
+            get_formatted_duration(chrono::Duration::milliseconds(999)),
+            "0s"
+        );
+        assert_eq!(format(59), "59s");
+        assert_eq!(format(60), "1m 00s");
+        assert_eq!(format(185), "3m 05s");
+        assert_eq!(format(3600 + 120 + 7), "1h 02m");
+        assert_eq!(format(3 * 86_400 + 4 * 3600 + 59), "3d 04h");
+    }
+
+    #[test]
+    fn test_throughput_summary_string() {
+        let mut history = ThroughputHistory::new(60);
+        assert_eq!(
+            get_throughput_summary_string(&history, Duration::from_secs(1), UnitSystem::Decimal),
+            None
+        );
+        for tick in 0..20 {
+            let sample = ThroughputSample {
+                received_bytes: 1000 * u128::from(tick + 1),
+                sent_bytes: 10 * u128::from(tick + 1),
+                ..ThroughputSample::default()
+            };
+            history.push(tick, sample, 60);
+        }
+        assert_eq!(
+            get_throughput_summary_string(&history, Duration::from_secs(1), UnitSystem::Decimal)
+                .unwrap(),
+            "Throughput of the last 20s (incoming / outgoing)\n   peak: 20.0 KB/s / 200 B/s\n   95th percentile: 19.0 KB/s / 190 B/s"
+        );
+    }
+}
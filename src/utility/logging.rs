@@ -0,0 +1,125 @@
+//! Module containing the setup of the diagnostic events emitted on the standard error.
+//!
+//! The events are emitted through `tracing`, each with a level, the module emitting it as target,
+//! a message and a list of `key=value` fields, inside the spans of the capture, parsing and flush in progress.
+//! Only the events not more verbose than the maximum level are emitted: the level can be specified
+//! with `--log-level`, or per target with the `RUST_LOG` environment variable
+//! (e.g. `RUST_LOG=warn,sniffnet::thread_parse_packets=debug`).
+
+use std::sync::{LockResult, Mutex, MutexGuard};
+use std::{panic, process, thread};
+
+use tracing::{error, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::enums::log_level::LogLevel;
+
+/// Installs the subscriber emitting the events on the standard error.
+///
+/// The events are filtered by the given level if specified, otherwise by `RUST_LOG` if set
+/// (invalid directives are ignored), falling back to `default_level`.
+pub fn init_logging(level: Option<LogLevel>, default_level: LogLevel) {
+    let filter = match level {
+        Some(level) => EnvFilter::default().add_directive(level.to_level_filter().into()),
+        None => EnvFilter::builder()
+            .with_default_directive(default_level.to_level_filter().into())
+            .from_env_lossy(),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// Installs a panic hook emitting an error event, besides the default message, when a thread panics.
+///
+/// A panic on the calling (main) thread terminates the process, while a panicked secondary thread
+/// just terminates: the locks it was holding are recovered by the other threads (see `unwrap_or_log_poisoned`).
+pub fn install_panic_hook() {
+    let main_thread = thread::current().id();
+    let orig_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        // invoke the default handler first
+        orig_hook(panic_info);
+        let thread = thread::current();
+        if thread.id() == main_thread {
+            process::exit(1);
+        }
+        error!(
+            thread = thread.name().unwrap_or_default(),
+            "thread panicked, the application keeps running"
+        );
+    }));
+}
+
+/// Returns the guard of a lock, even if another thread panicked while holding it
+/// (the data protected by the lock are still usable, at worst the last update is incomplete):
+/// in that case a warning event is emitted, reporting the operation in progress.
+///
+/// This way, a bug hit while parsing a single packet doesn't bring down the whole application.
+pub fn unwrap_or_log_poisoned<G>(result: LockResult<G>, context: &str) -> G {
+    result.unwrap_or_else(|err| {
+        warn!(context, "lock poisoned by a panicked thread, recovered");
+        err.into_inner()
+    })
+}
+
+/// Acquires the mutex, recovering it if poisoned (see `unwrap_or_log_poisoned`).
+pub fn lock_or_recover<'a, T>(mutex: &'a Mutex<T>, context: &str) -> MutexGuard<'a, T> {
+    unwrap_or_log_poisoned(mutex.lock(), context)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use crate::structs::info_traffic::InfoTraffic;
+    use crate::utility::logging::{install_panic_hook, lock_or_recover};
+
+    #[test]
+    fn test_lock_or_recover_poisoned_mutex() {
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let info_traffic2 = info_traffic.clone();
+        thread::spawn(move || {
+            let mut info_traffic = info_traffic2.lock().unwrap();
+            info_traffic.all_packets = 5;
+            panic!("parse bug while holding the lock");
+        })
+        .join()
+        .unwrap_err();
+        assert!(info_traffic.is_poisoned());
+
+        let mut recovered = lock_or_recover(&info_traffic, "test");
+        assert_eq!(recovered.all_packets, 5);
+        recovered.all_packets += 1;
+        drop(recovered);
+        // the mutex stays poisoned, but it keeps being usable
+        assert_eq!(lock_or_recover(&info_traffic, "test").all_packets, 6);
+    }
+
+    #[test]
+    fn test_secondary_thread_panic_doesnt_exit() {
+        // this test thread plays the main one
+        install_panic_hook();
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let panicking = {
+            let info_traffic = info_traffic.clone();
+            thread::Builder::new()
+                .name("thread_parse_packets".to_string())
+                .spawn(move || {
+                    let mut info_traffic = info_traffic.lock().unwrap();
+                    info_traffic.all_packets = 5;
+                    panic!("parse bug while holding the lock");
+                })
+                .unwrap()
+        };
+        assert!(panicking.join().is_err());
+
+        // the process is still alive, and another thread keeps reading the traffic data
+        let reader = thread::spawn(move || {
+            lock_or_recover(&info_traffic, "reading after a panic").all_packets
+        });
+        assert_eq!(reader.join().unwrap(), 5);
+    }
+}
@@ -1,66 +1,122 @@
 use std::cell::RefMut;
 use std::collections::VecDeque;
 
+use crate::structs::throughput_history::ThroughputSample;
 use crate::RunTimeData;
 
-/// This function is invoked every second by the application subscription
+/// This function is invoked at every refresh interval by the application subscription
 ///
-/// It updates data (packets and bytes per second) to be displayed in the charts of gui run page
-pub fn update_charts_data(mut charts_data: RefMut<RunTimeData>) {
-    let tot_seconds = charts_data.ticks;
+/// It records the rates (packets and bytes per second) of the last interval in the throughput history,
+/// and updates the data to be displayed in the charts of gui run page, spanning the last `window_seconds`
+///
+/// Samples are labeled with the number of their interval, and their values are rates per second
+/// whatever the refresh interval.
+/// The history is bounded by its own time window, extended to the one of the chart if shorter.
+/// The function is driven by the refresh timer, not by the arrival of packets: an interval without traffic
+/// is recorded as a zero sample, so that the time axis keeps advancing and idle periods show up as gaps.
+pub fn update_charts_data(mut charts_data: RefMut<RunTimeData>, window_seconds: usize) {
+    let tick = charts_data.ticks;
     charts_data.ticks += 1;
+    let window_intervals = charts_data.intervals_in(window_seconds);
+    let history_intervals = charts_data
+        .intervals_in(charts_data.throughput_history.window_seconds)
+        .max(window_intervals);
 
-    let sent_bytes_entry = charts_data.tot_sent_bytes - charts_data.tot_sent_bytes_prev;
-    let received_bytes_entry = charts_data.tot_received_bytes - charts_data.tot_received_bytes_prev;
-    let sent_packets_entry = charts_data.tot_sent_packets - charts_data.tot_sent_packets_prev;
-    let received_packets_entry =
-        charts_data.tot_received_packets - charts_data.tot_received_packets_prev;
-
-    // update sent bytes traffic data
-    if charts_data.sent_bytes.len() >= 30 {
-        charts_data.sent_bytes.pop_front();
-    }
-    charts_data.sent_bytes.push_back((
-        tot_seconds,
-        -<u128 as TryInto<i64>>::try_into(sent_bytes_entry).unwrap(),
-    ));
-    charts_data.min_sent_bytes = get_min(&charts_data.sent_bytes.clone());
-    charts_data.tot_sent_bytes_prev = charts_data.tot_sent_bytes;
-    // update received bytes traffic data
-    if charts_data.received_bytes.len() >= 30 {
-        charts_data.received_bytes.pop_front();
-    }
+    let sample = ThroughputSample {
+        sent_bytes: charts_data
+            .per_second(charts_data.tot_sent_bytes - charts_data.tot_sent_bytes_prev),
+        received_bytes: charts_data
+            .per_second(charts_data.tot_received_bytes - charts_data.tot_received_bytes_prev),
+        sent_packets: charts_data
+            .per_second(charts_data.tot_sent_packets - charts_data.tot_sent_packets_prev),
+        received_packets: charts_data
+            .per_second(charts_data.tot_received_packets - charts_data.tot_received_packets_prev),
+    };
     charts_data
-        .received_bytes
-        .push_back((tot_seconds, received_bytes_entry.try_into().unwrap()));
-    charts_data.max_received_bytes = get_max(&charts_data.received_bytes.clone());
+        .throughput_history
+        .push(tick, sample, history_intervals);
+    charts_data.tot_sent_bytes_prev = charts_data.tot_sent_bytes;
     charts_data.tot_received_bytes_prev = charts_data.tot_received_bytes;
+    charts_data.tot_sent_packets_prev = charts_data.tot_sent_packets;
+    charts_data.tot_received_packets_prev = charts_data.tot_received_packets;
+
+    // update the scale of the charts
+    let history = &charts_data.throughput_history;
+    let min_sent_bytes = get_min(&history.series(window_intervals, sent_bytes_value));
+    let max_received_bytes = get_max(&history.series(window_intervals, received_bytes_value));
+    let min_sent_packets = get_min(&history.series(window_intervals, sent_packets_value));
+    let max_received_packets = get_max(&history.series(window_intervals, received_packets_value));
+    charts_data.min_sent_bytes = min_sent_bytes;
+    charts_data.max_received_bytes = max_received_bytes;
+    charts_data.min_sent_packets = min_sent_packets;
+    charts_data.max_received_packets = max_received_packets;
 
-    // update sent packets traffic data
-    if charts_data.sent_packets.len() >= 30 {
-        charts_data.sent_packets.pop_front();
+    // update packets traffic data of each application protocol
+    let app_protocols = charts_data.app_protocols.clone();
+    for (app_protocol, summary) in &app_protocols {
+        let packets_prev = charts_data
+            .app_protocols_prev
+            .get(app_protocol)
+            .map_or(0, |summary_prev| summary_prev.packets);
+        let packets_entry = charts_data
+            .per_second(summary.packets - packets_prev)
+            .try_into()
+            .unwrap();
+        push_sample(
+            charts_data
+                .app_protocol_packets
+                .entry(*app_protocol)
+                .or_default(),
+            tick,
+            packets_entry,
+            window_intervals,
+        );
     }
-    charts_data.sent_packets.push_back((
-        tot_seconds,
-        -<u128 as TryInto<i64>>::try_into(sent_packets_entry).unwrap(),
-    ));
-    charts_data.min_sent_packets = get_min(&charts_data.sent_packets.clone());
-    charts_data.tot_sent_packets_prev = charts_data.tot_sent_packets;
-    // update received packets traffic data
-    if charts_data.received_packets.len() >= 30 {
-        charts_data.received_packets.pop_front();
+    charts_data.app_protocols_prev = app_protocols;
+}
+
+/// Appends the sample taken at interval `tick`, evicting the samples that fall out of the time window
+/// (spanning `window_intervals` intervals).
+///
+/// Since eviction depends only on the time of the samples, all the series stay aligned.
+fn push_sample(deque: &mut VecDeque<(u32, i64)>, tick: u32, value: i64, window_intervals: usize) {
+    while deque.front().is_some_and(|(sample_tick, _)| {
+        usize::try_from(tick - sample_tick).unwrap_or(usize::MAX) >= window_intervals
+    }) {
+        deque.pop_front();
     }
-    charts_data
-        .received_packets
-        .push_back((tot_seconds, received_packets_entry.try_into().unwrap()));
-    charts_data.max_received_packets = get_max(&charts_data.received_packets.clone());
-    charts_data.tot_received_packets_prev = charts_data.tot_received_packets;
+    deque.push_back((tick, value));
+}
+
+/// Value of the sent bytes in the charts (negative, since they're plotted below the time axis)
+pub fn sent_bytes_value(sample: &ThroughputSample) -> i64 {
+    -to_chart_value(sample.sent_bytes)
+}
+
+/// Value of the received bytes in the charts
+pub fn received_bytes_value(sample: &ThroughputSample) -> i64 {
+    to_chart_value(sample.received_bytes)
+}
+
+/// Value of the sent packets in the charts (negative, since they're plotted below the time axis)
+pub fn sent_packets_value(sample: &ThroughputSample) -> i64 {
+    -to_chart_value(sample.sent_packets)
+}
+
+/// Value of the received packets in the charts
+pub fn received_packets_value(sample: &ThroughputSample) -> i64 {
+    to_chart_value(sample.received_packets)
+}
+
+/// Converts a rate into a chart coordinate, saturating it if it doesn't fit
+fn to_chart_value(rate: u128) -> i64 {
+    i64::try_from(rate).unwrap_or(i64::MAX)
 }
 
 /// Finds the minimum y value to be displayed in charts
-fn get_min(deque: &VecDeque<(u32, i64)>) -> i64 {
+fn get_min(series: &[(u32, i64)]) -> i64 {
     let mut min = 0;
-    for (_, x) in deque.iter() {
+    for (_, x) in series {
         if *x < min {
             min = *x;
         }
@@ -69,12 +125,183 @@ fn get_min(deque: &VecDeque<(u32, i64)>) -> i64 {
 }
 
 /// Finds the maximum y value to be displayed in charts
-fn get_max(deque: &VecDeque<(u32, i64)>) -> i64 {
+fn get_max(series: &[(u32, i64)]) -> i64 {
     let mut max = 0;
-    for (_, x) in deque.iter() {
+    for (_, x) in series {
         if *x > max {
             max = *x;
         }
     }
     max
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::time::Duration;
+
+    use crate::structs::throughput_history::ThroughputHistory;
+    use crate::utility::manage_charts_data::{
+        received_bytes_value, received_packets_value, sent_bytes_value, sent_packets_value,
+        update_charts_data,
+    };
+    use crate::{AppProtocol, RunTimeData};
+
+    #[test]
+    fn test_samples_older_than_window_are_dropped() {
+        let charts_data = RefCell::new(RunTimeData::new());
+        for second in 1..=15 {
+            {
+                let mut charts_data = charts_data.borrow_mut();
+                charts_data.tot_sent_bytes += 100 * second;
+                charts_data.tot_received_bytes += 1000 * second;
+                charts_data.tot_sent_packets += second;
+                charts_data.tot_received_packets += 2 * second;
+                charts_data
+                    .app_protocols
+                    .entry(AppProtocol::DNS)
+                    .or_default()
+                    .packets += second;
+                if second >= 12 {
+                    charts_data
+                        .app_protocols
+                        .entry(AppProtocol::HTTPS)
+                        .or_default()
+                        .packets += 3 * second;
+                }
+            }
+            update_charts_data(charts_data.borrow_mut(), 10);
+        }
+
+        let charts_data = charts_data.borrow();
+        assert_eq!(charts_data.ticks, 15);
+        let history = &charts_data.throughput_history;
+        // the history is longer than the time window of the chart
+        assert_eq!(history.len(), 15);
+        for value in [
+            sent_bytes_value,
+            received_bytes_value,
+            sent_packets_value,
+            received_packets_value,
+        ] {
+            let series = history.series(10, value);
+            assert_eq!(series.len(), 10);
+            // the oldest samples (taken at seconds 0 to 4) are not displayed
+            assert!(series.iter().map(|(time, _)| *time).eq(5..15));
+        }
+        assert_eq!(
+            history.series(10, sent_bytes_value).first(),
+            Some(&(5, -600))
+        );
+        assert_eq!(
+            history.series(10, received_bytes_value).last(),
+            Some(&(14, 15000))
+        );
+        // the scale only considers the samples in the window
+        assert_eq!(charts_data.min_sent_bytes, -1500);
+        assert_eq!(charts_data.max_received_packets, 30);
+        // the series of the application protocols are windowed as well
+        let dns = &charts_data.app_protocol_packets[&AppProtocol::DNS];
+        assert!(dns.iter().map(|(time, _)| *time).eq(5..15));
+        assert_eq!(dns.back(), Some(&(14, 15)));
+        // the series of a protocol starts when its first packet is sniffed
+        let https = &charts_data.app_protocol_packets[&AppProtocol::HTTPS];
+        assert!(https.iter().map(|(time, _)| *time).eq(11..15));
+        assert_eq!(https.front(), Some(&(11, 36)));
+    }
+
+    #[test]
+    fn test_samples_with_longer_refresh_interval() {
+        let charts_data = RefCell::new(RunTimeData::new());
+        charts_data.borrow_mut().refresh_interval = Duration::from_secs(2);
+        for interval in 1..=10 {
+            {
+                let mut charts_data = charts_data.borrow_mut();
+                charts_data.tot_received_bytes += 1000;
+                charts_data.tot_sent_packets += 10;
+            }
+            update_charts_data(charts_data.borrow_mut(), 10);
+            assert_eq!(charts_data.borrow().ticks, interval);
+        }
+
+        let charts_data = charts_data.borrow();
+        // a window of 10 seconds holds 5 intervals of 2 seconds
+        assert_eq!(charts_data.intervals_in(10), 5);
+        let received_bytes = charts_data
+            .throughput_history
+            .series(5, received_bytes_value);
+        assert!(received_bytes.iter().map(|(tick, _)| *tick).eq(5..10));
+        // samples are rates per second
+        assert!(received_bytes.iter().all(|(_, bytes)| *bytes == 500));
+        assert_eq!(charts_data.min_sent_packets, -5);
+    }
+
+    #[test]
+    fn test_idle_intervals_record_zero_samples() {
+        let charts_data = RefCell::new(RunTimeData::new());
+        for interval in 0..10 {
+            // traffic only in the first two and in the last interval
+            if interval < 2 || interval == 9 {
+                let mut charts_data = charts_data.borrow_mut();
+                charts_data.tot_sent_bytes += 100;
+                charts_data.tot_received_packets += 3;
+                charts_data
+                    .app_protocols
+                    .entry(AppProtocol::DNS)
+                    .or_default()
+                    .packets += 3;
+            }
+            update_charts_data(charts_data.borrow_mut(), 30);
+        }
+
+        let charts_data = charts_data.borrow();
+        assert_eq!(charts_data.ticks, 10);
+        let values = |series: &[(u32, i64)]| -> Vec<i64> {
+            assert!(series.iter().map(|(tick, _)| *tick).eq(0..10));
+            series.iter().map(|(_, value)| *value).collect()
+        };
+        let history = &charts_data.throughput_history;
+        let idle = [0; 7];
+        assert_eq!(values(&history.series(30, sent_bytes_value))[2..9], idle);
+        assert_eq!(values(&history.series(30, sent_bytes_value))[9], -100);
+        assert_eq!(
+            values(&history.series(30, received_packets_value))[2..9],
+            idle
+        );
+        assert_eq!(values(&history.series(30, received_packets_value))[9], 3);
+        let dns: Vec<(u32, i64)> = charts_data.app_protocol_packets[&AppProtocol::DNS]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(values(&dns)[2..9], idle);
+        // the scale still accounts for the traffic before the idle intervals
+        assert_eq!(charts_data.min_sent_bytes, -100);
+    }
+
+    #[test]
+    fn test_throughput_history_window() {
+        let charts_data = RefCell::new(RunTimeData::new());
+        charts_data.borrow_mut().throughput_history = ThroughputHistory::new(20);
+        for interval in 1..=30 {
+            charts_data.borrow_mut().tot_received_bytes += 100 * interval;
+            update_charts_data(charts_data.borrow_mut(), 10);
+        }
+        {
+            let charts_data = charts_data.borrow();
+            let history = &charts_data.throughput_history;
+            assert_eq!(history.len(), 20);
+            assert_eq!(history.peak().unwrap().received_bytes, 3000);
+            assert_eq!(history.percentile(50.0).unwrap().received_bytes, 2000);
+            // the chart only displays its own window
+            assert_eq!(history.series(10, received_bytes_value).len(), 10);
+        }
+
+        // a history shorter than the chart window is extended to it
+        let charts_data = RefCell::new(RunTimeData::new());
+        charts_data.borrow_mut().throughput_history = ThroughputHistory::new(5);
+        for _ in 0..30 {
+            update_charts_data(charts_data.borrow_mut(), 10);
+        }
+        assert_eq!(charts_data.borrow().throughput_history.len(), 10);
+    }
+}
@@ -1,43 +1,87 @@
 use crate::enums::logged_notification::{
-    BytesThresholdExceeded, FavoriteTransmitted, LoggedNotification, PacketsThresholdExceeded,
+    BytesThresholdExceeded, CaptureLag, ConnectionRateExceeded, FavoriteTransmitted,
+    LoggedNotification, NewCountry, PacketsThresholdExceeded,
 };
+use crate::enums::notification_direction::NotificationDirection;
 use crate::enums::sound::{play, Sound};
+use crate::enums::threshold_mode::ThresholdMode;
 use crate::structs::notifications::Notifications;
-use crate::{InfoTraffic, RunTimeData};
+use crate::utility::countries::{country_flag_emoji, country_name};
+use crate::utility::get_formatted_strings::{
+    get_formatted_bytes_string, get_formatted_lag, get_formatted_time,
+};
+use crate::utility::logging::lock_or_recover;
+use crate::{InfoTraffic, RunTimeData, UnitSystem};
 use chrono::Local;
-use std::cell::RefMut;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Minimum time elapsed between two consecutive webhook calls
+pub const WEBHOOK_MIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum time waited for the webhook to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Minimum time elapsed between two consecutive notification sounds
+pub const SOUND_MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Checks if the user defined notifications thresholds have been exceeded in the last interval,
+/// comparing them with the rates per second over the refresh interval of the configured direction of traffic
+/// (the multiples of the bytes thresholds are interpreted in the given unit system);
+/// the corresponding notifications are logged, emitting sounds and posting them to the webhook (if configured).
+///
+/// After a packets or bytes threshold notification, the following ones of the same type are suppressed
+/// for the configured cooldown, so that a threshold exceeded continuously doesn't flood the log.
 pub fn notify_and_log(
-    mut runtime_data: RefMut<RunTimeData>,
-    notifications: Notifications,
+    runtime_data: &mut RunTimeData,
+    notifications: &Notifications,
     info_traffic: &Arc<Mutex<InfoTraffic>>,
+    unit_system: UnitSystem,
 ) {
-    let mut already_emitted_sound = false;
+    let start = Instant::now();
+    // a single sound is emitted per interval, and only if the previous one is not too recent
+    let can_emit_sound = is_sound_allowed(runtime_data.last_sound_emitted, start);
+    let mut already_emitted_sound = !can_emit_sound;
+    let mut emitted_notifications = 0;
+    let threshold_cooldown = Duration::from_secs(notifications.threshold_cooldown_seconds);
     // packets threshold
-    if notifications.packets_notification.threshold.is_some() {
-        let sent_packets_entry = runtime_data.tot_sent_packets - runtime_data.tot_sent_packets_prev;
-        let received_packets_entry =
-            runtime_data.tot_received_packets - runtime_data.tot_received_packets_prev;
-        if received_packets_entry + sent_packets_entry
+    if notifications.packets_notification.threshold.is_some()
+        && is_cooldown_expired(
+            runtime_data.packets_threshold_last_notified,
+            start,
+            threshold_cooldown,
+        )
+    {
+        let sent_packets_entry = runtime_data
+            .per_second(runtime_data.tot_sent_packets - runtime_data.tot_sent_packets_prev);
+        let received_packets_entry = runtime_data
+            .per_second(runtime_data.tot_received_packets - runtime_data.tot_received_packets_prev);
+        let direction = notifications.packets_notification.direction;
+        if direction.compared_value(received_packets_entry, sent_packets_entry)
             > u128::from(notifications.packets_notification.threshold.unwrap())
         {
             // log this notification
-            if runtime_data.logged_notifications.len() >= 30 {
-                runtime_data.logged_notifications.pop_back();
-            }
-            runtime_data.logged_notifications.push_front(
+            log_notification(
+                &mut runtime_data.logged_notifications,
                 LoggedNotification::PacketsThresholdExceeded(PacketsThresholdExceeded {
                     threshold: notifications.packets_notification.previous_threshold,
+                    direction,
                     incoming: received_packets_entry.try_into().unwrap(),
                     outgoing: sent_packets_entry.try_into().unwrap(),
-                    timestamp: Local::now().to_string().get(11..19).unwrap().to_string(),
+                    timestamp: Local::now(),
                 }),
+                notifications.max_logged_notifications,
             );
-            if notifications.packets_notification.sound.ne(&Sound::None) {
+            runtime_data.packets_threshold_last_notified = Some(start);
+            emitted_notifications += 1;
+            if !already_emitted_sound && notifications.packets_notification.sound.ne(&Sound::None) {
                 // emit sound
                 play(
-                    notifications.packets_notification.sound,
+                    &notifications.packets_notification.sound,
                     notifications.volume,
                 );
                 already_emitted_sound = true;
@@ -45,29 +89,51 @@ pub fn notify_and_log(
         }
     }
     // bytes threshold
-    if notifications.bytes_notification.threshold.is_some() {
-        let sent_bytes_entry = runtime_data.tot_sent_bytes - runtime_data.tot_sent_bytes_prev;
-        let received_bytes_entry =
-            runtime_data.tot_received_bytes - runtime_data.tot_received_bytes_prev;
-        if received_bytes_entry + sent_bytes_entry
-            > u128::from(notifications.bytes_notification.threshold.unwrap())
-        {
+    if let Some(threshold) = notifications
+        .bytes_notification
+        .threshold_in(unit_system)
+        .filter(|_| {
+            is_cooldown_expired(
+                runtime_data.bytes_threshold_last_notified,
+                start,
+                threshold_cooldown,
+            )
+        })
+    {
+        let threshold_mode = notifications.bytes_notification.threshold_mode;
+        let [sent_bytes_entry, received_bytes_entry] = [
+            runtime_data.tot_sent_bytes - runtime_data.tot_sent_bytes_prev,
+            runtime_data.tot_received_bytes - runtime_data.tot_received_bytes_prev,
+        ]
+        .map(|bytes| match threshold_mode {
+            ThresholdMode::PerSecond => runtime_data.per_second(bytes),
+            ThresholdMode::PerInterval => bytes,
+        });
+        let direction = notifications.bytes_notification.direction;
+        if direction.compared_value(received_bytes_entry, sent_bytes_entry) > threshold {
             //log this notification
-            if runtime_data.logged_notifications.len() >= 30 {
-                runtime_data.logged_notifications.pop_back();
-            }
-            runtime_data.logged_notifications.push_front(
+            log_notification(
+                &mut runtime_data.logged_notifications,
                 LoggedNotification::BytesThresholdExceeded(BytesThresholdExceeded {
                     threshold: notifications.bytes_notification.previous_threshold,
                     byte_multiple: notifications.bytes_notification.byte_multiple,
+                    unit_system,
+                    threshold_mode,
+                    direction,
                     incoming: received_bytes_entry.try_into().unwrap(),
                     outgoing: sent_bytes_entry.try_into().unwrap(),
-                    timestamp: Local::now().to_string().get(11..19).unwrap().to_string(),
+                    timestamp: Local::now(),
                 }),
+                notifications.max_logged_notifications,
             );
+            runtime_data.bytes_threshold_last_notified = Some(start);
+            emitted_notifications += 1;
             if !already_emitted_sound && notifications.bytes_notification.sound.ne(&Sound::None) {
                 // emit sound
-                play(notifications.bytes_notification.sound, notifications.volume);
+                play(
+                    &notifications.bytes_notification.sound,
+                    notifications.volume,
+                );
                 already_emitted_sound = true;
             }
         }
@@ -76,30 +142,1247 @@ pub fn notify_and_log(
     if notifications.favorite_notification.notify_on_favorite
         && !runtime_data.favorites_last_interval.is_empty()
     {
-        let info_traffic_lock = info_traffic.lock().unwrap();
-        for index in &runtime_data.favorites_last_interval.clone() {
-            //log this notification
-            if runtime_data.logged_notifications.len() >= 30 {
-                runtime_data.logged_notifications.pop_back();
+        let info_traffic_lock = lock_or_recover(info_traffic, "notifying favorites");
+        let now = Instant::now();
+        let debounce = Duration::from_secs(notifications.favorite_debounce_seconds);
+        for id in &runtime_data.favorites_last_interval.clone() {
+            if !should_notify_favorite(
+                &mut runtime_data.favorites_last_notified,
+                *id,
+                now,
+                debounce,
+            ) {
+                // a notification for this connection has been logged too recently
+                continue;
             }
-            let key_val = info_traffic_lock.map.get_index(*index).unwrap();
-            runtime_data
-                .logged_notifications
-                .push_front(LoggedNotification::FavoriteTransmitted(
-                    FavoriteTransmitted {
-                        connection: (key_val.0.clone(), key_val.1.clone()),
-                        timestamp: Local::now().to_string().get(11..19).unwrap().to_string(),
-                    },
-                ));
+            //log this notification
+            let Some(key_val) = info_traffic_lock.get_connection(*id) else {
+                continue;
+            };
+            log_notification(
+                &mut runtime_data.logged_notifications,
+                LoggedNotification::FavoriteTransmitted(FavoriteTransmitted {
+                    connection: (key_val.0.clone(), key_val.1.clone()),
+                    timestamp: Local::now(),
+                }),
+                notifications.max_logged_notifications,
+            );
+            emitted_notifications += 1;
             if !already_emitted_sound && notifications.favorite_notification.sound.ne(&Sound::None)
             {
                 // emit sound
                 play(
-                    notifications.favorite_notification.sound,
+                    &notifications.favorite_notification.sound,
                     notifications.volume,
                 );
                 already_emitted_sound = true;
             }
         }
     }
+    // connections rate threshold
+    if let Some(threshold) = notifications
+        .connection_rate_notification
+        .threshold_in(unit_system)
+    {
+        let info_traffic_lock = lock_or_recover(info_traffic, "notifying connection rates");
+        let connections_over_rate = get_connections_over_rate(&info_traffic_lock, threshold);
+        for id in &connections_over_rate {
+            if runtime_data.connections_over_rate.contains(id) {
+                // already notified: the connection has been exceeding the threshold since the previous interval
+                continue;
+            }
+            //log this notification
+            let Some(key_val) = info_traffic_lock.get_connection(*id) else {
+                continue;
+            };
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let rate = key_val.1.bytes_per_second() as u64;
+            log_notification(
+                &mut runtime_data.logged_notifications,
+                LoggedNotification::ConnectionRateExceeded(ConnectionRateExceeded {
+                    threshold: notifications
+                        .connection_rate_notification
+                        .previous_threshold,
+                    byte_multiple: notifications.connection_rate_notification.byte_multiple,
+                    unit_system,
+                    connection: (key_val.0.clone(), key_val.1.clone()),
+                    rate,
+                    timestamp: Local::now(),
+                }),
+                notifications.max_logged_notifications,
+            );
+            emitted_notifications += 1;
+            if !already_emitted_sound
+                && notifications
+                    .connection_rate_notification
+                    .sound
+                    .ne(&Sound::None)
+            {
+                // emit sound
+                play(
+                    &notifications.connection_rate_notification.sound,
+                    notifications.volume,
+                );
+                already_emitted_sound = true;
+            }
+        }
+        runtime_data.connections_over_rate = connections_over_rate;
+    } else {
+        runtime_data.connections_over_rate.clear();
+    }
+    // from new countries
+    if notifications.new_country_notification.notify_on_new_country
+        && !runtime_data.new_countries_last_interval.is_empty()
+    {
+        let info_traffic_lock = lock_or_recover(info_traffic, "notifying new countries");
+        for (country, id) in &std::mem::take(&mut runtime_data.new_countries_last_interval) {
+            if notifications.is_country_allowed(country) {
+                continue;
+            }
+            //log this notification
+            let Some(key_val) = info_traffic_lock.get_connection(*id) else {
+                continue;
+            };
+            log_notification(
+                &mut runtime_data.logged_notifications,
+                LoggedNotification::NewCountry(NewCountry {
+                    country: country.clone(),
+                    connection: (key_val.0.clone(), key_val.1.clone()),
+                    timestamp: Local::now(),
+                }),
+                notifications.max_logged_notifications,
+            );
+            emitted_notifications += 1;
+            if !already_emitted_sound
+                && notifications
+                    .new_country_notification
+                    .sound
+                    .ne(&Sound::None)
+            {
+                // emit sound
+                play(
+                    &notifications.new_country_notification.sound,
+                    notifications.volume,
+                );
+                already_emitted_sound = true;
+            }
+        }
+    }
+    // capture falling behind: notified when the lag starts exceeding the threshold (no sound is emitted)
+    let threshold_seconds = notifications.capture_lag_threshold_seconds;
+    if threshold_seconds > 0 {
+        let capture_lagging = runtime_data.capture_lag > Duration::from_secs(threshold_seconds);
+        if capture_lagging && !runtime_data.capture_lagging {
+            let lag_ms = u64::try_from(runtime_data.capture_lag.as_millis()).unwrap_or(u64::MAX);
+            log_notification(
+                &mut runtime_data.logged_notifications,
+                LoggedNotification::CaptureLag(CaptureLag {
+                    threshold_seconds,
+                    lag_ms,
+                    timestamp: Local::now(),
+                }),
+                notifications.max_logged_notifications,
+            );
+            emitted_notifications += 1;
+        }
+        runtime_data.capture_lagging = capture_lagging;
+    }
+    if can_emit_sound && already_emitted_sound {
+        runtime_data.last_sound_emitted = Some(start);
+    }
+    // webhook
+    if let Some(webhook_url) = &notifications.webhook_url {
+        let can_call_webhook = runtime_data
+            .last_webhook_call
+            .is_none_or(|last_call| last_call.elapsed() >= WEBHOOK_MIN_INTERVAL);
+        if emitted_notifications > 0 && can_call_webhook {
+            runtime_data.last_webhook_call = Some(Instant::now());
+            // emitted notifications are at the front of the log, most recent first
+            let emitted: Vec<&LoggedNotification> = runtime_data
+                .logged_notifications
+                .iter()
+                .take(emitted_notifications)
+                .rev()
+                .collect();
+            let payload = get_webhook_payload(&emitted);
+            post_to_webhook(webhook_url.clone(), payload);
+        }
+    }
+}
+
+/// Returns the identifiers of the connections whose bytes per second in the last refresh interval exceeded the threshold.
+fn get_connections_over_rate(info_traffic: &InfoTraffic, threshold: u128) -> HashSet<u64> {
+    #[allow(clippy::cast_precision_loss)]
+    let threshold = threshold as f64;
+    info_traffic
+        .map
+        .values()
+        .filter(|info| info.bytes_per_second() > threshold)
+        .map(|info| info.id)
+        .collect()
+}
+
+/// Inserts a notification at the front of the log, dropping the oldest ones
+/// so that the log never contains more than `max_logged_notifications` entries.
+fn log_notification(
+    logged_notifications: &mut VecDeque<LoggedNotification>,
+    notification: LoggedNotification,
+    max_logged_notifications: usize,
+) {
+    if max_logged_notifications == 0 {
+        logged_notifications.clear();
+        return;
+    }
+    info!(description = %get_webhook_text(&notification), "notification triggered");
+    logged_notifications.truncate(max_logged_notifications - 1);
+    logged_notifications.push_front(notification);
+}
+
+/// Checks whether a notification sound can be emitted at `now`,
+/// i.e. if no other sound has been emitted in the last `SOUND_MIN_INTERVAL`.
+fn is_sound_allowed(last_sound_emitted: Option<Instant>, now: Instant) -> bool {
+    last_sound_emitted
+        .is_none_or(|last_sound| now.saturating_duration_since(last_sound) >= SOUND_MIN_INTERVAL)
+}
+
+/// Checks whether the cooldown started by the last notification of a type (if any) has expired at `now`.
+fn is_cooldown_expired(last_notified: Option<Instant>, now: Instant, cooldown: Duration) -> bool {
+    last_notified
+        .is_none_or(|last_notified| now.saturating_duration_since(last_notified) >= cooldown)
+}
+
+/// Checks whether a notification can be emitted for the favorite connection with the given identifier,
+/// i.e. if no other notification has been emitted for it in the last `debounce` time window;
+/// in that case the last notification time of the connection is updated.
+fn should_notify_favorite(
+    favorites_last_notified: &mut HashMap<u64, Instant>,
+    id: u64,
+    now: Instant,
+    debounce: Duration,
+) -> bool {
+    match favorites_last_notified.get(&id) {
+        Some(last_notified) if now.duration_since(*last_notified) < debounce => false,
+        _ => {
+            favorites_last_notified.insert(id, now);
+            true
+        }
+    }
+}
+
+/// JSON payload posted to the webhook
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    /// Human readable summary of the notifications (displayed by Slack compatible webhooks)
+    text: String,
+    /// Notifications emitted in the last interval, from the oldest to the most recent
+    notifications: &'a [&'a LoggedNotification],
+}
+
+/// Builds the JSON payload describing the given notifications.
+fn get_webhook_payload(notifications: &[&LoggedNotification]) -> String {
+    let text = notifications
+        .iter()
+        .map(|notification| get_webhook_text(notification))
+        .collect::<Vec<String>>()
+        .join("\n");
+    serde_json::to_string(&WebhookPayload {
+        text,
+        notifications,
+    })
+    .expect("Error serializing webhook payload\n\r")
+}
+
+/// Returns a human readable description of a notification.
+fn get_webhook_text(notification: &LoggedNotification) -> String {
+    let time = get_formatted_time(notification.timestamp());
+    match notification {
+        LoggedNotification::PacketsThresholdExceeded(packets) => format!(
+            "[{}] Sniffnet: {}packets threshold exceeded ({} packets/s, threshold {})",
+            time,
+            get_direction_prefix(packets.direction),
+            packets
+                .direction
+                .compared_value(packets.incoming, packets.outgoing),
+            packets.threshold
+        ),
+        LoggedNotification::BytesThresholdExceeded(bytes) => format!(
+            "[{}] Sniffnet: {}bytes threshold exceeded ({}B{}, threshold {}{}B)",
+            time,
+            get_direction_prefix(bytes.direction),
+            get_formatted_bytes_string(
+                u128::from(bytes.direction.compared_value(bytes.incoming, bytes.outgoing)),
+                bytes.unit_system
+            )
+            .trim(),
+            match bytes.threshold_mode {
+                ThresholdMode::PerSecond => "/s",
+                ThresholdMode::PerInterval => " per interval",
+            },
+            bytes.threshold / bytes.byte_multiple.get_multiplier(),
+            bytes.byte_multiple.get_prefix(bytes.unit_system)
+        ),
+        LoggedNotification::FavoriteTransmitted(favorite) => format!(
+            "[{}] Sniffnet: favorite connection exchanged data ({}:{} - {}:{})",
+            time,
+            favorite.connection.0.address1,
+            favorite.connection.0.port1,
+            favorite.connection.0.address2,
+            favorite.connection.0.port2
+        ),
+        LoggedNotification::ConnectionRateExceeded(connection_rate) => format!(
+            "[{}] Sniffnet: connection rate threshold exceeded ({}:{} - {}:{}, {}B/s, threshold {}{}B)",
+            time,
+            connection_rate.connection.0.address1,
+            connection_rate.connection.0.port1,
+            connection_rate.connection.0.address2,
+            connection_rate.connection.0.port2,
+            get_formatted_bytes_string(u128::from(connection_rate.rate), connection_rate.unit_system)
+                .trim(),
+            connection_rate.threshold / connection_rate.byte_multiple.get_multiplier(),
+            connection_rate
+                .byte_multiple
+                .get_prefix(connection_rate.unit_system)
+        ),
+        LoggedNotification::NewCountry(new_country) => format!(
+            "[{}] Sniffnet: traffic exchanged with a new country ({} {}, {} - {})",
+            time,
+            country_flag_emoji(&new_country.country),
+            country_name(&new_country.country),
+            new_country.connection.0.address1,
+            new_country.connection.0.address2
+        ),
+        LoggedNotification::CaptureLag(capture_lag) => format!(
+            "[{}] Sniffnet: capture falling behind ({}, threshold {} s)",
+            time,
+            get_formatted_lag(capture_lag.lag_ms),
+            capture_lag.threshold_seconds
+        ),
+    }
+}
+
+/// Returns the qualifier of the traffic compared with a threshold, to be prepended to its description.
+fn get_direction_prefix(direction: NotificationDirection) -> &'static str {
+    match direction {
+        NotificationDirection::Both => "",
+        NotificationDirection::Inbound => "incoming ",
+        NotificationDirection::Outbound => "outgoing ",
+    }
+}
+
+/// Posts the payload to the webhook on a separate thread, so that network latency never blocks the caller.
+///
+/// Only `http` and `https` URLs are called; failures are logged as warning events.
+fn post_to_webhook(webhook_url: String, payload: String) {
+    if !is_http_url(&webhook_url) {
+        warn!(%webhook_url, "webhook not called, its URL must start with http:// or https://");
+        return;
+    }
+    let spawned = thread::Builder::new()
+        .name("thread_webhook".to_string())
+        .spawn(move || {
+            let result = ureq::post(&webhook_url)
+                .timeout(WEBHOOK_TIMEOUT)
+                .set("Content-Type", "application/json")
+                .send_string(&payload);
+            match result {
+                Ok(_) => {}
+                Err(ureq::Error::Status(status, _)) => warn!(status, "webhook call failed"),
+                Err(err) => warn!(%err, "cannot call webhook"),
+            }
+        });
+    if let Err(err) = spawned {
+        warn!(%err, "cannot start the thread calling the webhook");
+    }
+}
+
+/// Checks whether the URL has the `http` or `https` scheme (case insensitive).
+fn is_http_url(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, rest)| {
+        !rest.is_empty()
+            && (scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::logged_notification::{
+        BytesThresholdExceeded, LoggedNotification, PacketsThresholdExceeded,
+    };
+    use crate::enums::notification_direction::NotificationDirection;
+    use crate::enums::sound::Sound;
+    use crate::enums::threshold_mode::ThresholdMode;
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::info_address_port_pair::InfoAddressPortPair;
+    use crate::structs::notifications::{
+        BytesNotification, FavoriteNotification, NewCountryNotification, Notifications,
+        PacketsNotification,
+    };
+    use crate::structs::traffic_buffer::{FlushCadence, TrafficBuffer};
+    use crate::utility::countries::get_country_db_reader;
+    use crate::utility::get_formatted_strings::get_formatted_time;
+    use crate::utility::manage_notifications::{
+        get_connections_over_rate, get_webhook_payload, get_webhook_text, is_http_url,
+        is_sound_allowed, log_notification, notify_and_log, post_to_webhook,
+        should_notify_favorite, SOUND_MIN_INTERVAL,
+    };
+    use crate::{AppProtocol, ByteMultiple, InfoTraffic, RunTimeData, TransProtocol, UnitSystem};
+    use chrono::{DateTime, Local, TimeZone};
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// Time of a notification emitted the given number of seconds after a fixed instant.
+    fn timestamp(seconds: usize) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2023, 1, 31, 10, 30, 0).unwrap()
+            + chrono::Duration::seconds(i64::try_from(seconds).unwrap())
+    }
+
+    fn packets_notification(seconds: usize) -> LoggedNotification {
+        LoggedNotification::PacketsThresholdExceeded(PacketsThresholdExceeded {
+            threshold: 750,
+            direction: NotificationDirection::Both,
+            incoming: 1000,
+            outgoing: 200,
+            timestamp: timestamp(seconds),
+        })
+    }
+
+    #[test]
+    fn test_logged_notifications_never_exceed_max() {
+        let max_logged_notifications = 5;
+        let mut logged_notifications = VecDeque::new();
+        for i in 0..12 {
+            log_notification(
+                &mut logged_notifications,
+                packets_notification(i),
+                max_logged_notifications,
+            );
+            assert!(logged_notifications.len() <= max_logged_notifications);
+            // the most recent notification is at the front, the oldest still logged at the back
+            assert_eq!(logged_notifications[0].timestamp(), timestamp(i));
+            let oldest = i.saturating_sub(max_logged_notifications - 1);
+            assert_eq!(
+                logged_notifications.back().unwrap().timestamp(),
+                timestamp(oldest)
+            );
+        }
+        assert_eq!(logged_notifications.len(), max_logged_notifications);
+
+        log_notification(&mut logged_notifications, packets_notification(12), 0);
+        assert!(logged_notifications.is_empty());
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let packets = LoggedNotification::PacketsThresholdExceeded(PacketsThresholdExceeded {
+            threshold: 750,
+            direction: NotificationDirection::Both,
+            incoming: 1000,
+            outgoing: 200,
+            timestamp: timestamp(0),
+        });
+        let bytes = LoggedNotification::BytesThresholdExceeded(BytesThresholdExceeded {
+            threshold: 800_000,
+            byte_multiple: ByteMultiple::KB,
+            unit_system: UnitSystem::Decimal,
+            threshold_mode: ThresholdMode::PerSecond,
+            direction: NotificationDirection::Both,
+            incoming: 900_000,
+            outgoing: 100_000,
+            timestamp: timestamp(1),
+        });
+        // the text reports the time of day, the notifications the full timestamp
+        assert_eq!(
+            get_webhook_payload(&[&packets, &bytes]),
+            format!("{{\"text\":\"[10:30:00] Sniffnet: packets threshold exceeded (1200 packets/s, threshold 750)\\n\
+[10:30:01] Sniffnet: bytes threshold exceeded (1.0 MB/s, threshold 800KB)\",\
+\"notifications\":[\
+{{\"type\":\"PacketsThresholdExceeded\",\"threshold\":750,\"direction\":\"Both\",\"incoming\":1000,\"outgoing\":200,\"timestamp\":{}}},\
+{{\"type\":\"BytesThresholdExceeded\",\"threshold\":800000,\"byte_multiple\":\"KB\",\"unit_system\":\"Decimal\",\"threshold_mode\":\"PerSecond\",\"direction\":\"Both\",\"incoming\":900000,\"outgoing\":100000,\
+\"timestamp\":{}}}]}}",
+                serde_json::to_string(&timestamp(0)).unwrap(),
+                serde_json::to_string(&timestamp(1)).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_webhook_url_scheme() {
+        assert!(is_http_url(
+            "https://hooks.slack.com/services/T000/B000/XXXX"
+        ));
+        assert!(is_http_url("HTTP://127.0.0.1:8080/notify"));
+        assert!(!is_http_url("file:///etc/passwd"));
+        assert!(!is_http_url("--config=/tmp/curlrc"));
+        assert!(!is_http_url("https://"));
+    }
+
+    #[test]
+    fn test_post_to_webhook() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let webhook_url = format!("http://{}/notify", listener.local_addr().unwrap());
+        post_to_webhook(webhook_url, "{\"text\":\"hello\"}".to_string());
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim_end().is_empty() {
+                break;
+            }
+            headers.push(line.trim_end().to_lowercase());
+        }
+        let mut body = vec![0; 16];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+
+        assert_eq!(headers[0], "post /notify http/1.1");
+        assert!(headers.contains(&"content-type: application/json".to_string()));
+        assert!(headers.contains(&"content-length: 16".to_string()));
+        assert_eq!(body, b"{\"text\":\"hello\"}");
+    }
+    #[test]
+    fn test_favorite_notifications_debounce() {
+        let debounce = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut favorites_last_notified = HashMap::new();
+
+        assert!(should_notify_favorite(
+            &mut favorites_last_notified,
+            0,
+            start,
+            debounce
+        ));
+        // another favorite connection is not affected
+        assert!(should_notify_favorite(
+            &mut favorites_last_notified,
+            1,
+            start,
+            debounce
+        ));
+        // same connection inside the window
+        for secs in [1, 5, 9] {
+            let now = start + Duration::from_secs(secs);
+            assert!(!should_notify_favorite(
+                &mut favorites_last_notified,
+                0,
+                now,
+                debounce
+            ));
+        }
+        // suppressed notifications don't extend the window
+        let after_window = start + Duration::from_secs(10);
+        assert!(should_notify_favorite(
+            &mut favorites_last_notified,
+            0,
+            after_window,
+            debounce
+        ));
+        assert!(!should_notify_favorite(
+            &mut favorites_last_notified,
+            0,
+            after_window + Duration::from_secs(3),
+            debounce
+        ));
+
+        // a zero window never suppresses notifications
+        assert!(should_notify_favorite(
+            &mut favorites_last_notified,
+            0,
+            after_window,
+            Duration::ZERO
+        ));
+    }
+
+    #[test]
+    fn test_favorite_notifications() {
+        let key = |port: u16| {
+            AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                port,
+                "1.1.1.1".to_string(),
+                443,
+                TransProtocol::TCP,
+            )
+        };
+        let connection =
+            || InfoAddressPortPair::with_traffic(TrafficType::Outgoing, AppProtocol::HTTPS, 500, 1);
+        let mut favorite = connection();
+        favorite.is_favorite = true;
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::with_connections([
+            (key(50000), connection()),
+            (key(50001), favorite),
+        ])));
+        let notifications = Notifications {
+            favorite_notification: FavoriteNotification::on(Sound::None),
+            ..Notifications::default()
+        };
+        let mut runtime_data = RunTimeData::new();
+        // the favorite connection exchanged data, together with a favorite connection evicted meanwhile
+        runtime_data.favorites_last_interval = HashSet::from([1, 7]);
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+
+        assert_eq!(runtime_data.logged_notifications.len(), 1);
+        let LoggedNotification::FavoriteTransmitted(favorite) =
+            &runtime_data.logged_notifications[0]
+        else {
+            panic!("unexpected notification type");
+        };
+        assert!(favorite.connection.0 == key(50001));
+        assert_eq!(favorite.connection.1.id, 1);
+        assert_eq!(favorite.connection.1.transmitted_bytes, 500);
+
+        // no notification if favorites are not notified
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.favorites_last_interval = HashSet::from([1]);
+        notify_and_log(
+            &mut runtime_data,
+            &Notifications::default(),
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert!(runtime_data.logged_notifications.is_empty());
+    }
+
+    #[test]
+    fn test_new_country_notifications() {
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "8.8.8.8".to_string(),
+            53,
+            TransProtocol::UDP,
+        );
+        info_traffic.lock().unwrap().flush_packets(
+            "eth0",
+            [(key, 100, TrafficType::Outgoing, AppProtocol::DNS)],
+        );
+        let new_countries = info_traffic
+            .lock()
+            .unwrap()
+            .new_countries_last_interval
+            .clone();
+        let notifications = Notifications {
+            new_country_notification: NewCountryNotification::on(Sound::None),
+            ..Notifications::default()
+        };
+
+        // expected countries are not notified
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.new_countries_last_interval = new_countries.clone();
+        let allowing_us = Notifications {
+            allowed_countries: vec!["us".to_string()],
+            ..notifications.clone()
+        };
+        notify_and_log(
+            &mut runtime_data,
+            &allowing_us,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert!(runtime_data.logged_notifications.is_empty());
+
+        runtime_data.new_countries_last_interval = new_countries;
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert_eq!(runtime_data.logged_notifications.len(), 1);
+        let LoggedNotification::NewCountry(new_country) = &runtime_data.logged_notifications[0]
+        else {
+            panic!("unexpected notification type");
+        };
+        assert_eq!(new_country.country, "US");
+        assert_eq!(new_country.connection.0.address2, "8.8.8.8");
+        assert!(get_webhook_text(&runtime_data.logged_notifications[0]).ends_with(
+            "Sniffnet: traffic exchanged with a new country (\u{1F1FA}\u{1F1F8} United States, 192.168.1.10 - 8.8.8.8)"
+        ));
+        // the new countries are consumed by the notification
+        assert!(runtime_data.new_countries_last_interval.is_empty());
+    }
+
+    #[test]
+    fn test_connection_rate_notifications() {
+        let country_db_reader = get_country_db_reader(None);
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        for (port, bytes) in [(443, 50_000), (80, 100)] {
+            let key = AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                50000,
+                "192.168.1.1".to_string(),
+                port,
+                TransProtocol::TCP,
+            );
+            buffer.add_connection(key, bytes, TrafficType::Outgoing, AppProtocol::Other);
+        }
+        buffer.flush(
+            &mut info_traffic.lock().unwrap(),
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        let refresh = |secs: i64| {
+            let mut info_traffic = info_traffic.lock().unwrap();
+            let now = info_traffic.map[0].initial_timestamp + chrono::Duration::seconds(secs);
+            for info in info_traffic.map.values_mut() {
+                info.refresh_rate(now);
+            }
+        };
+        let notifications = Notifications {
+            connection_rate_notification: BytesNotification {
+                threshold: Some(10_000),
+                previous_threshold: 10_000,
+                byte_multiple: ByteMultiple::KB,
+                sound: Sound::None,
+                threshold_mode: ThresholdMode::PerSecond,
+                direction: NotificationDirection::Both,
+            },
+            ..Notifications::default()
+        };
+        let mut runtime_data = RunTimeData::new();
+
+        refresh(1);
+        assert_eq!(
+            get_connections_over_rate(&info_traffic.lock().unwrap(), 10_000),
+            HashSet::from([0])
+        );
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert_eq!(runtime_data.logged_notifications.len(), 1);
+        if let LoggedNotification::ConnectionRateExceeded(connection_rate) =
+            &runtime_data.logged_notifications[0]
+        {
+            assert_eq!(connection_rate.rate, 50_000);
+            assert_eq!(connection_rate.connection.0.port2, 443);
+        } else {
+            panic!("unexpected notification type");
+        }
+
+        // no further notification while the connection keeps exceeding the threshold
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert_eq!(runtime_data.logged_notifications.len(), 1);
+
+        // the connection goes idle and then exceeds the threshold again
+        refresh(2);
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert!(runtime_data.connections_over_rate.is_empty());
+        info_traffic.lock().unwrap().map[0].transmitted_bytes += 20_000;
+        refresh(3);
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert_eq!(runtime_data.logged_notifications.len(), 2);
+    }
+
+    #[test]
+    fn test_notification_timestamp_format() {
+        let notifications = Notifications {
+            packets_notification: PacketsNotification {
+                threshold: Some(10),
+                previous_threshold: 10,
+                sound: Sound::None,
+                ..PacketsNotification::default()
+            },
+            ..Notifications::default()
+        };
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.tot_sent_packets = 100;
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &Arc::new(Mutex::new(InfoTraffic::new())),
+            UnitSystem::Decimal,
+        );
+        assert_eq!(runtime_data.logged_notifications.len(), 1);
+        let time = get_formatted_time(runtime_data.logged_notifications[0].timestamp());
+        assert_eq!(time.len(), 8);
+        assert!(chrono::NaiveTime::parse_from_str(&time, "%H:%M:%S").is_ok());
+    }
+
+    #[test]
+    fn test_sounds_rate_limited_across_intervals() {
+        let start = Instant::now();
+        assert!(is_sound_allowed(None, start));
+        assert!(!is_sound_allowed(
+            Some(start),
+            start + Duration::from_secs(1)
+        ));
+        assert!(is_sound_allowed(Some(start), start + SOUND_MIN_INTERVAL));
+
+        let notifications = Notifications {
+            // muted, so that no sound is actually played by the test
+            volume: 0,
+            packets_notification: PacketsNotification {
+                threshold: Some(10),
+                previous_threshold: 10,
+                sound: Sound::Pop,
+                ..PacketsNotification::default()
+            },
+            threshold_cooldown_seconds: 0,
+            ..Notifications::default()
+        };
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.tot_sent_packets = 100;
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        let first_sound = runtime_data.last_sound_emitted.unwrap();
+
+        // the threshold is exceeded again right after: the notification is logged, but no sound is emitted
+        runtime_data.tot_sent_packets = 200;
+        runtime_data.tot_sent_packets_prev = 100;
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert_eq!(runtime_data.logged_notifications.len(), 2);
+        assert_eq!(runtime_data.last_sound_emitted, Some(first_sound));
+    }
+
+    #[test]
+    fn test_thresholds_compared_with_direction() {
+        // 100 packets and bytes received, 150 sent: the threshold is exceeded by the sent traffic and by the total
+        for (direction, exceeded) in [
+            (NotificationDirection::Both, true),
+            (NotificationDirection::Inbound, false),
+            (NotificationDirection::Outbound, true),
+        ] {
+            let notifications = Notifications {
+                packets_notification: PacketsNotification {
+                    threshold: Some(100),
+                    previous_threshold: 100,
+                    sound: Sound::None,
+                    direction,
+                },
+                bytes_notification: BytesNotification {
+                    threshold: Some(100),
+                    previous_threshold: 100,
+                    byte_multiple: ByteMultiple::B,
+                    sound: Sound::None,
+                    direction,
+                    ..BytesNotification::default()
+                },
+                ..Notifications::default()
+            };
+            let mut runtime_data = RunTimeData::new();
+            runtime_data.tot_received_packets = 100;
+            runtime_data.tot_sent_packets = 150;
+            runtime_data.tot_received_bytes = 100;
+            runtime_data.tot_sent_bytes = 150;
+            notify_and_log(
+                &mut runtime_data,
+                &notifications,
+                &Arc::new(Mutex::new(InfoTraffic::new())),
+                UnitSystem::Decimal,
+            );
+            if !exceeded {
+                assert!(runtime_data.logged_notifications.is_empty());
+                continue;
+            }
+            assert_eq!(runtime_data.logged_notifications.len(), 2);
+            let LoggedNotification::BytesThresholdExceeded(bytes) =
+                &runtime_data.logged_notifications[0]
+            else {
+                panic!("unexpected notification type");
+            };
+            assert_eq!(bytes.direction, direction);
+            let LoggedNotification::PacketsThresholdExceeded(packets) =
+                &runtime_data.logged_notifications[1]
+            else {
+                panic!("unexpected notification type");
+            };
+            assert_eq!(packets.direction, direction);
+            assert_eq!((packets.incoming, packets.outgoing), (100, 150));
+        }
+    }
+
+    #[test]
+    fn test_webhook_text_reports_direction() {
+        let packets = |direction| {
+            LoggedNotification::PacketsThresholdExceeded(PacketsThresholdExceeded {
+                threshold: 750,
+                direction,
+                incoming: 1000,
+                outgoing: 200,
+                timestamp: timestamp(0),
+            })
+        };
+        assert_eq!(
+            get_webhook_text(&packets(NotificationDirection::Inbound)),
+            "[10:30:00] Sniffnet: incoming packets threshold exceeded (1000 packets/s, threshold 750)"
+        );
+        assert_eq!(
+            get_webhook_text(&packets(NotificationDirection::Outbound)),
+            "[10:30:00] Sniffnet: outgoing packets threshold exceeded (200 packets/s, threshold 750)"
+        );
+    }
+
+    #[test]
+    fn test_thresholds_evaluated_per_second() {
+        let notifications = Notifications {
+            packets_notification: PacketsNotification {
+                threshold: Some(100),
+                previous_threshold: 100,
+                sound: Sound::None,
+                ..PacketsNotification::default()
+            },
+            ..Notifications::default()
+        };
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.refresh_interval = Duration::from_secs(5);
+        // 400 packets in 5 seconds are 80 packets per second: below the threshold
+        runtime_data.tot_sent_packets = 400;
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert!(runtime_data.logged_notifications.is_empty());
+
+        // 1000 packets in 5 seconds are 200 packets per second
+        runtime_data.tot_sent_packets = 1000;
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        let LoggedNotification::PacketsThresholdExceeded(packets) =
+            &runtime_data.logged_notifications[0]
+        else {
+            panic!("unexpected notification type");
+        };
+        assert_eq!(packets.outgoing, 200);
+    }
+
+    #[test]
+    fn test_threshold_notifications_cooldown() {
+        let notifications = Notifications {
+            packets_notification: PacketsNotification {
+                threshold: Some(10),
+                previous_threshold: 10,
+                sound: Sound::None,
+                ..PacketsNotification::default()
+            },
+            bytes_notification: BytesNotification {
+                threshold: Some(1000),
+                previous_threshold: 1000,
+                sound: Sound::None,
+                ..BytesNotification::default()
+            },
+            threshold_cooldown_seconds: 30,
+            ..Notifications::default()
+        };
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut runtime_data = RunTimeData::new();
+        // both the thresholds are exceeded in every interval
+        let exceed_thresholds = |runtime_data: &mut RunTimeData| {
+            runtime_data.tot_sent_packets_prev = runtime_data.tot_sent_packets;
+            runtime_data.tot_sent_bytes_prev = runtime_data.tot_sent_bytes;
+            runtime_data.tot_sent_packets += 100;
+            runtime_data.tot_sent_bytes += 5000;
+            notify_and_log(
+                runtime_data,
+                &notifications,
+                &info_traffic,
+                UnitSystem::Decimal,
+            );
+        };
+
+        exceed_thresholds(&mut runtime_data);
+        assert_eq!(runtime_data.logged_notifications.len(), 2);
+        // suppressed during the cooldown
+        for _ in 0..5 {
+            exceed_thresholds(&mut runtime_data);
+        }
+        assert_eq!(runtime_data.logged_notifications.len(), 2);
+
+        // the cooldown of the bytes notifications expires: the packets ones are still suppressed
+        let Some(expired) = Instant::now().checked_sub(Duration::from_secs(30)) else {
+            return;
+        };
+        runtime_data.bytes_threshold_last_notified = Some(expired);
+        exceed_thresholds(&mut runtime_data);
+        assert_eq!(runtime_data.logged_notifications.len(), 3);
+        assert!(matches!(
+            runtime_data.logged_notifications[0],
+            LoggedNotification::BytesThresholdExceeded(_)
+        ));
+
+        // logging resumes for both the types once the cooldowns expire
+        runtime_data.packets_threshold_last_notified = Some(expired);
+        runtime_data.bytes_threshold_last_notified = Some(expired);
+        exceed_thresholds(&mut runtime_data);
+        assert_eq!(runtime_data.logged_notifications.len(), 5);
+        exceed_thresholds(&mut runtime_data);
+        assert_eq!(runtime_data.logged_notifications.len(), 5);
+    }
+
+    #[test]
+    fn test_packets_and_bytes_thresholds() {
+        let notifications = Notifications {
+            packets_notification: PacketsNotification {
+                threshold: Some(50),
+                previous_threshold: 50,
+                sound: Sound::None,
+                ..PacketsNotification::default()
+            },
+            bytes_notification: BytesNotification {
+                threshold: Some(10_000),
+                previous_threshold: 10_000,
+                sound: Sound::None,
+                ..BytesNotification::default()
+            },
+            ..Notifications::default()
+        };
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut runtime_data = RunTimeData::new();
+        // (sent packets, received packets, sent bytes, received bytes) exchanged in each interval
+        let intervals = [
+            (10, 20, 1000, 2000),
+            (40, 20, 3000, 4000),
+            (5, 5, 8000, 7000),
+        ];
+        for (sent_packets, received_packets, sent_bytes, received_bytes) in intervals {
+            runtime_data.tot_sent_packets_prev = runtime_data.tot_sent_packets;
+            runtime_data.tot_received_packets_prev = runtime_data.tot_received_packets;
+            runtime_data.tot_sent_bytes_prev = runtime_data.tot_sent_bytes;
+            runtime_data.tot_received_bytes_prev = runtime_data.tot_received_bytes;
+            runtime_data.tot_sent_packets += sent_packets;
+            runtime_data.tot_received_packets += received_packets;
+            runtime_data.tot_sent_bytes += sent_bytes;
+            runtime_data.tot_received_bytes += received_bytes;
+            notify_and_log(
+                &mut runtime_data,
+                &notifications,
+                &info_traffic,
+                UnitSystem::Decimal,
+            );
+        }
+
+        // the first interval exceeds no threshold, the second one the packets threshold,
+        // the third one the bytes threshold (most recent notifications first)
+        assert_eq!(runtime_data.logged_notifications.len(), 2);
+        let LoggedNotification::BytesThresholdExceeded(bytes) =
+            &runtime_data.logged_notifications[0]
+        else {
+            panic!("unexpected notification type");
+        };
+        assert_eq!((bytes.outgoing, bytes.incoming), (8000, 7000));
+        assert_eq!(bytes.threshold, 10_000);
+        let LoggedNotification::PacketsThresholdExceeded(packets) =
+            &runtime_data.logged_notifications[1]
+        else {
+            panic!("unexpected notification type");
+        };
+        assert_eq!((packets.outgoing, packets.incoming), (40, 20));
+        assert_eq!(packets.threshold, 50);
+    }
+
+    #[test]
+    fn test_bytes_threshold_modes() {
+        /// Bytes (sent + received) logged when 6 KB are exchanged in the given interval, if the threshold of 2 KB is exceeded.
+        fn logged_bytes(threshold_mode: ThresholdMode, refresh_interval: Duration) -> Option<u32> {
+            let notifications = Notifications {
+                bytes_notification: BytesNotification {
+                    threshold: Some(2000),
+                    previous_threshold: 2000,
+                    sound: Sound::None,
+                    threshold_mode,
+                    ..BytesNotification::default()
+                },
+                ..Notifications::default()
+            };
+            let mut runtime_data = RunTimeData::new();
+            runtime_data.refresh_interval = refresh_interval;
+            runtime_data.tot_sent_bytes = 1000;
+            runtime_data.tot_received_bytes = 5000;
+            notify_and_log(
+                &mut runtime_data,
+                &notifications,
+                &Arc::new(Mutex::new(InfoTraffic::new())),
+                UnitSystem::Decimal,
+            );
+            runtime_data
+                .logged_notifications
+                .front()
+                .map(|notification| match notification {
+                    LoggedNotification::BytesThresholdExceeded(bytes) => {
+                        assert_eq!(bytes.threshold_mode, threshold_mode);
+                        bytes.incoming + bytes.outgoing
+                    }
+                    _ => panic!("unexpected notification type"),
+                })
+        }
+
+        // per second: the interval delta is normalized by the refresh interval
+        let mode = ThresholdMode::PerSecond;
+        assert_eq!(logged_bytes(mode, Duration::from_secs(1)), Some(6000));
+        assert_eq!(logged_bytes(mode, Duration::from_millis(500)), Some(12000));
+        assert_eq!(logged_bytes(mode, Duration::from_secs(2)), Some(3000));
+        assert_eq!(logged_bytes(mode, Duration::from_secs(5)), None);
+
+        // per interval: the raw delta is compared, whatever the refresh interval
+        let mode = ThresholdMode::PerInterval;
+        for refresh_interval in [500, 1000, 5000, 60_000].map(Duration::from_millis) {
+            assert_eq!(logged_bytes(mode, refresh_interval), Some(6000));
+        }
+    }
+
+    #[test]
+    fn test_webhook_text_per_interval() {
+        let bytes = LoggedNotification::BytesThresholdExceeded(BytesThresholdExceeded {
+            threshold: 800_000,
+            byte_multiple: ByteMultiple::KB,
+            unit_system: UnitSystem::Decimal,
+            threshold_mode: ThresholdMode::PerInterval,
+            direction: NotificationDirection::Both,
+            incoming: 900_000,
+            outgoing: 100_000,
+            timestamp: timestamp(0),
+        });
+        assert_eq!(
+            get_webhook_text(&bytes),
+            "[10:30:00] Sniffnet: bytes threshold exceeded (1.0 MB per interval, threshold 800KB)"
+        );
+    }
+
+    #[test]
+    fn test_bytes_threshold_unit_systems() {
+        // a threshold of 2K, exceeded by 2 030 bytes only if decimal
+        let notifications = Notifications {
+            bytes_notification: BytesNotification {
+                sound: Sound::None,
+                ..BytesNotification::from("2K", None)
+            },
+            ..Notifications::default()
+        };
+        let notified = |unit_system| {
+            let mut runtime_data = RunTimeData::new();
+            runtime_data.tot_sent_bytes = 30;
+            runtime_data.tot_received_bytes = 2000;
+            notify_and_log(
+                &mut runtime_data,
+                &notifications,
+                &Arc::new(Mutex::new(InfoTraffic::new())),
+                unit_system,
+            );
+            runtime_data.logged_notifications.pop_front()
+        };
+        assert!(notified(UnitSystem::Binary).is_none());
+        let Some(LoggedNotification::BytesThresholdExceeded(bytes)) = notified(UnitSystem::Decimal)
+        else {
+            panic!("unexpected notification type");
+        };
+        assert_eq!(bytes.unit_system, UnitSystem::Decimal);
+
+        let bytes = LoggedNotification::BytesThresholdExceeded(BytesThresholdExceeded {
+            threshold: 800_000,
+            byte_multiple: ByteMultiple::KB,
+            unit_system: UnitSystem::Binary,
+            threshold_mode: ThresholdMode::PerSecond,
+            direction: NotificationDirection::Both,
+            incoming: 900_000,
+            outgoing: 100_000,
+            timestamp: timestamp(0),
+        });
+        assert_eq!(
+            get_webhook_text(&bytes),
+            "[10:30:00] Sniffnet: bytes threshold exceeded (976.6 KiB/s, threshold 800KiB)"
+        );
+    }
+
+    #[test]
+    fn test_capture_lag_notifications() {
+        let notifications = Notifications::default();
+        let info_traffic = Arc::new(Mutex::new(InfoTraffic::new()));
+        let mut runtime_data = RunTimeData::new();
+        let mut notify_with_lag = |lag_ms| {
+            runtime_data.capture_lag = Duration::from_millis(lag_ms);
+            notify_and_log(
+                &mut runtime_data,
+                &notifications,
+                &info_traffic,
+                UnitSystem::Decimal,
+            );
+            runtime_data.logged_notifications.len()
+        };
+        assert_eq!(notify_with_lag(1000), 0);
+        assert_eq!(notify_with_lag(7250), 1);
+        // no further notification while the capture keeps falling behind
+        assert_eq!(notify_with_lag(9000), 1);
+        assert_eq!(notify_with_lag(200), 1);
+        assert_eq!(notify_with_lag(6000), 2);
+        let notification = &runtime_data.logged_notifications[1];
+        let LoggedNotification::CaptureLag(capture_lag) = notification else {
+            panic!("unexpected notification type");
+        };
+        assert_eq!(capture_lag.lag_ms, 7250);
+        assert!(get_webhook_text(notification)
+            .ends_with("Sniffnet: capture falling behind (7.2 s, threshold 5 s)"));
+
+        // the lag is not monitored if the threshold is not set
+        let notifications = Notifications {
+            capture_lag_threshold_seconds: 0,
+            ..Notifications::default()
+        };
+        let mut runtime_data = RunTimeData::new();
+        runtime_data.capture_lag = Duration::from_secs(60);
+        notify_and_log(
+            &mut runtime_data,
+            &notifications,
+            &info_traffic,
+            UnitSystem::Decimal,
+        );
+        assert!(runtime_data.logged_notifications.is_empty());
+    }
 }
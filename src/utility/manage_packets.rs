@@ -1,23 +1,207 @@
-use std::sync::{Arc, Mutex};
+use etherparse::{
+    ether_type, ip_number, Icmpv6Header, IpAuthenticationHeaderSlice, IpHeader,
+    Ipv6FragmentHeaderSlice, Ipv6RawExtensionHeaderSlice, PacketHeaders, TcpHeader,
+    TransportHeader, UdpHeader, VlanHeader,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
 
-use chrono::Local;
-use etherparse::{IpHeader, TransportHeader};
-use maxminddb::Reader;
-use pcap::{Active, Capture, Device};
+use pcap::{Activated, Active, Address, Capture, Device, Linktype};
 
-use crate::enums::app_protocol::from_port_to_application_protocol;
+use crate::enums::app_protocol::from_ports_to_application_protocol;
+use crate::enums::network_scope::NetworkScope;
 use crate::enums::traffic_type::TrafficType;
 use crate::structs::address_port_pair::AddressPortPair;
+use crate::structs::asn::Asn;
+use crate::structs::capture_options::CaptureOptions;
+use crate::structs::discovery_traffic::DiscoveryPacket;
 use crate::structs::info_address_port_pair::InfoAddressPortPair;
-use crate::utility::countries::get_country_code;
+use crate::structs::tcp_connection::{get_reverse_key, TcpFlags};
+use crate::utility::countries::{get_asn, get_country_code, get_geo_point, MmdbReader};
+use crate::utility::dns::is_dns_message;
 use crate::{AppProtocol, InfoTraffic, IpVersion, TransProtocol};
 
+/// Length of the header (the protocol family, in host byte order) of the packets
+/// captured on BSD and macOS loopback adapters
+const LOOPBACK_HEADER_LEN: usize = 4;
+
+/// Length of the header of the Linux cooked captures (e.g. on the `any` pseudo-device), version 1 and 2
+const LINUX_SLL_HEADER_LEN: usize = 16;
+const LINUX_SLL2_HEADER_LEN: usize = 20;
+
+/// Length of the 802.11 MAC header of the data frames exchanged within a BSS
+/// (without the fourth address and the QoS control field)
+const IEEE802_11_HEADER_LEN: usize = 24;
+
+/// LLC/SNAP header preceding the EtherType of the network layer packets carried by 802.11 data frames
+const LLC_SNAP_HEADER: [u8; 6] = [0xaa, 0xaa, 0x03, 0, 0, 0];
+
+/// First bytes of the HTTP/1.x requests (for each method) and responses
+const HTTP_SIGNATURES: [&[u8]; 10] = [
+    b"GET ",
+    b"POST ",
+    b"HEAD ",
+    b"PUT ",
+    b"DELETE ",
+    b"OPTIONS ",
+    b"PATCH ",
+    b"CONNECT ",
+    b"TRACE ",
+    b"HTTP/1.",
+];
+
+/// First bytes of the SSH identification strings (protocol version 2.0, or 1.99 for compatibility servers)
+const SSH_SIGNATURES: [&[u8]; 2] = [b"SSH-2.0-", b"SSH-1.99-"];
+
+/// Maximum length of a TLS record: 2^14 bytes of plaintext, plus 2048 bytes of expansion when encrypted
+const MAX_TLS_RECORD_LEN: u16 = (1 << 14) + 2048;
+
+/// Checks if the packets captured with the given link type can be parsed by `parse_packet_headers`.
+pub fn is_supported_linktype(linktype: Linktype) -> bool {
+    matches!(
+        linktype,
+        Linktype::ETHERNET
+            | Linktype::NULL
+            | Linktype::LOOP
+            | Linktype::RAW
+            | Linktype::IPV4
+            | Linktype::IPV6
+            | Linktype::IEEE802_11
+            | Linktype::IEEE802_11_RADIOTAP
+            | Linktype::LINUX_SLL
+            | Linktype::LINUX_SLL2
+    )
+}
+
+/// Parses the headers of a packet captured on a network adapter with the given link type.
+///
+/// Besides Ethernet frames, the packets of the loopback adapters of BSD and macOS (`NULL` and `LOOP`
+/// link types, encapsulated in a protocol family header), raw IP packets, the Linux cooked captures
+/// (`LINUX_SLL` and `LINUX_SLL2` link types, used by the `any` pseudo-device) and the 802.11 data frames
+/// of the adapters in monitor mode (optionally preceded by a radiotap header) are supported.
+///
+/// Returns `None` if the packet is malformed, doesn't carry a network layer packet
+/// (e.g. 802.11 management frames), or if the link type is not supported.
+pub fn parse_packet_headers(linktype: Linktype, packet: &[u8]) -> Option<PacketHeaders<'_>> {
+    let ip_packet = match linktype {
+        Linktype::ETHERNET => return PacketHeaders::from_ethernet_slice(packet).ok(),
+        Linktype::NULL | Linktype::LOOP => packet.get(LOOPBACK_HEADER_LEN..)?,
+        Linktype::RAW | Linktype::IPV4 | Linktype::IPV6 => packet,
+        // the protocol is the EtherType of the packet, at the end of the header in version 1
+        // and at its start in version 2
+        Linktype::LINUX_SLL => get_ip_payload(
+            u16::from_be_bytes([*packet.get(14)?, *packet.get(15)?]),
+            packet.get(LINUX_SLL_HEADER_LEN..)?,
+        )?,
+        Linktype::LINUX_SLL2 => get_ip_payload(
+            u16::from_be_bytes([*packet.first()?, *packet.get(1)?]),
+            packet.get(LINUX_SLL2_HEADER_LEN..)?,
+        )?,
+        Linktype::IEEE802_11 => get_ieee802_11_payload(packet)?,
+        Linktype::IEEE802_11_RADIOTAP => {
+            // the radiotap header starts with its version, a padding byte, and its little-endian length
+            let radiotap_len = usize::from(u16::from_le_bytes([*packet.get(2)?, *packet.get(3)?]));
+            get_ieee802_11_payload(packet.get(radiotap_len..)?)?
+        }
+        _ => return None,
+    };
+    PacketHeaders::from_ip_slice(ip_packet).ok()
+}
+
+/// Returns the IPv4 or IPv6 packet carried by an unencrypted 802.11 data frame (IEEE 802.11-2020, section 9.3.2).
+fn get_ieee802_11_payload(frame: &[u8]) -> Option<&[u8]> {
+    let [frame_control, flags, ..] = *frame else {
+        return None;
+    };
+    let frame_type = (frame_control >> 2) & 0x03;
+    let subtype = frame_control >> 4;
+    // data frames only, excluding the null ones (carrying no data) and the protected ones
+    if frame_type != 2 || subtype & 0x04 != 0 || flags & 0x40 != 0 {
+        return None;
+    }
+    let mut header_len = IEEE802_11_HEADER_LEN;
+    // fourth address, when the frame is exchanged between two access points
+    if flags & 0x03 == 0x03 {
+        header_len += 6;
+    }
+    // QoS control field, followed by the HT control field if the order bit is set
+    if subtype & 0x08 != 0 {
+        header_len += 2;
+        if flags & 0x80 != 0 {
+            header_len += 4;
+        }
+    }
+    let llc_snap = frame.get(header_len..header_len + LLC_SNAP_HEADER.len() + 2)?;
+    if llc_snap[..LLC_SNAP_HEADER.len()] != LLC_SNAP_HEADER {
+        return None;
+    }
+    get_ip_payload(
+        u16::from_be_bytes([llc_snap[6], llc_snap[7]]),
+        frame.get(header_len + llc_snap.len()..)?,
+    )
+}
+
+/// Returns the payload of a link layer frame if it's an IPv4 or IPv6 packet, according to its EtherType.
+fn get_ip_payload(ether_type: u16, payload: &[u8]) -> Option<&[u8]> {
+    match ether_type {
+        ether_type::IPV4 | ether_type::IPV6 => Some(payload),
+        _ => None,
+    }
+}
+
+/// Returns the identifier of the VLAN to which an 802.1Q tagged packet belongs.
+///
+/// For double tagged (QinQ) frames the outer identifier is returned, i.e. the service VLAN.
+pub fn get_vlan_id(vlan: &VlanHeader) -> u16 {
+    match vlan {
+        VlanHeader::Single(single) => single.vlan_identifier,
+        VlanHeader::Double(double) => double.outer.vlan_identifier,
+    }
+}
+
+/// Recognizes the LAN discovery packets: ARP requests and replies (from the EtherType)
+/// and IGMP messages (from the IP protocol number).
+///
+/// Only ARP packets resolving IPv4 addresses over Ethernet are recognized.
+pub fn get_discovery_packet(headers: &PacketHeaders) -> Option<DiscoveryPacket> {
+    if let Some(IpHeader::Version4(ipv4_header, _)) = &headers.ip {
+        if ipv4_header.protocol != ip_number::IGMP {
+            return None;
+        }
+        return headers
+            .payload
+            .first()
+            .map(|message_type| DiscoveryPacket::Igmp {
+                message_type: *message_type,
+            });
+    }
+    let ether_type = match &headers.vlan {
+        Some(VlanHeader::Single(vlan_header)) => vlan_header.ether_type,
+        Some(VlanHeader::Double(vlan_header)) => vlan_header.inner.ether_type,
+        None => headers.link.as_ref()?.ether_type,
+    };
+    if ether_type != ether_type::ARP {
+        return None;
+    }
+    // hardware type (Ethernet), protocol type (IPv4), address lengths (6 and 4 bytes) and operation
+    match headers.payload.get(..28)? {
+        [0, 1, 0x08, 0, 6, 4, 0, 1, .., t1, t2, t3, t4] => Some(DiscoveryPacket::ArpRequest {
+            target: Ipv4Addr::new(*t1, *t2, *t3, *t4),
+        }),
+        [0, 1, 0x08, 0, 6, 4, 0, 2, ..] => Some(DiscoveryPacket::ArpReply),
+        _ => None,
+    }
+}
+
 /// This function analyzes the network layer header passed as parameter and updates variables
 /// passed by reference on the basis of the packet header content.
+///
+/// The DSCP marking of the packet is stored in `dscp` (see `get_dscp_name`).
 pub fn analyze_network_header(
     network_header: Option<IpHeader>,
     exchanged_bytes: &mut u128,
     network_protocol: &mut IpVersion,
+    dscp: &mut u8,
     address1: &mut String,
     address2: &mut String,
     skip_packet: &mut bool,
@@ -36,12 +220,15 @@ pub fn analyze_network_header(
                 .replace(',', ".")
                 .replace(' ', "");
             *exchanged_bytes = u128::from(ipv4header.payload_len);
+            *dscp = ipv4header.differentiated_services_code_point;
         }
         Some(IpHeader::Version6(ipv6header, _)) => {
             *network_protocol = IpVersion::IPv6;
             *address1 = ipv6_from_long_dec_to_short_hex(ipv6header.source);
             *address2 = ipv6_from_long_dec_to_short_hex(ipv6header.destination);
             *exchanged_bytes = u128::from(ipv6header.payload_length);
+            // the lower 2 bits of the traffic class are the ECN field
+            *dscp = ipv6header.traffic_class >> 2;
         }
         _ => {
             *skip_packet = true;
@@ -49,14 +236,61 @@ pub fn analyze_network_header(
     }
 }
 
+/// Returns the name of a standard DSCP value: class selectors (`CS0` to `CS7`), assured forwarding
+/// (`AF11` to `AF43`), expedited forwarding (`EF`), voice admit (`VA`) and lower effort (`LE`).
+///
+/// `None` is returned for the values not assigned by the IETF.
+pub fn get_dscp_name(dscp: u8) -> Option<&'static str> {
+    let name = match dscp {
+        0 => "CS0",
+        1 => "LE",
+        8 => "CS1",
+        10 => "AF11",
+        12 => "AF12",
+        14 => "AF13",
+        16 => "CS2",
+        18 => "AF21",
+        20 => "AF22",
+        22 => "AF23",
+        24 => "CS3",
+        26 => "AF31",
+        28 => "AF32",
+        30 => "AF33",
+        32 => "CS4",
+        34 => "AF41",
+        36 => "AF42",
+        38 => "AF43",
+        40 => "CS5",
+        44 => "VA",
+        46 => "EF",
+        48 => "CS6",
+        56 => "CS7",
+        _ => return None,
+    };
+    Some(name)
+}
+
 /// This function analyzes the transport layer header passed as parameter and updates variables
 /// passed by reference on the basis of the packet header content.
+///
+/// ICMP and ICMPv6 messages have both ports set to 0 (so that they're aggregated per address pair),
+/// and their type and code are stored in `icmp_type_code`.
+/// The flags of TCP segments relevant to the connection state are stored in `tcp_flags`.
+/// UDP datagrams on port 443 are classified as QUIC if `payload` starts with a QUIC long header.
+/// The application protocol of TCP and UDP packets is looked up in `port_protocol_overrides`
+/// before the well-known ports; if neither port is known, it's detected from `payload`
+/// (see `detect_app_protocol_by_payload`).
+#[allow(clippy::too_many_arguments)]
 pub fn analyze_transport_header(
     transport_header: Option<TransportHeader>,
+    payload: &[u8],
+    port_protocol_overrides: &HashMap<u16, AppProtocol>,
     port1: &mut u16,
     port2: &mut u16,
     application_protocol: &mut AppProtocol,
     transport_protocol: &mut TransProtocol,
+    icmp_type_code: &mut Option<(u8, u8)>,
+    tcp_flags: &mut Option<TcpFlags>,
     skip_packet: &mut bool,
 ) {
     match transport_header {
@@ -64,149 +298,660 @@ pub fn analyze_transport_header(
             *port1 = udp_header.source_port;
             *port2 = udp_header.destination_port;
             *transport_protocol = TransProtocol::UDP;
-            *application_protocol = from_port_to_application_protocol(*port1);
-            if (*application_protocol).eq(&AppProtocol::Other) {
-                *application_protocol = from_port_to_application_protocol(*port2);
+            *application_protocol =
+                from_ports_to_application_protocol(*port1, *port2, port_protocol_overrides);
+            if *application_protocol == AppProtocol::Other {
+                *application_protocol = detect_app_protocol_by_payload(payload);
+            }
+            if (*port1 == 443 || *port2 == 443) && is_quic_long_header(payload) {
+                *application_protocol = AppProtocol::QUIC;
             }
         }
         Some(TransportHeader::Tcp(tcp_header)) => {
             *port1 = tcp_header.source_port;
             *port2 = tcp_header.destination_port;
             *transport_protocol = TransProtocol::TCP;
-            *application_protocol = from_port_to_application_protocol(*port1);
-            if (*application_protocol).eq(&AppProtocol::Other) {
-                *application_protocol = from_port_to_application_protocol(*port2);
+            *application_protocol =
+                from_ports_to_application_protocol(*port1, *port2, port_protocol_overrides);
+            if *application_protocol == AppProtocol::Other {
+                *application_protocol = detect_app_protocol_by_payload(payload);
             }
+            *tcp_flags = Some(TcpFlags {
+                syn: tcp_header.syn,
+                ack: tcp_header.ack,
+                fin: tcp_header.fin,
+                rst: tcp_header.rst,
+            });
         }
-        _ => {
+        Some(TransportHeader::Icmpv4(icmpv4_header)) => {
+            *port1 = 0;
+            *port2 = 0;
+            *transport_protocol = TransProtocol::ICMP;
+            *application_protocol = AppProtocol::Other;
+            let bytes = icmpv4_header.to_bytes();
+            *icmp_type_code = Some((bytes[0], bytes[1]));
+        }
+        Some(TransportHeader::Icmpv6(icmpv6_header)) => {
+            *port1 = 0;
+            *port2 = 0;
+            *transport_protocol = TransProtocol::ICMPv6;
+            *application_protocol = AppProtocol::Other;
+            *icmp_type_code = Some((
+                icmpv6_header.icmp_type.type_u8(),
+                icmpv6_header.icmp_type.code_u8(),
+            ));
+        }
+        None => {
             *skip_packet = true;
         }
     }
 }
 
-/// Function to insert the source and destination of a packet into the shared map containing the analyzed traffic.
+/// Retrieves the transport layer header of a packet and the transport layer payload,
+/// given its decoded IP and transport headers and the slice of data following them.
+///
+/// `etherparse` stops decoding the IPv6 extension headers when an extension header type occurs more than once:
+/// in this case the remaining extension headers are walked, so that the real TCP or UDP header is found.
+pub fn get_transport_header<'a>(
+    network_header: &Option<IpHeader>,
+    transport_header: Option<TransportHeader>,
+    payload: &'a [u8],
+) -> Option<(TransportHeader, &'a [u8])> {
+    if let Some(transport_header) = transport_header {
+        return Some((transport_header, payload));
+    }
+    let Some(IpHeader::Version6(ipv6_header, extensions)) = network_header else {
+        return None;
+    };
+    if extensions.is_fragmenting_payload() {
+        return None;
+    }
+    let mut next_header = extensions.next_header(ipv6_header.next_header).ok()?;
+    let mut rest = payload;
+    loop {
+        match next_header {
+            ip_number::TCP => {
+                return TcpHeader::from_slice(rest)
+                    .ok()
+                    .map(|(tcp_header, payload)| (TransportHeader::Tcp(tcp_header), payload));
+            }
+            ip_number::UDP => {
+                return UdpHeader::from_slice(rest)
+                    .ok()
+                    .map(|(udp_header, payload)| (TransportHeader::Udp(udp_header), payload));
+            }
+            ip_number::IPV6_ICMP => {
+                return Icmpv6Header::from_slice(rest)
+                    .ok()
+                    .map(|(icmpv6_header, payload)| {
+                        (TransportHeader::Icmpv6(icmpv6_header), payload)
+                    });
+            }
+            ip_number::IPV6_HOP_BY_HOP | ip_number::IPV6_ROUTE | ip_number::IPV6_DEST_OPTIONS => {
+                let extension = Ipv6RawExtensionHeaderSlice::from_slice(rest).ok()?;
+                rest = rest.get(extension.slice().len()..)?;
+                next_header = extension.next_header();
+            }
+            ip_number::AUTH => {
+                let extension = IpAuthenticationHeaderSlice::from_slice(rest).ok()?;
+                rest = rest.get(extension.slice().len()..)?;
+                next_header = extension.next_header();
+            }
+            ip_number::IPV6_FRAG => {
+                let extension = Ipv6FragmentHeaderSlice::from_slice(rest).ok()?;
+                if extension.is_fragmenting_payload() {
+                    return None;
+                }
+                rest = rest.get(extension.slice().len()..)?;
+                next_header = extension.next_header();
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Checks if the UDP payload starts with the long header of a QUIC packet,
+/// used during the handshake (RFC 9000, section 17.2).
+///
+/// Only QUIC version 1, version 2 and the IETF drafts are recognized.
+pub fn is_quic_long_header(payload: &[u8]) -> bool {
+    let Some([first_byte, version @ .., dcid_len]) = payload.get(..6) else {
+        return false;
+    };
+    // header form and fixed bits set
+    let long_header = first_byte & 0xc0 == 0xc0;
+    let known_version = match u32::from_be_bytes([version[0], version[1], version[2], version[3]]) {
+        0x0000_0001 | 0x6b33_43cf => true,
+        version => version & 0xffff_ff00 == 0xff00_0000,
+    };
+    // connection IDs are at most 20 bytes long
+    long_header && known_version && *dcid_len <= 20
+}
+
+/// Detects the application protocol of a TCP or UDP packet from the first bytes of its payload,
+/// recognizing the most common protocols when they run on nonstandard ports:
+/// - HTTP, from the method of the requests or the version of the responses;
+/// - TLS (reported as HTTPS), from the header of its records;
+/// - SSH, from the identification string exchanged at the beginning of the connection;
+/// - DNS, from the header and the question of the messages (also if prefixed by their length, as over TCP).
+///
+/// Only a bounded prefix of the payload is inspected, and nothing is allocated.
+/// Returns `AppProtocol::Other` if no signature matches.
+pub fn detect_app_protocol_by_payload(payload: &[u8]) -> AppProtocol {
+    if HTTP_SIGNATURES
+        .iter()
+        .any(|signature| payload.starts_with(signature))
+    {
+        return AppProtocol::HTTP;
+    }
+    if SSH_SIGNATURES
+        .iter()
+        .any(|signature| payload.starts_with(signature))
+    {
+        return AppProtocol::SSH;
+    }
+    if is_tls_record_header(payload) {
+        return AppProtocol::HTTPS;
+    }
+    let length_prefixed = payload
+        .get(..2)
+        .is_some_and(|len| usize::from(u16::from_be_bytes([len[0], len[1]])) == payload.len() - 2);
+    if is_dns_message(payload) || (length_prefixed && is_dns_message(&payload[2..])) {
+        return AppProtocol::DNS;
+    }
+    AppProtocol::Other
+}
+
+/// Checks if the payload starts with the header of a TLS record: a known content type
+/// (change cipher spec, alert, handshake or application data), a version from SSL 3.0 to TLS 1.3,
+/// and a length not exceeding the maximum one of an encrypted record.
+fn is_tls_record_header(payload: &[u8]) -> bool {
+    let Some(&[content_type, major, minor, len_high, len_low]) = payload.get(..5) else {
+        return false;
+    };
+    let len = u16::from_be_bytes([len_high, len_low]);
+    (0x14..=0x17).contains(&content_type)
+        && major == 0x03
+        && minor <= 0x04
+        && (1..=MAX_TLS_RECORD_LEN).contains(&len)
+}
+
+/// Extracts the server name from the SNI extension of a TLS ClientHello carried by a TCP segment.
+///
+/// Only the first segment of the handshake is considered (no reassembly is performed):
+/// returns `None` if the data are not a TLS ClientHello, or if the extension is not contained in the segment.
+/// The segment may also be cut by the capture snapshot length: the extensions are walked
+/// until the first one not fully captured, and the server name is returned if it was captured entirely.
+///
+/// The payload comes from the wire: it's only accessed with checked slicing, so that malformed
+/// or truncated messages are rejected instead of causing a panic.
+pub fn get_tls_sni(payload: &[u8]) -> Option<String> {
+    // TLS record header: content type (handshake), legacy version, length
+    if *payload.first()? != 0x16 || *payload.get(1)? != 0x03 {
+        return None;
+    }
+    let record_len = usize::from(u16::from_be_bytes([*payload.get(3)?, *payload.get(4)?]));
+    let record = payload.get(5..5 + record_len).unwrap_or(payload.get(5..)?);
+    // handshake header: message type (ClientHello), 3 bytes of length
+    if *record.first()? != 0x01 {
+        return None;
+    }
+    // skip handshake header, client version and random
+    let mut rest = record.get(4 + 2 + 32..)?;
+    // session id, cipher suites and compression methods
+    rest = skip_tls_vector(rest, 1)?;
+    rest = skip_tls_vector(rest, 2)?;
+    rest = skip_tls_vector(rest, 1)?;
+    let extensions_len = usize::from(u16::from_be_bytes([*rest.first()?, *rest.get(1)?]));
+    let mut extensions = rest.get(2..)?;
+    extensions = &extensions[..extensions_len.min(extensions.len())];
+    while let [type_high, type_low, len_high, len_low, ..] = *extensions {
+        let extension_type = u16::from_be_bytes([type_high, type_low]);
+        let extension_len = usize::from(u16::from_be_bytes([len_high, len_low]));
+        let extension = &extensions[4..];
+        let extension = &extension[..extension_len.min(extension.len())];
+        if extension_type == 0x0000 {
+            // server name list: list length, name type (host name), name length, name
+            if *extension.get(2)? != 0x00 {
+                return None;
+            }
+            let name_len =
+                usize::from(u16::from_be_bytes([*extension.get(3)?, *extension.get(4)?]));
+            let name = extension.get(5..5 + name_len)?;
+            if name.is_empty() || !name.iter().all(u8::is_ascii_graphic) {
+                return None;
+            }
+            return Some(String::from_utf8_lossy(name).to_ascii_lowercase());
+        }
+        // an extension not fully captured ends the walk
+        extensions = extensions.get(4 + extension_len..)?;
+    }
+    None
+}
+
+/// Skips a TLS variable-length vector, whose length is encoded in `len_bytes` bytes.
+fn skip_tls_vector(data: &[u8], len_bytes: usize) -> Option<&[u8]> {
+    let len = data
+        .get(..len_bytes)?
+        .iter()
+        .fold(0, |len, byte| (len << 8) + usize::from(*byte));
+    data.get(len_bytes + len..)
+}
+
+/// Function to merge the buffered traffic of an address:port pair into the shared map containing the analyzed traffic.
+///
+/// Broadcast and multicast traffic is merged into the respective summary instead,
+/// if `InfoTraffic::aggregate_broadcast_multicast` is set, while incoming traffic is merged
+/// into the entry of the opposite direction if `InfoTraffic::combine_bidirectional_flows` is set.
+///
+/// It also updates the application protocols packet count and the total sent or received packets and bytes:
+/// it must be called while holding the same lock used to update the other aggregate counters,
+/// so that a single critical section is needed for each flush of the buffered traffic.
 pub fn modify_or_insert_in_map(
-    info_traffic_mutex: &Arc<Mutex<InfoTraffic>>,
+    info_traffic: &mut InfoTraffic,
     key: AddressPortPair,
-    exchanged_bytes: u128,
-    traffic_type: TrafficType,
-    application_protocol: AppProtocol,
-    country_db_reader: &Reader<&[u8]>,
+    buffered: InfoAddressPortPair,
+    country_db_reader: Option<&MmdbReader>,
+    asn_db_reader: Option<&MmdbReader>,
+    city_db_reader: Option<&MmdbReader>,
 ) {
-    let now = Local::now();
-    let very_long_address = key.address1.len() > 25 || key.address2.len() > 25;
-    let mut info_traffic = info_traffic_mutex
-        .lock()
-        .expect("Error acquiring mutex\n\r");
-    let len = info_traffic.map.len();
-    let index = info_traffic.map.get_index_of(&key).unwrap_or(len);
-    let country = if index == len {
-        // first occurrence of key => retrieve country code
-        get_country_code(traffic_type, &key, country_db_reader)
+    let traffic_type = buffered.traffic_type;
+    let application_protocol = buffered.app_protocol;
+    let exchanged_bytes = buffered.transmitted_bytes;
+    let exchanged_packets = buffered.transmitted_packets;
+    let initial_timestamp = buffered.initial_timestamp;
+    let final_timestamp = buffered.final_timestamp;
+    let dns_query = buffered.dns_query.clone();
+    let tls_sni = buffered.tls_sni.clone();
+    let (src_mac, dst_mac) = (buffered.src_mac, buffered.dst_mac);
+    let vlan = buffered.vlan;
+    let icmp_type_code = buffered.icmp_type_code;
+    let tcp_state = buffered.tcp_state;
+    if info_traffic.aggregate_broadcast_multicast {
+        let summary = match traffic_type {
+            TrafficType::Broadcast => Some(&mut info_traffic.broadcast_summary),
+            TrafficType::Multicast => Some(&mut info_traffic.multicast_summary),
+            _ => None,
+        };
+        if let Some(summary) = summary {
+            summary.packets += exchanged_packets;
+            summary.bytes += exchanged_bytes;
+            update_protocols_and_totals(
+                info_traffic,
+                traffic_type,
+                application_protocol,
+                exchanged_packets,
+                exchanged_bytes,
+            );
+            return;
+        }
+    }
+    // the key and type of the entry may differ from those of the buffered traffic (see `get_map_key`);
+    // the totals are still accounted according to the actual direction of the traffic
+    let (key, connection_type) =
+        get_map_key(key, traffic_type, info_traffic.combine_bidirectional_flows);
+    // the TCP state is shared by both the directions of a connection
+    let reverse_key = tcp_state.map(|_| get_reverse_key(&key));
+    let existing_id = info_traffic.map.get(&key).map(|info| info.id);
+    let is_new = existing_id.is_none();
+    let (country, location, asn) = if is_new {
+        // first occurrence of key => retrieve country code, location and ASN
+        (
+            country_db_reader
+                .map(|reader| get_country_code(connection_type, &key, reader))
+                .unwrap_or_default(),
+            city_db_reader
+                .and_then(|reader| get_geo_point(connection_type, &key, reader))
+                .map(|(latitude, longitude)| (latitude as f32, longitude as f32)),
+            asn_db_reader
+                .map(|reader| get_asn(connection_type, &key, reader))
+                .unwrap_or_default(),
+        )
     } else {
         // this key already occurred
-        String::new()
+        (String::new(), None, Asn::default())
     };
-    let is_already_featured = info_traffic.favorites_last_interval.contains(&index);
+    let is_already_featured =
+        existing_id.is_some_and(|id| info_traffic.favorites_last_interval.contains(&id));
     let mut update_favorites_featured = false;
-    info_traffic
-        .map
-        .entry(key)
-        .and_modify(|info| {
-            info.transmitted_bytes += exchanged_bytes;
-            info.transmitted_packets += 1;
-            info.final_timestamp = now;
-            if info.is_favorite && !is_already_featured {
-                update_favorites_featured = true;
-            }
-        })
-        .or_insert(InfoAddressPortPair {
-            transmitted_bytes: exchanged_bytes,
-            transmitted_packets: 1,
-            initial_timestamp: now,
-            final_timestamp: now,
-            app_protocol: application_protocol,
-            very_long_address,
-            traffic_type,
-            country,
-            index,
-            is_favorite: false,
-        });
-    info_traffic.addresses_last_interval.insert(index);
+    let mut activity = None;
+    let id = if let Some(info) = info_traffic.map.get_mut(&key) {
+        info.transmitted_bytes += exchanged_bytes;
+        info.transmitted_packets += exchanged_packets;
+        info.sent_bytes += buffered.sent_bytes;
+        info.received_bytes += buffered.received_bytes;
+        info.sent_packets += buffered.sent_packets;
+        info.received_packets += buffered.received_packets;
+        info.initial_timestamp = info.initial_timestamp.min(initial_timestamp);
+        let previous_activity = info.final_timestamp;
+        info.final_timestamp = info.final_timestamp.max(final_timestamp);
+        activity = Some((previous_activity, info.final_timestamp));
+        if info.app_protocol == AppProtocol::Other {
+            // e.g. the handshake of a TCP connection on a nonstandard port, whose protocol is detected later
+            info.app_protocol = application_protocol;
+        }
+        if dns_query.is_some() {
+            info.dns_query = dns_query;
+        }
+        if tls_sni.is_some() {
+            info.tls_sni = tls_sni;
+        }
+        if src_mac.is_some() {
+            info.src_mac = src_mac;
+            info.dst_mac = dst_mac;
+        }
+        if vlan.is_some() {
+            info.vlan = vlan;
+        }
+        info.dscp = buffered.dscp;
+        if icmp_type_code.is_some() {
+            info.icmp_type_code = icmp_type_code;
+        }
+        if tcp_state.is_some() {
+            info.tcp_state = tcp_state;
+        }
+        if buffered.is_notable {
+            info.is_notable = true;
+        }
+        if info.is_favorite && !is_already_featured {
+            update_favorites_featured = true;
+        }
+        info.id
+    } else {
+        info_traffic.insert_connection(
+            key,
+            InfoAddressPortPair {
+                traffic_type: connection_type,
+                country,
+                location,
+                asn,
+                ..buffered
+            },
+        )
+    };
+    if let Some((previous, current)) = activity {
+        info_traffic.record_activity(id, previous, current);
+    }
+    info_traffic.addresses_last_interval.insert(id);
+    if let Some(reverse_info) = reverse_key.and_then(|key| info_traffic.map.get_mut(&key)) {
+        reverse_info.tcp_state = tcp_state;
+    }
+    if is_new {
+        let (_, info) = info_traffic.get_connection(id).unwrap();
+        let (country, is_favorite) = (info.country.clone(), info.is_favorite);
+        if !country.is_empty() && !info_traffic.seen_countries.contains(&country) {
+            // first connection with this country
+            info_traffic.seen_countries.insert(country.clone());
+            info_traffic.new_countries_last_interval.push((country, id));
+        }
+        if is_favorite {
+            // new connection marked as favorite by the configured rules
+            info_traffic.favorite_connections.insert(id);
+            update_favorites_featured = true;
+        }
+    }
     if update_favorites_featured {
-        info_traffic.favorites_last_interval.insert(index);
+        info_traffic.favorites_last_interval.insert(id);
+    }
+
+    update_protocols_and_totals(
+        info_traffic,
+        traffic_type,
+        application_protocol,
+        exchanged_packets,
+        exchanged_bytes,
+    );
+}
+
+/// Returns the key and the traffic type of the map entry in which the traffic of a directional flow is merged.
+///
+/// If `combine_bidirectional_flows` is set, incoming traffic is merged into the entry of the opposite direction,
+/// whose key has the local endpoint first and whose traffic type is outgoing:
+/// in this way the two directions of a conversation share the same entry, whichever sent first.
+/// Other flows are merged into the entry with their own key and type.
+pub fn get_map_key(
+    key: AddressPortPair,
+    traffic_type: TrafficType,
+    combine_bidirectional_flows: bool,
+) -> (AddressPortPair, TrafficType) {
+    if combine_bidirectional_flows && traffic_type == TrafficType::Incoming {
+        (get_reverse_key(&key), TrafficType::Outgoing)
+    } else {
+        (key, traffic_type)
     }
 }
 
-/// Determines if the input address is a multicast address or not.
+/// Accounts the buffered traffic in the application protocols packets and bytes count and in the total sent or received
+/// packets and bytes.
+pub fn update_protocols_and_totals(
+    info_traffic: &mut InfoTraffic,
+    traffic_type: TrafficType,
+    application_protocol: AppProtocol,
+    exchanged_packets: u128,
+    exchanged_bytes: u128,
+) {
+    //increment the packets and bytes count for the sniffed app protocol
+    let app_protocol_summary = info_traffic
+        .app_protocols
+        .entry(application_protocol)
+        .or_default();
+    app_protocol_summary.packets += exchanged_packets;
+    app_protocol_summary.bytes += exchanged_bytes;
+
+    // local traffic is both sent and received by this host: it's accounted only once, as received
+    if traffic_type == TrafficType::Outgoing {
+        //increment number of sent packets and bytes
+        info_traffic.tot_sent_packets += exchanged_packets;
+        info_traffic.tot_sent_bytes += exchanged_bytes;
+    } else {
+        //increment number of received packets and bytes
+        info_traffic.tot_received_packets += exchanged_packets;
+        info_traffic.tot_received_bytes += exchanged_bytes;
+    }
+}
+
+/// Determines the traffic type of a packet sent from `address1` to `address2`, given the addresses
+/// of the sniffed network adapter.
+///
+/// Traffic between two addresses of this host (i.e. addresses of the adapter or loopback ones)
+/// is local: it would be otherwise ambiguously reported as outgoing or incoming on multi-homed hosts,
+/// loopback adapters and container bridges.
+pub fn get_traffic_type(
+    address1: &str,
+    address2: &str,
+    my_interface_addresses: &[String],
+    broadcast_addresses: &[IpAddr],
+) -> TrafficType {
+    let is_host_address = |address: &str| {
+        my_interface_addresses
+            .iter()
+            .any(|my_address| my_address == address)
+            || address
+                .parse::<IpAddr>()
+                .is_ok_and(|address| address.is_loopback())
+    };
+    if is_host_address(address1) && is_host_address(address2) {
+        TrafficType::Local
+    } else if my_interface_addresses
+        .iter()
+        .any(|address| address == address1)
+    {
+        TrafficType::Outgoing
+    } else if my_interface_addresses
+        .iter()
+        .any(|address| address == address2)
+    {
+        TrafficType::Incoming
+    } else if is_multicast_address(address2) {
+        TrafficType::Multicast
+    } else if is_broadcast_address(address2, broadcast_addresses) {
+        TrafficType::Broadcast
+    } else {
+        TrafficType::Other
+    }
+}
+
+/// Returns the remote address of a connection with the given traffic type, if any.
+///
+/// Local, multicast, broadcast and unrelated traffic is not associated with a single remote host.
+pub fn get_remote_address(traffic_type: TrafficType, key: &AddressPortPair) -> Option<IpAddr> {
+    match traffic_type {
+        TrafficType::Outgoing => key.address2.parse().ok(),
+        TrafficType::Incoming => key.address1.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Determines if the input address is a multicast address or not
+/// (`224.0.0.0/4` for IPv4, `ff00::/8` for IPv6).
+///
+/// Link-local unicast addresses (`169.254.0.0/16`, `fe80::/10`) are not multicast addresses.
 ///
 /// # Arguments
 ///
 /// * `address` - string representing an IPv4 or IPv6 network address.
 pub fn is_multicast_address(address: &str) -> bool {
-    let mut ret_val = false;
-    if address.contains(':') {
-        //IPv6 address
-        if address.starts_with("ff") {
-            ret_val = true;
-        }
-    } else {
-        //IPv4 address
-        let first_group = address
-            .split('.')
-            .next()
-            .unwrap()
-            .to_string()
-            .parse::<u8>()
-            .unwrap();
-        if (224..=239).contains(&first_group) {
-            ret_val = true;
+    address
+        .parse::<IpAddr>()
+        .is_ok_and(|address| address.is_multicast())
+}
+
+/// Determines if the input address is a broadcast address or not.
+///
+/// Broadcast addresses are the limited broadcast `255.255.255.255`, the broadcast address of the
+/// IPv4 link-local subnet `169.254.255.255`, and the subnet-directed broadcast addresses of the sniffed network adapter.
+/// IPv6 has no broadcast addresses.
+///
+/// # Arguments
+///
+/// * `address` - string representing an IPv4 or IPv6 network address.
+///
+/// * `broadcast_addresses` - subnet-directed broadcast addresses of the network adapter (see `get_broadcast_addresses`).
+pub fn is_broadcast_address(address: &str, broadcast_addresses: &[IpAddr]) -> bool {
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ipv4)) => {
+            ipv4.is_broadcast()
+                || ipv4 == Ipv4Addr::new(169, 254, 255, 255)
+                || broadcast_addresses.contains(&IpAddr::V4(ipv4))
         }
+        _ => false,
     }
-    ret_val
 }
 
-/// Determines if the input address is a broadcast address or not.
+/// Determines if the input address belongs to the local network or not.
+///
+/// Local addresses are the private (`10.0.0.0/8`, `172.16.0.0/12`, `192.168.0.0/16`),
+/// unique local (`fc00::/7`), link-local (`169.254.0.0/16`, `fe80::/10`) and loopback ones,
+/// together with the broadcast addresses and the multicast addresses not routed beyond the link
+/// (`224.0.0.0/24`, `ff01::/16`, `ff02::/16`).
+/// Shared addresses (`100.64.0.0/10`) are assigned by the ISP, so they aren't local.
 ///
 /// # Arguments
 ///
 /// * `address` - string representing an IPv4 or IPv6 network address.
-pub fn is_broadcast_address(address: &str) -> bool {
-    let mut ret_val = false;
-    if !address.contains(':') {
-        //IPv4 address
-        let groups: Vec<u8> = address
-            .split('.')
-            .map(|str| str.parse::<u8>().unwrap())
-            .collect();
-        if *groups.first().unwrap() == 255
-            && *groups.get(1).unwrap() == 255
-            && *groups.get(2).unwrap() == 255
-            && *groups.get(3).unwrap() == 255
-        {
-            ret_val = true;
+pub fn is_local_address(address: &str) -> bool {
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ipv4)) => {
+            ipv4.is_private()
+                || ipv4.is_link_local()
+                || ipv4.is_loopback()
+                || ipv4.is_broadcast()
+                || ipv4.octets()[..3] == [224, 0, 0]
+        }
+        Ok(IpAddr::V6(ipv6)) => {
+            let first_segment = ipv6.segments()[0];
+            ipv6.is_loopback()
+                || first_segment & 0xfe00 == 0xfc00
+                || first_segment & 0xffc0 == 0xfe80
+                || first_segment == 0xff01
+                || first_segment == 0xff02
         }
-        // still missing a check for directed broadcast!
+        Err(_) => false,
+    }
+}
+
+/// Determines if a connection is in the given network scope: local network connections are
+/// the ones between two local addresses (see `is_local_address`), all the others are WAN connections.
+pub fn is_in_network_scope(scope: NetworkScope, address1: &str, address2: &str) -> bool {
+    let is_local = || is_local_address(address1) && is_local_address(address2);
+    match scope {
+        NetworkScope::Local => is_local(),
+        NetworkScope::Wan => !is_local(),
+        NetworkScope::Other => true,
     }
-    ret_val
+}
+
+/// Returns the subnet-directed broadcast addresses of the given network adapter addresses.
+///
+/// When not reported by the adapter, they're derived from the IPv4 address and netmask
+/// (subnets with fewer than 4 addresses have no broadcast address).
+pub fn get_broadcast_addresses(addresses: &[Address]) -> Vec<IpAddr> {
+    addresses
+        .iter()
+        .filter_map(|address| match (address.addr, address.netmask) {
+            _ if address.broadcast_addr.is_some() => address.broadcast_addr,
+            (IpAddr::V4(addr), Some(IpAddr::V4(netmask)))
+                if u32::from(netmask).count_ones() < 31 =>
+            {
+                Some(IpAddr::V4(Ipv4Addr::from(
+                    u32::from(addr) | !u32::from(netmask),
+                )))
+            }
+            _ => None,
+        })
+        .filter(IpAddr::is_ipv4)
+        .collect()
 }
 
 /// Determines if the capture opening resolves into an Error
-pub fn get_capture_result(device: &Device) -> (Option<String>, Option<Capture<Active>>) {
-    let cap_result = Capture::from_device(&*device.name)
-        .expect("Capture initialization error\n\r")
-        .promisc(true)
-        .snaplen(256) //limit stored packets slice dimension (to keep more in the buffer)
-        .immediate_mode(true) //parse packets ASAP!
-        .open();
-    if cap_result.is_err() {
-        let err_string = cap_result.err().unwrap().to_string();
-        (Some(err_string), None)
+///
+/// If `bpf` is specified, the Berkeley Packet Filter is applied to the capture in the kernel,
+/// and an invalid expression resolves into an Error as well.
+/// The capture is opened with the given options (see `CaptureOptions`).
+pub fn get_capture_result(
+    device: &Device,
+    bpf: Option<&str>,
+    options: &CaptureOptions,
+) -> (Option<String>, Option<Capture<Active>>) {
+    let cap_result = Capture::from_device(&*device.name).and_then(|cap| {
+        cap.promisc(options.promiscuous)
+            .snaplen(options.snaplen) //limit stored packets slice dimension (to keep more in the buffer)
+            .immediate_mode(true) //parse packets ASAP!
+            .timeout(options.read_timeout_ms) //wake up periodically to flush the buffered packets even if no traffic is sniffed
+            .open()
+    });
+    match cap_result {
+        Err(err) => (Some(get_activation_error(&err, options.promiscuous)), None),
+        Ok(mut cap) => match bpf.map_or(Ok(()), |bpf| set_bpf_filter(&mut cap, bpf)) {
+            Err(err_string) => (Some(err_string), None),
+            Ok(()) => (None, Some(cap)),
+        },
+    }
+}
+
+/// Returns a readable description of the error occurred opening a capture,
+/// hinting that promiscuous mode may be the cause when permissions are missing.
+fn get_activation_error(err: &pcap::Error, promiscuous: bool) -> String {
+    let description = err.to_string();
+    if promiscuous && description.to_lowercase().contains("permission") {
+        format!(
+            "{description}\n\nOn some platforms promiscuous mode requires elevated privileges: \
+            it can be disabled setting `promiscuous = false` in the capture configuration file"
+        )
     } else {
-        (None, cap_result.ok())
+        description
     }
 }
 
+/// Applies the Berkeley Packet Filter expression to the capture, returning a readable error if pcap rejects it.
+pub fn set_bpf_filter<T: Activated + ?Sized>(
+    cap: &mut Capture<T>,
+    bpf: &str,
+) -> Result<(), String> {
+    cap.filter(bpf, true)
+        .map_err(|err| format!("Invalid BPF filter \"{bpf}\": {err}"))
+}
+
 // Test for this function at the end of this file (run with cargo test)
 /// Function to convert a long decimal ipv6 address to a
 /// shorter compressed ipv6 address
@@ -302,7 +1047,658 @@ pub fn ipv6_from_long_dec_to_short_hex(ipv6_long: [u8; 16]) -> String {
 
 #[cfg(test)]
 mod test {
-    use crate::utility::manage_packets::ipv6_from_long_dec_to_short_hex;
+    use std::collections::{HashMap, HashSet};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    use etherparse::{ip_number, PacketHeaders};
+    use pcap::{Address, Capture, Linktype};
+
+    use crate::enums::network_scope::NetworkScope;
+    use crate::enums::tcp_state::TcpState;
+    use crate::enums::traffic_type::TrafficType;
+    use crate::structs::address_port_pair::AddressPortPair;
+    use crate::structs::capture_options::DEFAULT_SNAPLEN;
+    use crate::structs::discovery_traffic::DiscoveryPacket;
+    use crate::structs::favorite_rule::{get_favorite_matchers, FavoriteRule};
+    use crate::structs::info_traffic::TrafficSummary;
+    use crate::structs::tcp_connection::get_reverse_key;
+    use crate::structs::traffic_buffer::{FlushCadence, TrafficBuffer};
+    use crate::utility::countries::get_country_db_reader;
+    use crate::utility::dns::get_dns_query_name;
+    use crate::utility::manage_packets::{
+        analyze_network_header, analyze_transport_header, detect_app_protocol_by_payload,
+        get_activation_error, get_broadcast_addresses, get_discovery_packet, get_dscp_name,
+        get_tls_sni, get_traffic_type, get_transport_header, ipv6_from_long_dec_to_short_hex,
+        is_broadcast_address, is_in_network_scope, is_local_address, is_multicast_address,
+        is_quic_long_header, parse_packet_headers, set_bpf_filter,
+    };
+    use crate::{AppProtocol, InfoTraffic, IpVersion, TransProtocol};
+
+    fn feed_synthetic_packets(cadence: FlushCadence, n: u128) -> InfoTraffic {
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new("eth0".to_string(), cadence, Vec::new(), Vec::new());
+        for i in 0..n {
+            let exchanged_bytes = 100 + i % 50;
+            buffer.add_packet(exchanged_bytes);
+            // one packet out of four is filtered out and only updates the aggregate counters
+            if i % 4 != 0 {
+                let (traffic_type, app_protocol) = if i % 2 == 0 {
+                    (TrafficType::Outgoing, AppProtocol::HTTPS)
+                } else {
+                    (TrafficType::Incoming, AppProtocol::DNS)
+                };
+                let key = AddressPortPair::new(
+                    "192.168.1.10".to_string(),
+                    u16::try_from(i % 100).unwrap(),
+                    "192.168.1.1".to_string(),
+                    443,
+                    TransProtocol::TCP,
+                );
+                buffer.add_connection(key, exchanged_bytes, traffic_type, app_protocol);
+            }
+            if buffer.is_flush_due() {
+                buffer.flush(
+                    &mut info_traffic,
+                    country_db_reader.as_ref(),
+                    None,
+                    None,
+                    None,
+                );
+            }
+        }
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        info_traffic
+    }
+
+    fn assert_counters_consistent(info_traffic: &InfoTraffic, n: u128) {
+        let reported_packets = info_traffic.tot_sent_packets + info_traffic.tot_received_packets;
+        let reported_bytes = info_traffic.tot_sent_bytes + info_traffic.tot_received_bytes;
+        assert_eq!(info_traffic.all_packets, n);
+        assert_eq!(reported_packets, n * 3 / 4);
+        assert!(info_traffic.all_bytes > reported_bytes);
+        assert_eq!(
+            info_traffic
+                .app_protocols
+                .values()
+                .map(|summary| summary.packets)
+                .sum::<u128>(),
+            reported_packets
+        );
+        assert_eq!(
+            info_traffic
+                .map
+                .values()
+                .map(|info| info.transmitted_packets)
+                .sum::<u128>(),
+            reported_packets
+        );
+        assert_eq!(
+            info_traffic
+                .map
+                .values()
+                .map(|info| info.transmitted_bytes)
+                .sum::<u128>(),
+            reported_bytes
+        );
+        assert_eq!(
+            info_traffic.addresses_last_interval.len(),
+            info_traffic.map.len()
+        );
+        for (i, info) in info_traffic.map.values().enumerate() {
+            assert_eq!(info_traffic.get_index_of_connection(info.id), Some(i));
+            assert!(info.initial_timestamp <= info.final_timestamp);
+        }
+    }
+
+    #[test]
+    fn info_traffic_counters_consistent_after_many_packets() {
+        let n = 10_000;
+        assert_counters_consistent(
+            &feed_synthetic_packets(
+                FlushCadence {
+                    interval: Duration::ZERO,
+                    max_packets: 1,
+                },
+                n,
+            ),
+            n,
+        );
+    }
+
+    #[test]
+    fn connections_accounted_without_country_database() {
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "8.8.8.8".to_string(),
+            53,
+            TransProtocol::UDP,
+        );
+        buffer.add_packet(100);
+        buffer.add_connection(key, 100, TrafficType::Outgoing, AppProtocol::DNS);
+        buffer.flush(&mut info_traffic, None, None, None, None);
+        assert_eq!(info_traffic.all_packets, 1);
+        assert_eq!(info_traffic.tot_sent_bytes, 100);
+        assert_eq!(info_traffic.map.len(), 1);
+        assert_eq!(info_traffic.map[0].country, "");
+        assert!(info_traffic.new_countries_last_interval.is_empty());
+    }
+
+    #[test]
+    fn info_traffic_counters_consistent_with_batched_flushes() {
+        let n = 10_000;
+        let batched = feed_synthetic_packets(FlushCadence::default(), n);
+        assert_counters_consistent(&batched, n);
+        let immediate = feed_synthetic_packets(
+            FlushCadence {
+                interval: Duration::ZERO,
+                max_packets: 1,
+            },
+            n,
+        );
+        for (key, info) in &immediate.map {
+            let batched_info = batched.map.get(key).unwrap();
+            assert_eq!(info.transmitted_packets, batched_info.transmitted_packets);
+            assert_eq!(info.transmitted_bytes, batched_info.transmitted_bytes);
+        }
+    }
+
+    /// Builds an Ethernet frame carrying an IPv6 packet from the given headers.
+    fn ipv6_frame(next_header: u8, extensions: &[u8], transport: &[u8]) -> Vec<u8> {
+        let payload_len = u16::try_from(extensions.len() + transport.len()).unwrap();
+        let mut frame = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0x86, 0xdd];
+        frame.extend_from_slice(&[0x60, 0, 0, 0]);
+        frame.extend_from_slice(&payload_len.to_be_bytes());
+        frame.extend_from_slice(&[next_header, 64]);
+        frame.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        frame.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+        frame.extend_from_slice(extensions);
+        frame.extend_from_slice(transport);
+        frame
+    }
+
+    fn analyze_frame(frame: &[u8]) -> (u16, u16, TransProtocol, AppProtocol, bool) {
+        let (port1, port2, transport_protocol, application_protocol, _, skip_packet) =
+            analyze_frame_with_icmp(frame);
+        (
+            port1,
+            port2,
+            transport_protocol,
+            application_protocol,
+            skip_packet,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn analyze_frame_with_icmp(
+        frame: &[u8],
+    ) -> (u16, u16, TransProtocol, AppProtocol, Option<(u8, u8)>, bool) {
+        let headers = PacketHeaders::from_ethernet_slice(frame).unwrap();
+        let (transport_header, payload) =
+            get_transport_header(&headers.ip, headers.transport, headers.payload).unzip();
+        let mut port1 = 0;
+        let mut port2 = 0;
+        let mut application_protocol = AppProtocol::Other;
+        let mut transport_protocol = TransProtocol::Other;
+        let mut icmp_type_code = None;
+        let mut skip_packet = false;
+        analyze_transport_header(
+            transport_header,
+            payload.unwrap_or_default(),
+            &HashMap::new(),
+            &mut port1,
+            &mut port2,
+            &mut application_protocol,
+            &mut transport_protocol,
+            &mut icmp_type_code,
+            &mut None,
+            &mut skip_packet,
+        );
+        (
+            port1,
+            port2,
+            transport_protocol,
+            application_protocol,
+            icmp_type_code,
+            skip_packet,
+        )
+    }
+
+    /// Builds an Ethernet frame carrying an IPv4 ICMP echo message (type 8 for requests, 0 for replies).
+    fn icmp_echo_frame(source: [u8; 4], destination: [u8; 4], icmp_type: u8, seq: u16) -> Vec<u8> {
+        let mut frame = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0x08, 0x00];
+        frame.extend_from_slice(&[0x45, 0, 0, 36, 0, 0, 0x40, 0, 64, ip_number::ICMP, 0, 0]);
+        frame.extend_from_slice(&source);
+        frame.extend_from_slice(&destination);
+        frame.extend_from_slice(&[icmp_type, 0, 0, 0, 0x12, 0x34]);
+        frame.extend_from_slice(&seq.to_be_bytes());
+        frame.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        frame
+    }
+
+    #[test]
+    fn icmp_ping_request_and_reply() {
+        let local = [192, 168, 1, 10];
+        let remote = [1, 1, 1, 1];
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        for seq in 0..3 {
+            for (frame, expected_type) in [
+                (icmp_echo_frame(local, remote, 8, seq), 8),
+                (icmp_echo_frame(remote, local, 0, seq), 0),
+            ] {
+                let (port1, port2, transport_protocol, application_protocol, icmp_type_code, skip) =
+                    analyze_frame_with_icmp(&frame);
+                assert_eq!(
+                    (port1, port2, transport_protocol, application_protocol, skip),
+                    (0, 0, TransProtocol::ICMP, AppProtocol::Other, false)
+                );
+                assert_eq!(icmp_type_code, Some((expected_type, 0)));
+                let headers = PacketHeaders::from_ethernet_slice(&frame).unwrap();
+                let mut address1 = String::new();
+                let mut address2 = String::new();
+                analyze_network_header(
+                    headers.ip,
+                    &mut 0,
+                    &mut IpVersion::Other,
+                    &mut 0,
+                    &mut address1,
+                    &mut address2,
+                    &mut false,
+                );
+                let traffic_type = if address1 == "192.168.1.10" {
+                    TrafficType::Outgoing
+                } else {
+                    TrafficType::Incoming
+                };
+                let key =
+                    AddressPortPair::new(address1, port1, address2, port2, transport_protocol);
+                let info = buffer.add_connection(key, 16, traffic_type, application_protocol);
+                info.icmp_type_code = icmp_type_code;
+            }
+        }
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        // requests and replies of the different pings are aggregated per direction
+        assert_eq!(info_traffic.map.len(), 2);
+        let (request_key, requests) = info_traffic.map.get_index(0).unwrap();
+        assert_eq!(request_key.address2, "1.1.1.1");
+        assert_eq!(requests.transmitted_packets, 3);
+        assert_eq!(requests.icmp_type_code, Some((8, 0)));
+        let (reply_key, replies) = info_traffic.map.get_index(1).unwrap();
+        assert_eq!(reply_key.address1, "1.1.1.1");
+        assert_eq!(replies.transmitted_packets, 3);
+        assert_eq!(replies.icmp_type_code, Some((0, 0)));
+        assert_eq!(info_traffic.tot_sent_packets, 3);
+        assert_eq!(info_traffic.tot_received_packets, 3);
+    }
+
+    /// Builds an Ethernet frame carrying an IPv4 TCP segment without payload, with the given flags byte.
+    fn tcp_frame(source: [u8; 4], destination: [u8; 4], ports: (u16, u16), flags: u8) -> Vec<u8> {
+        let mut frame = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0x08, 0x00];
+        frame.extend_from_slice(&[0x45, 0, 0, 40, 0, 0, 0x40, 0, 64, ip_number::TCP, 0, 0]);
+        frame.extend_from_slice(&source);
+        frame.extend_from_slice(&destination);
+        frame.extend_from_slice(&ports.0.to_be_bytes());
+        frame.extend_from_slice(&ports.1.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        frame
+    }
+
+    #[test]
+    fn tcp_handshake_then_teardown() {
+        const FIN: u8 = 0x01;
+        const SYN: u8 = 0x02;
+        const ACK: u8 = 0x10;
+        let local = [192, 168, 1, 10];
+        let remote = [93, 184, 216, 34];
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let segments = [
+            (true, SYN, Some(TcpState::SynSeen)),
+            (false, SYN | ACK, Some(TcpState::SynSeen)),
+            (true, ACK, Some(TcpState::Established)),
+            (true, FIN | ACK, Some(TcpState::Closing)),
+            (false, ACK, Some(TcpState::Closing)),
+            (false, FIN | ACK, Some(TcpState::Closing)),
+            (true, ACK, Some(TcpState::Closed)),
+        ];
+        for (i, (outgoing, flags, expected_state)) in segments.into_iter().enumerate() {
+            let frame = if outgoing {
+                tcp_frame(local, remote, (50000, 80), flags)
+            } else {
+                tcp_frame(remote, local, (80, 50000), flags)
+            };
+            let headers = PacketHeaders::from_ethernet_slice(&frame).unwrap();
+            let (transport_header, payload) =
+                get_transport_header(&headers.ip, headers.transport, headers.payload).unzip();
+            let (mut port1, mut port2) = (0, 0);
+            let mut tcp_flags = None;
+            analyze_transport_header(
+                transport_header,
+                payload.unwrap_or_default(),
+                &HashMap::new(),
+                &mut port1,
+                &mut port2,
+                &mut AppProtocol::Other,
+                &mut TransProtocol::Other,
+                &mut None,
+                &mut tcp_flags,
+                &mut false,
+            );
+            let (address1, address2, traffic_type) = if outgoing {
+                ("192.168.1.10", "93.184.216.34", TrafficType::Outgoing)
+            } else {
+                ("93.184.216.34", "192.168.1.10", TrafficType::Incoming)
+            };
+            let key = AddressPortPair::new(
+                address1.to_string(),
+                port1,
+                address2.to_string(),
+                port2,
+                TransProtocol::TCP,
+            );
+            let tcp_state = buffer.update_tcp_state(&key, tcp_flags.unwrap());
+            assert_eq!(Some(tcp_state), expected_state);
+            let info = buffer.add_connection(key, 20, traffic_type, AppProtocol::HTTP);
+            info.tcp_state = Some(tcp_state);
+            // the state must stay consistent across the flushes
+            if i % 3 == 2 {
+                buffer.flush(
+                    &mut info_traffic,
+                    country_db_reader.as_ref(),
+                    None,
+                    None,
+                    None,
+                );
+            }
+        }
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(info_traffic.map.len(), 2);
+        for (_, info) in &info_traffic.map {
+            assert_eq!(info.tcp_state, Some(TcpState::Closed));
+        }
+    }
+
+    #[test]
+    fn icmpv6_after_repeated_extension_headers() {
+        let mut extensions = vec![ip_number::IPV6_DEST_OPTIONS, 0, 1, 4, 0, 0, 0, 0];
+        extensions.extend_from_slice(&[ip_number::IPV6_ICMP, 0, 1, 4, 0, 0, 0, 0]);
+        // echo request
+        let icmpv6 = [128, 0, 0, 0, 0x12, 0x34, 0, 1];
+        let frame = ipv6_frame(ip_number::IPV6_DEST_OPTIONS, &extensions, &icmpv6);
+        assert_eq!(
+            analyze_frame_with_icmp(&frame),
+            (
+                0,
+                0,
+                TransProtocol::ICMPv6,
+                AppProtocol::Other,
+                Some((128, 0)),
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn ipv6_hop_by_hop_followed_by_tcp() {
+        // hop-by-hop options header with a PadN option, next header TCP
+        let hop_by_hop = [ip_number::TCP, 0, 1, 4, 0, 0, 0, 0];
+        let tcp = [
+            0xc3, 0x50, 0x01, 0xbb, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xff, 0xff, 0, 0, 0, 0,
+        ];
+        let frame = ipv6_frame(ip_number::IPV6_HOP_BY_HOP, &hop_by_hop, &tcp);
+        assert_eq!(
+            analyze_frame(&frame),
+            (50000, 443, TransProtocol::TCP, AppProtocol::HTTPS, false)
+        );
+    }
+
+    #[test]
+    fn ipv6_repeated_extension_headers_followed_by_udp() {
+        // two destination options headers: etherparse stops decoding at the second one
+        let mut extensions = vec![ip_number::IPV6_DEST_OPTIONS, 0, 1, 4, 0, 0, 0, 0];
+        extensions.extend_from_slice(&[ip_number::UDP, 0, 1, 4, 0, 0, 0, 0]);
+        let udp = [0xd4, 0x31, 0x00, 0x35, 0, 8, 0, 0];
+        let frame = ipv6_frame(ip_number::IPV6_DEST_OPTIONS, &extensions, &udp);
+        assert!(PacketHeaders::from_ethernet_slice(&frame)
+            .unwrap()
+            .transport
+            .is_none());
+        assert_eq!(
+            analyze_frame(&frame),
+            (54321, 53, TransProtocol::UDP, AppProtocol::DNS, false)
+        );
+    }
+
+    #[test]
+    fn udp_443_quic_initial_packet() {
+        let mut quic_initial = vec![0x01, 0xbb, 0xc3, 0x50, 0, 0, 0, 0];
+        // long header with packet type Initial, version 1, 8 bytes destination connection ID
+        quic_initial.extend_from_slice(&[0xc3, 0, 0, 0, 1, 8, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0]);
+        quic_initial.resize(8 + 1200, 0);
+        let udp_len = u16::try_from(quic_initial.len()).unwrap();
+        quic_initial[4..6].copy_from_slice(&udp_len.to_be_bytes());
+        let frame = ipv6_frame(ip_number::UDP, &[], &quic_initial);
+        assert_eq!(
+            analyze_frame(&frame),
+            (443, 50000, TransProtocol::UDP, AppProtocol::QUIC, false)
+        );
+
+        // a DNS message sent to UDP port 443 isn't QUIC
+        let mut dns = vec![0xc3, 0x50, 0x01, 0xbb, 0, 37, 0, 0];
+        dns.extend_from_slice(&[0xc3, 0x21, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+        dns.extend_from_slice(&[3, b'w', b'w', b'w', 7, b'e', b'x', b'a', b'm', b'p', b'l']);
+        dns.extend_from_slice(&[b'e', 3, b'c', b'o', b'm', 0, 0, 1, 0, 1]);
+        let frame = ipv6_frame(ip_number::UDP, &[], &dns);
+        assert_eq!(
+            analyze_frame(&frame),
+            (50000, 443, TransProtocol::UDP, AppProtocol::HTTPS, false)
+        );
+
+        // QUIC short header packets are not recognized
+        let short_header = [0x01, 0xbb, 0xc3, 0x50, 0, 14, 0, 0, 0x43, 1, 2, 3, 4, 5];
+        let frame = ipv6_frame(ip_number::UDP, &[], &short_header);
+        assert_eq!(analyze_frame(&frame).3, AppProtocol::HTTPS);
+    }
+
+    #[test]
+    fn is_quic_long_header_versions() {
+        for version in [[0, 0, 0, 1], [0x6b, 0x33, 0x43, 0xcf], [0xff, 0, 0, 29]] {
+            let header = [[0xc0].as_slice(), &version, &[8]].concat();
+            assert!(is_quic_long_header(&header), "{version:?}");
+        }
+        // unknown version, fixed bit not set, destination connection ID too long, truncated
+        for header in [
+            [0xc0, 0, 0, 0, 2, 8].as_slice(),
+            &[0x80, 0, 0, 0, 1, 8],
+            &[0xc0, 0, 0, 0, 1, 21],
+            &[0xc0, 0, 0, 0, 1],
+        ] {
+            assert!(!is_quic_long_header(header), "{header:?}");
+        }
+    }
+
+    #[test]
+    fn detect_app_protocol_by_payload_signatures() {
+        let mut dns_query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 1];
+        dns_query.extend_from_slice(b"\x08sniffnet\x03net\x00\x00\x01\x00\x01");
+        let mut dns_over_tcp = u16::try_from(dns_query.len())
+            .unwrap()
+            .to_be_bytes()
+            .to_vec();
+        dns_over_tcp.extend_from_slice(&dns_query);
+        for (payload, expected) in [
+            (
+                b"GET /index.html HTTP/1.1\r\nHost: sniffnet.net\r\n\r\n".as_slice(),
+                AppProtocol::HTTP,
+            ),
+            (b"POST /api HTTP/1.1\r\n", AppProtocol::HTTP),
+            (
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n",
+                AppProtocol::HTTP,
+            ),
+            (b"SSH-2.0-OpenSSH_9.3\r\n", AppProtocol::SSH),
+            (b"SSH-1.99-Cisco-1.25\r\n", AppProtocol::SSH),
+            (
+                &tls_client_hello(&sni_extension("sniffnet.net")),
+                AppProtocol::HTTPS,
+            ),
+            // application data record of TLS 1.3
+            (
+                &[0x17, 0x03, 0x03, 0x00, 0x20, 0xaa, 0xbb],
+                AppProtocol::HTTPS,
+            ),
+            (&dns_query, AppProtocol::DNS),
+            (&dns_over_tcp, AppProtocol::DNS),
+        ] {
+            assert_eq!(
+                detect_app_protocol_by_payload(payload),
+                expected,
+                "{payload:?}"
+            );
+        }
+        for payload in [
+            b"".as_slice(),
+            b"GET",
+            b"get / HTTP/1.1\r\n",
+            b"SSH-",
+            // unknown TLS content type, unknown version, empty record
+            &[0x18, 0x03, 0x03, 0x00, 0x20],
+            &[0x16, 0x04, 0x00, 0x00, 0x20],
+            &[0x16, 0x03, 0x01, 0x00, 0x00],
+            // truncated DNS message
+            &dns_query[..20],
+            &[0; 64],
+        ] {
+            assert_eq!(
+                detect_app_protocol_by_payload(payload),
+                AppProtocol::Other,
+                "{payload:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn connection_protocol_detected_after_handshake() {
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let key = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "192.168.1.1".to_string(),
+            8000,
+            TransProtocol::TCP,
+        );
+        // the handshake is flushed before the request is sniffed
+        buffer.add_connection(key.clone(), 0, TrafficType::Outgoing, AppProtocol::Other);
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(info_traffic.map[&key].app_protocol, AppProtocol::Other);
+        buffer.add_connection(key.clone(), 0, TrafficType::Outgoing, AppProtocol::Other);
+        buffer.add_connection(key.clone(), 60, TrafficType::Outgoing, AppProtocol::HTTP);
+        buffer.add_connection(key.clone(), 0, TrafficType::Outgoing, AppProtocol::Other);
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(info_traffic.map[&key].app_protocol, AppProtocol::HTTP);
+        assert_eq!(info_traffic.app_protocols[&AppProtocol::Other].packets, 1);
+        assert_eq!(info_traffic.app_protocols[&AppProtocol::HTTP].packets, 3);
+    }
+
+    #[test]
+    fn payload_detection_on_nonstandard_ports() {
+        let tcp_segment = |ports: (u16, u16), payload: &[u8]| {
+            let mut segment = ports.0.to_be_bytes().to_vec();
+            segment.extend_from_slice(&ports.1.to_be_bytes());
+            segment
+                .extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0]);
+            segment.extend_from_slice(payload);
+            ipv6_frame(ip_number::TCP, &[], &segment)
+        };
+        let request = b"GET / HTTP/1.1\r\nHost: sniffnet.net\r\n\r\n";
+        assert_eq!(
+            analyze_frame(&tcp_segment((50000, 8000), request)).3,
+            AppProtocol::HTTP
+        );
+        assert_eq!(
+            analyze_frame(&tcp_segment((2222, 50000), b"SSH-2.0-OpenSSH_9.3\r\n")).3,
+            AppProtocol::SSH
+        );
+        // segments without payload (e.g. the handshake) are not classified
+        assert_eq!(
+            analyze_frame(&tcp_segment((50000, 8000), &[])).3,
+            AppProtocol::Other
+        );
+        // the well-known ports take precedence over the payload
+        assert_eq!(
+            analyze_frame(&tcp_segment((50000, 22), request)).3,
+            AppProtocol::SSH
+        );
+    }
+
+    #[test]
+    fn udp_port_5353_is_mdns() {
+        let udp = [0x14, 0xe9, 0x14, 0xe9, 0, 8, 0, 0];
+        let frame = ipv6_frame(ip_number::UDP, &[], &udp);
+        assert_eq!(
+            analyze_frame(&frame),
+            (5353, 5353, TransProtocol::UDP, AppProtocol::mDNS, false)
+        );
+    }
 
     #[test]
     fn ipv6_simple_test() {
@@ -403,4 +1799,803 @@ mod test {
             ipv6_from_long_dec_to_short_hex([0, 16, 16, 0, 0, 1, 7, 0, 0, 2, 216, 0, 1, 0, 0, 1]);
         assert_eq!(result, "10:1000:1:700:2:d800:100:1".to_string());
     }
+    #[test]
+    fn test_bpf_filter_validation() {
+        let path = std::env::temp_dir().join("sniffnet_test_bpf_filter.pcap");
+        let mut file = Vec::new();
+        for field in [0xa1b2_c3d4_u32, 0x0004_0002, 0, 0, 65535, 1] {
+            file.extend_from_slice(&field.to_ne_bytes());
+        }
+        std::fs::write(&path, file).unwrap();
+        let mut cap = Capture::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(set_bpf_filter(&mut cap, "udp port 53"), Ok(()));
+        let err = set_bpf_filter(&mut cap, "udp port invalid").unwrap_err();
+        assert!(err.starts_with("Invalid BPF filter \"udp port invalid\": "));
+    }
+
+    #[test]
+    fn test_multicast_addresses() {
+        for address in [
+            "224.0.0.1",
+            "224.0.0.251",
+            "239.255.255.250",
+            "ff02::1",
+            "ff02::fb",
+            "ff05::1:3",
+            "ff0e::101",
+        ] {
+            assert!(is_multicast_address(address), "{address}");
+        }
+        for address in [
+            "223.255.255.255",
+            "240.0.0.1",
+            "255.255.255.255",
+            "192.168.1.10",
+            "169.254.12.34",
+            "100.64.0.1",
+            "fe80::1",
+            "fe80::211:22ff:fe33:4455",
+            // "ff::1" is 00ff::1, not a multicast address
+            "ff::1",
+            "2001:db8::ff",
+            "not an address",
+            "",
+        ] {
+            assert!(!is_multicast_address(address), "{address}");
+        }
+    }
+
+    #[test]
+    fn test_broadcast_addresses() {
+        let broadcast_addresses = ["192.168.1.255".parse().unwrap()];
+        for address in ["255.255.255.255", "169.254.255.255", "192.168.1.255"] {
+            assert!(
+                is_broadcast_address(address, &broadcast_addresses),
+                "{address}"
+            );
+        }
+        for address in [
+            "192.168.2.255",
+            "192.168.1.10",
+            "169.254.12.34",
+            "100.127.255.255",
+            "224.0.0.1",
+            "ff02::1",
+            "fe80::1",
+            "not an address",
+        ] {
+            assert!(
+                !is_broadcast_address(address, &broadcast_addresses),
+                "{address}"
+            );
+        }
+        // subnet-directed broadcast addresses are only recognized for the sniffed network adapter
+        assert!(!is_broadcast_address("192.168.1.255", &[]));
+    }
+
+    #[test]
+    fn test_local_addresses() {
+        for address in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "172.31.255.254",
+            "192.168.1.10",
+            "169.254.12.34",
+            "127.0.0.1",
+            "255.255.255.255",
+            "224.0.0.251",
+            "fd12:3456:789a::1",
+            "fc00::1",
+            "fe80::211:22ff:fe33:4455",
+            "::1",
+            "ff02::fb",
+        ] {
+            assert!(is_local_address(address), "{address}");
+        }
+        for address in [
+            "8.8.8.8",
+            "172.32.0.1",
+            "100.64.0.1",
+            "239.255.255.250",
+            "2001:db8::1",
+            "ff05::1:3",
+            "not an address",
+        ] {
+            assert!(!is_local_address(address), "{address}");
+        }
+    }
+
+    #[test]
+    fn test_network_scope() {
+        let pairs = [
+            ("192.168.1.10", "192.168.1.1", true),
+            ("fe80::1", "ff02::fb", true),
+            ("10.0.0.5", "192.168.1.255", true),
+            ("192.168.1.10", "8.8.8.8", false),
+            ("142.250.180.14", "192.168.1.10", false),
+            ("fd00::2", "2001:4860:4860::8888", false),
+            ("93.184.216.34", "8.8.8.8", false),
+        ];
+        for (address1, address2, is_local) in pairs {
+            assert_eq!(
+                is_in_network_scope(NetworkScope::Local, address1, address2),
+                is_local,
+                "{address1} {address2}"
+            );
+            assert_eq!(
+                is_in_network_scope(NetworkScope::Wan, address1, address2),
+                !is_local,
+                "{address1} {address2}"
+            );
+            assert!(is_in_network_scope(NetworkScope::Other, address1, address2));
+        }
+    }
+
+    #[test]
+    fn test_get_broadcast_addresses() {
+        let address = |addr: &str, netmask: Option<&str>, broadcast_addr: Option<&str>| Address {
+            addr: addr.parse().unwrap(),
+            netmask: netmask.map(|netmask| netmask.parse().unwrap()),
+            broadcast_addr: broadcast_addr.map(|broadcast_addr| broadcast_addr.parse().unwrap()),
+            dst_addr: None,
+        };
+        let addresses = [
+            address("192.168.1.10", Some("255.255.255.0"), Some("192.168.1.255")),
+            address("10.1.2.3", Some("255.255.0.0"), None),
+            address("100.64.0.1", Some("255.192.0.0"), None),
+            // point-to-point link
+            address("172.16.0.1", Some("255.255.255.254"), None),
+            address("fe80::1", Some("ffff:ffff:ffff:ffff::"), None),
+        ];
+        assert_eq!(
+            get_broadcast_addresses(&addresses),
+            vec![
+                "192.168.1.255".parse::<IpAddr>().unwrap(),
+                "10.1.255.255".parse().unwrap(),
+                "100.127.255.255".parse().unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_activation_error() {
+        let denied =
+            pcap::Error::PcapError("eth0: You don't have permission to capture".to_string());
+        assert!(get_activation_error(&denied, true).contains("promiscuous = false"));
+        assert_eq!(
+            get_activation_error(&denied, false),
+            "libpcap error: eth0: You don't have permission to capture"
+        );
+        let missing = pcap::Error::PcapError("eth9: No such device exists".to_string());
+        assert_eq!(
+            get_activation_error(&missing, true),
+            "libpcap error: eth9: No such device exists"
+        );
+    }
+    #[test]
+    fn test_connections_matching_favorite_rules() {
+        let favorite_rules = get_favorite_matchers(&[FavoriteRule {
+            network: Some("192.168.1.0/24".to_string()),
+            port: Some(22),
+        }]);
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence {
+                interval: Duration::ZERO,
+                max_packets: 1,
+            },
+            favorite_rules,
+            Vec::new(),
+        );
+        for port in [443, 22, 22, 80] {
+            let key = AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                50000,
+                "192.168.1.1".to_string(),
+                port,
+                TransProtocol::TCP,
+            );
+            buffer.add_connection(key, 100, TrafficType::Outgoing, AppProtocol::Other);
+            buffer.flush(
+                &mut info_traffic,
+                country_db_reader.as_ref(),
+                None,
+                None,
+                None,
+            );
+        }
+        assert_eq!(info_traffic.map.len(), 3);
+        let favorites: Vec<u16> = info_traffic
+            .map
+            .iter()
+            .filter(|(_, info)| info.is_favorite)
+            .map(|(key, _)| key.port2)
+            .collect();
+        assert_eq!(favorites, vec![22]);
+        assert_eq!(info_traffic.favorite_connections, HashSet::from([1]));
+        // the new favorite feeds the favorite notification path
+        assert_eq!(info_traffic.favorites_last_interval, HashSet::from([1]));
+    }
+    #[test]
+    fn test_connections_with_gateway() {
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            vec!["192.168.1.1".to_string(), "fe80::1".to_string()],
+        );
+        for (address1, address2) in [
+            ("192.168.1.10", "192.168.1.1"),
+            ("192.168.1.1", "192.168.1.10"),
+            ("192.168.1.10", "8.8.8.8"),
+            ("fe80::10", "fe80::1"),
+        ] {
+            let key = AddressPortPair::new(
+                address1.to_string(),
+                50000,
+                address2.to_string(),
+                53,
+                TransProtocol::UDP,
+            );
+            buffer.add_connection(key, 100, TrafficType::Outgoing, AppProtocol::DNS);
+        }
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        let gateway_flags: Vec<bool> = info_traffic
+            .map
+            .values()
+            .map(|info| info.is_gateway)
+            .collect();
+        assert_eq!(gateway_flags, vec![true, true, false, true]);
+    }
+    #[test]
+    fn test_new_countries_tracking() {
+        let mut info_traffic = InfoTraffic::new();
+        for (remote, port) in [
+            ("192.168.1.1", 53),
+            ("8.8.8.8", 53),
+            ("8.8.8.8", 853),
+            ("8.8.4.4", 53),
+        ] {
+            let key = AddressPortPair::new(
+                "192.168.1.10".to_string(),
+                50000,
+                remote.to_string(),
+                port,
+                TransProtocol::UDP,
+            );
+            info_traffic.flush_packets(
+                "eth0",
+                [(key, 100, TrafficType::Outgoing, AppProtocol::DNS)],
+            );
+        }
+        // local addresses have no country, and later connections with the same country are not new
+        assert_eq!(
+            info_traffic.new_countries_last_interval,
+            vec![("US".to_string(), 1)]
+        );
+        assert_eq!(
+            info_traffic.seen_countries,
+            HashSet::from(["US".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_traffic_type() {
+        let my_addresses = ["192.168.1.10".to_string(), "172.17.0.1".to_string()];
+        let broadcast_addresses = ["192.168.1.255".parse().unwrap()];
+        let traffic_type = |address1, address2| {
+            get_traffic_type(address1, address2, &my_addresses, &broadcast_addresses)
+        };
+        // loopback traffic, even when not sniffed on the loopback adapter
+        assert_eq!(traffic_type("127.0.0.1", "127.0.0.1"), TrafficType::Local);
+        assert_eq!(traffic_type("127.0.0.1", "127.0.1.1"), TrafficType::Local);
+        assert_eq!(traffic_type("::1", "::1"), TrafficType::Local);
+        // traffic between two addresses of this host (e.g. a container bridge)
+        assert_eq!(
+            traffic_type("192.168.1.10", "172.17.0.1"),
+            TrafficType::Local
+        );
+        assert_eq!(traffic_type("172.17.0.1", "127.0.0.1"), TrafficType::Local);
+        assert_eq!(
+            traffic_type("192.168.1.10", "8.8.8.8"),
+            TrafficType::Outgoing
+        );
+        assert_eq!(
+            traffic_type("172.17.0.2", "172.17.0.1"),
+            TrafficType::Incoming
+        );
+        assert_eq!(
+            traffic_type("192.168.1.20", "224.0.0.251"),
+            TrafficType::Multicast
+        );
+        assert_eq!(
+            traffic_type("192.168.1.20", "192.168.1.255"),
+            TrafficType::Broadcast
+        );
+        assert_eq!(
+            traffic_type("192.168.1.20", "192.168.1.30"),
+            TrafficType::Other
+        );
+    }
+
+    #[test]
+    fn test_local_traffic_accounted_once() {
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "lo".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        for (port1, port2) in [(50000, 8080), (8080, 50000), (50000, 8080)] {
+            let key = AddressPortPair::new(
+                "127.0.0.1".to_string(),
+                port1,
+                "127.0.0.1".to_string(),
+                port2,
+                TransProtocol::TCP,
+            );
+            buffer.add_connection(key, 100, TrafficType::Local, AppProtocol::Other);
+        }
+        buffer.flush(
+            &mut info_traffic,
+            country_db_reader.as_ref(),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(info_traffic.tot_sent_packets, 0);
+        assert_eq!(info_traffic.tot_received_packets, 3);
+        assert_eq!(info_traffic.tot_received_bytes, 300);
+        assert_eq!(info_traffic.map.len(), 2);
+        assert!(info_traffic
+            .map
+            .values()
+            .all(|info| info.traffic_type == TrafficType::Local && info.country.is_empty()));
+    }
+
+    #[test]
+    fn test_broadcast_multicast_aggregation() {
+        let country_db_reader = get_country_db_reader(None);
+        let mut info_traffic = InfoTraffic::new();
+        let mut buffer = TrafficBuffer::new(
+            "eth0".to_string(),
+            FlushCadence::default(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let mut add_traffic = |info_traffic: &mut InfoTraffic| {
+            for (remote, traffic_type) in [
+                ("255.255.255.255", TrafficType::Broadcast),
+                ("224.0.0.251", TrafficType::Multicast),
+                ("239.255.255.250", TrafficType::Multicast),
+                ("8.8.8.8", TrafficType::Outgoing),
+            ] {
+                let key = AddressPortPair::new(
+                    "192.168.1.10".to_string(),
+                    5353,
+                    remote.to_string(),
+                    5353,
+                    TransProtocol::UDP,
+                );
+                buffer.add_connection(key, 100, traffic_type, AppProtocol::mDNS);
+            }
+            buffer.flush(info_traffic, country_db_reader.as_ref(), None, None, None);
+        };
+
+        add_traffic(&mut info_traffic);
+        assert_eq!(info_traffic.map.len(), 4);
+
+        info_traffic.aggregate_broadcast_multicast = true;
+        add_traffic(&mut info_traffic);
+        // the connections already in the map are not updated anymore
+        assert_eq!(info_traffic.map.len(), 4);
+        assert!(info_traffic
+            .map
+            .values()
+            .filter(|info| info.traffic_type != TrafficType::Outgoing)
+            .all(|info| info.transmitted_packets == 1));
+        assert_eq!(
+            info_traffic.broadcast_summary,
+            TrafficSummary {
+                packets: 1,
+                bytes: 100
+            }
+        );
+        assert_eq!(
+            info_traffic.multicast_summary,
+            TrafficSummary {
+                packets: 2,
+                bytes: 200
+            }
+        );
+        // the aggregated traffic is still accounted in the totals
+        assert_eq!(info_traffic.tot_received_packets, 6);
+        assert_eq!(info_traffic.tot_sent_packets, 2);
+        assert_eq!(info_traffic.app_protocols[&AppProtocol::mDNS].packets, 8);
+
+        // the aggregation is reversible
+        info_traffic.aggregate_broadcast_multicast = false;
+        add_traffic(&mut info_traffic);
+        assert_eq!(info_traffic.multicast_summary.packets, 2);
+        assert!(info_traffic
+            .map
+            .values()
+            .filter(|info| info.traffic_type != TrafficType::Outgoing)
+            .all(|info| info.transmitted_packets == 2));
+    }
+
+    #[test]
+    fn test_combine_bidirectional_flows() {
+        let country_db_reader = get_country_db_reader(None);
+        let request = AddressPortPair::new(
+            "192.168.1.10".to_string(),
+            50000,
+            "8.8.8.8".to_string(),
+            53,
+            TransProtocol::UDP,
+        );
+        let response = get_reverse_key(&request);
+        let add_traffic = |info_traffic: &mut InfoTraffic| {
+            let mut buffer = TrafficBuffer::new(
+                "eth0".to_string(),
+                FlushCadence::default(),
+                Vec::new(),
+                Vec::new(),
+            );
+            // the response is observed first
+            buffer.add_connection(
+                response.clone(),
+                120,
+                TrafficType::Incoming,
+                AppProtocol::DNS,
+            );
+            buffer.add_connection(request.clone(), 40, TrafficType::Outgoing, AppProtocol::DNS);
+            buffer.add_connection(
+                response.clone(),
+                80,
+                TrafficType::Incoming,
+                AppProtocol::DNS,
+            );
+            buffer.flush(info_traffic, country_db_reader.as_ref(), None, None, None);
+        };
+
+        let mut info_traffic = InfoTraffic::new();
+        add_traffic(&mut info_traffic);
+        assert_eq!(info_traffic.map.len(), 2);
+        assert_eq!(info_traffic.map[&response].received_bytes, 200);
+        assert_eq!(info_traffic.map[&request].sent_bytes, 40);
+
+        let mut info_traffic = InfoTraffic::new();
+        info_traffic.combine_bidirectional_flows = true;
+        add_traffic(&mut info_traffic);
+        assert_eq!(info_traffic.map.len(), 1);
+        // the entry has the local endpoint first
+        let info = &info_traffic.map[&request];
+        assert_eq!(info.traffic_type, TrafficType::Outgoing);
+        assert_eq!(info.transmitted_packets, 3);
+        assert_eq!(info.transmitted_bytes, 240);
+        assert_eq!((info.sent_bytes, info.received_bytes), (40, 200));
+        assert_eq!((info.sent_packets, info.received_packets), (1, 2));
+        assert_eq!(info.total_bytes(), info.transmitted_bytes);
+        assert_eq!(info.country, "US");
+        // the totals are accounted according to the actual direction of the traffic
+        assert_eq!(info_traffic.tot_sent_packets, 1);
+        assert_eq!(info_traffic.tot_sent_bytes, 40);
+        assert_eq!(info_traffic.tot_received_packets, 2);
+        assert_eq!(info_traffic.tot_received_bytes, 200);
+
+        // the traffic exchanged later is merged into the same entry
+        add_traffic(&mut info_traffic);
+        assert_eq!(info_traffic.map.len(), 1);
+        assert_eq!(info_traffic.map[&request].transmitted_packets, 6);
+        assert_eq!(info_traffic.map[&request].received_bytes, 400);
+    }
+
+    /// Builds a TLS record carrying a ClientHello with the given extensions.
+    fn tls_client_hello(extensions: &[u8]) -> Vec<u8> {
+        let mut hello = vec![0x03, 0x03];
+        hello.extend_from_slice(&[7; 32]);
+        // session id, cipher suites and compression methods
+        hello.extend_from_slice(&[4, 1, 2, 3, 4]);
+        hello.extend_from_slice(&[0, 4, 0x13, 0x01, 0x13, 0x02]);
+        hello.extend_from_slice(&[1, 0]);
+        hello.extend_from_slice(&u16::try_from(extensions.len()).unwrap().to_be_bytes());
+        hello.extend_from_slice(extensions);
+        let hello_len = u32::try_from(hello.len()).unwrap().to_be_bytes();
+        let mut handshake = vec![0x01, hello_len[1], hello_len[2], hello_len[3]];
+        handshake.extend_from_slice(&hello);
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&u16::try_from(handshake.len()).unwrap().to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    /// Builds a TLS SNI extension for the given host name.
+    fn sni_extension(host_name: &str) -> Vec<u8> {
+        let name_len = u16::try_from(host_name.len()).unwrap();
+        let mut extension = vec![0, 0];
+        extension.extend_from_slice(&(name_len + 5).to_be_bytes());
+        extension.extend_from_slice(&(name_len + 3).to_be_bytes());
+        extension.push(0);
+        extension.extend_from_slice(&name_len.to_be_bytes());
+        extension.extend_from_slice(host_name.as_bytes());
+        extension
+    }
+
+    #[test]
+    fn test_tls_sni_from_client_hello() {
+        // supported groups extension preceding the server name
+        let mut extensions = vec![0, 0x0a, 0, 4, 0, 2, 0, 0x1d];
+        extensions.extend_from_slice(&sni_extension("WWW.Example.com"));
+        assert_eq!(
+            get_tls_sni(&tls_client_hello(&extensions)),
+            Some("www.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tls_sni_not_available() {
+        // no SNI extension
+        assert_eq!(
+            get_tls_sni(&tls_client_hello(&[0, 0x0a, 0, 4, 0, 2, 0, 0x1d])),
+            None
+        );
+        // not TLS
+        assert_eq!(get_tls_sni(b"GET / HTTP/1.1\r\n"), None);
+        assert_eq!(get_tls_sni(&[]), None);
+        // TLS alert and ServerHello
+        let mut hello = tls_client_hello(&sni_extension("example.com"));
+        hello[0] = 0x15;
+        assert_eq!(get_tls_sni(&hello), None);
+        hello[0] = 0x16;
+        hello[5] = 0x02;
+        assert_eq!(get_tls_sni(&hello), None);
+        // ClientHello split across segments: the extension is not in the first one
+        let hello = tls_client_hello(&sni_extension("example.com"));
+        for len in [3, 10, 50, hello.len() - 1] {
+            assert_eq!(get_tls_sni(&hello[..len]), None);
+        }
+    }
+
+    #[test]
+    fn test_tls_sni_from_truncated_client_hello() {
+        // a ClientHello as sent by a browser: the server name is followed by other extensions
+        // (including a key share) that don't fit the default snapshot length
+        let mut extensions = sni_extension("www.example.com");
+        // extended master secret, renegotiation info, supported groups, EC point formats
+        extensions.extend_from_slice(&[0, 0x17, 0, 0, 0xff, 0x01, 0, 1, 0]);
+        extensions.extend_from_slice(&[0, 0x0a, 0, 8, 0, 6, 0, 0x1d, 0, 0x17, 0, 0x18]);
+        extensions.extend_from_slice(&[0, 0x0b, 0, 2, 1, 0]);
+        // key share (X25519) and supported versions
+        extensions.extend_from_slice(&[0, 0x33, 0, 0x26, 0, 0x24, 0, 0x1d, 0, 0x20]);
+        extensions.extend_from_slice(&[0x5a; 32]);
+        extensions.extend_from_slice(&[0, 0x2b, 0, 5, 4, 0x03, 0x04, 0x03, 0x03]);
+        let mut client_hello = tls_client_hello(&extensions);
+        // 32 bytes of session id and 16 cipher suites
+        client_hello.splice(43..48, [32].into_iter().chain([0xab; 32]));
+        client_hello.splice(76..82, [0, 32].into_iter().chain([0x13; 32]));
+        let hello_len = u16::try_from(client_hello.len() - 5).unwrap().to_be_bytes();
+        client_hello[3..5].copy_from_slice(&hello_len);
+        client_hello[7..9].copy_from_slice(&(u16::from_be_bytes(hello_len) - 4).to_be_bytes());
+        assert!(client_hello.len() > 190);
+        assert_eq!(
+            get_tls_sni(&client_hello),
+            Some("www.example.com".to_string())
+        );
+        // the payload kept by the default snapshot length of an Ethernet + IPv4 + TCP packet
+        let snaplen = usize::try_from(DEFAULT_SNAPLEN).unwrap();
+        assert_eq!(snaplen - 14 - 20 - 32, 190);
+        assert_eq!(
+            get_tls_sni(&client_hello[..190]),
+            Some("www.example.com".to_string())
+        );
+
+        // the server name following an extension not fully captured is not available
+        let mut extensions = vec![0, 0x33, 0, 0x26, 0, 0x24, 0, 0x1d, 0, 0x20];
+        extensions.extend_from_slice(&[0x5a; 32]);
+        extensions.extend_from_slice(&sni_extension("www.example.com"));
+        let client_hello = tls_client_hello(&extensions);
+        let len = client_hello.len() - extensions.len() + 20;
+        assert_eq!(get_tls_sni(&client_hello[..len]), None);
+        // and so is a server name captured only partially
+        let client_hello = tls_client_hello(&sni_extension("www.example.com"));
+        assert_eq!(get_tls_sni(&client_hello[..client_hello.len() - 3]), None);
+    }
+
+    /// Builds an Ethernet frame (with an optional VLAN tag) carrying an ARP packet with the given operation.
+    fn arp_frame(vlan_tagged: bool, operation: u8, target: [u8; 4]) -> Vec<u8> {
+        let mut frame = vec![0xff; 6];
+        frame.extend_from_slice(&[0, 1, 2, 3, 4, 5]);
+        if vlan_tagged {
+            frame.extend_from_slice(&[0x81, 0x00, 0, 10]);
+        }
+        frame.extend_from_slice(&[0x08, 0x06, 0, 1, 0x08, 0, 6, 4, 0, operation]);
+        frame.extend_from_slice(&[0, 1, 2, 3, 4, 5, 192, 168, 1, 10]);
+        frame.extend_from_slice(&[0; 6]);
+        frame.extend_from_slice(&target);
+        frame
+    }
+
+    /// Builds an Ethernet frame carrying an IPv4 packet with the given protocol and payload.
+    fn ipv4_frame(protocol: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![1, 0, 0x5e, 0, 0, 1, 0, 1, 2, 3, 4, 5, 0x08, 0x00, 0x45, 0];
+        frame.extend_from_slice(&u16::try_from(20 + payload.len()).unwrap().to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 1, protocol, 0, 0]);
+        frame.extend_from_slice(&[192, 168, 1, 1, 224, 0, 0, 1]);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn test_dscp_extraction() {
+        let dscp_of = |frame: &[u8]| {
+            let headers = PacketHeaders::from_ethernet_slice(frame).unwrap();
+            let mut dscp = u8::MAX;
+            analyze_network_header(
+                headers.ip,
+                &mut 0,
+                &mut IpVersion::Other,
+                &mut dscp,
+                &mut String::new(),
+                &mut String::new(),
+                &mut false,
+            );
+            dscp
+        };
+        assert_eq!(dscp_of(&ipv4_frame(ip_number::UDP, &[0; 8])), 0);
+        // EF with ECN capable transport: the ECN bits are not part of the DSCP
+        let mut frame = ipv4_frame(ip_number::UDP, &[0; 8]);
+        frame[15] = (46 << 2) | 0b01;
+        assert_eq!(dscp_of(&frame), 46);
+        // AF41 in the traffic class, spanning the first two bytes of the IPv6 header
+        let mut frame = ipv6_frame(ip_number::UDP, &[], &[0; 8]);
+        frame[14] = 0x60 | (34 >> 2);
+        frame[15] = (34 << 6) | 0b11_0000;
+        assert_eq!(dscp_of(&frame), 34);
+    }
+
+    #[test]
+    fn test_dscp_names() {
+        assert_eq!(get_dscp_name(0), Some("CS0"));
+        assert_eq!(get_dscp_name(8), Some("CS1"));
+        assert_eq!(get_dscp_name(56), Some("CS7"));
+        assert_eq!(get_dscp_name(10), Some("AF11"));
+        assert_eq!(get_dscp_name(34), Some("AF41"));
+        assert_eq!(get_dscp_name(38), Some("AF43"));
+        assert_eq!(get_dscp_name(46), Some("EF"));
+        assert_eq!(get_dscp_name(44), Some("VA"));
+        assert_eq!(get_dscp_name(1), Some("LE"));
+        assert_eq!(get_dscp_name(13), None);
+        assert_eq!(get_dscp_name(63), None);
+    }
+
+    #[test]
+    fn test_discovery_packets() {
+        let discovery_packet = |frame: &[u8]| {
+            get_discovery_packet(&PacketHeaders::from_ethernet_slice(frame).unwrap())
+        };
+        let target = [192, 168, 1, 1];
+        for vlan_tagged in [false, true] {
+            assert_eq!(
+                discovery_packet(&arp_frame(vlan_tagged, 1, target)),
+                Some(DiscoveryPacket::ArpRequest {
+                    target: Ipv4Addr::from(target)
+                })
+            );
+            assert_eq!(
+                discovery_packet(&arp_frame(vlan_tagged, 2, target)),
+                Some(DiscoveryPacket::ArpReply)
+            );
+        }
+        // RARP and truncated ARP packets
+        assert_eq!(discovery_packet(&arp_frame(false, 3, target)), None);
+        assert_eq!(discovery_packet(&arp_frame(false, 1, target)[..40]), None);
+
+        let igmp_query = [0x11, 0x64, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            discovery_packet(&ipv4_frame(ip_number::IGMP, &igmp_query)),
+            Some(DiscoveryPacket::Igmp { message_type: 0x11 })
+        );
+        assert_eq!(discovery_packet(&ipv4_frame(ip_number::IGMP, &[])), None);
+        assert_eq!(
+            discovery_packet(&ipv4_frame(ip_number::UDP, &[0, 53, 0, 53, 0, 8, 0, 0])),
+            None
+        );
+    }
+
+    /// Returns pseudo-random byte slices of up to 300 bytes (xorshift generator, reproducible across runs),
+    /// followed by every truncation and some single-byte corruptions of the given valid messages.
+    fn fuzz_inputs(valid_messages: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut inputs: Vec<Vec<u8>> = (0..2000)
+            .map(|_| {
+                let len = usize::try_from(next() % 300).unwrap();
+                (0..len).map(|_| next().to_be_bytes()[0]).collect()
+            })
+            .collect();
+        for message in valid_messages {
+            for len in 0..=message.len() {
+                inputs.push(message[..len].to_vec());
+            }
+            for _ in 0..200 {
+                let mut corrupted = message.clone();
+                let position = usize::try_from(next()).unwrap() % corrupted.len();
+                corrupted[position] = next().to_be_bytes()[0];
+                inputs.push(corrupted);
+            }
+        }
+        inputs
+    }
+
+    #[test]
+    fn test_payload_parsers_never_panic() {
+        let tls_hello = tls_client_hello(&sni_extension("sniffnet.net"));
+        let mut dns_query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        dns_query.extend_from_slice(b"\x08sniffnet\x03net\x00\x00\x01\x00\x01");
+        let quic_initial = [0xc3, 0, 0, 0, 1, 8, 1, 2, 3, 4, 5, 6, 7, 8];
+        let udp_dns = {
+            let mut datagram = vec![0xc3, 0x50, 0, 53];
+            datagram.extend_from_slice(&u16::try_from(8 + dns_query.len()).unwrap().to_be_bytes());
+            datagram.extend_from_slice(&[0, 0]);
+            datagram.extend_from_slice(&dns_query);
+            ipv4_frame(ip_number::UDP, &datagram)
+        };
+        let valid_messages = [
+            tls_hello,
+            dns_query,
+            quic_initial.to_vec(),
+            udp_dns,
+            arp_frame(true, 1, [192, 168, 1, 1]),
+        ];
+
+        // the test fails if any of the parsers panics
+        for input in fuzz_inputs(&valid_messages) {
+            get_tls_sni(&input);
+            is_quic_long_header(&input);
+            get_dns_query_name(&input, TransProtocol::TCP);
+            get_dns_query_name(&input, TransProtocol::UDP);
+            detect_app_protocol_by_payload(&input);
+            for linktype in [
+                Linktype::ETHERNET,
+                Linktype::NULL,
+                Linktype::RAW,
+                Linktype::IEEE802_11,
+                Linktype::IEEE802_11_RADIOTAP,
+                Linktype::LINUX_SLL,
+                Linktype::LINUX_SLL2,
+            ] {
+                let Some(headers) = parse_packet_headers(linktype, &input) else {
+                    continue;
+                };
+                get_discovery_packet(&headers);
+                if let Some((_, payload)) =
+                    get_transport_header(&headers.ip, headers.transport.clone(), headers.payload)
+                {
+                    get_tls_sni(payload);
+                    is_quic_long_header(payload);
+                    get_dns_query_name(payload, TransProtocol::UDP);
+                }
+            }
+        }
+    }
 }
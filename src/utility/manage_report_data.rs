@@ -1,51 +1,115 @@
-use crate::structs::address_port_pair::AddressPortPair;
-use crate::structs::info_address_port_pair::InfoAddressPortPair;
-use crate::{InfoTraffic, ReportType, RunTimeData};
+use crate::enums::metric::Metric;
+use crate::enums::sort_column::SortColumn;
+use crate::utility::logging::lock_or_recover;
+use crate::{ChartType, InfoTraffic, ReportType, RunTimeData};
 use std::cell::RefMut;
-use std::cmp::min;
 use std::sync::{Arc, Mutex};
 
+/// Number of connections displayed in the top talkers summary
+const TOP_TALKERS: usize = 5;
+
+/// Updates the connections to be displayed in the report, considering only those matching the search query.
+///
+/// If a column has been selected in the report header, connections are ordered by it
+/// (descending if the flag is set) instead of the default order of the report type.
 pub fn update_report_data(
     mut runtime_data: RefMut<RunTimeData>,
     info_traffic: &Arc<Mutex<InfoTraffic>>,
     report_type: ReportType,
+    report_sort: Option<(SortColumn, bool)>,
+    search: &str,
 ) {
-    let info_traffic_lock = info_traffic.lock().unwrap();
+    let info_traffic_lock = lock_or_recover(info_traffic, "updating report");
     runtime_data.report_vec = Vec::default();
+    // matching indexes are in ascending order
+    let matching_indexes = info_traffic_lock.filter_connections(search);
+    let sort = report_sort.or(match report_type {
+        ReportType::MostRecent => Some((SortColumn::Recency, true)),
+        ReportType::MostPackets => Some((SortColumn::Packets, true)),
+        ReportType::MostBytes => Some((SortColumn::Bytes, true)),
+        // favorites are listed in order of appearance
+        ReportType::Favorites => None,
+    });
+    let displayed_indexes = match sort {
+        Some((sort_column, descending)) => info_traffic_lock
+            .sort_connections(sort_column, descending)
+            .into_iter()
+            .filter(|index| matching_indexes.binary_search(index).is_ok())
+            .collect(),
+        None => matching_indexes,
+    };
 
     if report_type.ne(&ReportType::Favorites) {
-        let mut sorted_vec: Vec<(&AddressPortPair, &InfoAddressPortPair)> =
-            info_traffic_lock.map.iter().collect();
-
-        match report_type {
-            ReportType::MostRecent => {
-                sorted_vec.sort_by(|&(_, a), &(_, b)| b.final_timestamp.cmp(&a.final_timestamp));
-            }
-            ReportType::MostPackets => {
-                sorted_vec
-                    .sort_by(|&(_, a), &(_, b)| b.transmitted_packets.cmp(&a.transmitted_packets));
-            }
-            ReportType::MostBytes => {
-                sorted_vec
-                    .sort_by(|&(_, a), &(_, b)| b.transmitted_bytes.cmp(&a.transmitted_bytes));
-            }
-            ReportType::Favorites => {}
-        }
-
-        let n_entry = min(sorted_vec.len(), 15);
-        for i in 0..n_entry {
-            let key_val = *sorted_vec.get(i).unwrap();
+        for index in displayed_indexes.into_iter().take(15) {
+            let key_val = info_traffic_lock.map.get_index(index).unwrap();
             runtime_data
                 .report_vec
                 .push((key_val.0.clone(), key_val.1.clone()));
         }
     } else {
         // favorites
-        for index in &info_traffic_lock.favorite_connections {
-            let key_val = info_traffic_lock.map.get_index(*index).unwrap();
-            runtime_data
-                .report_vec
-                .push((key_val.0.clone(), key_val.1.clone()));
+        for index in displayed_indexes {
+            let key_val = info_traffic_lock.map.get_index(index).unwrap();
+            if info_traffic_lock
+                .favorite_connections
+                .contains(&key_val.1.id)
+            {
+                runtime_data
+                    .report_vec
+                    .push((key_val.0.clone(), key_val.1.clone()));
+            }
         }
     }
 }
+
+/// Updates the connections that exchanged the most traffic, measured according to the displayed chart kind.
+pub fn update_top_talkers(
+    mut runtime_data: RefMut<RunTimeData>,
+    info_traffic: &Arc<Mutex<InfoTraffic>>,
+    chart_type: ChartType,
+) {
+    let metric = match chart_type {
+        ChartType::Packets => Metric::Packets,
+        ChartType::Bytes => Metric::Bytes,
+    };
+    runtime_data.top_talkers =
+        lock_or_recover(info_traffic, "updating top talkers").top_talkers(TOP_TALKERS, metric);
+}
+
+/// Updates the total traffic exchanged with each country.
+pub fn update_country_breakdown(
+    mut runtime_data: RefMut<RunTimeData>,
+    info_traffic: &Arc<Mutex<InfoTraffic>>,
+) {
+    runtime_data.country_breakdown =
+        lock_or_recover(info_traffic, "updating country breakdown").country_breakdown();
+}
+
+/// Updates the packet size histogram.
+pub fn update_size_histogram(
+    mut runtime_data: RefMut<RunTimeData>,
+    info_traffic: &Arc<Mutex<InfoTraffic>>,
+) {
+    runtime_data.size_histogram =
+        lock_or_recover(info_traffic, "updating size histogram").size_histogram_buckets();
+}
+
+/// Updates the LAN discovery traffic (ARP and IGMP) observed so far.
+pub fn update_discovery_traffic(
+    mut runtime_data: RefMut<RunTimeData>,
+    info_traffic: &Arc<Mutex<InfoTraffic>>,
+) {
+    runtime_data.discovery_traffic = lock_or_recover(info_traffic, "updating discovery traffic")
+        .discovery
+        .clone();
+}
+
+/// Updates the traffic sniffed on each network adapter.
+pub fn update_per_interface(
+    mut runtime_data: RefMut<RunTimeData>,
+    info_traffic: &Arc<Mutex<InfoTraffic>>,
+) {
+    runtime_data.per_interface = lock_or_recover(info_traffic, "updating traffic per adapter")
+        .per_interface
+        .clone();
+}
@@ -1,5 +1,10 @@
+pub mod anonymize;
 pub mod countries;
+pub mod dns;
+pub mod export_connections;
+pub mod gateway;
 pub mod get_formatted_strings;
+pub mod logging;
 pub mod manage_charts_data;
 pub mod manage_notifications;
 pub mod manage_packets;
@@ -133,6 +133,42 @@ pub const MON_AMOUR_STYLE: Palette = Palette {
     round_borders: Color::BLACK,
 };
 
+// colorblind theme: blue and orange are distinguishable with all the common forms of color blindness
+const PRIMARY_COLORBLIND: Color = Color {
+    r: 26.0 / 255.0,
+    g: 26.0 / 255.0,
+    b: 26.0 / 255.0,
+    a: 1.0,
+};
+const SECONDARY_COLORBLIND: Color = Color {
+    r: 58.0 / 255.0,
+    g: 143.0 / 255.0,
+    b: 214.0 / 255.0,
+    a: 1.0,
+};
+const BUTTONS_COLORBLIND: Color = Color {
+    r: 46.0 / 255.0,
+    g: 46.0 / 255.0,
+    b: 46.0 / 255.0,
+    a: 1.0,
+};
+const OUTGOING_COLORBLIND: Color = Color {
+    r: 230.0 / 255.0,
+    g: 159.0 / 255.0,
+    b: 0.0,
+    a: 1.0,
+};
+pub const COLORBLIND_STYLE: Palette = Palette {
+    primary: PRIMARY_COLORBLIND,
+    secondary: SECONDARY_COLORBLIND,
+    buttons: BUTTONS_COLORBLIND,
+    incoming: SECONDARY_COLORBLIND,
+    outgoing: OUTGOING_COLORBLIND,
+    text_headers: Color::BLACK,
+    text_body: Color::WHITE,
+    round_borders: Color::BLACK,
+};
+
 // gui Text fonts
 // pub const INCONSOLATA: Font = Font::External {
 //     name: "inconsolata_regular",
@@ -159,8 +195,15 @@ pub fn get_font_headers(style: StyleType) -> Font {
 
 pub fn get_color_mix_chart(style: StyleType) -> f64 {
     match style {
-        StyleType::Night | StyleType::DeepSea => 0.3,
+        StyleType::Night | StyleType::DeepSea | StyleType::Colorblind => 0.3,
         StyleType::Day | StyleType::MonAmour => 0.8,
+        StyleType::Custom => {
+            if get_colors(style).text_body == Color::WHITE {
+                0.3
+            } else {
+                0.8
+            }
+        }
     }
 }
 
@@ -175,6 +218,7 @@ pub const YETI_DAY: &[u8] = include_bytes!("../../resources/palettes/YetiDay.png
 pub const YETI_NIGHT: &[u8] = include_bytes!("../../resources/palettes/YetiNight.png");
 pub const DEEP_SEA: &[u8] = include_bytes!("../../resources/palettes/DeepSea.png");
 pub const MON_AMOUR: &[u8] = include_bytes!("../../resources/palettes/MonAmour.png");
+pub const COLORBLIND: &[u8] = include_bytes!("../../resources/palettes/Colorblind.png");
 
 // font sizes
 pub const FONT_SIZE_FOOTER: f32 = 15.0;
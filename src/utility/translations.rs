@@ -71,6 +71,36 @@ pub fn ip_version_translation(language: Language) -> Text<'static> {
     })
 }
 
+pub fn network_scope_translation(language: Language) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => "Network scope",
+        Language::IT => "Ambito di rete",
+        Language::FR => "Portée réseau",
+        Language::ES => "Ámbito de red",
+        Language::PL => "Zakres sieci",
+    })
+}
+
+pub fn local_network_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "local network only",
+        Language::IT => "solo rete locale",
+        Language::FR => "réseau local uniquement",
+        Language::ES => "solo red local",
+        Language::PL => "tylko sieć lokalna",
+    }
+}
+
+pub fn wan_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "WAN only",
+        Language::IT => "solo WAN",
+        Language::FR => "WAN uniquement",
+        Language::ES => "solo WAN",
+        Language::PL => "tylko WAN",
+    }
+}
+
 pub fn transport_protocol_translation(language: Language) -> &'static str {
     match language {
         Language::EN => "Transport protocol",
@@ -91,6 +121,36 @@ pub fn traffic_rate_translation(language: Language) -> Text<'static> {
     })
 }
 
+pub fn search_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Search by address, port or country",
+        Language::IT => "Cerca per indirizzo, porta o paese",
+        Language::FR => "Rechercher par adresse, port ou pays",
+        Language::ES => "Buscar por dirección, puerto o país",
+        Language::PL => "Szukaj według adresu, portu lub kraju",
+    }
+}
+
+pub fn log_scale_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Logarithmic scale",
+        Language::IT => "Scala logaritmica",
+        Language::FR => "Échelle logarithmique",
+        Language::ES => "Escala logarítmica",
+        Language::PL => "Skala logarytmiczna",
+    }
+}
+
+pub fn per_app_protocol_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Per application protocol",
+        Language::IT => "Per protocollo applicativo",
+        Language::FR => "Par protocole applicatif",
+        Language::ES => "Por protocolo de aplicación",
+        Language::PL => "Według protokołu aplikacji",
+    }
+}
+
 pub fn relevant_connections_translation(language: Language) -> Text<'static> {
     Text::new(match language {
         Language::EN => "Relevant connections:",
@@ -263,6 +323,30 @@ pub fn filtered_bytes_translation(
     })
 }
 
+pub fn dropped_packets_translation(
+    language: Language,
+    dropped: &str,
+    if_dropped: &str,
+) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => format!(
+            "Warning: the capture is lossy!\n   {dropped} packets dropped by the system, {if_dropped} by the network adapter"
+        ),
+        Language::IT => format!(
+            "Attenzione: la cattura è incompleta!\n   {dropped} pacchetti scartati dal sistema, {if_dropped} dalla scheda di rete"
+        ),
+        Language::FR => format!(
+            "Attention: la capture est incomplète!\n   {dropped} paquets perdus par le système, {if_dropped} par la carte réseau"
+        ),
+        Language::ES => format!(
+            "Atención: ¡la captura es incompleta!\n   {dropped} paquetes descartados por el sistema, {if_dropped} por el adaptador de red"
+        ),
+        Language::PL => format!(
+            "Uwaga: przechwytywanie jest niepełne!\n   {dropped} pakietów odrzuconych przez system, {if_dropped} przez kartę sieciową"
+        ),
+    })
+}
+
 pub fn filtered_application_translation(language: Language) -> Text<'static> {
     Text::new(match language {
         Language::EN => "Filtered packets per application protocol:",
@@ -273,6 +357,80 @@ pub fn filtered_application_translation(language: Language) -> Text<'static> {
     })
 }
 
+pub fn top_talkers_translation(language: Language) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => "Top talkers:",
+        Language::IT => "Connessioni principali:",
+        Language::FR => "Principales connexions:",
+        Language::ES => "Conexiones principales:",
+        Language::PL => "Główne połączenia:",
+    })
+}
+
+pub fn country_breakdown_translation(language: Language) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => "Traffic per country:",
+        Language::IT => "Traffico per paese:",
+        Language::FR => "Trafic par pays:",
+        Language::ES => "Tráfico por país:",
+        Language::PL => "Ruch według kraju:",
+    })
+}
+
+pub fn aggregate_broadcast_multicast_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Aggregate broadcast and multicast traffic",
+        Language::IT => "Aggrega il traffico broadcast e multicast",
+        Language::FR => "Agréger le trafic broadcast et multicast",
+        Language::ES => "Agregar el tráfico broadcast y multicast",
+        Language::PL => "Agreguj ruch rozgłoszeniowy i multicast",
+    }
+}
+
+pub fn aggregated_traffic_translation(
+    language: Language,
+    broadcast: &str,
+    multicast: &str,
+) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => {
+            format!("Broadcast traffic:\n   {broadcast}\nMulticast traffic:\n   {multicast}")
+        }
+        Language::IT => {
+            format!("Traffico broadcast:\n   {broadcast}\nTraffico multicast:\n   {multicast}")
+        }
+        Language::FR => {
+            format!("Trafic broadcast:\n   {broadcast}\nTrafic multicast:\n   {multicast}")
+        }
+        Language::ES => {
+            format!("Tráfico broadcast:\n   {broadcast}\nTráfico multicast:\n   {multicast}")
+        }
+        Language::PL => {
+            format!("Ruch rozgłoszeniowy:\n   {broadcast}\nRuch multicast:\n   {multicast}")
+        }
+    })
+}
+
+pub fn packet_sizes_translation(language: Language) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => "Packets per size:",
+        Language::IT => "Pacchetti per dimensione:",
+        Language::FR => "Paquets par taille:",
+        Language::ES => "Paquetes por tamaño:",
+        Language::PL => "Pakiety według rozmiaru:",
+    })
+}
+
+pub fn gateway_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Default gateway",
+        Language::IT => "Gateway predefinito",
+        Language::FR => "Passerelle par défaut",
+        Language::ES => "Puerta de enlace predeterminada",
+        Language::PL => "Brama domyślna",
+    }
+}
+
 pub fn no_favorites_translation(language: Language) -> Text<'static> {
     Text::new(match language {
         Language::EN => "Nothing to show at the moment.\n\
@@ -483,6 +641,16 @@ pub fn deep_sea_translation(language: Language) -> &'static str {
     }
 }
 
+pub fn colorblind_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "High contrast colors, safe for color blindness",
+        Language::IT => "Colori ad alto contrasto, adatti al daltonismo",
+        Language::FR => "Couleurs à contraste élevé, adaptées au daltonisme",
+        Language::ES => "Colores de alto contraste, aptos para el daltonismo",
+        Language::PL => "Kolory o wysokim kontraście, bezpieczne dla daltonistów",
+    }
+}
+
 pub fn mon_amour_translation(language: Language) -> &'static str {
     match language {
         Language::EN => "Lovely theme made for dreamers",
@@ -581,6 +749,18 @@ pub fn bytes_threshold_translation(language: Language) -> &'static str {
     }
 }
 
+pub fn connection_rate_threshold_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Notify me when a bytes threshold is exceeded by a single connection",
+        Language::IT => {
+            "Notificami quando una soglia di byte è superata da una singola connessione"
+        }
+        Language::FR => "Me notifier lorsqu'un seuil de donnée est atteint par une seule connexion",
+        Language::ES => "Notificarme cuando una sola conexión exceda un límite de bytes",
+        Language::PL => "Powiadom mnie, gdy pojedyncze połączenie przekroczy próg bajtów",
+    }
+}
+
 pub fn per_second_translation(language: Language) -> &'static str {
     match language {
         Language::EN => "(per second)",
@@ -611,6 +791,16 @@ pub fn favorite_notification_translation(language: Language) -> &'static str {
     }
 }
 
+pub fn new_country_notification_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Notify me when data are exchanged with a new country",
+        Language::IT => "Notificami quando vengono scambiati dati con un nuovo paese",
+        Language::FR => "Notifiez-moi lorsque des données sont échangées avec un nouveau pays",
+        Language::ES => "Notificarme cuando se intercambien datos con un nuevo país",
+        Language::PL => "Powiadom mnie, gdy dane zostaną wymienione z nowym krajem",
+    }
+}
+
 pub fn threshold_translation(language: Language) -> String {
     match language {
         Language::EN => "Threshold: ".to_string(),
@@ -649,6 +839,36 @@ pub fn open_report_translation(language: Language) -> &'static str {
     }
 }
 
+pub fn export_csv_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Export connections as CSV",
+        Language::IT => "Esporta connessioni in CSV",
+        Language::FR => "Exporter les connexions en CSV",
+        Language::ES => "Exportar las conexiones en CSV",
+        Language::PL => "Eksportuj połączenia do CSV",
+    }
+}
+
+pub fn pause_capture_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Pause the capture",
+        Language::IT => "Metti in pausa la cattura",
+        Language::FR => "Mettre en pause la capture",
+        Language::ES => "Pausar la captura",
+        Language::PL => "Wstrzymaj przechwytywanie",
+    }
+}
+
+pub fn resume_capture_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Resume the capture",
+        Language::IT => "Riprendi la cattura",
+        Language::FR => "Reprendre la capture",
+        Language::ES => "Reanudar la captura",
+        Language::PL => "Wznów przechwytywanie",
+    }
+}
+
 pub fn bytes_exceeded_translation(language: Language) -> &'static str {
     match language {
         Language::EN => "Bytes threshold exceeded!",
@@ -659,6 +879,16 @@ pub fn bytes_exceeded_translation(language: Language) -> &'static str {
     }
 }
 
+pub fn connection_rate_exceeded_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Connection bytes threshold exceeded!",
+        Language::IT => "Soglia di Byte superata da una connessione!",
+        Language::FR => "Seuil de donnée atteint par une connexion!",
+        Language::ES => "¡Límite de bytes superado por una conexión!",
+        Language::PL => "Próg bajtów przekroczony przez połączenie!",
+    }
+}
+
 pub fn bytes_exceeded_value_translation(language: Language, value: &str) -> String {
     let trimmed_value = value.trim();
     match language {
@@ -703,6 +933,16 @@ pub fn favorite_transmitted_translation(language: Language) -> &'static str {
     }
 }
 
+pub fn new_country_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Data exchanged with a new country!",
+        Language::IT => "Dati scambiati con un nuovo paese!",
+        Language::FR => "Données échangées avec un nouveau pays!",
+        Language::ES => "¡Datos intercambiados con un nuevo país!",
+        Language::PL => "Dane wymienione z nowym krajem!",
+    }
+}
+
 pub fn no_notifications_set_translation(language: Language) -> Text<'static> {
     Text::new(match language {
         Language::EN => "You haven't enabled notifications yet!\n\n\
@@ -748,12 +988,142 @@ pub fn no_notifications_received_translation(language: Language) -> Text<'static
     })
 }
 
-pub fn only_last_30_translation(language: Language) -> &'static str {
+pub fn only_last_n_translation(language: Language, n: usize) -> String {
+    match language {
+        Language::EN => format!("Only the last {n} notifications are displayed"),
+        Language::IT => format!("Solo le ultime {n} notifiche sono mostrate"),
+        Language::FR => format!("Seulement les {n} dernières notifications sont affichées"),
+        Language::ES => format!("Sólo se muestran las últimas {n} notificaciones"),
+        Language::PL => format!("Wyświetlane jest tylko {n} ostatnich powiadomień"),
+    }
+}
+
+pub fn threshold_per_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Threshold per",
+        Language::IT => "Soglia per",
+        Language::FR => "Seuil par",
+        Language::ES => "Umbral por",
+        Language::PL => "Próg na",
+    }
+}
+
+pub fn second_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "second",
+        Language::IT => "secondo",
+        Language::FR => "seconde",
+        Language::ES => "segundo",
+        Language::PL => "sekundę",
+    }
+}
+
+pub fn refresh_interval_translation(language: Language) -> &'static str {
     match language {
-        Language::EN => "Only the last 30 notifications are displayed",
-        Language::IT => "Solo le ultime 30 notifiche sono mostrate",
-        Language::FR => "Seulement les 30 dernières notifications sont affichées",
-        Language::ES => "Sólo se muestran las últimas 30 notificaciones",
-        Language::PL => "Wyświetlane jest tylko 30 ostatnich powiadomień",
+        Language::EN => "refresh interval",
+        Language::IT => "intervallo di aggiornamento",
+        Language::FR => "intervalle de rafraîchissement",
+        Language::ES => "intervalo de actualización",
+        Language::PL => "interwał odświeżania",
     }
 }
+
+pub fn per_interval_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "(per refresh interval)",
+        Language::IT => "(per intervallo di aggiornamento)",
+        Language::FR => "(par intervalle de rafraîchissement)",
+        Language::ES => "(por intervalo de actualización)",
+        Language::PL => "(na interwał odświeżania)",
+    }
+}
+
+pub fn capture_lag_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Capture falling behind",
+        Language::IT => "Cattura in ritardo",
+        Language::FR => "Capture en retard",
+        Language::ES => "Captura con retraso",
+        Language::PL => "Przechwytywanie opóźnione",
+    }
+}
+
+pub fn capture_lag_value_translation(language: Language, lag: &str) -> String {
+    match language {
+        Language::EN => format!("Packets processed {lag} after their capture"),
+        Language::IT => format!("Pacchetti elaborati {lag} dopo la cattura"),
+        Language::FR => format!("Paquets traités {lag} après leur capture"),
+        Language::ES => format!("Paquetes procesados {lag} después de su captura"),
+        Language::PL => format!("Pakiety przetworzone {lag} po przechwyceniu"),
+    }
+}
+
+pub fn capture_lag_hint_translation(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Consider applying a BPF filter or using a faster machine",
+        Language::IT => "Valuta di applicare un filtro BPF o di usare una macchina più veloce",
+        Language::FR => "Envisagez d'appliquer un filtre BPF ou d'utiliser une machine plus rapide",
+        Language::ES => "Considera aplicar un filtro BPF o usar una máquina más rápida",
+        Language::PL => "Rozważ zastosowanie filtra BPF lub użycie szybszego komputera",
+    }
+}
+
+pub fn discovery_traffic_translation(language: Language) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => "LAN discovery traffic:",
+        Language::IT => "Traffico di rilevamento LAN:",
+        Language::FR => "Trafic de découverte LAN:",
+        Language::ES => "Tráfico de descubrimiento LAN:",
+        Language::PL => "Ruch wykrywania w sieci LAN:",
+    })
+}
+
+pub fn arp_counts_translation(language: Language, requests: &str, replies: &str) -> String {
+    match language {
+        Language::EN => format!("ARP: {requests} requests, {replies} replies"),
+        Language::IT => format!("ARP: {requests} richieste, {replies} risposte"),
+        Language::FR => format!("ARP: {requests} requêtes, {replies} réponses"),
+        Language::ES => format!("ARP: {requests} solicitudes, {replies} respuestas"),
+        Language::PL => format!("ARP: {requests} zapytań, {replies} odpowiedzi"),
+    }
+}
+
+pub fn igmp_counts_translation(language: Language, queries: &str, reports: &str) -> String {
+    match language {
+        Language::EN => format!("IGMP: {queries} queries, {reports} reports"),
+        Language::IT => format!("IGMP: {queries} interrogazioni, {reports} rapporti"),
+        Language::FR => format!("IGMP: {queries} requêtes, {reports} rapports"),
+        Language::ES => format!("IGMP: {queries} consultas, {reports} informes"),
+        Language::PL => format!("IGMP: {queries} zapytań, {reports} raportów"),
+    }
+}
+
+pub fn per_interface_translation(language: Language) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => "Traffic per network adapter:",
+        Language::IT => "Traffico per adattatore di rete:",
+        Language::FR => "Trafic par carte réseau:",
+        Language::ES => "Tráfico por adaptador de red:",
+        Language::PL => "Ruch według karty sieciowej:",
+    })
+}
+
+pub fn interface_traffic_translation(language: Language, received: &str, sent: &str) -> String {
+    match language {
+        Language::EN => format!("{received} received, {sent} sent"),
+        Language::IT => format!("{received} ricevuti, {sent} inviati"),
+        Language::FR => format!("{received} reçus, {sent} envoyés"),
+        Language::ES => format!("{received} recibidos, {sent} enviados"),
+        Language::PL => format!("{received} odebrane, {sent} wysłane"),
+    }
+}
+
+pub fn capture_stalled_translation(language: Language, reason: &str) -> Text<'static> {
+    Text::new(match language {
+        Language::EN => format!("Warning: the capture is stalled!\n   {reason}"),
+        Language::IT => format!("Attenzione: la cattura è bloccata!\n   {reason}"),
+        Language::FR => format!("Attention: la capture est bloquée!\n   {reason}"),
+        Language::ES => format!("Atención: ¡la captura está bloqueada!\n   {reason}"),
+        Language::PL => format!("Uwaga: przechwytywanie zostało wstrzymane!\n   {reason}"),
+    })
+}